@@ -0,0 +1,368 @@
+//! ML training chips: a product is warped onto a target-aligned UTM or Web Mercator grid,
+//! optionally autoscaled, and cut into fixed-size GeoTIFF chips with one JSON sidecar each.
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use gdal::Dataset;
+use ndarray::Array2;
+use rayon::prelude::*;
+use serde::Serialize;
+use tracing::info;
+
+use super::stack::date_label;
+use super::{
+    autoscale_from_reference, bitdepth_arg_to_bitdepth, grid_read_options, operation_data,
+    parse_resample_alg, reader_hint_for_params, resolve_grid_crs, validate_product_params,
+};
+use crate::core::params::ProcessingParams;
+use crate::core::processing::autoscale::StretchWindow;
+use crate::core::processing::chips::{
+    ChipOptions, ChipWindow, chip_coverage, chip_windows, extract_chip,
+};
+use crate::core::processing::expr::BandExpr;
+use crate::core::processing::mask::ValidMask;
+use crate::core::processing::pipeline::{
+    process_native_data_fused, process_operation_data_pipeline_with_window,
+    process_scalar_data_fused_with_floor,
+};
+use crate::error::{Error, Result};
+use crate::io::sentinel1::{SafeMetadata, SafeReader, TargetCrsArg};
+use crate::io::writers::tiff::{
+    write_tiff_bands_f32, write_tiff_multiband_u8, write_tiff_multiband_u16, write_tiff_u8,
+    write_tiff_u16,
+};
+use crate::types::{
+    AutoscaleStrategy, BitDepth, ChipValues, Polarization, ProcessingOperation, ProductMode,
+};
+
+/// One written chip
+#[derive(Debug, Clone, Serialize)]
+pub struct ChipInfo {
+    pub name: String,
+    pub row: i64,
+    pub col: i64,
+    /// Fraction of valid pixels
+    pub coverage: f64,
+    pub path: PathBuf,
+}
+
+/// Summary of a chip run
+#[derive(Debug, Clone, Serialize)]
+pub struct ChipReport {
+    pub crs: String,
+    /// Band labels in band order, e.g. `["VV", "VH"]`
+    pub bands: Vec<String>,
+    pub chips: Vec<ChipInfo>,
+    /// Chips below `ChipOptions.min_coverage`
+    pub skipped: usize,
+}
+
+/// Whole-scene band samples chips are cut from
+enum Samples {
+    F32(Vec<Vec<f32>>),
+    U8(Vec<Vec<u8>>),
+    U16(Vec<Vec<u16>>),
+}
+
+/// Cut `input` into chips under `output_dir`.
+///
+/// The grid follows `params.target_crs` (the product's UTM zone when `auto`/unset; use
+/// `EPSG:3857` for Web Mercator) and `params.pixel_spacing` (10 m when unset) with
+/// target-aligned pixels, so chip rows and columns line up across products. A single
+/// polarization, operation or expression gives one band per chip, `Multiband` the co-/cross-pol
+/// pair. Values are the processed floats (NaN nodata) or the autoscaled integers of
+/// `params.bit_depth` (0 nodata), per `options.values`.
+pub fn process_safe_to_chips(
+    input: &Path,
+    output_dir: &Path,
+    params: &ProcessingParams,
+    options: &ChipOptions,
+) -> Result<ChipReport> {
+    options.validate()?;
+    validate_product_params(params)?;
+    if params.product != ProductMode::Image {
+        return Err(Error::InvalidArgument {
+            arg: "product",
+            value: format!("{} (chips are cut from image products)", params.product),
+        });
+    }
+
+    let crs = resolve_grid_crs(params, input)?;
+    let reader = SafeReader::open_with_read_options(
+        input,
+        reader_hint_for_params(params)?,
+        Some(TargetCrsArg::Custom(crs.clone())),
+        parse_resample_alg(params.resample_alg.as_deref())?,
+        None,
+        &grid_read_options(params),
+    )?;
+    let meta = reader.metadata();
+    let gt = meta
+        .geotransform
+        .ok_or_else(|| Error::Processing(format!("Warped {:?} has no geotransform", input)))?;
+    if gt[2] != 0.0 || gt[4] != 0.0 {
+        return Err(Error::Processing(format!("Warped {:?} is not north-up", input)));
+    }
+    let projection = meta.projection.clone().unwrap_or_else(|| crs.clone());
+
+    let bands = chip_bands(&reader, params)?;
+    let (rows, cols) = bands[0].1.dim();
+    let labels: Vec<String> = bands.iter().map(|(label, _, _)| label.clone()).collect();
+
+    let strategy = match params.autoscale_reference.as_deref() {
+        Some(reference) => autoscale_from_reference(reference)?,
+        None => params.autoscale,
+    };
+    let bit_depth = bitdepth_arg_to_bitdepth(params.bit_depth);
+    let mut masks = Vec::with_capacity(bands.len());
+    let mut windows = Vec::new();
+    let samples = match options.values {
+        ChipValues::Float => {
+            let mut values = Vec::with_capacity(bands.len());
+            for (_, processed, operation) in &bands {
+                let db_floor = params.autoscale_params.db_floor;
+                let (_, mask, _) = if operation.skips_db_conversion() {
+                    process_native_data_fused(processed)
+                } else {
+                    process_scalar_data_fused_with_floor(processed, db_floor)
+                };
+                masks.push(mask);
+                values.push(processed.as_standard_layout().iter().copied().collect());
+            }
+            Samples::F32(values)
+        }
+        ChipValues::Autoscaled => {
+            let (mut u8_bands, mut u16_bands) = (Vec::new(), Vec::new());
+            for (_, processed, operation) in &bands {
+                let (_, mask, scaled_u8, scaled_u16, window) =
+                    process_operation_data_pipeline_with_window(
+                        processed,
+                        bit_depth,
+                        strategy,
+                        &params.autoscale_params,
+                        *operation,
+                    );
+                masks.push(mask);
+                windows.push(window);
+                match scaled_u16 {
+                    Some(v) if bit_depth == BitDepth::U16 => u16_bands.push(v),
+                    _ => u8_bands.push(scaled_u8),
+                }
+            }
+            if bit_depth == BitDepth::U16 {
+                Samples::U16(u16_bands)
+            } else {
+                Samples::U8(u8_bands)
+            }
+        }
+    };
+    drop(bands);
+    // A chip pixel is valid only where every band is
+    let mask = if masks.len() == 1 {
+        masks.pop().expect("one mask")
+    } else {
+        let valid: Vec<bool> = (0..cols * rows).map(|i| masks.iter().all(|m| m.get(i))).collect();
+        ValidMask::from_bools(&valid)
+    };
+
+    std::fs::create_dir_all(output_dir)?;
+    let product = meta.product_id.clone().unwrap_or_default();
+    let pol = labels.join("+").to_lowercase();
+    let date = date_label(&meta.acquisition_start);
+    let candidates = chip_windows(gt, cols, rows, options);
+    let total = candidates.len();
+    let mut chips: Vec<ChipInfo> = candidates
+        .into_par_iter()
+        .map(|window| -> Result<Option<ChipInfo>> {
+            let coverage = chip_coverage(&mask, cols, rows, &window, options.size);
+            if coverage < options.min_coverage || coverage == 0.0 {
+                return Ok(None);
+            }
+            let name = options.chip_name(&product, &pol, &date, window.row, window.col);
+            let path = output_dir.join(format!("{}.tif", name));
+            let mut ds = write_chip(&path, &samples, &mask, cols, rows, &window, options.size)
+                .map_err(Error::external)?;
+            ds.set_geo_transform(&window.geotransform(gt)).map_err(Error::external)?;
+            ds.set_projection(&projection).map_err(Error::external)?;
+            for (i, label) in labels.iter().enumerate() {
+                let mut band = ds.rasterband(i + 1).map_err(Error::external)?;
+                band.set_description(label).map_err(Error::external)?;
+                let nodata = if options.values == ChipValues::Float { f64::NAN } else { 0.0 };
+                band.set_no_data_value(Some(nodata)).map_err(Error::external)?;
+            }
+            drop(ds);
+
+            let sidecar = chip_sidecar(
+                meta, &name, &labels, &window, gt, &crs, coverage, options, strategy, &windows,
+                bit_depth,
+            );
+            std::fs::write(
+                output_dir.join(format!("{}.json", name)),
+                serde_json::to_string_pretty(&sidecar).map_err(Error::external)?,
+            )?;
+            Ok(Some(ChipInfo {
+                name,
+                row: window.row,
+                col: window.col,
+                coverage,
+                path,
+            }))
+        })
+        .filter_map(|r| r.transpose())
+        .collect::<Result<_>>()?;
+    chips.sort_by_key(|c| (c.row, c.col));
+    info!(
+        "{} chips of {} px written to {:?} ({} below {:.0}% coverage)",
+        chips.len(),
+        options.size,
+        output_dir,
+        total - chips.len(),
+        options.min_coverage * 100.0
+    );
+
+    Ok(ChipReport {
+        crs,
+        bands: labels,
+        skipped: total - chips.len(),
+        chips,
+    })
+}
+
+/// Bands selected by `params` with their labels and the operation their values come from
+fn chip_bands<'a>(
+    reader: &'a SafeReader,
+    params: &ProcessingParams,
+) -> Result<Vec<(String, Cow<'a, Array2<f32>>, ProcessingOperation)>> {
+    if let Some(source) = params.expression.as_deref() {
+        let expr = BandExpr::parse(source)?;
+        let operation = ProcessingOperation::Expression { scale: params.expression_scale };
+        let data = reader.expression_data(&expr)?;
+        return Ok(vec![("EXPR".to_string(), Cow::Owned(data), operation)]);
+    }
+    let single = ProcessingOperation::SingleBand;
+    Ok(match params.polarization {
+        Polarization::Vv => vec![("VV".to_string(), Cow::Borrowed(reader.vv_data()?), single)],
+        Polarization::Vh => vec![("VH".to_string(), Cow::Borrowed(reader.vh_data()?), single)],
+        Polarization::Hh => vec![("HH".to_string(), Cow::Borrowed(reader.hh_data()?), single)],
+        Polarization::Hv => vec![("HV".to_string(), Cow::Borrowed(reader.hv_data()?), single)],
+        Polarization::OP(op) => vec![(
+            op.to_string().to_uppercase(),
+            Cow::Owned(operation_data(reader, op)?),
+            ProcessingOperation::PolarOp(op),
+        )],
+        Polarization::Multiband => {
+            let pair = if reader.vv_data().is_ok() && reader.vh_data().is_ok() {
+                [("VV", reader.vv_data()?), ("VH", reader.vh_data()?)]
+            } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
+                [("HH", reader.hh_data()?), ("HV", reader.hv_data()?)]
+            } else {
+                return Err(Error::Processing(format!(
+                    "Multiband requires VV+VH or HH+HV; available: {}",
+                    reader.get_available_polarizations()
+                )));
+            };
+            pair.into_iter()
+                .map(|(label, data)| (label.to_string(), Cow::Borrowed(data), single))
+                .collect()
+        }
+    })
+}
+
+/// GeoTIFF of one chip; geotransform, projection and band metadata are set by the caller
+fn write_chip(
+    path: &Path,
+    samples: &Samples,
+    mask: &ValidMask,
+    cols: usize,
+    rows: usize,
+    window: &ChipWindow,
+    size: usize,
+) -> std::result::Result<Dataset, Box<dyn std::error::Error>> {
+    match samples {
+        Samples::F32(bands) => {
+            let chips: Vec<Vec<f32>> = bands
+                .iter()
+                .map(|b| extract_chip(b, mask, cols, rows, window, size, f32::NAN))
+                .collect();
+            let refs: Vec<&[f32]> = chips.iter().map(Vec::as_slice).collect();
+            write_tiff_bands_f32(path, size, size, &refs)
+        }
+        Samples::U8(bands) => {
+            let chips: Vec<Vec<u8>> = bands
+                .iter()
+                .map(|b| extract_chip(b, mask, cols, rows, window, size, 0))
+                .collect();
+            match chips.as_slice() {
+                [band] => write_tiff_u8(path, size, size, band),
+                [band1, band2] => write_tiff_multiband_u8(path, size, size, band1, band2),
+                _ => Err(format!("{} bands cannot be written as a u8 chip", chips.len()).into()),
+            }
+        }
+        Samples::U16(bands) => {
+            let chips: Vec<Vec<u16>> = bands
+                .iter()
+                .map(|b| extract_chip(b, mask, cols, rows, window, size, 0))
+                .collect();
+            match chips.as_slice() {
+                [band] => write_tiff_u16(path, size, size, band),
+                [band1, band2] => write_tiff_multiband_u16(path, size, size, band1, band2),
+                _ => Err(format!("{} bands cannot be written as a u16 chip", chips.len()).into()),
+            }
+        }
+    }
+}
+
+/// Per-chip metadata JSON: product, grid position, footprint, coverage and value encoding
+#[allow(clippy::too_many_arguments)]
+fn chip_sidecar(
+    meta: &SafeMetadata,
+    name: &str,
+    labels: &[String],
+    window: &ChipWindow,
+    gt: [f64; 6],
+    crs: &str,
+    coverage: f64,
+    options: &ChipOptions,
+    strategy: AutoscaleStrategy,
+    windows: &[StretchWindow],
+    bit_depth: BitDepth,
+) -> serde_json::Value {
+    let chip_gt = window.geotransform(gt);
+    let x1 = chip_gt[0] + options.size as f64 * chip_gt[1];
+    let y1 = chip_gt[3] + options.size as f64 * chip_gt[5];
+    let (dtype, nodata) = match (options.values, bit_depth) {
+        (ChipValues::Float, _) => ("float32", serde_json::Value::from("nan")),
+        (ChipValues::Autoscaled, BitDepth::U8) => ("uint8", serde_json::Value::from(0)),
+        (ChipValues::Autoscaled, BitDepth::U16) => ("uint16", serde_json::Value::from(0)),
+    };
+    let mut sidecar = serde_json::json!({
+        "name": name,
+        "product_id": meta.product_id,
+        "platform": meta.platform,
+        "acquisition_start": meta.acquisition_start,
+        "relative_orbit": meta.relative_orbit,
+        "pass_direction": meta.pass_direction,
+        "bands": labels,
+        "values": options.values.to_string(),
+        "dtype": dtype,
+        "nodata": nodata,
+        "grid": {
+            "row": window.row,
+            "col": window.col,
+            "size": options.size,
+            "overlap": options.overlap,
+            "stride": options.stride(),
+        },
+        "crs": crs,
+        "geotransform": chip_gt,
+        "bounds": [chip_gt[0].min(x1), chip_gt[3].min(y1), chip_gt[0].max(x1), chip_gt[3].max(y1)],
+        "coverage": coverage,
+    });
+    if options.values == ChipValues::Autoscaled {
+        sidecar["autoscale"] = serde_json::json!({
+            "strategy": strategy.to_string(),
+            "windows": windows,
+        });
+    }
+    sidecar
+}
@@ -0,0 +1,99 @@
+//! Product inspection without processing: manifest and annotation metadata, measurement
+//! rasters, auto-resolved CRS, footprint and the file inventory of a SAFE product.
+use std::path::{Path, PathBuf};
+
+use gdal::Dataset;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::io::footprint::Footprint;
+use crate::io::sentinel1::{SafeMetadata, SafeReader, resolve_auto_target_crs};
+
+/// One file of a SAFE product
+#[derive(Debug, Clone, Serialize)]
+pub struct SafeFile {
+    /// Path relative to the SAFE directory
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Everything `sarpro info` reports about a product
+#[derive(Debug, Clone, Serialize)]
+pub struct ProductInfo {
+    pub path: PathBuf,
+    pub metadata: SafeMetadata,
+    /// Polarizations with a measurement raster, e.g. `["VV", "VH"]`
+    pub polarizations: Vec<String>,
+    /// Measurement raster size in pixels, from the first raster's header
+    pub width: usize,
+    pub height: usize,
+    /// CRS `--target-crs auto` resolves to (UTM/UPS zone of the scene centre)
+    pub auto_crs: Option<String>,
+    /// Footprint from the GCP geolocation grid
+    pub footprint: Option<Footprint>,
+    pub files: Vec<SafeFile>,
+    /// Sum of all file sizes in bytes
+    pub total_size: u64,
+}
+
+/// Inspect `input` without loading any raster: metadata from `manifest.safe` and the
+/// annotations, measurement sizes from the TIFF headers, and footprint from the GCPs.
+/// Parts that cannot be derived (CRS, footprint) are reported as missing.
+pub fn inspect_safe(input: &Path) -> Result<ProductInfo> {
+    if !input.join("manifest.safe").is_file() {
+        return Err(Error::InvalidArgument {
+            arg: "input",
+            value: format!("{:?} (no manifest.safe; not a SAFE product)", input),
+        });
+    }
+    let mut metadata = SafeReader::read_metadata(input)?;
+    let measurements = SafeReader::read_measurements(input)?;
+    let (width, height) = match measurements.first() {
+        Some((_, path)) => Dataset::open(path).map_err(Error::external)?.raster_size(),
+        None => (metadata.samples, metadata.lines),
+    };
+    metadata.samples = width;
+    metadata.lines = height;
+
+    let footprint = match SafeReader::read_footprint(input) {
+        Ok(footprint) => Some(footprint),
+        Err(e) => {
+            warn!("No footprint for {:?}: {}", input, e);
+            None
+        }
+    };
+
+    let mut files = Vec::new();
+    collect_files(input, input, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let total_size = files.iter().map(|f| f.size).sum();
+
+    Ok(ProductInfo {
+        path: input.to_path_buf(),
+        metadata,
+        polarizations: measurements.into_iter().map(|(pol, _)| pol).collect(),
+        width,
+        height,
+        auto_crs: resolve_auto_target_crs(input),
+        footprint,
+        files,
+        total_size,
+    })
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<SafeFile>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            files.push(SafeFile {
+                path: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+                size: entry.metadata()?.len(),
+            });
+        }
+    }
+    Ok(())
+}
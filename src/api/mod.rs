@@ -1,24 +1,51 @@
 //! High-level, ergonomic library API: process SAFE to files or in-memory buffers,
 //! batch helpers for directories, and typed save/load helpers. Prefer using these
 //! entrypoints over low-level processing modules when integrating SARPRO.
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 use ndarray::Array2;
+use tracing::{info, warn};
 
 use crate::core::params::ProcessingParams;
-use crate::core::processing::pipeline::process_scalar_data_pipeline;
+use crate::core::processing::change::{
+    ChangeOptions, change_arrays, common_grid_window, crop_window,
+};
+use crate::core::processing::expr::BandExpr;
+use crate::core::processing::pipeline::{
+    process_operation_data_pipeline, process_scalar_data_pipeline,
+};
 use crate::core::processing::resize::resize_image_data;
 use crate::core::processing::save::{
-    save_processed_image, save_processed_multiband_image_sequential,
+    SaveOptions, save_change_raster, save_footprint, save_kmz, save_processed_image,
+    save_processed_multiband_image_sequential, save_ship_detections, save_water_mask,
 };
 use crate::core::processing::synthetic_rgb::create_synthetic_rgb_by_mode_and_strategy;
 use crate::error::{Error, Result};
-use crate::io::sentinel1::{TargetCrsArg, SafeMetadata, SafeReader};
+use crate::io::discovery::{discover_products, is_safe_zip, unpack_safe_zip};
+use crate::io::sentinel1::{
+    ReadOptions, SafeMetadata, SafeReader, TargetCrsArg, resolve_auto_target_crs,
+};
+use crate::io::slices::{SliceGroup, assemble_slices, group_slices};
+use crate::io::writers::metadata::read_autoscale_reference;
 use crate::types::{
     AutoscaleStrategy, BitDepth, BitDepthArg, OutputFormat, Polarization, PolarizationOperation,
-    ProcessingOperation, SyntheticRgbMode,
+    ProcessingOperation, ProductMode, SyntheticRgbMode,
 };
 
+mod chips;
+mod info;
+mod serve;
+mod stack;
+mod tiles;
+mod validate;
+pub use chips::{ChipInfo, ChipReport, process_safe_to_chips};
+pub use info::{ProductInfo, SafeFile, inspect_safe};
+pub use serve::TileServer;
+pub use stack::{StackOptions, StackReport, build_stacks, build_stacks_from_dir};
+pub use tiles::{TileOptions, TileReport, process_safe_to_tiles};
+pub use validate::{ValidationIssue, ValidationReport, validate_safe};
+
 fn operation_to_str(op: PolarizationOperation) -> &'static str {
     match op {
         PolarizationOperation::Sum => "sum",
@@ -26,6 +53,10 @@ fn operation_to_str(op: PolarizationOperation) -> &'static str {
         PolarizationOperation::Ratio => "ratio",
         PolarizationOperation::NDiff => "normalized_diff",
         PolarizationOperation::LogRatio => "log_ratio",
+        PolarizationOperation::Rvi => "rvi",
+        PolarizationOperation::CrossPolDb => "cross_pol_db",
+        PolarizationOperation::DepolRatio => "depol_ratio",
+        PolarizationOperation::Dpsvi => "dpsvi",
     }
 }
 
@@ -46,6 +77,14 @@ fn pol_to_reader_hint(pol: &Polarization) -> Option<&'static str> {
     }
 }
 
+/// Reader hint for `params`: the bands of `params.expression` when set, else the polarization
+fn reader_hint_for_params(params: &ProcessingParams) -> Result<Option<&'static str>> {
+    match params.expression.as_deref() {
+        Some(source) => Ok(Some(BandExpr::parse(source)?.reader_hint())),
+        None => Ok(pol_to_reader_hint(&params.polarization)),
+    }
+}
+
 /// Result of in-memory processing
 #[derive(Debug, Clone)]
 pub struct ProcessedImage {
@@ -80,9 +119,9 @@ pub fn process_safe_to_buffer(
     )?;
 
     match (output_format, polarization) {
-        // Single-band TIFF (U8/U16)
+        // Single-band TIFF/PNG (U8/U16)
         (
-            OutputFormat::TIFF,
+            OutputFormat::TIFF | OutputFormat::PNG,
             Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv,
         ) => {
             let processed = match polarization {
@@ -111,7 +150,7 @@ pub fn process_safe_to_buffer(
                 width: final_cols,
                 height: final_rows,
                 bit_depth,
-                format: OutputFormat::TIFF,
+                format: output_format,
                 gray: if matches!(bit_depth, BitDepth::U8) {
                     Some(final_u8)
                 } else {
@@ -173,7 +212,7 @@ pub fn process_safe_to_buffer(
                 width: final_cols,
                 height: final_rows,
                 bit_depth,
-                format: OutputFormat::TIFF,
+                format: output_format,
                 gray: if matches!(bit_depth, BitDepth::U8) {
                     Some(final1_u8)
                 } else {
@@ -199,8 +238,11 @@ pub fn process_safe_to_buffer(
             })
         }
 
-        // Synthetic RGB JPEG (two bands => RGB), prefer VV/VH else HH/HV
-        (OutputFormat::JPEG, Polarization::Multiband) => {
+        // Synthetic RGB JPEG/PNG/WebP (two bands => RGB), prefer VV/VH else HH/HV
+        (
+            OutputFormat::JPEG | OutputFormat::PNG | OutputFormat::WebP,
+            Polarization::Multiband,
+        ) => {
             let (band1, band2) = if reader.vv_data().is_ok() && reader.vh_data().is_ok() {
                 (reader.vv_data()?, reader.vh_data()?)
             } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
@@ -236,7 +278,7 @@ pub fn process_safe_to_buffer(
                 width: final_cols,
                 height: final_rows,
                 bit_depth: BitDepth::U8,
-                format: OutputFormat::JPEG,
+                format: output_format,
                 gray: None,
                 gray16: None,
                 rgb: Some(rgb),
@@ -246,9 +288,9 @@ pub fn process_safe_to_buffer(
             })
         }
 
-        // Single-band JPEG grayscale (always U8)
+        // Single-band JPEG/WebP grayscale (always U8)
         (
-            OutputFormat::JPEG,
+            OutputFormat::JPEG | OutputFormat::WebP,
             Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv,
         ) => {
             let processed = match polarization {
@@ -270,7 +312,7 @@ pub fn process_safe_to_buffer(
                 width: final_cols,
                 height: final_rows,
                 bit_depth: BitDepth::U8,
-                format: OutputFormat::JPEG,
+                format: output_format,
                 gray: Some(final_u8),
                 gray16: None,
                 rgb: None,
@@ -291,6 +333,10 @@ pub fn process_safe_to_buffer(
                         PolarizationOperation::Ratio => reader.ratio_data()?,
                         PolarizationOperation::NDiff => reader.normalized_diff_data()?,
                         PolarizationOperation::LogRatio => reader.log_ratio_data()?,
+                        PolarizationOperation::Rvi => reader.rvi_data()?,
+                        PolarizationOperation::CrossPolDb => reader.cross_pol_db_data()?,
+                        PolarizationOperation::DepolRatio => reader.depol_ratio_data()?,
+                        PolarizationOperation::Dpsvi => reader.dpsvi_data()?,
                     }
                 } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
                     match op {
@@ -299,6 +345,10 @@ pub fn process_safe_to_buffer(
                         PolarizationOperation::Ratio => reader.ratio_hh_hv_data()?,
                         PolarizationOperation::NDiff => reader.normalized_diff_hh_hv_data()?,
                         PolarizationOperation::LogRatio => reader.log_ratio_hh_hv_data()?,
+                        PolarizationOperation::Rvi => reader.rvi_hh_hv_data()?,
+                        PolarizationOperation::CrossPolDb => reader.cross_pol_db_hh_hv_data()?,
+                        PolarizationOperation::DepolRatio => reader.depol_ratio_hh_hv_data()?,
+                        PolarizationOperation::Dpsvi => reader.dpsvi_hh_hv_data()?,
                     }
                 } else {
                     return Err(Error::Processing(format!(
@@ -309,9 +359,13 @@ pub fn process_safe_to_buffer(
                 };
 
             match format {
-                OutputFormat::TIFF => {
-                    let (db_data, _m, s_u8, s_u16) =
-                        process_scalar_data_pipeline(&combined, bit_depth, autoscale);
+                OutputFormat::TIFF | OutputFormat::PNG => {
+                    let (db_data, _m, s_u8, s_u16) = process_operation_data_pipeline(
+                        &combined,
+                        bit_depth,
+                        autoscale,
+                        ProcessingOperation::PolarOp(op),
+                    );
                     let (rows, cols) = db_data.dim();
                     let (final_cols, final_rows, final_u8, final_u16) = resize_image_data(
                         &s_u8,
@@ -328,7 +382,7 @@ pub fn process_safe_to_buffer(
                         width: final_cols,
                         height: final_rows,
                         bit_depth,
-                        format: OutputFormat::TIFF,
+                        format: output_format,
                         gray: if matches!(bit_depth, BitDepth::U8) {
                             Some(final_u8)
                         } else {
@@ -345,9 +399,13 @@ pub fn process_safe_to_buffer(
                         metadata: reader.metadata.clone(),
                     })
                 }
-                OutputFormat::JPEG => {
-                    let (db_data, _m, s_u8, _s_u16) =
-                        process_scalar_data_pipeline(&combined, BitDepth::U8, autoscale);
+                OutputFormat::JPEG | OutputFormat::WebP => {
+                    let (db_data, _m, s_u8, _s_u16) = process_operation_data_pipeline(
+                        &combined,
+                        BitDepth::U8,
+                        autoscale,
+                        ProcessingOperation::PolarOp(op),
+                    );
                     let (rows, cols) = db_data.dim();
                     let (final_cols, final_rows, final_u8, _) =
                         resize_image_data(&s_u8, None, cols, rows, target_size, BitDepth::U8, pad)
@@ -356,7 +414,7 @@ pub fn process_safe_to_buffer(
                         width: final_cols,
                         height: final_rows,
                         bit_depth: BitDepth::U8,
-                        format: OutputFormat::JPEG,
+                        format: output_format,
                         gray: Some(final_u8),
                         gray16: None,
                         rgb: None,
@@ -390,8 +448,11 @@ pub fn process_safe_to_buffer_with_mode(
     )?;
 
     match (output_format, polarization) {
-        // Delegate to existing branches with_same logic; only Multiband+JPEG uses mode
-        (OutputFormat::JPEG, Polarization::Multiband) => {
+        // Delegate to existing branches with_same logic; only Multiband+RGB formats use mode
+        (
+            OutputFormat::JPEG | OutputFormat::PNG | OutputFormat::WebP,
+            Polarization::Multiband,
+        ) => {
             let (band1, band2) = if reader.vv_data().is_ok() && reader.vh_data().is_ok() {
                 (reader.vv_data()?, reader.vh_data()?)
             } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
@@ -427,7 +488,7 @@ pub fn process_safe_to_buffer_with_mode(
                 width: final_cols,
                 height: final_rows,
                 bit_depth: BitDepth::U8,
-                format: OutputFormat::JPEG,
+                format: output_format,
                 gray: None,
                 gray16: None,
                 rgb: Some(rgb),
@@ -469,7 +530,8 @@ pub fn iterate_safe_products(input_dir: &Path) -> Result<std::vec::IntoIter<std:
     Ok(dirs.into_iter())
 }
 
-/// Process all SAFE subdirectories from `input_dir` into `output_dir` using `params`.
+/// Process the SAFE products found in `input_dir` (see `ProcessingParams::discovery`) into
+/// `output_dir` using `params`.
 /// If `continue_on_error` is true, errors are logged in the report and processing continues; otherwise, the first error is returned.
 pub fn process_directory_to_path(
     input_dir: &Path,
@@ -477,68 +539,232 @@ pub fn process_directory_to_path(
     params: &ProcessingParams,
     continue_on_error: bool,
 ) -> Result<BatchReport> {
+    let products = discover_products(input_dir, &params.discovery)?;
+    info!("Found {} products in {:?}", products.len(), input_dir);
+    process_products_to_path(&products, output_dir, params, continue_on_error)
+}
+
+/// Process a list of SAFE directories or zipped products (e.g. from `read_product_list`) into
+/// `output_dir`, like [`process_directory_to_path`]. Zips are unpacked to a temporary
+/// directory for the duration of their run.
+pub fn process_products_to_path(
+    products: &[PathBuf],
+    output_dir: &Path,
+    params: &ProcessingParams,
+    continue_on_error: bool,
+) -> Result<BatchReport> {
+    validate_product_params(params)?;
     std::fs::create_dir_all(output_dir).map_err(Error::from)?;
 
     let mut report = BatchReport::default();
 
-    let mut iter = iterate_safe_products(input_dir)?;
-    while let Some(path) = iter.next() {
-        // Early viability check to allow skipping unsupported product types
-        // Map target CRS and resample algorithm similarly to single-file path
+    // Corrupted products are set aside here instead of failing inside GDAL later; zips are
+    // checked once unpacked
+    let mut invalid: HashSet<PathBuf> = HashSet::new();
+    if params.validate_inputs {
+        for path in products.iter().filter(|p| p.is_dir()) {
+            if !fails_validation(path) {
+                continue;
+            }
+            report.errors += 1;
+            if !continue_on_error {
+                return Err(Error::Processing(format!("{:?} failed validation", path)));
+            }
+            invalid.insert(path.clone());
+        }
+    }
+
+    // Multi-slice datatakes are written as one assembled product and skipped below
+    let mut assembled: HashSet<PathBuf> = HashSet::new();
+    if params.assemble_slices {
+        let inputs: Vec<PathBuf> = products
+            .iter()
+            .filter(|p| p.is_dir() && !invalid.contains(*p))
+            .cloned()
+            .collect();
+        for group in group_safe_slices(&inputs) {
+            if group.products.len() < 2 {
+                continue;
+            }
+            let stem = group.products[0]
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .trim_end_matches(".SAFE")
+                .to_string();
+            let ext = params.output_extension();
+            let output_path = output_dir.join(format!("{}_ASSEMBLED.{}", stem, ext));
+            info!(
+                "Assembling {} slices of datatake {} into {:?}",
+                group.products.len(),
+                group.data_take_id.as_deref().unwrap_or("?"),
+                output_path
+            );
+            assembled.extend(group.products.iter().cloned());
+            match process_slices_to_path(&group.products, &output_path, params) {
+                Ok(()) => report.processed += 1,
+                Err(e) => {
+                    warn!("Error assembling {:?}: {}", group.products, e);
+                    report.errors += 1;
+                    if !continue_on_error {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+
+    for product in products {
+        if assembled.contains(product) || invalid.contains(product) {
+            continue;
+        }
+        let unpacked = if is_safe_zip(product) {
+            match unpack_safe_zip(product) {
+                Ok(unpacked) => Some(unpacked),
+                Err(e) => {
+                    warn!("Cannot unpack {:?}: {}", product, e);
+                    report.errors += 1;
+                    if !continue_on_error {
+                        return Err(e.into());
+                    }
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+        let path = unpacked.as_ref().map_or(product, |u| &u.path).clone();
+        if unpacked.is_some() && params.validate_inputs && fails_validation(&path) {
+            report.errors += 1;
+            if !continue_on_error {
+                return Err(Error::Processing(format!("{:?} failed validation", product)));
+            }
+            continue;
+        }
+        if let Some(aoi) = params.aoi.as_ref() {
+            if SafeReader::read_footprint(&path).is_ok_and(|f| !f.intersects_aoi(aoi)) {
+                info!("Skipping {:?}: footprint does not intersect the AOI", path);
+                report.skipped += 1;
+                continue;
+            }
+        }
         let target_arg: Option<TargetCrsArg> = match params.target_crs.as_deref() {
             Some(t) if t.eq_ignore_ascii_case("none") => Some(TargetCrsArg::None),
             Some(t) if t.eq_ignore_ascii_case("auto") => Some(TargetCrsArg::Auto),
             Some(t) => Some(TargetCrsArg::Custom(t.to_string())),
             None => None,
         };
-        let resample_alg = match params.resample_alg.as_deref() {
-            Some("nearest") => Some(gdal::raster::ResampleAlg::NearestNeighbour),
-            Some("bilinear") => Some(gdal::raster::ResampleAlg::Bilinear),
-            Some("cubic") => Some(gdal::raster::ResampleAlg::Cubic),
-            Some("lanczos") | None => Some(gdal::raster::ResampleAlg::Lanczos),
-            Some(_) => Some(gdal::raster::ResampleAlg::Lanczos),
-        };
+        let resample_alg = parse_resample_alg(params.resample_alg.as_deref())?;
+        let safe_name = path.file_name().unwrap().to_string_lossy();
+        let output_path = output_dir.join(format!("{}.{}", safe_name, params.output_extension()));
+        info!("Processing: {:?} -> {:?}", path, output_path);
 
-        match SafeReader::open_with_warnings_with_options(
+        // Unsupported product types are skipped rather than counted as errors
+        let processed = SafeReader::open_with_warnings_with_read_options(
             &path,
-            pol_to_reader_hint(&params.polarization),
+            reader_hint_for_params(params)?,
             target_arg,
             resample_alg,
-            params.size,
-        )? {
-            Some(_) => {
-                // Determine output file name
-                let safe_name = path.file_name().unwrap().to_string_lossy();
-                let ext = match params.format {
-                    OutputFormat::TIFF => "tiff",
-                    OutputFormat::JPEG => "jpg",
-                };
-                let output_path = output_dir.join(format!("{}.{}", safe_name, ext));
-
-                match process_safe_to_path(&path, &output_path, params) {
-                    Ok(()) => report.processed += 1,
-                    Err(e) => {
-                        report.errors += 1;
-                        if !continue_on_error {
-                            return Err(e);
-                        }
-                    }
+            params.target_size(),
+            &params.read_options(),
+        )
+        .map_err(Error::from)
+        .and_then(|reader| match reader {
+            Some(reader) => process_reader_to_path(&reader, &output_path, params).map(|()| true),
+            None => Ok(false),
+        });
+        match processed {
+            Ok(true) => report.processed += 1,
+            Ok(false) => report.skipped += 1,
+            Err(e) => {
+                warn!("Error processing {:?}: {}", product, e);
+                report.errors += 1;
+                if !continue_on_error {
+                    return Err(e);
                 }
             }
-            None => {
-                report.skipped += 1;
-                continue;
-            }
         }
     }
 
     Ok(report)
 }
 
+/// Log the manifest problems of `path`; true when it is corrupted or cannot be checked
+fn fails_validation(path: &Path) -> bool {
+    match validate_safe(path, true) {
+        Ok(validation) => {
+            for issue in &validation.issues {
+                warn!("{:?}: {}", path, issue);
+            }
+            !validation.is_valid()
+        }
+        Err(e) => {
+            warn!("{:?}: {}", path, e);
+            true
+        }
+    }
+}
+
+/// Reject product modes that cannot be combined with the requested polarization or options
+fn validate_product_params(params: &ProcessingParams) -> Result<()> {
+    if params.product != ProductMode::Image
+        && (params.expression.is_some()
+            || !matches!(
+                params.polarization,
+                Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv
+            ))
+    {
+        return Err(Error::InvalidArgument {
+            arg: "polarization",
+            value: format!(
+                "{} ({} product needs vv, vh, hh or hv)",
+                params.polarization, params.product
+            ),
+        });
+    }
+    params.autoscale_params.validate()?;
+    params.encode.validate()?;
+    parse_resample_alg(params.resample_alg.as_deref())?;
+    if (params.colormap.is_some() || params.colormap_file.is_some())
+        && params.expression.is_none()
+        && params.polarization == Polarization::Multiband
+    {
+        return Err(Error::InvalidArgument {
+            arg: "colormap",
+            value: "multiband outputs cannot be colormapped; use a single-band polarization, \
+                    operation or expression"
+                .to_string(),
+        });
+    }
+    if params.kmz {
+        if !matches!(params.format, OutputFormat::JPEG | OutputFormat::PNG) {
+            return Err(Error::InvalidArgument {
+                arg: "kmz",
+                value: format!("{:?} output (KMZ packages a JPEG or PNG image)", params.format),
+            });
+        }
+        if params.pad || params.product == ProductMode::Water {
+            return Err(Error::InvalidArgument {
+                arg: "kmz",
+                value: "needs an unpadded image product; padding has no ground position"
+                    .to_string(),
+            });
+        }
+    }
+    if params.product == ProductMode::Ships && params.ships.guard >= params.ships.background {
+        return Err(Error::InvalidArgument {
+            arg: "ships.background",
+            value: format!(
+                "{} (must be larger than ships.guard = {})",
+                params.ships.background, params.ships.guard
+            ),
+        });
+    }
+    Ok(())
+}
+
 /// Process a SAFE input to an output path using ProcessingParams
 pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingParams) -> Result<()> {
-    let bit_depth = bitdepth_arg_to_bitdepth(params.bit_depth);
-
     // Open reader according to polarization
     // Map target CRS into internal enum; resolution happens inside reader
     let target_arg: Option<TargetCrsArg> = match params.target_crs.as_deref() {
@@ -548,22 +774,109 @@ pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingPara
         None => None,
     };
 
-    let resample_alg = match params.resample_alg.as_deref() {
-        Some("nearest") => Some(gdal::raster::ResampleAlg::NearestNeighbour),
-        Some("bilinear") => Some(gdal::raster::ResampleAlg::Bilinear),
-        Some("cubic") => Some(gdal::raster::ResampleAlg::Cubic),
-        Some("lanczos") | None => Some(gdal::raster::ResampleAlg::Lanczos),
-        Some(_) => Some(gdal::raster::ResampleAlg::Lanczos),
-    };
+    let resample_alg = parse_resample_alg(params.resample_alg.as_deref())?;
 
-    let reader = SafeReader::open_with_options(
+    validate_product_params(params)?;
+
+    let reader = SafeReader::open_with_read_options(
         input,
-        pol_to_reader_hint(&params.polarization),
+        reader_hint_for_params(params)?,
         target_arg,
         resample_alg,
-        params.size,
+        params.target_size(),
+        &params.read_options(),
     )?;
 
+    process_reader_to_path(&reader, output, params)
+}
+
+/// `Fixed` autoscale strategy with the clip window recorded in an earlier output: its JSON
+/// sidecar, a JSON file with `min_db`/`max_db`/`gamma`, or a raster with `AUTOSCALE_*` metadata
+pub fn autoscale_from_reference(path: &Path) -> Result<AutoscaleStrategy> {
+    let window = read_autoscale_reference(path).map_err(|e| Error::InvalidArgument {
+        arg: "autoscale_reference",
+        value: e.to_string(),
+    })?;
+    info!(
+        "Autoscale reference {:?}: {} .. {} dB, gamma {}",
+        path, window.min_db, window.max_db, window.gamma
+    );
+    Ok(AutoscaleStrategy::Fixed {
+        min_db: window.min_db,
+        max_db: window.max_db,
+        gamma: window.gamma,
+    })
+}
+
+/// Write an already opened (or assembled) product to `output` according to `params`, plus
+/// `<output>.kmz` and the footprint files when `params.kmz` / `params.footprint` are set
+fn process_reader_to_path(reader: &SafeReader, output: &Path, params: &ProcessingParams) -> Result<()> {
+    save_reader_outputs(reader, output, params)?;
+    if params.footprint {
+        match reader.footprint() {
+            Some(footprint) => save_footprint(output, &footprint, Some(reader.metadata()))
+                .map_err(|e| Error::external(e))?,
+            None => warn!("No footprint for {:?}: no valid pixels or no geolocation", output),
+        }
+    }
+    if params.kmz {
+        save_kmz(
+            output,
+            reader.metadata(),
+            reader.geolocation().as_ref(),
+            output_operation(reader, params),
+        )
+        .map_err(|e| Error::external(e))?;
+    }
+    Ok(())
+}
+
+/// Operation applied to `reader`'s bands under `params`; multiband prefers VV/VH like the
+/// save path does
+fn output_operation(reader: &SafeReader, params: &ProcessingParams) -> ProcessingOperation {
+    if params.expression.is_some() {
+        return ProcessingOperation::Expression { scale: params.expression_scale };
+    }
+    match params.polarization {
+        Polarization::Multiband if reader.vv_data().is_ok() && reader.vh_data().is_ok() => {
+            ProcessingOperation::MultibandVvVh
+        }
+        Polarization::Multiband => ProcessingOperation::MultibandHhHv,
+        Polarization::OP(op) => ProcessingOperation::PolarOp(op),
+        _ => ProcessingOperation::SingleBand,
+    }
+}
+
+fn save_reader_outputs(reader: &SafeReader, output: &Path, params: &ProcessingParams) -> Result<()> {
+    let resolved;
+    let params = match params.autoscale_reference.as_deref() {
+        Some(reference) => {
+            resolved = ProcessingParams {
+                autoscale: autoscale_from_reference(reference)?,
+                autoscale_reference: None,
+                ..params.clone()
+            };
+            &resolved
+        }
+        None => params,
+    };
+    let options = params.save_options()?;
+
+    if let Some(source) = params.expression.as_deref() {
+        let expr = BandExpr::parse(source)?;
+        let processed = reader.expression_data(&expr)?;
+        let mut metadata = reader.metadata().clone();
+        metadata.expression = Some(expr.source().to_string());
+        return save_processed_image(
+            &processed,
+            output,
+            Some(&metadata),
+            ProcessingOperation::Expression { scale: params.expression_scale },
+            &options,
+        )
+        .map_err(|e| Error::external(e));
+    }
+
     match params.polarization {
         Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv => {
             let processed = match params.polarization {
@@ -574,18 +887,47 @@ pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingPara
                 _ => unreachable!(),
             };
 
+            if params.product == ProductMode::Water {
+                let result = save_water_mask(
+                    processed,
+                    output,
+                    Some(reader.metadata()),
+                    &params.water,
+                    &params.autoscale_params,
+                )
+                .map_err(|e| Error::external(e))?;
+                info!(
+                    "Water mask: threshold {:.2} dB, {} of {} valid pixels are water",
+                    result.threshold_db, result.water_pixels, result.valid_pixels
+                );
+                return Ok(());
+            }
+
             save_processed_image(
                 processed,
                 output,
-                params.format,
-                bit_depth,
-                params.size,
                 Some(reader.metadata()),
-                params.pad,
-                params.autoscale,
                 ProcessingOperation::SingleBand,
+                &options,
             )
-            .map_err(|e| Error::external(e))
+            .map_err(|e| Error::external(e))?;
+
+            if params.product == ProductMode::Ships {
+                let geolocation = reader.geolocation();
+                if geolocation.is_none() {
+                    warn!("No geolocation available; ship targets keep pixel coordinates only");
+                }
+                let targets = save_ship_detections(
+                    processed,
+                    output,
+                    Some(reader.metadata()),
+                    geolocation.as_ref(),
+                    &params.ships,
+                )
+                .map_err(|e| Error::external(e))?;
+                info!("Ship detection: {} targets", targets.len());
+            }
+            Ok(())
         }
         Polarization::Multiband => {
             // Prefer VV/VH if present, otherwise HH/HV
@@ -596,14 +938,10 @@ pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingPara
                     vv,
                     vh,
                     output,
-                    params.format,
-                    bit_depth,
-                    params.size,
                     Some(reader.metadata()),
-                    params.pad,
-                    params.autoscale,
                     ProcessingOperation::MultibandVvVh,
                     params.synrgb_mode,
+                    &options,
                 )
                 .map_err(|e| Error::external(e))
             } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
@@ -613,14 +951,10 @@ pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingPara
                     hh,
                     hv,
                     output,
-                    params.format,
-                    bit_depth,
-                    params.size,
                     Some(reader.metadata()),
-                    params.pad,
-                    params.autoscale,
                     ProcessingOperation::MultibandHhHv,
                     params.synrgb_mode,
+                    &options,
                 )
                 .map_err(|e| Error::external(e))
             } else {
@@ -640,6 +974,10 @@ pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingPara
                         PolarizationOperation::Ratio => reader.ratio_data()?,
                         PolarizationOperation::NDiff => reader.normalized_diff_data()?,
                         PolarizationOperation::LogRatio => reader.log_ratio_data()?,
+                        PolarizationOperation::Rvi => reader.rvi_data()?,
+                        PolarizationOperation::CrossPolDb => reader.cross_pol_db_data()?,
+                        PolarizationOperation::DepolRatio => reader.depol_ratio_data()?,
+                        PolarizationOperation::Dpsvi => reader.dpsvi_data()?,
                     }
                 } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
                     match op {
@@ -648,6 +986,10 @@ pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingPara
                         PolarizationOperation::Ratio => reader.ratio_hh_hv_data()?,
                         PolarizationOperation::NDiff => reader.normalized_diff_hh_hv_data()?,
                         PolarizationOperation::LogRatio => reader.log_ratio_hh_hv_data()?,
+                        PolarizationOperation::Rvi => reader.rvi_hh_hv_data()?,
+                        PolarizationOperation::CrossPolDb => reader.cross_pol_db_hh_hv_data()?,
+                        PolarizationOperation::DepolRatio => reader.depol_ratio_hh_hv_data()?,
+                        PolarizationOperation::Dpsvi => reader.dpsvi_hh_hv_data()?,
                     }
                 } else {
                     return Err(Error::Processing(format!(
@@ -660,19 +1002,59 @@ pub fn process_safe_to_path(input: &Path, output: &Path, params: &ProcessingPara
             save_processed_image(
                 &processed,
                 output,
-                params.format,
-                bit_depth,
-                params.size,
                 Some(reader.metadata()),
-                params.pad,
-                params.autoscale,
                 ProcessingOperation::PolarOp(op),
+                &options,
             )
             .map_err(|e| Error::external(e))
         }
     }
 }
 
+/// Group SAFE products into runs of consecutive slices of one datatake (see
+/// `io::slices::group_slices`). Products whose metadata cannot be read are skipped.
+pub fn group_safe_slices(inputs: &[PathBuf]) -> Vec<SliceGroup> {
+    let products: Vec<(PathBuf, SafeMetadata)> = inputs
+        .iter()
+        .filter_map(|path| match SafeReader::read_metadata(path) {
+            Ok(meta) => Some((path.clone(), meta)),
+            Err(e) => {
+                warn!("Skipping {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    group_slices(&products)
+}
+
+/// Assemble consecutive slices of one datatake and write them as one product.
+///
+/// Every slice is warped onto a shared grid: `params.target_crs` (`auto`/unset resolves the
+/// UTM zone of the first slice), `params.pixel_spacing` (10 m when unset) and target-aligned
+/// pixels. The mosaic then goes through the regular pipeline once, so the whole datatake gets
+/// one autoscale. `inputs` should be in acquisition order, as returned by `group_safe_slices`.
+pub fn process_slices_to_path(inputs: &[PathBuf], output: &Path, params: &ProcessingParams) -> Result<()> {
+    validate_product_params(params)?;
+    let first = inputs.first().ok_or_else(|| Error::MissingArgument {
+        arg: "slices to assemble".to_string(),
+    })?;
+    let target_crs = resolve_grid_crs(params, first)?;
+    let read_opts = grid_read_options(params);
+    let mut readers = Vec::with_capacity(inputs.len());
+    for path in inputs {
+        readers.push(SafeReader::open_with_read_options(
+            path,
+            reader_hint_for_params(params)?,
+            Some(TargetCrsArg::Custom(target_crs.clone())),
+            parse_resample_alg(params.resample_alg.as_deref())?,
+            None,
+            &read_opts,
+        )?);
+    }
+    let reader = assemble_slices(readers)?;
+    process_reader_to_path(&reader, output, params)
+}
+
 /// Convenience variant with explicit options (typed)
 pub fn process_safe_with_options(
     input: &Path,
@@ -691,6 +1073,14 @@ pub fn process_safe_with_options(
         None,
         size,
     )?;
+    let options = SaveOptions {
+        format,
+        bit_depth,
+        target_size: size,
+        pad,
+        strategy: autoscale,
+        ..SaveOptions::default()
+    };
 
     match polarization {
         Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv => {
@@ -705,13 +1095,9 @@ pub fn process_safe_with_options(
             save_processed_image(
                 processed,
                 output,
-                format,
-                bit_depth,
-                size,
                 Some(reader.metadata()),
-                pad,
-                autoscale,
                 ProcessingOperation::SingleBand,
+                &options,
             )
             .map_err(|e| Error::external(e))
         }
@@ -723,14 +1109,10 @@ pub fn process_safe_with_options(
                     vv,
                     vh,
                     output,
-                    format,
-                    bit_depth,
-                    size,
                     Some(reader.metadata()),
-                    pad,
-                    autoscale,
                     ProcessingOperation::MultibandVvVh,
                     SyntheticRgbMode::Default,
+                    &options,
                 )
                 .map_err(|e| Error::external(e))
             } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
@@ -740,14 +1122,10 @@ pub fn process_safe_with_options(
                     hh,
                     hv,
                     output,
-                    format,
-                    bit_depth,
-                    size,
                     Some(reader.metadata()),
-                    pad,
-                    autoscale,
                     ProcessingOperation::MultibandHhHv,
                     SyntheticRgbMode::Default,
+                    &options,
                 )
                 .map_err(|e| Error::external(e))
             } else {
@@ -766,6 +1144,10 @@ pub fn process_safe_with_options(
                         PolarizationOperation::Ratio => reader.ratio_data()?,
                         PolarizationOperation::NDiff => reader.normalized_diff_data()?,
                         PolarizationOperation::LogRatio => reader.log_ratio_data()?,
+                        PolarizationOperation::Rvi => reader.rvi_data()?,
+                        PolarizationOperation::CrossPolDb => reader.cross_pol_db_data()?,
+                        PolarizationOperation::DepolRatio => reader.depol_ratio_data()?,
+                        PolarizationOperation::Dpsvi => reader.dpsvi_data()?,
                     }
                 } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
                     match op {
@@ -774,6 +1156,10 @@ pub fn process_safe_with_options(
                         PolarizationOperation::Ratio => reader.ratio_hh_hv_data()?,
                         PolarizationOperation::NDiff => reader.normalized_diff_hh_hv_data()?,
                         PolarizationOperation::LogRatio => reader.log_ratio_hh_hv_data()?,
+                        PolarizationOperation::Rvi => reader.rvi_hh_hv_data()?,
+                        PolarizationOperation::CrossPolDb => reader.cross_pol_db_hh_hv_data()?,
+                        PolarizationOperation::DepolRatio => reader.depol_ratio_hh_hv_data()?,
+                        PolarizationOperation::Dpsvi => reader.dpsvi_hh_hv_data()?,
                     }
                 } else {
                     return Err(Error::Processing(format!(
@@ -786,13 +1172,9 @@ pub fn process_safe_with_options(
             save_processed_image(
                 &processed,
                 output,
-                format,
-                bit_depth,
-                size,
                 Some(reader.metadata()),
-                pad,
-                autoscale,
                 ProcessingOperation::PolarOp(op),
+                &options,
             )
             .map_err(|e| Error::external(e))
         }
@@ -811,17 +1193,15 @@ pub fn save_image(
     autoscale: AutoscaleStrategy,
     operation: ProcessingOperation,
 ) -> Result<()> {
-    save_processed_image(
-        processed,
-        output,
+    let options = SaveOptions {
         format,
         bit_depth,
         target_size,
-        metadata,
         pad,
-        autoscale,
-        operation,
-    )
+        strategy: autoscale,
+        ..SaveOptions::default()
+    };
+    save_processed_image(processed, output, metadata, operation, &options)
     .map_err(|e| Error::external(e))
 }
 
@@ -839,18 +1219,22 @@ pub fn save_multiband_image(
     operation: ProcessingOperation,
 ) -> Result<()> {
     // We omit the specific multiband label for now; metadata will still include polarizations
+    let options = SaveOptions {
+        format,
+        bit_depth,
+        target_size,
+        pad,
+        strategy: autoscale,
+        ..SaveOptions::default()
+    };
     save_processed_multiband_image_sequential(
         processed1,
         processed2,
         output,
-        format,
-        bit_depth,
-        target_size,
         metadata,
-        pad,
-        autoscale,
         operation,
         SyntheticRgbMode::Default,
+        &options,
     )
     .map_err(|e| Error::external(e))
 }
@@ -895,6 +1279,10 @@ pub fn load_operation(
             PolarizationOperation::Ratio => reader.ratio_data()?,
             PolarizationOperation::NDiff => reader.normalized_diff_data()?,
             PolarizationOperation::LogRatio => reader.log_ratio_data()?,
+            PolarizationOperation::Rvi => reader.rvi_data()?,
+            PolarizationOperation::CrossPolDb => reader.cross_pol_db_data()?,
+            PolarizationOperation::DepolRatio => reader.depol_ratio_data()?,
+            PolarizationOperation::Dpsvi => reader.dpsvi_data()?,
         };
         Ok((data, reader.metadata.clone()))
     } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
@@ -904,6 +1292,10 @@ pub fn load_operation(
             PolarizationOperation::Ratio => reader.ratio_hh_hv_data()?,
             PolarizationOperation::NDiff => reader.normalized_diff_hh_hv_data()?,
             PolarizationOperation::LogRatio => reader.log_ratio_hh_hv_data()?,
+            PolarizationOperation::Rvi => reader.rvi_hh_hv_data()?,
+            PolarizationOperation::CrossPolDb => reader.cross_pol_db_hh_hv_data()?,
+            PolarizationOperation::DepolRatio => reader.depol_ratio_hh_hv_data()?,
+            PolarizationOperation::Dpsvi => reader.dpsvi_hh_hv_data()?,
         };
         Ok((data, reader.metadata.clone()))
     } else {
@@ -914,3 +1306,224 @@ pub fn load_operation(
         )))
     }
 }
+
+/// Evaluate a band-math expression (e.g. `"(VV-VH)/(VV+VH)"`) and return the result + metadata.
+/// The expression is recorded in `SafeMetadata::expression`.
+pub fn load_expression(input: &Path, expression: &str) -> Result<(Array2<f32>, SafeMetadata)> {
+    let expr = BandExpr::parse(expression)?;
+    let reader = SafeReader::open_with_options(input, Some(expr.reader_hint()), None, None, None)?;
+    let data = reader.expression_data(&expr)?;
+    let mut metadata = reader.metadata.clone();
+    metadata.expression = Some(expr.source().to_string());
+    Ok((data, metadata))
+}
+
+/// Loaded band of `reader` by polarization name ("VV", "VH", "HH", "HV")
+fn band_data<'a>(reader: &'a SafeReader, pol: &str) -> Option<&'a Array2<f32>> {
+    match pol {
+        "VV" => reader.vv_data().ok(),
+        "VH" => reader.vh_data().ok(),
+        "HH" => reader.hh_data().ok(),
+        "HV" => reader.hv_data().ok(),
+        _ => None,
+    }
+}
+
+/// Polarimetric operation `op` on the VV/VH pair of `reader`, or HH/HV when VV/VH is missing
+fn operation_data(reader: &SafeReader, op: PolarizationOperation) -> Result<Array2<f32>> {
+    if reader.vv_data().is_ok() && reader.vh_data().is_ok() {
+        Ok(match op {
+            PolarizationOperation::Sum => reader.sum_data()?,
+            PolarizationOperation::Diff => reader.difference_data()?,
+            PolarizationOperation::Ratio => reader.ratio_data()?,
+            PolarizationOperation::NDiff => reader.normalized_diff_data()?,
+            PolarizationOperation::LogRatio => reader.log_ratio_data()?,
+            PolarizationOperation::Rvi => reader.rvi_data()?,
+            PolarizationOperation::CrossPolDb => reader.cross_pol_db_data()?,
+            PolarizationOperation::DepolRatio => reader.depol_ratio_data()?,
+            PolarizationOperation::Dpsvi => reader.dpsvi_data()?,
+        })
+    } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
+        Ok(match op {
+            PolarizationOperation::Sum => reader.sum_hh_hv_data()?,
+            PolarizationOperation::Diff => reader.difference_hh_hv_data()?,
+            PolarizationOperation::Ratio => reader.ratio_hh_hv_data()?,
+            PolarizationOperation::NDiff => reader.normalized_diff_hh_hv_data()?,
+            PolarizationOperation::LogRatio => reader.log_ratio_hh_hv_data()?,
+            PolarizationOperation::Rvi => reader.rvi_hh_hv_data()?,
+            PolarizationOperation::CrossPolDb => reader.cross_pol_db_hh_hv_data()?,
+            PolarizationOperation::DepolRatio => reader.depol_ratio_hh_hv_data()?,
+            PolarizationOperation::Dpsvi => reader.dpsvi_hh_hv_data()?,
+        })
+    } else {
+        Err(Error::Processing(format!(
+            "Operation {} requires VV+VH or HH+HV; available: {}",
+            operation_to_str(op),
+            reader.get_available_polarizations()
+        )))
+    }
+}
+
+/// Fallback output pixel spacing (meters) for multi-product grids when `params.pixel_spacing` is unset
+const DEFAULT_GRID_PIXEL_SPACING: f64 = 10.0;
+
+/// Target CRS shared by several products: `params.target_crs`, or the UTM zone of
+/// `reference` when it is unset or `auto`. Reprojection cannot be disabled here.
+fn resolve_grid_crs(params: &ProcessingParams, reference: &Path) -> Result<String> {
+    match params.target_crs.as_deref() {
+        Some(t) if t.eq_ignore_ascii_case("none") => Err(Error::InvalidArgument {
+            arg: "target_crs",
+            value: "none (multi-product outputs need a common map grid)".to_string(),
+        }),
+        Some(t) if !t.eq_ignore_ascii_case("auto") => Ok(t.to_string()),
+        _ => resolve_auto_target_crs(reference).ok_or_else(|| {
+            Error::Processing(format!("Could not resolve a target CRS for {:?}", reference))
+        }),
+    }
+}
+
+/// Read options for a shared grid: the params' options with a fixed pixel spacing and
+/// target-aligned pixels, so independently warped products line up pixel for pixel
+fn grid_read_options(params: &ProcessingParams) -> ReadOptions {
+    let mut read_opts = params.read_options();
+    read_opts.pixel_spacing = Some(params.pixel_spacing.unwrap_or(DEFAULT_GRID_PIXEL_SPACING));
+    read_opts.target_aligned_pixels = true;
+    read_opts
+}
+
+/// Resampling algorithm named by `--resample-alg` / `ProcessingParams.resample_alg`. None
+/// keeps the reader defaults (bilinear warps, averaging downsampling); unknown names fail.
+pub fn parse_resample_alg(name: Option<&str>) -> Result<Option<gdal::raster::ResampleAlg>> {
+    match name.map(str::to_ascii_lowercase).as_deref() {
+        None => Ok(None),
+        Some("nearest") => Ok(Some(gdal::raster::ResampleAlg::NearestNeighbour)),
+        Some("bilinear") => Ok(Some(gdal::raster::ResampleAlg::Bilinear)),
+        Some("cubic") => Ok(Some(gdal::raster::ResampleAlg::Cubic)),
+        Some("lanczos") => Ok(Some(gdal::raster::ResampleAlg::Lanczos)),
+        Some(other) => Err(Error::InvalidArgument {
+            arg: "resample_alg",
+            value: format!("{} (expected nearest, bilinear, cubic or lanczos)", other),
+        }),
+    }
+}
+
+/// Summary of a bi-temporal change run
+#[derive(Debug, Clone)]
+pub struct ChangeReport {
+    /// Polarizations compared, one output band each
+    pub polarizations: Vec<String>,
+    pub width: usize,
+    pub height: usize,
+    pub geotransform: [f64; 6],
+    /// Mask threshold applied (None when no mask was written)
+    pub threshold: Option<f64>,
+    pub increased_pixels: usize,
+    pub decreased_pixels: usize,
+    /// Colormapped preview of the first band (None unless `ChangeOptions.colormap` is set)
+    pub preview_path: Option<PathBuf>,
+}
+
+/// Compute change between two SAFE products of the same track and write it to `output`.
+///
+/// Both products are warped to one grid: `params.target_crs` (`auto`/unset resolves the UTM
+/// zone of `before`), `params.pixel_spacing` (10 m when unset) and target-aligned pixels, then
+/// cropped to their overlap. `params.polarization` selects one band, or `Multiband` for the
+/// co-/cross-pol pair present in both products. AOI and resampling options are honoured.
+pub fn process_change_to_path(
+    before: &Path,
+    after: &Path,
+    output: &Path,
+    params: &ProcessingParams,
+    change: &ChangeOptions,
+) -> Result<ChangeReport> {
+    let target_crs = resolve_grid_crs(params, before)?;
+    let hint = match params.polarization {
+        Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv => {
+            pol_to_reader_hint(&params.polarization)
+        }
+        Polarization::Multiband => Some("all_pairs"),
+        Polarization::OP(_) => {
+            return Err(Error::InvalidArgument {
+                arg: "polarization",
+                value: format!("{} (change needs a single polarization or multiband)", params.polarization),
+            });
+        }
+    };
+    let resample_alg = parse_resample_alg(params.resample_alg.as_deref())?;
+    let read_opts = grid_read_options(params);
+
+    let open = |path: &Path| {
+        SafeReader::open_with_read_options(
+            path,
+            hint,
+            Some(TargetCrsArg::Custom(target_crs.clone())),
+            resample_alg,
+            None,
+            &read_opts,
+        )
+    };
+    let reader_before = open(before)?;
+    let reader_after = open(after)?;
+
+    let candidates: Vec<Vec<&str>> = match params.polarization {
+        Polarization::Vv => vec![vec!["VV"]],
+        Polarization::Vh => vec![vec!["VH"]],
+        Polarization::Hh => vec![vec!["HH"]],
+        Polarization::Hv => vec![vec!["HV"]],
+        _ => vec![vec!["VV", "VH"], vec!["HH", "HV"]],
+    };
+    let pols = candidates
+        .into_iter()
+        .find(|pols| {
+            pols.iter().all(|p| {
+                band_data(&reader_before, p).is_some() && band_data(&reader_after, p).is_some()
+            })
+        })
+        .ok_or_else(|| {
+            Error::Processing(format!(
+                "Products share no {} data; before: {}, after: {}",
+                params.polarization,
+                reader_before.get_available_polarizations(),
+                reader_after.get_available_polarizations()
+            ))
+        })?;
+
+    let meta_before = reader_before.metadata();
+    let meta_after = reader_after.metadata();
+    let (gt_before, gt_after) = meta_before
+        .geotransform
+        .zip(meta_after.geotransform)
+        .ok_or_else(|| Error::Processing("Warped products have no geotransform".to_string()))?;
+    let dims = |m: &SafeMetadata| (m.lines, m.samples);
+    let (win_before, win_after, gt) =
+        common_grid_window(gt_before, dims(meta_before), gt_after, dims(meta_after)).ok_or_else(
+            || Error::Processing("Products do not overlap on a common grid".to_string()),
+        )?;
+
+    let mut bands = Vec::with_capacity(pols.len());
+    for pol in &pols {
+        let b = crop_window(band_data(&reader_before, pol).expect("checked above"), win_before);
+        let a = crop_window(band_data(&reader_after, pol).expect("checked above"), win_after);
+        bands.push((pol.to_string(), change_arrays(&b, &a, change.method)));
+    }
+
+    let mut metadata = meta_after.clone();
+    metadata.geotransform = Some(gt);
+    metadata.samples = win_after.2;
+    metadata.lines = win_after.3;
+    metadata.polarizations = pols.iter().map(|p| p.to_string()).collect();
+
+    let summary = save_change_raster(&bands, output, &metadata, meta_before, change)
+        .map_err(|e| Error::external(e))?;
+
+    Ok(ChangeReport {
+        polarizations: metadata.polarizations,
+        width: win_after.2,
+        height: win_after.3,
+        geotransform: gt,
+        threshold: summary.threshold,
+        increased_pixels: summary.increased_pixels,
+        decreased_pixels: summary.decreased_pixels,
+        preview_path: summary.preview_path,
+    })
+}
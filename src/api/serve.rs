@@ -0,0 +1,420 @@
+//! Preview server for tile pyramids written by `process_safe_to_tiles`. A folder pyramid, an
+//! MBTiles or a PMTiles archive is served over HTTP as `/{z}/{x}/{y}.<ext>` tiles with a
+//! `/tilejson.json` manifest. PMTiles archives are also served whole at `/<file name>` with
+//! byte-range support, for clients that read the archive directly.
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::{debug, info, warn};
+
+use crate::error::{Error, Result};
+use crate::io::writers::tilearchive::{MbtilesReader, PmtilesReader, TilesetMetadata};
+use crate::types::{TileArchive, TileFormat};
+
+/// Request headers beyond this count are rejected
+const MAX_HEADERS: usize = 100;
+/// Content type of the raw PMTiles archive
+const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Where served tiles come from
+enum TileSource {
+    /// `{z}/{x}/{y}.<ext>` files next to a `tilejson.json`
+    Folder(PathBuf),
+    Mbtiles(MbtilesReader),
+    Pmtiles(PmtilesReader),
+}
+
+impl TileSource {
+    fn open(path: &Path) -> Result<(Self, TilesetMetadata)> {
+        if path.is_dir() {
+            let manifest = path.join("tilejson.json");
+            let json: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(&manifest)?).map_err(Error::external)?;
+            let meta = TilesetMetadata::from_tilejson(&json).ok_or_else(|| {
+                Error::Processing(format!("{:?} does not describe an image tile set", manifest))
+            })?;
+            return Ok((TileSource::Folder(path.to_path_buf()), meta));
+        }
+        match TileArchive::from_path(path) {
+            Some(TileArchive::Mbtiles) => {
+                let reader = MbtilesReader::open(path).map_err(Error::external)?;
+                let meta = reader.metadata().map_err(Error::external)?;
+                Ok((TileSource::Mbtiles(reader), meta))
+            }
+            Some(TileArchive::Pmtiles) => {
+                let reader = PmtilesReader::open(path).map_err(Error::external)?;
+                let meta = reader.metadata().clone();
+                Ok((TileSource::Pmtiles(reader), meta))
+            }
+            None => Err(Error::InvalidArgument {
+                arg: "tiles",
+                value: format!(
+                    "{} (expected a tile folder with tilejson.json, .mbtiles or .pmtiles)",
+                    path.display()
+                ),
+            }),
+        }
+    }
+
+    fn tile(&mut self, z: u8, x: u32, y: u32, ext: &str) -> Result<Option<Vec<u8>>> {
+        match self {
+            TileSource::Folder(root) => {
+                let path = root
+                    .join(z.to_string())
+                    .join(x.to_string())
+                    .join(format!("{}.{}", y, ext));
+                match std::fs::read(path) {
+                    Ok(data) => Ok(Some(data)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            TileSource::Mbtiles(reader) => reader.tile(z, x, y).map_err(Error::external),
+            TileSource::Pmtiles(reader) => reader.tile(z, x, y).map_err(Error::external),
+        }
+    }
+}
+
+/// Blocking HTTP server for one tile folder or archive. Each connection is handled on its own
+/// thread and answers a single GET or HEAD request.
+pub struct TileServer {
+    listener: TcpListener,
+    state: Arc<ServerState>,
+}
+
+struct ServerState {
+    source: Mutex<TileSource>,
+    metadata: TilesetMetadata,
+    /// URL path and file of a PMTiles archive that is also served whole
+    archive: Option<(String, PathBuf)>,
+    local_addr: SocketAddr,
+}
+
+/// Parsed request line and the headers the server uses
+struct Request {
+    method: String,
+    path: String,
+    host: Option<String>,
+    range: Option<String>,
+}
+
+impl TileServer {
+    /// Open the tiles at `path` (a folder written by `process_safe_to_tiles`, `.mbtiles` or
+    /// `.pmtiles`) and listen on `addr`, e.g. `127.0.0.1:8080` (port 0 picks a free port)
+    pub fn bind(path: &Path, addr: &str) -> Result<Self> {
+        let (source, metadata) = TileSource::open(path)?;
+        let archive = match (&source, path.file_name().and_then(|n| n.to_str())) {
+            (TileSource::Pmtiles(_), Some(name)) => Some((format!("/{}", name), path.into())),
+            _ => None,
+        };
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        info!("Serving {:?} ({} tiles) on http://{}", path, metadata.format, local_addr);
+        Ok(Self {
+            listener,
+            state: Arc::new(ServerState {
+                source: Mutex::new(source),
+                metadata,
+                archive,
+                local_addr,
+            }),
+        })
+    }
+
+    /// Address the server listens on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.state.local_addr
+    }
+
+    /// Description of the served tileset
+    pub fn metadata(&self) -> &TilesetMetadata {
+        &self.state.metadata
+    }
+
+    /// URL path of the whole PMTiles archive, when one is served
+    pub fn archive_path(&self) -> Option<&str> {
+        self.state.archive.as_ref().map(|(url, _)| url.as_str())
+    }
+
+    /// Accept connections until the listener fails
+    pub fn run(self) -> Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Connection failed: {}", e);
+                    continue;
+                }
+            };
+            let state = Arc::clone(&self.state);
+            std::thread::spawn(move || {
+                if let Err(e) = state.handle(stream) {
+                    debug!("Request failed: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ServerState {
+    fn handle(&self, mut stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let Some(request) = read_request(&stream)? else {
+            return respond(&mut stream, "400 Bad Request", "text/plain", b"Bad request\n", false);
+        };
+        if request.method != "GET" && request.method != "HEAD" {
+            let status = "405 Method Not Allowed";
+            return write_head(&mut stream, status, "text/plain", 0, &[("Allow", "GET, HEAD")]);
+        }
+        let head = request.method == "HEAD";
+        let path = request.path.split('?').next().unwrap_or_default();
+        debug!("{} {}", request.method, path);
+
+        if matches!(path, "/" | "/tilejson.json") {
+            let host = request.host.unwrap_or_else(|| self.local_addr.to_string());
+            let mut url = format!("http://{}/{{z}}/{{x}}/{{y}}.", host);
+            url.push_str(self.metadata.format.extension());
+            let body = serde_json::to_vec_pretty(&self.metadata.to_tilejson(&url))
+                .map_err(Error::external)?;
+            return respond(&mut stream, "200 OK", "application/json", &body, head);
+        }
+        if let Some((_, file)) = self.archive.as_ref().filter(|(url, _)| url == path) {
+            return send_file_range(&mut stream, file, request.range.as_deref(), head);
+        }
+        let format = self.metadata.format;
+        let tile = parse_tile_path(path)
+            .filter(|(_, _, _, ext)| TileFormat::from_extension(ext) == Some(format));
+        let data = match tile {
+            Some((z, x, y, ext)) => {
+                let mut source = self
+                    .source
+                    .lock()
+                    .map_err(|_| Error::Processing("tile source lock poisoned".to_string()))?;
+                source.tile(z, x, y, ext)?
+            }
+            None => None,
+        };
+        match data {
+            Some(data) => {
+                respond(&mut stream, "200 OK", self.metadata.format.mime_type(), &data, head)
+            }
+            None => respond(&mut stream, "404 Not Found", "text/plain", b"Not found\n", head),
+        }
+    }
+}
+
+/// Request line plus `Host` and `Range` headers; None for a malformed request
+fn read_request(stream: &TcpStream) -> Result<Option<Request>> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        host: None,
+        range: None,
+    };
+    for _ in 0..MAX_HEADERS {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            return Ok(Some(request));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = Some(value.trim().to_string());
+            if name.eq_ignore_ascii_case("host") {
+                request.host = value;
+            } else if name.eq_ignore_ascii_case("range") {
+                request.range = value;
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Status line and headers of a response with a `content_length` byte body
+fn write_head(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    content_length: u64,
+    headers: &[(&str, &str)],
+) -> Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n",
+        status, content_type, content_length
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    Ok(())
+}
+
+/// Complete response; `head` keeps the headers of `body` but leaves it out
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+    head: bool,
+) -> Result<()> {
+    write_head(stream, status, content_type, body.len() as u64, &[])?;
+    if !head {
+        stream.write_all(body)?;
+    }
+    Ok(())
+}
+
+/// Whole `file`, or the byte range requested by a `Range` header (206 Partial Content)
+fn send_file_range(
+    stream: &mut TcpStream,
+    file: &Path,
+    range: Option<&str>,
+    head: bool,
+) -> Result<()> {
+    let mut file = File::open(file)?;
+    let len = file.metadata()?.len();
+    let (start, end) = match range.map(|r| parse_range(r, len)) {
+        None => {
+            write_head(stream, "200 OK", OCTET_STREAM, len, &[("Accept-Ranges", "bytes")])?;
+            (0, len)
+        }
+        Some(Some((start, end))) => {
+            let content_range = format!("bytes {}-{}/{}", start, end - 1, len);
+            let headers = [("Accept-Ranges", "bytes"), ("Content-Range", &content_range)];
+            write_head(stream, "206 Partial Content", OCTET_STREAM, end - start, &headers)?;
+            (start, end)
+        }
+        Some(None) => {
+            let content_range = format!("bytes */{}", len);
+            let status = "416 Range Not Satisfiable";
+            write_head(stream, status, OCTET_STREAM, 0, &[("Content-Range", &content_range)])?;
+            return Ok(());
+        }
+    };
+    if !head {
+        file.seek(SeekFrom::Start(start))?;
+        std::io::copy(&mut file.take(end - start), stream)?;
+    }
+    Ok(())
+}
+
+/// `z`, `x`, `y` and extension of a `/{z}/{x}/{y}.<ext>` path
+fn parse_tile_path(path: &str) -> Option<(u8, u32, u32, &str)> {
+    let mut parts = path.strip_prefix('/')?.split('/');
+    let (z, x, last) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let (y, ext) = last.split_once('.')?;
+    Some((z.parse().ok()?, x.parse().ok()?, y.parse().ok()?, ext))
+}
+
+/// Half-open byte range `[start, end)` of a single-range `bytes=` header over `len` bytes;
+/// None when it is malformed or not satisfiable
+fn parse_range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let (first, last) = header.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (first.trim(), last.trim()) {
+        ("", suffix) => {
+            let n: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(n), len)
+        }
+        (start, "") => (start.parse().ok()?, len),
+        (start, end) => {
+            let end = end.parse::<u64>().ok()?.saturating_add(1);
+            (start.parse().ok()?, end.min(len))
+        }
+    };
+    (start < end).then_some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_paths() {
+        assert_eq!(parse_tile_path("/3/4/5.png"), Some((3, 4, 5, "png")));
+        assert_eq!(parse_tile_path("/12/3423/1763.webp"), Some((12, 3423, 1763, "webp")));
+        assert_eq!(parse_tile_path("/3/4/5"), None);
+        assert_eq!(parse_tile_path("/3/4/5.png/6"), None);
+        assert_eq!(parse_tile_path("/a/4/5.png"), None);
+        assert_eq!(parse_tile_path("/300/4/5.png"), None);
+        assert_eq!(parse_tile_path("/../4/5.png"), None);
+    }
+
+    #[test]
+    fn byte_ranges() {
+        assert_eq!(parse_range("bytes=0-126", 1000), Some((0, 127)));
+        assert_eq!(parse_range("bytes=900-", 1000), Some((900, 1000)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 1000)));
+        assert_eq!(parse_range("bytes=990-2000", 1000), Some((990, 1000)));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=5-2", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), None);
+    }
+
+    fn get(addr: SocketAddr, request: &str) -> (String, Vec<u8>) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8(response[..split].to_vec()).unwrap();
+        (head, response[split + 4..].to_vec())
+    }
+
+    #[test]
+    fn folder_pyramids_are_served() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = TilesetMetadata {
+            name: "S1A".to_string(),
+            description: String::new(),
+            attribution: "ESA".to_string(),
+            format: TileFormat::Png,
+            min_zoom: 0,
+            max_zoom: 1,
+            bounds: [10.0, 45.0, 12.0, 46.0],
+            center: (11.0, 45.5, 0),
+        };
+        let tilejson = meta.to_tilejson("{z}/{x}/{y}.png");
+        std::fs::write(dir.path().join("tilejson.json"), tilejson.to_string()).unwrap();
+        std::fs::create_dir_all(dir.path().join("1/1")).unwrap();
+        std::fs::write(dir.path().join("1/1/0.png"), b"tile").unwrap();
+
+        let server = TileServer::bind(dir.path(), "127.0.0.1:0").unwrap();
+        let addr = server.local_addr();
+        assert_eq!(server.archive_path(), None);
+        std::thread::spawn(move || server.run());
+
+        let (head, body) = get(addr, "GET /1/1/0.png HTTP/1.1\r\nHost: tiles\r\n\r\n");
+        assert!(head.starts_with("HTTP/1.1 200 OK"), "{}", head);
+        assert!(head.contains("Content-Type: image/png"));
+        assert_eq!(body, b"tile");
+
+        let (head, _) = get(addr, "GET /1/0/0.png HTTP/1.1\r\n\r\n");
+        assert!(head.starts_with("HTTP/1.1 404"), "{}", head);
+        let (head, _) = get(addr, "GET /1/1/0.jpg HTTP/1.1\r\n\r\n");
+        assert!(head.starts_with("HTTP/1.1 404"), "{}", head);
+        let (head, _) = get(addr, "POST /1/1/0.png HTTP/1.1\r\n\r\n");
+        assert!(head.starts_with("HTTP/1.1 405"), "{}", head);
+
+        let (head, body) = get(addr, "GET /tilejson.json HTTP/1.1\r\nHost: tiles:80\r\n\r\n");
+        assert!(head.starts_with("HTTP/1.1 200 OK"), "{}", head);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["tiles"][0], "http://tiles:80/{z}/{x}/{y}.png");
+        assert_eq!(json["attribution"], "ESA");
+    }
+}
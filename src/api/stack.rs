@@ -0,0 +1,493 @@
+//! Multi-temporal stacks: products are grouped by relative orbit and polarization, warped
+//! onto one target-aligned grid and written as a time series (one band per date) together
+//! with per-pixel temporal statistics.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use gdal::raster::Buffer;
+use gdal::{Dataset, DriverManager, Metadata};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::{band_data, grid_read_options, parse_resample_alg, resolve_grid_crs};
+use crate::core::params::ProcessingParams;
+use crate::core::processing::temporal::temporal_stats;
+use crate::error::{Error, Result};
+use crate::io::sentinel1::{SafeMetadata, SafeReader, TargetCrsArg};
+use crate::io::writers::tiff::write_tiff_bands_f32;
+use crate::types::{Polarization, StackFormat, TemporalStat};
+
+/// Rows read per block when computing temporal statistics
+const STATS_BLOCK_ROWS: usize = 256;
+
+/// Options for stack building on top of the shared `ProcessingParams`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackOptions {
+    pub format: StackFormat,
+    /// Temporal statistics written to `<stack>_stats.tif`; empty disables it
+    pub stats: Vec<TemporalStat>,
+    /// Groups with fewer dates are skipped
+    pub min_dates: usize,
+}
+
+impl Default for StackOptions {
+    fn default() -> Self {
+        Self {
+            format: StackFormat::Gtiff,
+            stats: vec![
+                TemporalStat::Median,
+                TemporalStat::Mean,
+                TemporalStat::Std,
+                TemporalStat::Min,
+                TemporalStat::Max,
+                TemporalStat::Cv,
+            ],
+            min_dates: 2,
+        }
+    }
+}
+
+/// One written stack
+#[derive(Debug, Clone, Serialize)]
+pub struct StackReport {
+    /// Output base name, e.g. `stack_r044_vv`
+    pub name: String,
+    pub relative_orbit: Option<u32>,
+    pub polarization: String,
+    /// Acquisition start per band, in band order (oldest first)
+    pub dates: Vec<String>,
+    pub products: Vec<String>,
+    pub width: usize,
+    pub height: usize,
+    pub geotransform: [f64; 6],
+    pub crs: String,
+    pub stack_path: PathBuf,
+    pub stats_path: Option<PathBuf>,
+}
+
+/// Group key: relative orbit and polarization ("VV", "VH", ...)
+type GroupKey = (Option<u32>, String);
+
+/// One date warped onto the grid, kept in a temporary GeoTIFF until the stack is assembled
+struct WarpedDate {
+    path: PathBuf,
+    meta: SafeMetadata,
+    geotransform: [f64; 6],
+    cols: usize,
+    rows: usize,
+}
+
+/// Build stacks from every SAFE product directly under `input_dir`
+pub fn build_stacks_from_dir(
+    input_dir: &Path,
+    output_dir: &Path,
+    params: &ProcessingParams,
+    options: &StackOptions,
+) -> Result<Vec<StackReport>> {
+    let inputs: Vec<PathBuf> = super::iterate_safe_products(input_dir)?.collect();
+    build_stacks(&inputs, output_dir, params, options)
+}
+
+/// Group `inputs` by relative orbit and polarization and write one stack per group into
+/// `output_dir`. `params.polarization` selects a single polarization, or `Multiband` for
+/// every polarization present. The grid follows `params.target_crs` (UTM of the first
+/// product when `auto`/unset), `params.pixel_spacing` (10 m when unset) and `params.aoi`.
+/// Values are linear intensity with NaN nodata.
+pub fn build_stacks(
+    inputs: &[PathBuf],
+    output_dir: &Path,
+    params: &ProcessingParams,
+    options: &StackOptions,
+) -> Result<Vec<StackReport>> {
+    let wanted: &[&str] = match params.polarization {
+        Polarization::Vv => &["VV"],
+        Polarization::Vh => &["VH"],
+        Polarization::Hh => &["HH"],
+        Polarization::Hv => &["HV"],
+        Polarization::Multiband => &["VV", "VH", "HH", "HV"],
+        Polarization::OP(_) => {
+            return Err(Error::InvalidArgument {
+                arg: "polarization",
+                value: format!("{} (stacks need a single polarization or multiband)", params.polarization),
+            });
+        }
+    };
+
+    let mut groups: BTreeMap<GroupKey, Vec<(PathBuf, SafeMetadata)>> = BTreeMap::new();
+    for path in inputs {
+        let meta = match SafeReader::read_metadata(path) {
+            Ok(meta) if meta.product_type.eq_ignore_ascii_case("GRD") => meta,
+            Ok(meta) => {
+                warn!("Skipping {:?}: unsupported product type {}", path, meta.product_type);
+                continue;
+            }
+            Err(e) => {
+                warn!("Skipping {:?}: {}", path, e);
+                continue;
+            }
+        };
+        for pol in wanted {
+            if meta.polarizations.iter().any(|p| p.eq_ignore_ascii_case(pol)) {
+                groups
+                    .entry((meta.relative_orbit, pol.to_string()))
+                    .or_default()
+                    .push((path.clone(), meta.clone()));
+            }
+        }
+    }
+
+    std::fs::create_dir_all(output_dir)?;
+    let mut reports = Vec::new();
+    for ((relative_orbit, pol), mut products) in groups {
+        if products.len() < options.min_dates.max(1) {
+            info!(
+                "Skipping track {:?} {}: {} date(s), {} required",
+                relative_orbit,
+                pol,
+                products.len(),
+                options.min_dates
+            );
+            continue;
+        }
+        products.sort_by(|a, b| a.1.acquisition_start.cmp(&b.1.acquisition_start));
+        let name = match relative_orbit {
+            Some(r) => format!("stack_r{:03}_{}", r, pol.to_lowercase()),
+            None => format!("stack_unknown_{}", pol.to_lowercase()),
+        };
+        info!("Building {} from {} products", name, products.len());
+        reports.push(build_one_stack(
+            &name,
+            relative_orbit,
+            &pol,
+            &products,
+            output_dir,
+            params,
+            options,
+        )?);
+    }
+    Ok(reports)
+}
+
+/// Compact date label for file names: 2024-01-13T05:12:33.123 -> 20240113T051233
+pub(super) fn date_label(acquisition_start: &str) -> String {
+    acquisition_start
+        .chars()
+        .take(19)
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+fn build_one_stack(
+    name: &str,
+    relative_orbit: Option<u32>,
+    pol: &str,
+    products: &[(PathBuf, SafeMetadata)],
+    output_dir: &Path,
+    params: &ProcessingParams,
+    options: &StackOptions,
+) -> Result<StackReport> {
+    let crs = resolve_grid_crs(params, &products[0].0)?;
+    let read_opts = grid_read_options(params);
+    let resample_alg = parse_resample_alg(params.resample_alg.as_deref())?;
+    let tmp = tempfile::tempdir()?;
+
+    // Warp each date on its own; only one date is held in memory at a time
+    let mut dates: Vec<WarpedDate> = Vec::with_capacity(products.len());
+    for (i, (path, _)) in products.iter().enumerate() {
+        let reader = SafeReader::open_with_read_options(
+            path,
+            Some(match pol {
+                "VH" => "vh",
+                "HH" => "hh",
+                "HV" => "hv",
+                _ => "vv",
+            }),
+            Some(TargetCrsArg::Custom(crs.clone())),
+            resample_alg,
+            None,
+            &read_opts,
+        )?;
+        let data = band_data(&reader, pol)
+            .ok_or_else(|| Error::Processing(format!("{:?} has no {} data", path, pol)))?;
+        let meta = reader.metadata().clone();
+        let geotransform = meta
+            .geotransform
+            .ok_or_else(|| Error::Processing(format!("Warped {:?} has no geotransform", path)))?;
+        let (rows, cols) = data.dim();
+        let std_data = data.as_standard_layout();
+        let slice = std_data.as_slice().expect("standard layout array is contiguous");
+        let tmp_path = tmp.path().join(format!("{:03}.tif", i));
+        let mut ds =
+            write_tiff_bands_f32(&tmp_path, cols, rows, &[slice]).map_err(Error::external)?;
+        ds.set_geo_transform(&geotransform).map_err(Error::external)?;
+        ds.set_projection(meta.projection.as_deref().unwrap_or(&crs))
+            .map_err(Error::external)?;
+        dates.push(WarpedDate {
+            path: tmp_path,
+            meta,
+            geotransform,
+            cols,
+            rows,
+        });
+    }
+
+    // Union of the date extents; target-aligned pixels put every date on whole-pixel offsets
+    let (px, py) = (dates[0].geotransform[1], dates[0].geotransform[5]);
+    let tol = 1e-6 * px.abs().max(py.abs());
+    if dates.iter().any(|d| {
+        (d.geotransform[1] - px).abs() > tol
+            || (d.geotransform[5] - py).abs() > tol
+            || d.geotransform[2] != 0.0
+            || d.geotransform[4] != 0.0
+    }) {
+        return Err(Error::Processing(format!(
+            "{}: warped dates do not share a north-up pixel grid",
+            name
+        )));
+    }
+    let x0 = dates.iter().map(|d| d.geotransform[0]).fold(f64::INFINITY, f64::min);
+    let y0 = dates.iter().map(|d| d.geotransform[3]).fold(f64::NEG_INFINITY, f64::max);
+    let x1 = dates
+        .iter()
+        .map(|d| d.geotransform[0] + d.cols as f64 * px)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let y1 = dates
+        .iter()
+        .map(|d| d.geotransform[3] + d.rows as f64 * py)
+        .fold(f64::INFINITY, f64::min);
+    let cols = ((x1 - x0) / px).round() as usize;
+    let rows = ((y1 - y0) / py).round() as usize;
+    let gt = [x0, px, 0.0, y0, 0.0, py];
+    let offset = |d: &WarpedDate| {
+        (
+            ((d.geotransform[0] - x0) / px).round() as usize,
+            ((d.geotransform[3] - y0) / py).round() as usize,
+        )
+    };
+    let projection = dates[0].meta.projection.clone().unwrap_or_else(|| crs.clone());
+    info!("{}: {} dates on a {}x{} grid", name, dates.len(), cols, rows);
+
+    let stack_path = match options.format {
+        StackFormat::Gtiff => {
+            let path = output_dir.join(format!("{}.tif", name));
+            write_stack_gtiff(&path, &dates, cols, rows, gt, &projection, offset)?;
+            path
+        }
+        StackFormat::Vrt => {
+            let path = output_dir.join(format!("{}.vrt", name));
+            write_stack_vrt(&path, name, &dates, cols, rows, gt, &projection, offset)?;
+            path
+        }
+    };
+
+    let stats_path = if options.stats.is_empty() || dates.len() < 2 {
+        None
+    } else {
+        let path = output_dir.join(format!("{}_stats.tif", name));
+        write_temporal_stats(&stack_path, &path, dates.len(), &options.stats)?;
+        Some(path)
+    };
+
+    let report = StackReport {
+        name: name.to_string(),
+        relative_orbit,
+        polarization: pol.to_string(),
+        dates: dates.iter().map(|d| d.meta.acquisition_start.clone()).collect(),
+        products: dates
+            .iter()
+            .map(|d| d.meta.product_id.clone().unwrap_or_default())
+            .collect(),
+        width: cols,
+        height: rows,
+        geotransform: gt,
+        crs,
+        stack_path,
+        stats_path,
+    };
+    let manifest = serde_json::json!({
+        "stack": report,
+        "format": options.format.to_string(),
+        "units": "linear intensity",
+        "stats": options.stats.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+        "platforms": dates.iter().map(|d| d.meta.platform.clone()).collect::<Vec<_>>(),
+        "pass_direction": dates[0].meta.pass_direction,
+    });
+    std::fs::write(
+        output_dir.join(format!("{}.json", name)),
+        serde_json::to_string_pretty(&manifest).map_err(Error::external)?,
+    )?;
+    Ok(report)
+}
+
+/// Band metadata shared by the GTiff and VRT layouts
+fn date_items(meta: &SafeMetadata) -> [(&'static str, String); 3] {
+    [
+        ("ACQUISITION_START", meta.acquisition_start.clone()),
+        ("PRODUCT_ID", meta.product_id.clone().unwrap_or_default()),
+        ("PLATFORM", meta.platform.clone()),
+    ]
+}
+
+fn read_date(d: &WarpedDate) -> Result<Vec<f32>> {
+    let ds = Dataset::open(&d.path).map_err(Error::external)?;
+    let buf: Buffer<f32> = ds
+        .rasterband(1)
+        .and_then(|b| b.read_as((0, 0), (d.cols, d.rows), (d.cols, d.rows), None))
+        .map_err(Error::external)?;
+    Ok(buf.data().to_vec())
+}
+
+fn write_stack_gtiff(
+    path: &Path,
+    dates: &[WarpedDate],
+    cols: usize,
+    rows: usize,
+    gt: [f64; 6],
+    projection: &str,
+    offset: impl Fn(&WarpedDate) -> (usize, usize),
+) -> Result<()> {
+    let driver = DriverManager::get_driver_by_name("GTiff").map_err(Error::external)?;
+    let mut ds = driver
+        .create_with_band_type::<f32, _>(path, cols, rows, dates.len())
+        .map_err(Error::external)?;
+    ds.set_geo_transform(&gt).map_err(Error::external)?;
+    ds.set_projection(projection).map_err(Error::external)?;
+    for (i, d) in dates.iter().enumerate() {
+        let mut band = ds.rasterband(i + 1).map_err(Error::external)?;
+        band.set_no_data_value(Some(f64::NAN)).map_err(Error::external)?;
+        band.fill(f64::NAN, None).map_err(Error::external)?;
+        band.set_description(&d.meta.acquisition_start).map_err(Error::external)?;
+        for (k, v) in date_items(&d.meta) {
+            band.set_metadata_item(k, &v, "").map_err(Error::external)?;
+        }
+        let (x, y) = offset(d);
+        let mut buf = Buffer::new((d.cols, d.rows), read_date(d)?);
+        band.write((x as isize, y as isize), (d.cols, d.rows), &mut buf)
+            .map_err(Error::external)?;
+    }
+    ds.set_metadata_item("STACK_DATES", &dates.len().to_string(), "")
+        .map_err(Error::external)?;
+    info!("Wrote stack {:?}", path);
+    Ok(())
+}
+
+/// One COG per date under `<name>/`, placed into the stack grid by a VRT
+#[allow(clippy::too_many_arguments)]
+fn write_stack_vrt(
+    path: &Path,
+    name: &str,
+    dates: &[WarpedDate],
+    cols: usize,
+    rows: usize,
+    gt: [f64; 6],
+    projection: &str,
+    offset: impl Fn(&WarpedDate) -> (usize, usize),
+) -> Result<()> {
+    let dir = path.with_file_name(name);
+    std::fs::create_dir_all(&dir)?;
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+
+    let mut xml = format!(
+        "<VRTDataset rasterXSize=\"{}\" rasterYSize=\"{}\">\n  <SRS>{}</SRS>\n  <GeoTransform>{:.12}, {:.12}, {:.12}, {:.12}, {:.12}, {:.12}</GeoTransform>\n",
+        cols,
+        rows,
+        escape(projection),
+        gt[0],
+        gt[1],
+        gt[2],
+        gt[3],
+        gt[4],
+        gt[5]
+    );
+    for (i, d) in dates.iter().enumerate() {
+        let file_name = format!("{}.tif", date_label(&d.meta.acquisition_start));
+        let cog = dir.join(&file_name);
+        let status = Command::new("gdal_translate")
+            .args(["-q", "-of", "COG", "-co", "COMPRESS=DEFLATE", "-co", "PREDICTOR=YES"])
+            .arg(&d.path)
+            .arg(&cog)
+            .status()
+            .map_err(|e| Error::External(format!("gdal_translate exec error: {}", e)))?;
+        if !status.success() {
+            return Err(Error::External(format!("gdal_translate failed for {:?}", cog)));
+        }
+
+        let (x, y) = offset(d);
+        xml.push_str(&format!(
+            "  <VRTRasterBand dataType=\"Float32\" band=\"{}\">\n    <Description>{}</Description>\n    <NoDataValue>nan</NoDataValue>\n    <Metadata>\n",
+            i + 1,
+            escape(&d.meta.acquisition_start)
+        ));
+        for (k, v) in date_items(&d.meta) {
+            xml.push_str(&format!("      <MDI key=\"{}\">{}</MDI>\n", k, escape(&v)));
+        }
+        xml.push_str(&format!(
+            "    </Metadata>\n    <SimpleSource>\n      <SourceFilename relativeToVRT=\"1\">{}/{}</SourceFilename>\n      <SourceBand>1</SourceBand>\n      <SrcRect xOff=\"0\" yOff=\"0\" xSize=\"{}\" ySize=\"{}\" />\n      <DstRect xOff=\"{}\" yOff=\"{}\" xSize=\"{}\" ySize=\"{}\" />\n    </SimpleSource>\n  </VRTRasterBand>\n",
+            escape(name),
+            escape(&file_name),
+            d.cols,
+            d.rows,
+            x,
+            y,
+            d.cols,
+            d.rows
+        ));
+    }
+    xml.push_str("</VRTDataset>\n");
+    std::fs::write(path, xml)?;
+    info!("Wrote stack {:?} ({} COGs in {:?})", path, dates.len(), dir);
+    Ok(())
+}
+
+/// Temporal statistics over all bands of `stack`, computed in row blocks
+fn write_temporal_stats(
+    stack: &Path,
+    output: &Path,
+    n_dates: usize,
+    stats: &[TemporalStat],
+) -> Result<()> {
+    let src = Dataset::open(stack).map_err(Error::external)?;
+    let (cols, rows) = src.raster_size();
+    let driver = DriverManager::get_driver_by_name("GTiff").map_err(Error::external)?;
+    let mut out = driver
+        .create_with_band_type::<f32, _>(output, cols, rows, stats.len())
+        .map_err(Error::external)?;
+    if let Ok(gt) = src.geo_transform() {
+        out.set_geo_transform(&gt).map_err(Error::external)?;
+    }
+    out.set_projection(&src.projection()).map_err(Error::external)?;
+    for (k, stat) in stats.iter().enumerate() {
+        let mut band = out.rasterband(k + 1).map_err(Error::external)?;
+        band.set_no_data_value(Some(f64::NAN)).map_err(Error::external)?;
+        band.set_description(&stat.to_string()).map_err(Error::external)?;
+    }
+
+    for y in (0..rows).step_by(STATS_BLOCK_ROWS) {
+        let h = STATS_BLOCK_ROWS.min(rows - y);
+        let mut blocks = Vec::with_capacity(n_dates);
+        for b in 1..=n_dates {
+            let buf: Buffer<f32> = src
+                .rasterband(b)
+                .and_then(|band| band.read_as((0, y as isize), (cols, h), (cols, h), None))
+                .map_err(Error::external)?;
+            blocks.push(buf.data().to_vec());
+        }
+        let slices: Vec<&[f32]> = blocks.iter().map(|b| b.as_slice()).collect();
+        for (k, values) in temporal_stats(&slices, stats).into_iter().enumerate() {
+            let mut buf = Buffer::new((cols, h), values);
+            out.rasterband(k + 1)
+                .and_then(|mut band| band.write((0, y as isize), (cols, h), &mut buf))
+                .map_err(Error::external)?;
+        }
+    }
+    out.set_metadata_item("TEMPORAL_DATES", &n_dates.to_string(), "")
+        .map_err(Error::external)?;
+    info!("Wrote temporal statistics {:?}", output);
+    Ok(())
+}
@@ -0,0 +1,484 @@
+//! Web Mercator XYZ tile pyramids: a product is warped to EPSG:3857, autoscaled once over the
+//! whole scene and cut into `{z}/{x}/{y}` tiles, written as a folder with a TileJSON manifest
+//! or packed into one MBTiles/PMTiles archive.
+use std::path::{Path, PathBuf};
+
+use ndarray::Array2;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::{
+    autoscale_from_reference, operation_data, parse_resample_alg, reader_hint_for_params,
+    validate_product_params,
+};
+use crate::core::params::ProcessingParams;
+use crate::core::processing::autoscale::{
+    AutoscaleParams, autoscale_db_image_tamed_synrgb_u8_with_params,
+};
+use crate::core::processing::colormap::{ColormapOptions, apply_palette};
+use crate::core::processing::expr::BandExpr;
+use crate::core::processing::pipeline::{
+    process_operation_data_pipeline_with_window, process_scalar_data_pipeline_with_window,
+};
+use crate::core::processing::synthetic_rgb::create_synthetic_rgb_by_mode_and_strategy;
+use crate::core::processing::tiles::{
+    MAX_TILE_ZOOM, MercatorImage, Tile, mercator_to_lonlat, native_zoom, overview_zoom,
+};
+use crate::error::{Error, Result};
+use crate::io::sentinel1::{SafeReader, TargetCrsArg};
+use crate::io::writers::encode::EncodeOptions;
+use crate::io::writers::jpeg::{write_gray_jpeg_with_options, write_rgb_jpeg_with_options};
+use crate::io::writers::png::{write_gray_png, write_rgb_png};
+use crate::io::writers::tilearchive::{
+    MbtilesWriter, PmtilesWriter, TilesetMetadata, pmtiles_tile_id,
+};
+use crate::io::writers::webp::{write_gray_webp, write_rgb_webp};
+use crate::types::{
+    AutoscaleStrategy, BitDepth, Polarization, ProcessingOperation, ProductMode, TileArchive,
+    TileFormat,
+};
+
+/// CRS of the XYZ tile grid
+const TILE_CRS: &str = "EPSG:3857";
+
+/// Options for tile pyramids on top of the shared `ProcessingParams`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TileOptions {
+    pub format: TileFormat,
+    /// Shallowest zoom; None picks the deepest zoom at which the scene fits in one tile
+    pub min_zoom: Option<u8>,
+    /// Deepest zoom; None picks the first zoom at least as fine as the warped pixels
+    pub max_zoom: Option<u8>,
+    /// Tile side in pixels (256 or 512)
+    pub tile_size: usize,
+    /// Prefix of the tile URL template in `tilejson.json` (e.g. `https://example.com/s1`);
+    /// None writes a template relative to the manifest
+    pub base_url: Option<String>,
+    /// Pack the tiles into one archive file instead of a folder pyramid
+    pub archive: Option<TileArchive>,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        Self {
+            format: TileFormat::Png,
+            min_zoom: None,
+            max_zoom: None,
+            tile_size: 256,
+            base_url: None,
+            archive: None,
+        }
+    }
+}
+
+impl TileOptions {
+    pub fn validate(&self) -> Result<()> {
+        if !matches!(self.tile_size, 256 | 512) {
+            return Err(Error::InvalidArgument {
+                arg: "tile_size",
+                value: format!("{} (must be 256 or 512)", self.tile_size),
+            });
+        }
+        for (arg, zoom) in [("min_zoom", self.min_zoom), ("max_zoom", self.max_zoom)] {
+            match zoom {
+                Some(z) if z > MAX_TILE_ZOOM => {
+                    return Err(Error::InvalidArgument {
+                        arg,
+                        value: format!("{} (must be at most {})", z, MAX_TILE_ZOOM),
+                    });
+                }
+                _ => {}
+            }
+        }
+        match (self.min_zoom, self.max_zoom) {
+            (Some(min), Some(max)) if min > max => Err(Error::InvalidArgument {
+                arg: "min_zoom",
+                value: format!("{} (greater than max_zoom {})", min, max),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Summary of a written tile pyramid
+#[derive(Debug, Clone, Serialize)]
+pub struct TileReport {
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    /// Tiles written per zoom level, from `min_zoom` up; empty tiles are skipped
+    pub tiles_per_zoom: Vec<usize>,
+    /// `[west, south, east, north]` in degrees
+    pub bounds: [f64; 4],
+    /// `tilejson.json` of a folder pyramid, or the archive file
+    pub output: PathBuf,
+}
+
+/// Autoscaled scene ready for tiling
+struct ScaledImage {
+    cols: usize,
+    rows: usize,
+    /// 1 for gray, 3 for interleaved RGB (colormaps and synthetic RGB)
+    channels: usize,
+    data: Vec<u8>,
+    alpha: Vec<u8>,
+}
+
+/// Where rendered tiles go
+enum TileSink {
+    Directory(PathBuf),
+    Mbtiles(MbtilesWriter),
+    Pmtiles(PmtilesWriter),
+}
+
+/// Cut `input` into an XYZ tile pyramid at `output`: a folder of `{z}/{x}/{y}.{ext}` tiles plus
+/// `tilejson.json`, or the archive file when `options.archive` is set.
+///
+/// The product is warped to EPSG:3857 (`params.target_crs` is ignored) at
+/// `params.pixel_spacing` when set, autoscaled once over the whole scene so tiles match across
+/// zoom levels, then resampled into tiles. Polarizations, operations, expressions, colormaps
+/// and multiband synthetic RGB follow `params` as for single images; nodata is transparent
+/// in PNG and WebP tiles and black in JPEG tiles. Tiles without any data are not written.
+/// Archives hold the encoded tiles of one zoom level in memory before adding them.
+pub fn process_safe_to_tiles(
+    input: &Path,
+    output: &Path,
+    params: &ProcessingParams,
+    options: &TileOptions,
+) -> Result<TileReport> {
+    options.validate()?;
+    validate_product_params(params)?;
+    if params.product != ProductMode::Image {
+        return Err(Error::InvalidArgument {
+            arg: "product",
+            value: format!("{} (tiles are rendered from image products)", params.product),
+        });
+    }
+    match params.target_crs.as_deref() {
+        Some(t) if !t.eq_ignore_ascii_case(TILE_CRS) && !t.eq_ignore_ascii_case("auto") => {
+            warn!("Tiles are always cut in {}; target CRS {} ignored", TILE_CRS, t);
+        }
+        _ => {}
+    }
+
+    let reader = SafeReader::open_with_read_options(
+        input,
+        reader_hint_for_params(params)?,
+        Some(TargetCrsArg::Custom(TILE_CRS.to_string())),
+        parse_resample_alg(params.resample_alg.as_deref())?,
+        None,
+        &params.read_options(),
+    )?;
+    let meta = reader.metadata();
+    let geotransform = meta
+        .geotransform
+        .ok_or_else(|| Error::Processing(format!("Warped {:?} has no geotransform", input)))?;
+    if geotransform[2] != 0.0 || geotransform[4] != 0.0 {
+        return Err(Error::Processing(format!("Warped {:?} is not north-up", input)));
+    }
+
+    let strategy = match params.autoscale_reference.as_deref() {
+        Some(reference) => autoscale_from_reference(reference)?,
+        None => params.autoscale,
+    };
+    let image = autoscaled_image(&reader, params, strategy)?;
+    let mercator = MercatorImage {
+        cols: image.cols,
+        rows: image.rows,
+        channels: image.channels,
+        data: &image.data,
+        alpha: &image.alpha,
+        geotransform,
+    };
+
+    let bounds = mercator.bounds();
+    let max_zoom = options
+        .max_zoom
+        .unwrap_or_else(|| native_zoom(geotransform[1], options.tile_size));
+    let min_zoom = options.min_zoom.unwrap_or_else(|| overview_zoom(bounds).min(max_zoom));
+    if min_zoom > max_zoom {
+        return Err(Error::InvalidArgument {
+            arg: "min_zoom",
+            value: format!("{} (greater than max_zoom {})", min_zoom, max_zoom),
+        });
+    }
+    info!(
+        "Tiling {}x{} px into {:?}, zoom {}..={}",
+        image.cols, image.rows, output, min_zoom, max_zoom
+    );
+
+    let (west, south) = mercator_to_lonlat(bounds[0], bounds[1]);
+    let (east, north) = mercator_to_lonlat(bounds[2], bounds[3]);
+    let name = meta.product_id.clone().unwrap_or_else(|| {
+        input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+    });
+    let content = params
+        .expression
+        .clone()
+        .unwrap_or_else(|| params.polarization.to_string());
+    let tileset = TilesetMetadata {
+        name,
+        description: format!(
+            "{} {} acquired {}",
+            meta.platform, content, meta.acquisition_start
+        ),
+        attribution: format!(
+            "Contains modified Copernicus Sentinel data {}",
+            meta.acquisition_start.get(..4).unwrap_or_default()
+        ),
+        format: options.format,
+        min_zoom,
+        max_zoom,
+        bounds: [west, south, east, north],
+        center: ((west + east) / 2.0, (south + north) / 2.0, min_zoom),
+    };
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut sink = match options.archive {
+        None => TileSink::Directory(output.to_path_buf()),
+        Some(TileArchive::Mbtiles) => {
+            TileSink::Mbtiles(MbtilesWriter::create(output).map_err(Error::external)?)
+        }
+        Some(TileArchive::Pmtiles) => {
+            TileSink::Pmtiles(PmtilesWriter::create(output).map_err(Error::external)?)
+        }
+    };
+    // Archive tiles are encoded through the regular writers into this scratch directory
+    let scratch = tempfile::tempdir()?;
+
+    let ext = options.format.extension();
+    let mut tiles_per_zoom = Vec::with_capacity((max_zoom - min_zoom) as usize + 1);
+    for z in min_zoom..=max_zoom {
+        let indices = mercator.tile_indices(z);
+        let written = match &mut sink {
+            // Tiles are rendered and written one by one so a zoom level is never held in memory
+            TileSink::Directory(dir) => indices
+                .into_par_iter()
+                .map(|(x, y)| -> Result<usize> {
+                    let Some(tile) = mercator.render_tile(z, x, y, options.tile_size) else {
+                        return Ok(0);
+                    };
+                    let tile_dir = dir.join(z.to_string()).join(x.to_string());
+                    std::fs::create_dir_all(&tile_dir)?;
+                    write_tile(
+                        &tile_dir.join(format!("{}.{}", y, ext)),
+                        &tile,
+                        image.channels,
+                        options,
+                        &params.encode,
+                    )
+                    .map_err(Error::external)?;
+                    Ok(1)
+                })
+                .try_reduce(|| 0, |a, b| Ok(a + b))?,
+            archive => {
+                let mut encoded: Vec<(u32, u32, Vec<u8>)> = indices
+                    .into_par_iter()
+                    .filter_map(|(x, y)| mercator.render_tile(z, x, y, options.tile_size))
+                    .map(|tile| -> Result<(u32, u32, Vec<u8>)> {
+                        let name = format!("{}_{}_{}.{}", z, tile.x, tile.y, ext);
+                        let path = scratch.path().join(name);
+                        write_tile(&path, &tile, image.channels, options, &params.encode)
+                            .map_err(Error::external)?;
+                        let bytes = std::fs::read(&path)?;
+                        std::fs::remove_file(&path)?;
+                        Ok((tile.x, tile.y, bytes))
+                    })
+                    .collect::<Result<_>>()?;
+                match archive {
+                    TileSink::Pmtiles(writer) => {
+                        encoded.sort_by_key(|(x, y, _)| pmtiles_tile_id(z, *x, *y));
+                        for (x, y, bytes) in &encoded {
+                            writer.add_tile(z, *x, *y, bytes).map_err(Error::external)?;
+                        }
+                    }
+                    TileSink::Mbtiles(writer) => {
+                        for (x, y, bytes) in &encoded {
+                            writer.add_tile(z, *x, *y, bytes).map_err(Error::external)?;
+                        }
+                    }
+                    TileSink::Directory(_) => unreachable!(),
+                }
+                encoded.len()
+            }
+        };
+        info!("Zoom {}: {} tiles", z, written);
+        tiles_per_zoom.push(written);
+    }
+
+    let output = match sink {
+        TileSink::Directory(dir) => {
+            let template = format!("{{z}}/{{x}}/{{y}}.{}", ext);
+            let tile_url = match options.base_url.as_deref() {
+                Some(base) => format!("{}/{}", base.trim_end_matches('/'), template),
+                None => template,
+            };
+            let tilejson_path = dir.join("tilejson.json");
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(
+                &tilejson_path,
+                serde_json::to_string_pretty(&tileset.to_tilejson(&tile_url))
+                    .map_err(Error::external)?,
+            )?;
+            tilejson_path
+        }
+        TileSink::Mbtiles(writer) => {
+            writer.finish(&tileset).map_err(Error::external)?;
+            output.to_path_buf()
+        }
+        TileSink::Pmtiles(writer) => {
+            writer.finish(&tileset).map_err(Error::external)?;
+            output.to_path_buf()
+        }
+    };
+
+    Ok(TileReport {
+        min_zoom,
+        max_zoom,
+        tiles_per_zoom,
+        bounds: tileset.bounds,
+        output,
+    })
+}
+
+/// Whole-scene autoscaled image for `params`: gray, colormapped RGB or synthetic RGB
+fn autoscaled_image(
+    reader: &SafeReader,
+    params: &ProcessingParams,
+    strategy: AutoscaleStrategy,
+) -> Result<ScaledImage> {
+    let colormap = params.colormap_options()?;
+    let colormap = colormap.as_ref();
+    let ap = &params.autoscale_params;
+
+    if let Some(source) = params.expression.as_deref() {
+        let expr = BandExpr::parse(source)?;
+        let operation = ProcessingOperation::Expression { scale: params.expression_scale };
+        return Ok(scale_single(&reader.expression_data(&expr)?, strategy, ap, colormap, operation));
+    }
+
+    match params.polarization {
+        Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv => {
+            let processed = match params.polarization {
+                Polarization::Vv => reader.vv_data()?,
+                Polarization::Vh => reader.vh_data()?,
+                Polarization::Hh => reader.hh_data()?,
+                Polarization::Hv => reader.hv_data()?,
+                _ => unreachable!(),
+            };
+            Ok(scale_single(processed, strategy, ap, colormap, ProcessingOperation::SingleBand))
+        }
+        Polarization::OP(op) => Ok(scale_single(
+            &operation_data(reader, op)?,
+            strategy,
+            ap,
+            colormap,
+            ProcessingOperation::PolarOp(op),
+        )),
+        Polarization::Multiband => {
+            if reader.vv_data().is_ok() && reader.vh_data().is_ok() {
+                Ok(scale_synrgb(reader.vv_data()?, reader.vh_data()?, strategy, params))
+            } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
+                Ok(scale_synrgb(reader.hh_data()?, reader.hv_data()?, strategy, params))
+            } else {
+                Err(Error::Processing(format!(
+                    "Multiband requires VV+VH or HH+HV; available: {}",
+                    reader.get_available_polarizations()
+                )))
+            }
+        }
+    }
+}
+
+fn scale_single(
+    processed: &Array2<f32>,
+    strategy: AutoscaleStrategy,
+    autoscale_params: &AutoscaleParams,
+    colormap: Option<&ColormapOptions>,
+    operation: ProcessingOperation,
+) -> ScaledImage {
+    let (_, valid_mask, scaled_u8, _, _) = process_operation_data_pipeline_with_window(
+        processed,
+        BitDepth::U8,
+        strategy,
+        autoscale_params,
+        operation,
+    );
+    let (rows, cols) = processed.dim();
+    let alpha = (0..cols * rows)
+        .map(|i| if valid_mask.get(i) { 255 } else { 0 })
+        .collect();
+    match colormap {
+        Some(colormap) => ScaledImage {
+            cols,
+            rows,
+            channels: 3,
+            data: apply_palette(&scaled_u8, &colormap.ramp.palette()),
+            alpha,
+        },
+        None => ScaledImage {
+            cols,
+            rows,
+            channels: 1,
+            data: scaled_u8,
+            alpha,
+        },
+    }
+}
+
+/// Synthetic RGB of a co-/cross-pol pair; opaque only where both bands are valid
+fn scale_synrgb(
+    band1: &Array2<f32>,
+    band2: &Array2<f32>,
+    strategy: AutoscaleStrategy,
+    params: &ProcessingParams,
+) -> ScaledImage {
+    let ap = &params.autoscale_params;
+    let (_, valid1, scaled1, _, _) =
+        process_scalar_data_pipeline_with_window(band1, BitDepth::U8, strategy, ap);
+    let (db2, valid2, scaled2, _, _) =
+        process_scalar_data_pipeline_with_window(band2, BitDepth::U8, strategy, ap);
+    let scaled2 = if matches!(strategy, AutoscaleStrategy::Tamed) {
+        autoscale_db_image_tamed_synrgb_u8_with_params(&db2, &valid2, false, ap)
+    } else {
+        scaled2
+    };
+    let (rows, cols) = band1.dim();
+    let alpha = (0..cols * rows)
+        .map(|i| if valid1.get(i) && valid2.get(i) { 255 } else { 0 })
+        .collect();
+    ScaledImage {
+        cols,
+        rows,
+        channels: 3,
+        data: create_synthetic_rgb_by_mode_and_strategy(
+            params.synrgb_mode,
+            strategy,
+            &scaled1,
+            &scaled2,
+        ),
+        alpha,
+    }
+}
+
+fn write_tile(
+    path: &Path,
+    tile: &Tile,
+    channels: usize,
+    options: &TileOptions,
+    encode: &EncodeOptions,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let n = options.tile_size;
+    let alpha = Some(tile.alpha.as_slice());
+    match (options.format, channels) {
+        (TileFormat::Png, 1) => write_gray_png(path, n, n, &tile.data, alpha),
+        (TileFormat::Png, _) => write_rgb_png(path, n, n, &tile.data, alpha),
+        (TileFormat::Webp, 1) => write_gray_webp(path, n, n, &tile.data, alpha, &encode.webp),
+        (TileFormat::Webp, _) => write_rgb_webp(path, n, n, &tile.data, alpha, &encode.webp),
+        (TileFormat::Jpeg, 1) => write_gray_jpeg_with_options(path, n, n, &tile.data, &encode.jpeg),
+        (TileFormat::Jpeg, _) => write_rgb_jpeg_with_options(path, n, n, &tile.data, &encode.jpeg),
+    }
+}
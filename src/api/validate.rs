@@ -0,0 +1,309 @@
+//! Integrity checks of SAFE products against their manifest: every listed file present with
+//! the recorded size and MD5, and an annotation and a measurement for each polarization.
+//! Zipped products are unpacked to a temporary directory first.
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+use crate::io::discovery::{is_safe_zip, unpack_safe_zip};
+use crate::io::sentinel1::{DataObject, SafeReader};
+
+const MEASUREMENT_SCHEMA: &str = "s1Level1MeasurementSchema";
+const ANNOTATION_SCHEMA: &str = "s1Level1ProductSchema";
+
+/// One problem found in a product
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationIssue {
+    /// A file listed in the manifest does not exist
+    MissingFile { path: String },
+    SizeMismatch {
+        path: String,
+        expected: u64,
+        actual: u64,
+    },
+    ChecksumMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    /// A listed file exists but could not be read
+    Unreadable { path: String, error: String },
+    /// A polarization of the manifest without its annotation or measurement object
+    MissingPair {
+        polarization: String,
+        component: String,
+    },
+    /// The manifest lists no data objects
+    EmptyManifest,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingFile { path } => write!(f, "{}: missing", path),
+            Self::SizeMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: size {} bytes, manifest says {}", path, actual, expected),
+            Self::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: MD5 {}, manifest says {}", path, actual, expected),
+            Self::Unreadable { path, error } => write!(f, "{}: unreadable ({})", path, error),
+            Self::MissingPair {
+                polarization,
+                component,
+            } => write!(f, "{}: no {} in the manifest", polarization, component),
+            Self::EmptyManifest => write!(f, "manifest.safe lists no data objects"),
+        }
+    }
+}
+
+/// Outcome of [`validate_safe`]
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    pub path: PathBuf,
+    /// Data objects listed in the manifest
+    pub objects: usize,
+    /// Files whose MD5 was computed and compared
+    pub checksums_verified: usize,
+    /// Files with a manifest MD5 that was not computed because the file is missing, has the
+    /// wrong size or cannot be read
+    pub checksums_skipped: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check `input` against its `manifest.safe`: each data object must exist with the recorded
+/// size and, when `verify_checksums` is set, the recorded MD5; each polarization needs an
+/// annotation and a measurement object. Files are checked in parallel. `input` is a SAFE
+/// directory or a `.zip` holding one. Errors only when the manifest itself cannot be read;
+/// everything else is reported as a [`ValidationIssue`].
+pub fn validate_safe(input: &Path, verify_checksums: bool) -> Result<ValidationReport> {
+    if is_safe_zip(input) {
+        let unpacked = unpack_safe_zip(input).map_err(|e| Error::InvalidArgument {
+            arg: "input",
+            value: format!("{:?} (cannot unpack a SAFE product: {})", input, e),
+        })?;
+        let report = validate_safe(&unpacked.path, verify_checksums)?;
+        return Ok(ValidationReport {
+            path: input.to_path_buf(),
+            ..report
+        });
+    }
+    if !input.join("manifest.safe").is_file() {
+        return Err(Error::InvalidArgument {
+            arg: "input",
+            value: format!("{:?} (no manifest.safe; not a SAFE product)", input),
+        });
+    }
+    let objects = SafeReader::read_data_objects(input)?;
+    let polarizations = SafeReader::read_metadata(input)?.polarizations;
+
+    let checked: Vec<(Option<ValidationIssue>, Checksum)> = objects
+        .par_iter()
+        .map(|obj| check_object(input, obj, verify_checksums))
+        .collect();
+    let count = |outcome: Checksum| checked.iter().filter(|(_, c)| *c == outcome).count();
+    let checksums_verified = count(Checksum::Verified);
+    let checksums_skipped = count(Checksum::Skipped);
+    let mut issues: Vec<ValidationIssue> =
+        checked.into_iter().filter_map(|(issue, _)| issue).collect();
+
+    if objects.is_empty() {
+        issues.push(ValidationIssue::EmptyManifest);
+    } else {
+        for pol in &polarizations {
+            // Sentinel-1 file names carry the polarization as e.g. `-vv-`
+            let tag = format!("-{}-", pol.to_lowercase());
+            let has = |schema: &str| {
+                objects
+                    .iter()
+                    .any(|o| o.rep_id == schema && o.href.to_lowercase().contains(&tag))
+            };
+            for (schema, component) in
+                [(ANNOTATION_SCHEMA, "annotation"), (MEASUREMENT_SCHEMA, "measurement")]
+            {
+                if !has(schema) {
+                    issues.push(ValidationIssue::MissingPair {
+                        polarization: pol.clone(),
+                        component: component.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(ValidationReport {
+        path: input.to_path_buf(),
+        objects: objects.len(),
+        checksums_verified,
+        checksums_skipped,
+        issues,
+    })
+}
+
+/// What became of the MD5 of one data object
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Checksum {
+    Verified,
+    /// Requested and listed, but an earlier check failed
+    Skipped,
+    /// Not requested, or not listed in the manifest
+    NotChecked,
+}
+
+/// First problem with one data object, and what became of its MD5
+fn check_object(
+    input: &Path,
+    obj: &DataObject,
+    verify_checksum: bool,
+) -> (Option<ValidationIssue>, Checksum) {
+    let path = obj.href.clone();
+    let skipped = if verify_checksum && obj.md5.is_some() {
+        Checksum::Skipped
+    } else {
+        Checksum::NotChecked
+    };
+    let actual_size = match std::fs::metadata(input.join(&obj.href)) {
+        Ok(meta) if meta.is_file() => meta.len(),
+        Ok(_) => return (Some(ValidationIssue::MissingFile { path }), skipped),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return (Some(ValidationIssue::MissingFile { path }), skipped);
+        }
+        Err(e) => {
+            let error = e.to_string();
+            return (Some(ValidationIssue::Unreadable { path, error }), skipped);
+        }
+    };
+    if let Some(expected) = obj.size.filter(|&size| size != actual_size) {
+        let actual = actual_size;
+        return (Some(ValidationIssue::SizeMismatch { path, expected, actual }), skipped);
+    }
+    let Some(expected) = obj.md5.clone().filter(|_| verify_checksum) else {
+        return (None, Checksum::NotChecked);
+    };
+    match file_md5(&input.join(&obj.href)) {
+        Ok(actual) if actual == expected => (None, Checksum::Verified),
+        Ok(actual) => (
+            Some(ValidationIssue::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            }),
+            Checksum::Verified,
+        ),
+        Err(e) => {
+            let error = e.to_string();
+            (Some(ValidationIssue::Unreadable { path, error }), Checksum::Skipped)
+        }
+    }
+}
+
+fn file_md5(path: &Path) -> std::io::Result<String> {
+    let mut context = md5::Context::new();
+    std::io::copy(&mut BufReader::new(File::open(path)?), &mut context)?;
+    Ok(format!("{:x}", context.compute()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Manifest listing `(href, size, md5)` data objects
+    fn manifest(objects: &[(&str, u64, String)]) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\"?>\n<xfdu:XFDU xmlns:xfdu=\"urn:ccsds:schema:xfdu:1\">\n\
+             <dataObjectSection>\n",
+        );
+        for (i, (href, size, md5)) in objects.iter().enumerate() {
+            xml.push_str(&format!(
+                "<dataObject ID=\"obj{i}\" repID=\"s1Level1ProductSchema\">\
+                 <byteStream mimeType=\"text/xml\" size=\"{size}\">\
+                 <fileLocation locatorType=\"URL\" href=\"./{href}\"/>\
+                 <checksum checksumName=\"MD5\">{md5}</checksum>\
+                 </byteStream></dataObject>\n"
+            ));
+        }
+        xml.push_str("</dataObjectSection>\n</xfdu:XFDU>\n");
+        xml
+    }
+
+    /// Zip holding `files` under `S1A_TEST.SAFE/`
+    fn write_zip(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, data) in files {
+            zip.start_file(format!("S1A_TEST.SAFE/{}", name), options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn zipped_products_are_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let good: &[u8] = b"<product/>";
+        let short: &[u8] = b"<calibration/>";
+        let md5 = |data: &[u8]| format!("{:x}", md5::compute(data));
+        let manifest = manifest(&[
+            ("support/a.xml", good.len() as u64, md5(good)),
+            ("support/b.xml", short.len() as u64 + 1, md5(short)),
+            ("support/c.xml", 3, md5(b"abc")),
+        ]);
+        let zip = dir.path().join("S1A_TEST.zip");
+        write_zip(
+            &zip,
+            &[
+                ("manifest.safe", manifest.as_bytes()),
+                ("support/a.xml", good),
+                ("support/b.xml", short),
+            ],
+        );
+
+        let report = validate_safe(&zip, true).unwrap();
+        assert_eq!(report.path, zip);
+        assert_eq!(report.objects, 3);
+        assert_eq!(report.checksums_verified, 1);
+        assert_eq!(report.checksums_skipped, 2);
+        assert_eq!(
+            report.issues,
+            vec![
+                ValidationIssue::SizeMismatch {
+                    path: "support/b.xml".to_string(),
+                    expected: short.len() as u64 + 1,
+                    actual: short.len() as u64,
+                },
+                ValidationIssue::MissingFile {
+                    path: "support/c.xml".to_string(),
+                },
+            ]
+        );
+
+        let report = validate_safe(&zip, false).unwrap();
+        assert_eq!((report.checksums_verified, report.checksums_skipped), (0, 0));
+        assert_eq!(report.issues.len(), 2);
+    }
+
+    #[test]
+    fn zips_without_a_product_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip = dir.path().join("notes.zip");
+        write_zip(&zip, &[("readme.txt", b"not a product")]);
+        let err = validate_safe(&zip, true).unwrap_err().to_string();
+        assert!(err.contains("cannot unpack a SAFE product"), "{}", err);
+    }
+}
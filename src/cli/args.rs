@@ -1,12 +1,26 @@
-use clap::Parser;
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
-use sarpro::types::{OutputFormat, SyntheticRgbMode};
+use sarpro::types::{
+    ChangeMethod, ChipValues, Colormap, ExprScale, JpegSubsampling, OutputFormat, ProductMode, StackFormat, SyntheticRgbMode, TemporalStat, TileArchive, TileFormat, WaterThreshold,
+};
+use sarpro::core::processing::autoscale::DEFAULT_DB_FLOOR;
 use sarpro::{AutoscaleStrategy, BitDepthArg, InputFormat, Polarization};
 
 #[derive(Parser)]
-#[command(name = "sarpro", version, about = "SARPRO CLI")]
+#[command(
+    name = "sarpro",
+    version,
+    about = "SARPRO CLI",
+    args_conflicts_with_subcommands = true
+)]
 pub struct CliArgs {
+    /// Subcommand; without one, the flags below run `process`, or `batch` when --input-dir or
+    /// --batch is given
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Input SAFE directory (single file mode)
     #[arg(short, long)]
     pub input: Option<PathBuf>,
@@ -23,10 +37,57 @@ pub struct CliArgs {
     #[arg(long)]
     pub output_dir: Option<PathBuf>,
 
-    /// Output format (tiff or jpeg)
+    /// Batch mode: continue processing other files when encountering unsupported products
+    #[arg(long, default_value_t = false)]
+    pub batch: bool,
+
+    /// Batch mode: mosaic consecutive slices of one datatake into a single product
+    /// (warped to a common target-aligned grid, autoscaled once)
+    #[arg(long, default_value_t = false)]
+    pub assemble_slices: bool,
+
+    /// Batch mode: check each product against its manifest (sizes and MD5) before processing
+    /// and skip corrupted ones
+    #[arg(long, default_value_t = false)]
+    pub validate: bool,
+
+    #[command(flatten)]
+    pub options: ProcessOptions,
+}
+
+/// Processing flags shared by `process`, `batch` and the subcommand-less invocation
+#[derive(Args)]
+pub struct ProcessOptions {
+    /// Output format (tiff, jpeg, png or webp). PNG and WebP get an alpha channel that makes
+    /// nodata and padding transparent
     #[arg(short = 'f', long, value_enum, default_value_t = OutputFormat::TIFF)]
     pub format: OutputFormat,
 
+    /// JPEG quality (1-100)
+    #[arg(long, default_value_t = 100)]
+    pub jpeg_quality: u8,
+
+    /// Write progressive JPEGs
+    #[arg(long, default_value_t = false)]
+    pub jpeg_progressive: bool,
+
+    /// JPEG chroma subsampling for RGB outputs (444, 422 or 420)
+    #[arg(long, value_enum, default_value_t = JpegSubsampling::S444)]
+    pub jpeg_subsampling: JpegSubsampling,
+
+    /// Write a tiled JPEG-compressed GeoTIFF (<output>.tif) when the image exceeds the
+    /// 65535-pixel JPEG limit, instead of failing
+    #[arg(long, default_value_t = false)]
+    pub jpeg_tiff_fallback: bool,
+
+    /// WebP lossy quality (1-100)
+    #[arg(long, default_value_t = 85)]
+    pub webp_quality: u8,
+
+    /// Encode WebP losslessly (--webp-quality is ignored)
+    #[arg(long, default_value_t = false)]
+    pub webp_lossless: bool,
+
     /// Input format (only SAFE supported currently)
     #[arg(long, value_enum, default_value_t = InputFormat::Safe)]
     pub input_format: InputFormat,
@@ -43,6 +104,76 @@ pub struct CliArgs {
     #[arg(long, value_enum, default_value_t = AutoscaleStrategy::Clahe)]
     pub autoscale: AutoscaleStrategy,
 
+    /// Fixed dB clip window MIN,MAX (e.g. -25,0) used for every scene instead of --autoscale,
+    /// so a time series or batch shares one stretch
+    #[arg(long, allow_hyphen_values = true, value_name = "MIN,MAX")]
+    pub fixed_range: Option<String>,
+
+    /// Gamma applied after the --fixed-range stretch
+    #[arg(long, default_value_t = 1.0, requires = "fixed_range")]
+    pub fixed_gamma: f64,
+
+    /// Reuse the clip window recorded in an earlier output (GeoTIFF or its JSON sidecar, or a
+    /// JSON file with min_db/max_db/gamma) as a fixed stretch
+    #[arg(long, conflicts_with = "fixed_range")]
+    pub autoscale_reference: Option<PathBuf>,
+
+    /// Low percentile (0-100) for the lower clip point, replacing the strategy's own cut
+    #[arg(long)]
+    pub low_percentile: Option<f64>,
+
+    /// High percentile (0-100) for the upper clip point, replacing the strategy's own cut
+    #[arg(long)]
+    pub high_percentile: Option<f64>,
+
+    /// Gamma applied after the stretch, replacing the strategy's own gamma
+    #[arg(long)]
+    pub gamma: Option<f64>,
+
+    /// CLAHE tile grid columns
+    #[arg(long, default_value_t = 8)]
+    pub clahe_tiles_x: usize,
+
+    /// CLAHE tile grid rows
+    #[arg(long, default_value_t = 8)]
+    pub clahe_tiles_y: usize,
+
+    /// CLAHE clip limit, as a multiple of the mean histogram bin count
+    #[arg(long, default_value_t = 2.0)]
+    pub clahe_clip_limit: f64,
+
+    /// CLAHE histogram bins
+    #[arg(long, default_value_t = 256)]
+    pub clahe_bins: usize,
+
+    /// dB value below which pixels are treated as no-data by the autoscale stage
+    #[arg(long, allow_hyphen_values = true, default_value_t = DEFAULT_DB_FLOOR)]
+    pub db_floor: f64,
+
+    /// Render single-band outputs through a color ramp: RGB JPEG, RGBA PNG/WebP, or a
+    /// paletted GeoTIFF with a GDAL color table (always 8-bit)
+    #[arg(long, value_enum)]
+    pub colormap: Option<Colormap>,
+
+    /// Custom color ramp instead of --colormap: JSON ({"stops": [[position, r, g, b], ...]})
+    /// or a GMT .cpt file
+    #[arg(long, conflicts_with = "colormap")]
+    pub colormap_file: Option<PathBuf>,
+
+    /// With a colormap, also write <output>.legend.jpg labelled with the clip limits
+    #[arg(long, default_value_t = false)]
+    pub legend: bool,
+
+    /// Also write <output>.kmz: the JPEG/PNG as a Google Earth GroundOverlay (gx:LatLonQuad
+    /// on the corner GCPs for swaths that are not north-up) with a product placemark
+    #[arg(long, default_value_t = false, conflicts_with = "pad")]
+    pub kmz: bool,
+
+    /// Also write the data footprint (valid-pixel outline in WGS84, split at the antimeridian)
+    /// as <output>.footprint.geojson and <output>.footprint.wkt
+    #[arg(long, default_value_t = false)]
+    pub footprint: bool,
+
     /// Image size for scaling. Options:
     /// - Predefined: 512, 1024, 2048
     /// - Custom: any positive integer (e.g., 1536)
@@ -54,10 +185,6 @@ pub struct CliArgs {
     #[arg(long, default_value_t = false)]
     pub log: bool,
 
-    /// Batch mode: continue processing other files when encountering unsupported products
-    #[arg(long, default_value_t = false)]
-    pub batch: bool,
-
     /// Add padding to make square images (centers image and adds zero padding to top/bottom)
     #[arg(long, default_value_t = false)]
     pub pad: bool,
@@ -71,7 +198,486 @@ pub struct CliArgs {
     #[arg(long)]
     pub resample_alg: Option<String>,
 
-    /// Synthetic RGB mode (only used when --format jpeg and --polarization multiband)
+    /// Synthetic RGB mode (used when --polarization multiband with --format jpeg, png or webp)
+    #[arg(long = "synrgb-mode", value_enum, default_value_t = SyntheticRgbMode::Default)]
+    pub synrgb_mode: SyntheticRgbMode,
+
+    /// Area of interest as lon/lat bounding box: min_lon,min_lat,max_lon,max_lat
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "aoi")]
+    pub aoi_bbox: Option<String>,
+
+    /// Area of interest from a GeoJSON file (Polygon/MultiPolygon)
+    #[arg(long)]
+    pub aoi: Option<PathBuf>,
+
+    /// Set pixels outside the AOI geometry to nodata
+    #[arg(long, default_value_t = false)]
+    pub mask_outside_aoi: bool,
+
+    /// Output pixel spacing in meters (e.g. 10, 20, 100); replaces --size.
+    /// Target CRS units when reprojecting, otherwise derived from the annotation pixel spacing
+    #[arg(long, conflicts_with = "size")]
+    pub pixel_spacing: Option<f64>,
+
+    /// Align output pixels to multiples of --pixel-spacing (gdalwarp -tap); requires --target-crs
+    #[arg(long = "target-aligned-pixels", alias = "tap", default_value_t = false, requires = "pixel_spacing")]
+    pub target_aligned_pixels: bool,
+
+    /// Band-math expression over VV, VH, HH, HV (e.g. "10*log10(VH/VV)", "(VV-VH)/(VV+VH)");
+    /// replaces --polarization. Functions: log10, ln, exp, sqrt, abs, db, lin, min, max, pow
+    #[arg(long, allow_hyphen_values = true)]
+    pub expr: Option<String>,
+
+    /// Units of the --expr result: 'linear' intensity is converted to dB before autoscaling;
+    /// 'db' and 'native' (indices such as "(VV-VH)/(VV+VH)") are stretched as-is
+    #[arg(long, value_enum, default_value_t = ExprScale::Linear, requires = "expr")]
+    pub expr_scale: ExprScale,
+
+    /// Output product: 'image' (scaled backscatter), 'water' (1/0/255 water mask GeoTIFF
+    /// with threshold and area statistics in the JSON sidecar) or 'ships' (image plus CA-CFAR
+    /// detections as <output>.ships.geojson / .ships.csv); water and ships need a single polarization
+    #[arg(long, value_enum, default_value_t = ProductMode::Image, conflicts_with = "expr")]
+    pub product: ProductMode,
+
+    /// Water threshold method (otsu or ki for Kittler-Illingworth)
+    #[arg(long, value_enum, default_value_t = WaterThreshold::Otsu)]
+    pub water_threshold: WaterThreshold,
+
+    /// Re-estimate the water threshold from tiles with a bimodal histogram
+    #[arg(long, default_value_t = false)]
+    pub water_tile_refine: bool,
+
+    /// Tile size in pixels for --water-tile-refine
+    #[arg(long, default_value_t = 256)]
+    pub water_tile_size: usize,
+
+    /// CFAR guard window half-width in pixels (ships product)
+    #[arg(long, default_value_t = 4)]
+    pub cfar_guard: usize,
+
+    /// CFAR background window half-width in pixels; must exceed --cfar-guard (ships product)
+    #[arg(long, default_value_t = 12)]
+    pub cfar_background: usize,
+
+    /// CFAR probability of false alarm per pixel (ships product)
+    #[arg(long, default_value_t = 1e-6)]
+    pub cfar_pfa: f64,
+
+    /// Smallest target kept, in detected pixels (ships product)
+    #[arg(long, default_value_t = 2)]
+    pub ship_min_pixels: usize,
+
+    /// Largest target kept, in detected pixels (ships product)
+    #[arg(long, default_value_t = 5000)]
+    pub ship_max_pixels: usize,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Process one SAFE product into an image (the default without a subcommand)
+    Process(ProcessArgs),
+    /// Process every SAFE product in a directory, continuing past failed products
+    Batch(BatchArgs),
+    /// Bi-temporal change detection between two SAFE products of the same track
+    Change(ChangeArgs),
+    /// Co-gridded time-series stacks grouped by relative orbit and polarization
+    Stack(StackArgs),
+    /// Web Mercator XYZ tile pyramid with a TileJSON manifest
+    #[command(alias = "tile")]
+    Tiles(TileArgs),
+    /// Fixed-size ML training chips on a map grid, each with a JSON sidecar
+    Chips(ChipArgs),
+    /// Print product metadata, polarizations, auto CRS, footprint and files without processing
+    Info(InfoArgs),
+    /// Check SAFE products against their manifest: files, sizes, MD5 and polarization pairs
+    Validate(ValidateArgs),
+    /// Serve a tile folder, MBTiles or PMTiles archive over HTTP for previews in web maps
+    Serve(ServeArgs),
+}
+
+#[derive(Args)]
+pub struct ProcessArgs {
+    /// Input SAFE directory
+    #[arg(short, long)]
+    pub input: PathBuf,
+
+    /// Output filename
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    #[command(flatten)]
+    pub options: ProcessOptions,
+}
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// Input directory containing SAFE subdirectories and zipped products
+    #[arg(long, required_unless_present = "input_list")]
+    pub input_dir: Option<PathBuf>,
+
+    /// Text file listing SAFE directories or zips, one path per line ('#' starts a comment)
+    #[arg(long, conflicts_with = "input_dir")]
+    pub input_list: Option<PathBuf>,
+
+    /// Output directory; each product is written as <SAFE name>.<extension>
+    #[arg(long)]
+    pub output_dir: PathBuf,
+
+    /// Mosaic consecutive slices of one datatake into a single product
+    /// (warped to a common target-aligned grid, autoscaled once)
+    #[arg(long, default_value_t = false)]
+    pub assemble_slices: bool,
+
+    /// Check each product against its manifest (sizes and MD5) before processing and skip
+    /// corrupted ones
+    #[arg(long, default_value_t = false)]
+    pub validate: bool,
+
+    #[command(flatten)]
+    pub discovery: DiscoveryArgs,
+
+    #[command(flatten)]
+    pub options: ProcessOptions,
+}
+
+/// Output grid, resampling, AOI and logging flags of `change`, `stack`, `tiles` and `chips`
+#[derive(Args)]
+pub struct GridArgs {
+    /// Target CRS of the output grid (e.g. EPSG:32633); 'auto' uses the UTM zone of the
+    /// (first) product. Tiles are always cut in EPSG:3857
+    #[arg(long, default_value = "auto")]
+    pub target_crs: String,
+
+    /// Grid pixel spacing in target CRS units (default 10 m; for tiles about the native
+    /// spacing in Web Mercator meters)
+    #[arg(long)]
+    pub pixel_spacing: Option<f64>,
+
+    /// Optional resampling algorithm (nearest, bilinear, cubic, lanczos)
+    #[arg(long)]
+    pub resample_alg: Option<String>,
+
+    /// Area of interest as lon/lat bounding box: min_lon,min_lat,max_lon,max_lat
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "aoi")]
+    pub aoi_bbox: Option<String>,
+
+    /// Area of interest from a GeoJSON file (Polygon/MultiPolygon)
+    #[arg(long)]
+    pub aoi: Option<PathBuf>,
+
+    /// Enable logging
+    #[arg(long, default_value_t = false)]
+    pub log: bool,
+}
+
+/// Where `batch` looks for products and which ones it keeps
+#[derive(Args, Default)]
+pub struct DiscoveryArgs {
+    /// Search --input-dir recursively (e.g. year/month/day trees) for SAFE directories and zips
+    #[arg(short = 'r', long, default_value_t = false)]
+    pub recursive: bool,
+
+    /// Glob on product names, or on paths relative to --input-dir when it contains '/'
+    /// (e.g. "S1A_IW_GRDH_*", "2023/0[1-3]/*/*.SAFE"); repeatable, any may match
+    #[arg(long = "pattern", value_name = "GLOB")]
+    pub patterns: Vec<String>,
+
+    /// Keep only these platforms (e.g. S1A,S1C)
+    #[arg(long, value_delimiter = ',')]
+    pub platform: Vec<String>,
+
+    /// Keep only these acquisition modes (IW, EW, SM, WV)
+    #[arg(long, value_delimiter = ',')]
+    pub mode: Vec<String>,
+
+    /// Keep only products carrying all of these polarizations (e.g. VV,VH)
+    #[arg(long, value_delimiter = ',')]
+    pub polarization_set: Vec<String>,
+
+    /// Keep products acquired on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub start_date: Option<NaiveDate>,
+
+    /// Keep products acquired on or before this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub end_date: Option<NaiveDate>,
+
+    /// Keep only these absolute orbits: numbers or N-M ranges, comma-separated
+    #[arg(long, value_delimiter = ',')]
+    pub orbit: Vec<String>,
+
+    /// Keep only ascending or descending passes (read from each manifest)
+    #[arg(long, value_parser = ["ascending", "descending"], ignore_case = true)]
+    pub pass: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ChangeArgs {
+    /// Earlier SAFE product (reference date)
+    #[arg(long)]
+    pub before: PathBuf,
+
+    /// Later SAFE product of the same track
+    #[arg(long)]
+    pub after: PathBuf,
+
+    /// Output Float32 GeoTIFF with one change band per polarization
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Polarization to compare (vv, vh, hh, hv) or multiband for the co-/cross-pol pair
+    #[arg(long, value_enum, default_value_t = Polarization::Vv)]
+    pub polarization: Polarization,
+
+    /// Change measure (log-ratio in dB, difference and normalized-diff on linear intensity)
+    #[arg(long, value_enum, default_value_t = ChangeMethod::LogRatio)]
+    pub method: ChangeMethod,
+
+    /// Also write <output>_mask.tif: 0 no change, 1 increase, 2 decrease, 255 nodata
+    #[arg(long, default_value_t = false)]
+    pub mask: bool,
+
+    /// Absolute change threshold for --mask; estimated with Otsu when omitted
+    #[arg(long, requires = "mask")]
+    pub threshold: Option<f64>,
+
+    /// Also write <output>_preview.jpg: the first change band through this color ramp over
+    /// ±98th percentile of |change| (rdbu suits signed change)
+    #[arg(long, value_enum)]
+    pub colormap: Option<Colormap>,
+
+    /// Custom JSON or .cpt color ramp for the preview instead of --colormap
+    #[arg(long, conflicts_with = "colormap")]
+    pub colormap_file: Option<PathBuf>,
+
+    /// With a preview colormap, also write <output>_preview.legend.jpg
+    #[arg(long, default_value_t = false)]
+    pub legend: bool,
+
+    #[command(flatten)]
+    pub grid: GridArgs,
+}
+
+#[derive(Args)]
+pub struct StackArgs {
+    /// Directory containing SAFE products
+    #[arg(long, conflicts_with = "inputs", required_unless_present = "inputs")]
+    pub input_dir: Option<PathBuf>,
+
+    /// Explicit list of SAFE products
+    #[arg(long, num_args = 1..)]
+    pub inputs: Vec<PathBuf>,
+
+    /// Output directory for stacks, statistics and JSON manifests
+    #[arg(long)]
+    pub output_dir: PathBuf,
+
+    /// Polarization to stack (vv, vh, hh, hv) or multiband for one stack per available polarization
+    #[arg(long, value_enum, default_value_t = Polarization::Vv)]
+    pub polarization: Polarization,
+
+    /// Stack container: one multi-band GeoTIFF, or per-date COGs referenced by a VRT
+    #[arg(long, value_enum, default_value_t = StackFormat::Gtiff)]
+    pub format: StackFormat,
+
+    /// Temporal statistics to write to <stack>_stats.tif (comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "median,mean,std,min,max,cv",
+        conflicts_with = "no_stats"
+    )]
+    pub stats: Vec<TemporalStat>,
+
+    /// Skip temporal statistics
+    #[arg(long, default_value_t = false)]
+    pub no_stats: bool,
+
+    /// Minimum number of dates per stack; smaller groups are skipped
+    #[arg(long, default_value_t = 2)]
+    pub min_dates: usize,
+
+    #[command(flatten)]
+    pub grid: GridArgs,
+}
+
+#[derive(Args)]
+pub struct TileArgs {
+    /// Input SAFE product
+    #[arg(short, long)]
+    pub input: PathBuf,
+
+    /// Output directory for {z}/{x}/{y} tiles and tilejson.json
+    #[arg(long, conflicts_with = "output", required_unless_present = "output")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Single-file archive (.mbtiles or .pmtiles) instead of a tile directory
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Archive type for --output; inferred from its extension when omitted
+    #[arg(long, value_enum, requires = "output")]
+    pub archive: Option<TileArchive>,
+
+    /// Tile format; PNG and WebP tiles have transparent nodata, JPEG tiles black
+    #[arg(long, value_enum, default_value_t = TileFormat::Png)]
+    pub format: TileFormat,
+
+    /// Shallowest zoom level (default: the zoom at which the scene fits in one tile)
+    #[arg(long)]
+    pub min_zoom: Option<u8>,
+
+    /// Deepest zoom level (default: the first zoom at least as fine as the warped pixels)
+    #[arg(long)]
+    pub max_zoom: Option<u8>,
+
+    /// Tile size in pixels (256 or 512)
+    #[arg(long, default_value_t = 256)]
+    pub tile_size: usize,
+
+    /// Base URL written in front of {z}/{x}/{y} in tilejson.json (default: relative template)
+    #[arg(long)]
+    pub tile_url: Option<String>,
+
+    /// Polarization (vv, vh, hh, hv), operation, or multiband for synthetic RGB tiles
+    #[arg(long, value_enum, default_value_t = Polarization::Vv)]
+    pub polarization: Polarization,
+
+    /// Autoscaling strategy, applied once over the whole scene
+    #[arg(long, value_enum, default_value_t = AutoscaleStrategy::Clahe)]
+    pub autoscale: AutoscaleStrategy,
+
+    /// Synthetic RGB mode for multiband tiles
     #[arg(long = "synrgb-mode", value_enum, default_value_t = SyntheticRgbMode::Default)]
     pub synrgb_mode: SyntheticRgbMode,
+
+    /// Render single-band tiles through a color ramp
+    #[arg(long, value_enum)]
+    pub colormap: Option<Colormap>,
+
+    /// Custom JSON or .cpt color ramp instead of --colormap
+    #[arg(long, conflicts_with = "colormap")]
+    pub colormap_file: Option<PathBuf>,
+
+    /// JPEG tile quality (1-100)
+    #[arg(long, default_value_t = 90)]
+    pub jpeg_quality: u8,
+
+    /// WebP tile lossy quality (1-100)
+    #[arg(long, default_value_t = 85)]
+    pub webp_quality: u8,
+
+    /// Encode WebP tiles losslessly (--webp-quality is ignored)
+    #[arg(long, default_value_t = false)]
+    pub webp_lossless: bool,
+
+    #[command(flatten)]
+    pub grid: GridArgs,
+}
+
+#[derive(Args)]
+pub struct ChipArgs {
+    /// Input SAFE product
+    #[arg(short, long)]
+    pub input: PathBuf,
+
+    /// Output directory for chip GeoTIFFs and their JSON sidecars
+    #[arg(long)]
+    pub output_dir: PathBuf,
+
+    /// Chip side in pixels
+    #[arg(long, default_value_t = 256)]
+    pub size: usize,
+
+    /// Pixels shared by neighbouring chips
+    #[arg(long, default_value_t = 0)]
+    pub overlap: usize,
+
+    /// Drop chips with a smaller fraction of valid pixels (0-1)
+    #[arg(long, default_value_t = 0.5)]
+    pub min_coverage: f64,
+
+    /// Chip file stem; {product}, {pol}, {date}, {row} and {col} are replaced
+    #[arg(long, default_value = "{product}_{pol}_r{row}_c{col}")]
+    pub name_template: String,
+
+    /// Chip samples: processed floats (linear backscatter or operation values, NaN nodata) or
+    /// autoscaled integers of --bit-depth (0 nodata)
+    #[arg(long, value_enum, default_value_t = ChipValues::Float)]
+    pub values: ChipValues,
+
+    /// Polarization (vv, vh, hh, hv), operation, or multiband for VV+VH / HH+HV chips
+    #[arg(long, value_enum, default_value_t = Polarization::Vv)]
+    pub polarization: Polarization,
+
+    /// Band-math expression over VV, VH, HH, HV; replaces --polarization
+    #[arg(long, allow_hyphen_values = true)]
+    pub expr: Option<String>,
+
+    /// Units of the --expr result: linear (converted to dB), db or native (stretched as-is)
+    #[arg(long, value_enum, default_value_t = ExprScale::Linear, requires = "expr")]
+    pub expr_scale: ExprScale,
+
+    /// Autoscaling strategy for --values autoscaled, applied once over the whole scene
+    #[arg(long, value_enum, default_value_t = AutoscaleStrategy::Clahe)]
+    pub autoscale: AutoscaleStrategy,
+
+    /// Bit depth of autoscaled chips (8 or 16)
+    #[arg(long, value_enum, default_value_t = BitDepthArg::U8)]
+    pub bit_depth: BitDepthArg,
+
+    /// dB value below which pixels are treated as no-data
+    #[arg(long, allow_hyphen_values = true, default_value_t = DEFAULT_DB_FLOOR)]
+    pub db_floor: f64,
+
+    #[command(flatten)]
+    pub grid: GridArgs,
+}
+
+#[derive(Args)]
+pub struct InfoArgs {
+    /// SAFE product to inspect
+    pub input: PathBuf,
+
+    /// Print as JSON instead of text
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    /// Enable logging
+    #[arg(long, default_value_t = false)]
+    pub log: bool,
+}
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    /// SAFE products to check
+    #[arg(required = true)]
+    pub inputs: Vec<PathBuf>,
+
+    /// Only check presence and sizes; skip the MD5 checksums
+    #[arg(long, default_value_t = false)]
+    pub no_checksum: bool,
+
+    /// Print the reports as JSON instead of text
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    /// Enable logging
+    #[arg(long, default_value_t = false)]
+    pub log: bool,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Tile folder with tilejson.json, .mbtiles or .pmtiles archive written by `sarpro tiles`
+    pub tiles: PathBuf,
+
+    /// Address and port to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub bind: String,
+
+    /// Enable logging
+    #[arg(long, default_value_t = false)]
+    pub log: bool,
 }
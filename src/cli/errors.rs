@@ -9,16 +9,38 @@ pub enum AppError {
     #[error("Size must be greater than 0, got: {size}")]
     ZeroSize { size: usize },
 
-    #[error(
-        "No complete polarization data available for operation: {operation}. Available: {available}"
-    )]
-    IncompleDataPair {
-        operation: String,
-        available: String,
-    },
-
     // #[error("Unsupported product type: {product}")]
     // UnsupportedProduct { product: String },
+    #[error("Pixel spacing must be a positive number of meters, got: {spacing}")]
+    InvalidPixelSpacing { spacing: f64 },
+
+    #[error("Invalid AOI: {0}")]
+    InvalidAoi(String),
+
+    #[error("Invalid expression: {0}")]
+    Expression(#[from] sarpro::core::processing::expr::ExprError),
+
+    #[error("Product mode '{mode}' needs a single polarization (vv, vh, hh or hv), got: {polarization}")]
+    SingleBandRequired { mode: String, polarization: String },
+
+    #[error("--colormap needs a single-band output (a polarization, operation or --expr), got: multiband")]
+    ColormapMultiband,
+
+    #[error("Invalid fixed autoscale range: {0}")]
+    InvalidFixedRange(String),
+
+    #[error("Invalid ship detection options: {0}")]
+    InvalidShipOptions(String),
+
+    #[error("--kmz packages a JPEG or PNG image of the image or ships product, got: {0}")]
+    KmzUnsupported(String),
+
+    #[error("Invalid product filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("{invalid} of {total} products failed validation")]
+    ValidationFailed { invalid: usize, total: usize },
+
     #[error("Missing required argument: {arg}")]
     MissingArgument { arg: String },
 
@@ -1,373 +1,712 @@
-use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 
 use tracing::{info, warn};
 
-use gdal::raster::ResampleAlg;
-use sarpro::core::processing::save::{
-    save_processed_image, save_processed_multiband_image_sequential,
+use sarpro::api::{
+    StackOptions, TileOptions, TileServer, autoscale_from_reference, build_stacks,
+    build_stacks_from_dir, inspect_safe, process_change_to_path, process_products_to_path,
+    process_safe_to_chips, process_safe_to_path, process_safe_to_tiles, validate_safe,
+};
+use sarpro::core::params::ProcessingParams;
+use sarpro::core::processing::change::ChangeOptions;
+use sarpro::core::processing::chips::ChipOptions;
+use sarpro::core::processing::colormap::ColormapOptions;
+use sarpro::core::processing::expr::BandExpr;
+use sarpro::core::processing::autoscale::AutoscaleParams;
+use sarpro::core::processing::ships::ShipOptions;
+use sarpro::core::processing::water::WaterOptions;
+use sarpro::io::sentinel1::ReadOptions;
+use sarpro::io::writers::encode::{EncodeOptions, JpegOptions, WebpOptions};
+use sarpro::io::discovery::{DiscoveryOptions, ProductFilter, discover_products, read_product_list};
+use sarpro::io::Aoi;
+use sarpro::types::{OutputFormat, ProductMode, SyntheticRgbMode, TileArchive};
+use sarpro::{AutoscaleStrategy, Polarization};
+
+use super::args::{
+    BatchArgs, ChangeArgs, ChipArgs, CliArgs, Command, DiscoveryArgs, GridArgs, InfoArgs,
+    ProcessArgs, ProcessOptions, ServeArgs, StackArgs, TileArgs, ValidateArgs,
 };
-use sarpro::io::sentinel1::TargetCrsArg;
-use sarpro::io::SafeReader;
-use sarpro::types::{BitDepth, OutputFormat, ProcessingOperation, SyntheticRgbMode};
-use sarpro::{AutoscaleStrategy, BitDepthArg, InputFormat, Polarization, PolarizationOperation};
-
-use super::args::CliArgs;
 use super::errors::AppError;
 
-fn process_single_file(
-    input: &PathBuf,
-    output: &PathBuf,
-    format: OutputFormat,
-    bit_depth: BitDepthArg,
-    input_format: InputFormat,
-    polarization: Polarization,
-    autoscale: AutoscaleStrategy,
-    size: &str,
-    batch_mode: bool,
-    pad: bool,
-    target_crs: Option<&str>,
-    resample_alg: Option<&str>,
-    synrgb_mode: SyntheticRgbMode,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let polarization_str = match polarization {
-        Polarization::Vv => None,
-        Polarization::Vh => Some("vh"),
-        Polarization::Hh => Some("hh"),
-        Polarization::Hv => Some("hv"),
-        Polarization::Multiband | Polarization::OP(_) => Some("all_pairs"),
+/// Long-side size from `--size`: None for "original", otherwise a positive pixel count
+fn parse_target_size(size: &str) -> Result<Option<usize>, AppError> {
+    if size == "original" {
+        return Ok(None);
+    }
+    let parsed_size = size.parse::<usize>().map_err(|_| AppError::InvalidSize {
+        size: size.to_string(),
+    })?;
+    if parsed_size == 0 {
+        return Err(AppError::ZeroSize { size: parsed_size });
+    }
+    Ok(Some(parsed_size))
+}
+
+/// Autoscale strategy from --autoscale, overridden by --fixed-range or --autoscale-reference
+fn autoscale_from_args(
+    args: &ProcessOptions,
+) -> Result<AutoscaleStrategy, Box<dyn std::error::Error>> {
+    if let Some(reference) = &args.autoscale_reference {
+        return Ok(autoscale_from_reference(reference)?);
+    }
+    let Some(range) = args.fixed_range.as_deref() else {
+        return Ok(args.autoscale);
     };
+    let bounds: Vec<f64> = range
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| AppError::InvalidFixedRange(format!("'{}' is not MIN,MAX in dB", range)))?;
+    match bounds[..] {
+        [min_db, max_db] if min_db < max_db && args.fixed_gamma > 0.0 => {
+            Ok(AutoscaleStrategy::Fixed {
+                min_db,
+                max_db,
+                gamma: args.fixed_gamma,
+            })
+        }
+        [_, _] => Err(AppError::InvalidFixedRange(format!(
+            "need MIN < MAX and a positive --fixed-gamma, got '{}' and {}",
+            range, args.fixed_gamma
+        ))
+        .into()),
+        _ => Err(AppError::InvalidFixedRange(format!("'{}' is not MIN,MAX in dB", range)).into()),
+    }
+}
 
-    // autoscale now passed as typed enum directly
+/// Percentile, gamma, CLAHE and dB-floor overrides from the autoscale tuning flags
+fn autoscale_params_from_args(args: &ProcessOptions) -> AutoscaleParams {
+    AutoscaleParams {
+        low_percentile: args.low_percentile,
+        high_percentile: args.high_percentile,
+        gamma: args.gamma,
+        clahe_tiles_x: args.clahe_tiles_x,
+        clahe_tiles_y: args.clahe_tiles_y,
+        clahe_clip_limit: args.clahe_clip_limit,
+        clahe_bins: args.clahe_bins,
+        db_floor: args.db_floor,
+    }
+}
 
-    let target_size = if size == "original" {
-        None
-    } else {
-        let parsed_size = size.parse::<usize>().map_err(|_| AppError::InvalidSize {
-            size: size.to_string(),
-        })?;
+/// Debug-level logging for `--log`
+fn init_logging(log: bool) {
+    if log {
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .init();
+    }
+}
 
-        if parsed_size == 0 {
-            return Err(AppError::ZeroSize { size: parsed_size }.into());
+/// `--pixel-spacing` must be a positive number of meters
+fn check_pixel_spacing(spacing: Option<f64>) -> Result<(), AppError> {
+    match spacing {
+        Some(spacing) if !spacing.is_finite() || spacing <= 0.0 => {
+            Err(AppError::InvalidPixelSpacing { spacing })
         }
+        _ => Ok(()),
+    }
+}
+
+/// Grid, resampling and AOI parameters from the flags shared by `change`, `stack`, `tiles`
+/// and `chips`
+fn grid_params_from_args(args: &GridArgs) -> Result<ProcessingParams, AppError> {
+    check_pixel_spacing(args.pixel_spacing)?;
+    Ok(ProcessingParams {
+        target_crs: Some(args.target_crs.clone()),
+        resample_alg: args.resample_alg.clone(),
+        aoi: parse_aoi(args.aoi_bbox.as_deref(), args.aoi.as_deref())?,
+        pixel_spacing: args.pixel_spacing,
+        ..Default::default()
+    })
+}
+
+fn parse_aoi(
+    aoi_bbox: Option<&str>,
+    aoi_path: Option<&std::path::Path>,
+) -> Result<Option<Aoi>, AppError> {
+    match (aoi_bbox, aoi_path) {
+        (Some(bbox), _) => Ok(Some(
+            Aoi::from_bbox_str(bbox).map_err(|e| AppError::InvalidAoi(e.to_string()))?,
+        )),
+        (None, Some(path)) => Ok(Some(
+            Aoi::from_geojson_file(path).map_err(|e| AppError::InvalidAoi(e.to_string()))?,
+        )),
+        (None, None) => Ok(None),
+    }
+}
+
+fn read_options_from_args(args: &ProcessOptions) -> Result<ReadOptions, AppError> {
+    let aoi = parse_aoi(args.aoi_bbox.as_deref(), args.aoi.as_deref())?;
+    if args.mask_outside_aoi && aoi.is_none() {
+        return Err(AppError::MissingArgument {
+            arg: "--aoi or --aoi-bbox (required by --mask-outside-aoi)".to_string(),
+        });
+    }
+    check_pixel_spacing(args.pixel_spacing)?;
+    Ok(ReadOptions {
+        aoi,
+        mask_outside_aoi: args.mask_outside_aoi,
+        pixel_spacing: args.pixel_spacing,
+        target_aligned_pixels: args.target_aligned_pixels,
+    })
+}
 
-        Some(parsed_size)
+fn run_change(args: ChangeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(args.grid.log);
+
+    let params = ProcessingParams {
+        polarization: args.polarization,
+        target_aligned_pixels: true,
+        ..grid_params_from_args(&args.grid)?
+    };
+    let options = ChangeOptions {
+        method: args.method,
+        mask: args.mask,
+        threshold: args.threshold,
+        colormap: ColormapOptions::resolve(
+            args.colormap,
+            args.colormap_file.as_deref(),
+            args.legend,
+        )?,
     };
 
-    let reader = if batch_mode {
-        match input_format {
-            InputFormat::Safe => {
-                // Map resample algorithm
-                let resample = match resample_alg {
-                    Some("nearest") => Some(ResampleAlg::NearestNeighbour),
-                    Some("bilinear") => Some(ResampleAlg::Bilinear),
-                    Some("cubic") => Some(ResampleAlg::Cubic),
-                    Some("lanczos") => Some(ResampleAlg::Lanczos),
-                    _ => None,
-                };
-                // Map target CRS string into internal enum; resolution happens inside reader
-                let target_arg: Option<TargetCrsArg> = match target_crs {
-                    Some(t) if t.eq_ignore_ascii_case("none") => Some(TargetCrsArg::None),
-                    Some(t) if t.eq_ignore_ascii_case("auto") => Some(TargetCrsArg::Auto),
-                    Some(t) => Some(TargetCrsArg::Custom(t.to_string())),
-                    None => None,
-                };
-                match SafeReader::open_with_warnings_with_options(
-                    input,
-                    polarization_str,
-                    target_arg,
-                    resample,
-                    target_size,
-                )? {
-                Some(reader) => reader,
-                None => {
-                    warn!("Skipping unsupported product type: {:?}", input);
-                    return Ok(());
+    info!("Change: {:?} -> {:?}", args.before, args.after);
+    let report = process_change_to_path(&args.before, &args.after, &args.output, &params, &options)?;
+    info!(
+        "Change written to {:?}: {} {}x{}, threshold {:?}, +{} / -{} pixels",
+        args.output,
+        report.polarizations.join(","),
+        report.width,
+        report.height,
+        report.threshold,
+        report.increased_pixels,
+        report.decreased_pixels
+    );
+    if let Some(preview) = &report.preview_path {
+        info!("Change preview written to {:?}", preview);
+    }
+    Ok(())
+}
+
+fn run_stack(args: StackArgs) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(args.grid.log);
+
+    let params = ProcessingParams {
+        polarization: args.polarization,
+        target_aligned_pixels: true,
+        ..grid_params_from_args(&args.grid)?
+    };
+    let options = StackOptions {
+        format: args.format,
+        stats: if args.no_stats { Vec::new() } else { args.stats },
+        min_dates: args.min_dates,
+    };
+
+    let reports = match &args.input_dir {
+        Some(dir) => build_stacks_from_dir(dir, &args.output_dir, &params, &options)?,
+        None => build_stacks(&args.inputs, &args.output_dir, &params, &options)?,
+    };
+    if reports.is_empty() {
+        warn!("No stack written: no group reached {} dates", options.min_dates);
+    }
+    for report in &reports {
+        info!(
+            "{}: {} dates, {}x{} -> {:?}",
+            report.name,
+            report.dates.len(),
+            report.width,
+            report.height,
+            report.stack_path
+        );
+    }
+    Ok(())
+}
+
+fn run_tiles(args: TileArgs) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(args.grid.log);
+
+    let params = ProcessingParams {
+        polarization: args.polarization,
+        autoscale: args.autoscale,
+        synrgb_mode: args.synrgb_mode,
+        colormap: args.colormap,
+        colormap_file: args.colormap_file,
+        encode: EncodeOptions {
+            jpeg: JpegOptions {
+                quality: args.jpeg_quality,
+                ..Default::default()
+            },
+            webp: WebpOptions {
+                quality: args.webp_quality,
+                lossless: args.webp_lossless,
+            },
+        },
+        ..grid_params_from_args(&args.grid)?
+    };
+    let (output, archive) = match (args.output_dir, args.output) {
+        (Some(dir), _) => (dir, None),
+        (None, Some(file)) => {
+            let archive = args.archive.or_else(|| TileArchive::from_path(&file)).ok_or_else(|| {
+                AppError::MissingArgument {
+                    arg: "--archive (the --output extension is neither .mbtiles nor .pmtiles)"
+                        .to_string(),
                 }
-            }
+            })?;
+            (file, Some(archive))
         }
-        }
-    } else {
-        match input_format {
-            InputFormat::Safe => {
-                let resample_alg = match resample_alg {
-                    Some("nearest") => Some(ResampleAlg::NearestNeighbour),
-                    Some("bilinear") => Some(ResampleAlg::Bilinear),
-                    Some("cubic") => Some(ResampleAlg::Cubic),
-                    Some("lanczos") => Some(ResampleAlg::Lanczos),
-                    _ => None,
-                };
-                let target_arg: Option<TargetCrsArg> = match target_crs {
-                    Some(t) if t.eq_ignore_ascii_case("none") => Some(TargetCrsArg::None),
-                    Some(t) if t.eq_ignore_ascii_case("auto") => Some(TargetCrsArg::Auto),
-                    Some(t) => Some(TargetCrsArg::Custom(t.to_string())),
-                    None => None,
-                };
-                SafeReader::open_with_options(
-                    input,
-                    polarization_str,
-                    target_arg,
-                    resample_alg,
-                    target_size,
-                )?
+        (None, None) => {
+            return Err(AppError::MissingArgument {
+                arg: "--output-dir or --output".to_string(),
             }
+            .into());
         }
     };
+    let options = TileOptions {
+        format: args.format,
+        min_zoom: args.min_zoom,
+        max_zoom: args.max_zoom,
+        tile_size: args.tile_size,
+        base_url: args.tile_url,
+        archive,
+    };
+
+    let report = process_safe_to_tiles(&args.input, &output, &params, &options)?;
+    info!(
+        "{} tiles at zoom {}..={} written to {:?}",
+        report.tiles_per_zoom.iter().sum::<usize>(),
+        report.min_zoom,
+        report.max_zoom,
+        report.output
+    );
+    Ok(())
+}
 
-    let bit_depth_enum = match bit_depth {
-        BitDepthArg::U8 => BitDepth::U8,
-        BitDepthArg::U16 => BitDepth::U16,
+fn run_chips(args: ChipArgs) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(args.grid.log);
+
+    let params = ProcessingParams {
+        polarization: args.polarization,
+        expression: args.expr,
+        expression_scale: args.expr_scale,
+        autoscale: args.autoscale,
+        bit_depth: args.bit_depth,
+        autoscale_params: AutoscaleParams {
+            db_floor: args.db_floor,
+            ..Default::default()
+        },
+        target_aligned_pixels: true,
+        ..grid_params_from_args(&args.grid)?
+    };
+    let options = ChipOptions {
+        size: args.size,
+        overlap: args.overlap,
+        min_coverage: args.min_coverage,
+        name_template: args.name_template,
+        values: args.values,
     };
 
-    match polarization {
-        Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv => {
-            let processed = match polarization {
-                Polarization::Vv => reader.vv_data()?,
-                Polarization::Vh => reader.vh_data()?,
-                Polarization::Hh => reader.hh_data()?,
-                Polarization::Hv => reader.hv_data()?,
-                _ => unreachable!(),
-            };
+    let report = process_safe_to_chips(&args.input, &args.output_dir, &params, &options)?;
+    info!(
+        "{} chips ({}) on {} written to {:?}, {} below coverage skipped",
+        report.chips.len(),
+        report.bands.join("+"),
+        report.crs,
+        args.output_dir,
+        report.skipped
+    );
+    Ok(())
+}
 
-            let bytes = processed.len() * std::mem::size_of::<f32>();
-            info!(
-                "Memory usage (approx): {:.2} MB",
-                bytes as f64 / 1024.0 / 1024.0
-            );
+fn run_info(args: InfoArgs) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(args.log);
 
-            save_processed_image(
-                &processed,
-                output.as_path(),
-                format,
-                bit_depth_enum,
-                target_size,
-                Some(reader.metadata()),
-                pad,
-                autoscale,
-                ProcessingOperation::SingleBand,
-            )
-        }
-        Polarization::Multiband => {
-            if reader.vv_data().is_ok() && reader.vh_data().is_ok() {
-                let vv_processed = reader.vv_data()?;
-                let vh_processed = reader.vh_data()?;
-
-                let total_bytes =
-                    (vv_processed.len() + vh_processed.len()) * std::mem::size_of::<f32>();
-                info!(
-                    "Memory usage (Multiband VV/VH): {:.2} MB",
-                    total_bytes as f64 / 1024.0 / 1024.0
-                );
-
-                save_processed_multiband_image_sequential(
-                    &vv_processed,
-                    &vh_processed,
-                    output.as_path(),
-                    format,
-                    bit_depth_enum,
-                    target_size,
-                    Some(reader.metadata()),
-                    pad,
-                    autoscale,
-                    ProcessingOperation::MultibandVvVh,
-                    synrgb_mode,
-                )
-            } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
-                let hh_processed = reader.hh_data()?;
-                let hv_processed = reader.hv_data()?;
-
-                let total_bytes =
-                    (hh_processed.len() + hv_processed.len()) * std::mem::size_of::<f32>();
-                info!(
-                    "Memory usage (Multiband HH/HV): {:.2} MB",
-                    total_bytes as f64 / 1024.0 / 1024.0
-                );
-
-                save_processed_multiband_image_sequential(
-                    &hh_processed,
-                    &hv_processed,
-                    output.as_path(),
-                    format,
-                    bit_depth_enum,
-                    target_size,
-                    Some(reader.metadata()),
-                    pad,
-                    autoscale,
-                    ProcessingOperation::MultibandHhHv,
-                    synrgb_mode,
-                )
-            } else {
-                let available = reader.get_available_polarizations();
-                return Err(AppError::IncompleDataPair {
-                    operation: "multiband".to_string(),
-                    available,
-                }
-                .into());
-            }
+    let product = inspect_safe(&args.input)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&product)?);
+        return Ok(());
+    }
+
+    let meta = &product.metadata;
+    let or_dash = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+    println!("Product:        {}", or_dash(meta.product_id.clone()));
+    println!("Path:           {}", product.path.display());
+    println!("Platform:       {} {}", meta.platform, meta.instrument);
+    println!(
+        "Type / mode:    {} / {}",
+        meta.product_type,
+        or_dash(meta.instrument_mode.clone())
+    );
+    println!("Start (UTC):    {}", meta.acquisition_start);
+    println!("Stop (UTC):     {}", meta.acquisition_stop);
+    println!(
+        "Orbit:          {} (relative {}), {}",
+        meta.orbit_number,
+        or_dash(meta.relative_orbit.map(|o| o.to_string())),
+        or_dash(meta.pass_direction.clone())
+    );
+    println!("Polarizations:  {}", product.polarizations.join(", "));
+    println!("Size:           {} x {} pixels", product.width, product.height);
+    if let (Some(range), Some(azimuth)) = (meta.pixel_spacing_range, meta.pixel_spacing_azimuth) {
+        println!("Pixel spacing:  {} m range, {} m azimuth", range, azimuth);
+    }
+    println!("Auto CRS:       {}", or_dash(product.auto_crs.clone()));
+    match &product.footprint {
+        Some(footprint) => {
+            let [west, south, east, north] = footprint.bounds();
+            println!(
+                "Bounds:         W {:.4} S {:.4} E {:.4} N {:.4}{}",
+                west,
+                south,
+                east,
+                north,
+                if footprint.crosses_antimeridian() { " (crosses antimeridian)" } else { "" }
+            );
+            println!("Footprint:      {}", footprint.to_wkt());
         }
-        Polarization::OP(_) => {
-            let processed = if reader.vv_data().is_ok() && reader.vh_data().is_ok() {
-                match polarization {
-                    Polarization::OP(PolarizationOperation::Sum) => reader.sum_data()?,
-                    Polarization::OP(PolarizationOperation::Diff) => reader.difference_data()?,
-                    Polarization::OP(PolarizationOperation::Ratio) => reader.ratio_data()?,
-                    Polarization::OP(PolarizationOperation::NDiff) => {
-                        reader.normalized_diff_data()?
-                    }
-                    Polarization::OP(PolarizationOperation::LogRatio) => reader.log_ratio_data()?,
-                    _ => unreachable!(),
-                }
-            } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
-                match polarization {
-                    Polarization::OP(PolarizationOperation::Sum) => reader.sum_hh_hv_data()?,
-                    Polarization::OP(PolarizationOperation::Diff) => {
-                        reader.difference_hh_hv_data()?
-                    }
-                    Polarization::OP(PolarizationOperation::Ratio) => reader.ratio_hh_hv_data()?,
-                    Polarization::OP(PolarizationOperation::NDiff) => {
-                        reader.normalized_diff_hh_hv_data()?
-                    }
-                    Polarization::OP(PolarizationOperation::LogRatio) => {
-                        reader.log_ratio_hh_hv_data()?
-                    }
-                    _ => unreachable!(),
-                }
-            } else {
-                let available = reader.get_available_polarizations();
-                return Err(AppError::IncompleDataPair {
-                    operation: polarization.to_string(),
-                    available,
-                }
-                .into());
-            };
+        None => println!("Footprint:      -"),
+    }
+    println!("Files ({}, {} bytes):", product.files.len(), product.total_size);
+    for file in &product.files {
+        println!("  {:>12}  {}", file.size, file.path.display());
+    }
+    Ok(())
+}
+
+fn run_validate(args: ValidateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(args.log);
 
-            let bytes = processed.len() * std::mem::size_of::<f32>();
-            info!(
-                "Memory usage (approx): {:.2} MB",
-                bytes as f64 / 1024.0 / 1024.0
+    let mut reports = Vec::with_capacity(args.inputs.len());
+    for input in &args.inputs {
+        reports.push(validate_safe(input, !args.no_checksum)?);
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in &reports {
+            let status = if report.is_valid() { "OK" } else { "INVALID" };
+            let skipped = match report.checksums_skipped {
+                0 => String::new(),
+                n => format!(", {} skipped", n),
+            };
+            println!(
+                "{}  {} ({} files, {} checksums verified{})",
+                status,
+                report.path.display(),
+                report.objects,
+                report.checksums_verified,
+                skipped
             );
+            for issue in &report.issues {
+                println!("    {}", issue);
+            }
+        }
+    }
 
-            save_processed_image(
-                &processed,
-                output.as_path(),
-                format,
-                bit_depth_enum,
-                target_size,
-                Some(reader.metadata()),
-                pad,
-                autoscale,
-                ProcessingOperation::PolarOp(match polarization {
-                    Polarization::OP(op) => op,
-                    _ => unreachable!(),
-                }),
-            )
+    let invalid = reports.iter().filter(|r| !r.is_valid()).count();
+    if invalid > 0 {
+        return Err(AppError::ValidationFailed {
+            invalid,
+            total: reports.len(),
         }
+        .into());
     }
+    Ok(())
 }
 
-pub fn run(args: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
-    if args.log {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .init();
+fn run_serve(args: ServeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    init_logging(args.log);
+
+    let server = TileServer::bind(&args.tiles, &args.bind)?;
+    let meta = server.metadata();
+    let addr = server.local_addr();
+    println!(
+        "Serving {} ({} tiles, zoom {}-{})",
+        args.tiles.display(),
+        meta.format,
+        meta.min_zoom,
+        meta.max_zoom
+    );
+    println!("TileJSON:  http://{}/tilejson.json", addr);
+    println!("Tiles:     http://{}/{{z}}/{{x}}/{{y}}.{}", addr, meta.format.extension());
+    if let Some(archive) = server.archive_path() {
+        println!("Archive:   http://{}{} (range requests)", addr, archive);
     }
+    println!("Press Ctrl+C to stop");
+    server.run()?;
+    Ok(())
+}
 
-    let batch_mode = args.batch || args.input_dir.is_some();
-
-    if batch_mode {
-        let input_dir = args.input_dir.ok_or(AppError::MissingArgument {
-            arg: "--input-dir".to_string(),
-        })?;
-        let output_dir = args.output_dir.ok_or(AppError::MissingArgument {
-            arg: "--output-dir".to_string(),
-        })?;
-
-        fs::create_dir_all(&output_dir)?;
-
-        info!("Starting batch processing from directory: {:?}", input_dir);
-        info!("Output directory: {:?}", output_dir);
-
-        let mut processed = 0;
-        let mut skipped = 0;
-        let mut errors = 0;
-
-        for entry in fs::read_dir(&input_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                let safe_name = path.file_name().unwrap().to_string_lossy();
-                let output_name = format!(
-                    "{}.{}",
-                    safe_name,
-                    match args.format {
-                        OutputFormat::TIFF => "tiff",
-                        OutputFormat::JPEG => "jpg",
-                    }
-                );
-                let output_path = output_dir.join(&output_name);
-
-                info!("Processing: {:?} -> {:?}", path, output_path);
-
-                match process_single_file(
-                    &path,
-                    &output_path,
-                    args.format,
-                    args.bit_depth,
-                    args.input_format,
-                    args.polarization,
-                    args.autoscale,
-                    &args.size,
-                    true,
-                    args.pad,
-                    args.target_crs.as_deref(),
-                    args.resample_alg.as_deref(),
-                    args.synrgb_mode,
-                ) {
-                    Ok(()) => {
-                        info!("Successfully processed: {:?}\n", path);
-                        processed += 1;
-                    }
-                    Err(e) => {
-                        warn!("Error processing {:?}: {}", path, e);
-                        errors += 1;
-                    }
-                }
-            } else {
-                info!("Skipping non-directory: {:?}", path);
-                skipped += 1;
-            }
+/// Discovery options from the `batch` recursion, pattern and filter flags
+fn discovery_from_args(args: &DiscoveryArgs) -> Result<DiscoveryOptions, AppError> {
+    if let (Some(start), Some(end)) = (args.start_date, args.end_date) {
+        if start > end {
+            return Err(AppError::InvalidFilter(format!(
+                "--start-date {} is after --end-date {}",
+                start, end
+            )));
         }
+    }
+    let orbits = args
+        .orbit
+        .iter()
+        .map(|o| ProductFilter::parse_orbit_range(o))
+        .collect::<Result<_, _>>()
+        .map_err(|e| AppError::InvalidFilter(e.to_string()))?;
+    Ok(DiscoveryOptions {
+        recursive: args.recursive,
+        patterns: args.patterns.clone(),
+        filter: ProductFilter {
+            platforms: args.platform.clone(),
+            modes: args.mode.clone(),
+            polarizations: args.polarization_set.clone(),
+            start_date: args.start_date,
+            end_date: args.end_date,
+            orbits,
+            pass_direction: args.pass.as_ref().map(|p| p.to_uppercase()),
+        },
+    })
+}
 
-        info!("Batch processing complete!");
-        info!("Processed: {}", processed);
-        info!("Skipped: {}", skipped);
-        info!("Errors: {}", errors);
-    } else {
-        let input = args.input.ok_or(AppError::MissingArgument {
-            arg: "--input".to_string(),
-        })?;
-        let output = args.output.ok_or(AppError::MissingArgument {
-            arg: "--output".to_string(),
-        })?;
-
-        process_single_file(
-            &input,
-            &output,
-            args.format,
-            args.bit_depth,
-            args.input_format,
+/// Validated settings shared by `process` and `batch`
+struct ProcessSetup {
+    read_opts: ReadOptions,
+    autoscale: AutoscaleStrategy,
+    autoscale_params: AutoscaleParams,
+    encode: EncodeOptions,
+    water: WaterOptions,
+    ships: ShipOptions,
+}
+
+/// Set up logging and check the processing flags before any product is opened
+fn process_setup(args: &ProcessOptions) -> Result<ProcessSetup, Box<dyn std::error::Error>> {
+    init_logging(args.log);
+
+    let read_opts = read_options_from_args(args)?;
+    let autoscale = autoscale_from_args(args)?;
+    let autoscale_params = autoscale_params_from_args(args);
+    autoscale_params.validate()?;
+    let colormap =
+        ColormapOptions::resolve(args.colormap, args.colormap_file.as_deref(), args.legend)?;
+    if colormap.is_some() && args.expr.is_none() && args.polarization == Polarization::Multiband {
+        return Err(AppError::ColormapMultiband.into());
+    }
+    let encode = EncodeOptions {
+        jpeg: JpegOptions {
+            quality: args.jpeg_quality,
+            progressive: args.jpeg_progressive,
+            subsampling: args.jpeg_subsampling,
+            tiff_fallback: args.jpeg_tiff_fallback,
+        },
+        webp: WebpOptions {
+            quality: args.webp_quality,
+            lossless: args.webp_lossless,
+        },
+    };
+    encode.validate()?;
+    if let Some(expr) = args.expr.as_deref() {
+        BandExpr::parse(expr).map_err(AppError::from)?;
+    }
+
+    let water = WaterOptions {
+        method: args.water_threshold,
+        tile_refinement: args.water_tile_refine,
+        tile_size: args.water_tile_size,
+    };
+    let ships = ShipOptions {
+        guard: args.cfar_guard,
+        background: args.cfar_background,
+        pfa: args.cfar_pfa,
+        min_pixels: args.ship_min_pixels,
+        max_pixels: args.ship_max_pixels,
+    };
+    if args.product != ProductMode::Image
+        && !matches!(
             args.polarization,
-            args.autoscale,
-            &args.size,
-            false,
-            args.pad,
-            args.target_crs.as_deref(),
-            args.resample_alg.as_deref(),
-            args.synrgb_mode,
-        )?;
-        info!("Successfully processed: {:?} -> {:?}\n", input, output);
+            Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv
+        )
+    {
+        return Err(AppError::SingleBandRequired {
+            mode: args.product.to_string(),
+            polarization: args.polarization.to_string(),
+        }
+        .into());
+    }
+    if args.product == ProductMode::Ships {
+        if ships.guard >= ships.background {
+            return Err(AppError::InvalidShipOptions(format!(
+                "--cfar-background ({}) must be larger than --cfar-guard ({})",
+                ships.background, ships.guard
+            ))
+            .into());
+        }
+        if !(ships.pfa > 0.0 && ships.pfa < 1.0) {
+            return Err(AppError::InvalidShipOptions(format!(
+                "--cfar-pfa must be in (0, 1), got {}",
+                ships.pfa
+            ))
+            .into());
+        }
+    }
+    if args.kmz
+        && (args.product == ProductMode::Water
+            || !matches!(args.format, OutputFormat::JPEG | OutputFormat::PNG))
+    {
+        return Err(AppError::KmzUnsupported(format!(
+            "--format {:?} --product {}",
+            args.format, args.product
+        ))
+        .into());
     }
+    if args.product == ProductMode::Water && args.format != OutputFormat::TIFF {
+        warn!("Water masks are always written as GeoTIFF; ignoring --format {:?}", args.format);
+    }
+    if args.synrgb_mode != SyntheticRgbMode::Default
+        && (args.polarization != Polarization::Multiband
+            || args.expr.is_some()
+            || args.format == OutputFormat::TIFF)
+    {
+        warn!(
+            "--synrgb-mode only applies to multiband JPEG, PNG and WebP outputs; ignoring {}",
+            args.synrgb_mode
+        );
+    }
+
+    Ok(ProcessSetup {
+        read_opts,
+        autoscale,
+        autoscale_params,
+        encode,
+        water,
+        ships,
+    })
+}
+
+/// Shared processing parameters from the `process`/`batch` flags and their checked setup
+fn processing_params(
+    opts: &ProcessOptions,
+    setup: &ProcessSetup,
+) -> Result<ProcessingParams, AppError> {
+    Ok(ProcessingParams {
+        format: opts.format,
+        input_format: opts.input_format,
+        bit_depth: opts.bit_depth,
+        polarization: opts.polarization,
+        // --autoscale-reference is already resolved into a Fixed strategy
+        autoscale: setup.autoscale,
+        autoscale_params: setup.autoscale_params,
+        colormap: opts.colormap,
+        colormap_file: opts.colormap_file.clone(),
+        colormap_legend: opts.legend,
+        synrgb_mode: opts.synrgb_mode,
+        size: parse_target_size(&opts.size)?,
+        pad: opts.pad,
+        target_crs: opts.target_crs.clone(),
+        resample_alg: opts.resample_alg.clone(),
+        aoi: setup.read_opts.aoi.clone(),
+        mask_outside_aoi: setup.read_opts.mask_outside_aoi,
+        pixel_spacing: setup.read_opts.pixel_spacing,
+        target_aligned_pixels: setup.read_opts.target_aligned_pixels,
+        expression: opts.expr.clone(),
+        expression_scale: opts.expr_scale,
+        product: opts.product,
+        water: setup.water.clone(),
+        ships: setup.ships.clone(),
+        assemble_slices: false,
+        validate_inputs: false,
+        discovery: DiscoveryOptions::default(),
+        autoscale_reference: None,
+        encode: setup.encode,
+        kmz: opts.kmz,
+        footprint: opts.footprint,
+    })
+}
+
+fn run_process(args: ProcessArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let opts = &args.options;
+    let setup = process_setup(opts)?;
+    let (input, output) = (&args.input, &args.output);
+
+    process_safe_to_path(input, output, &processing_params(opts, &setup)?)?;
+    info!("Successfully processed: {:?} -> {:?}\n", input, output);
+    Ok(())
+}
+
+fn run_batch(args: BatchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let opts = &args.options;
+    let setup = process_setup(opts)?;
+    let output_dir = &args.output_dir;
+    let discovery = discovery_from_args(&args.discovery)?;
+    let products = match (&args.input_dir, &args.input_list) {
+        (_, Some(list)) => {
+            info!("Starting batch processing from list: {:?}", list);
+            discovery.select(read_product_list(list)?, Path::new(""))?
+        }
+        (Some(input_dir), None) => {
+            info!("Starting batch processing from directory: {:?}", input_dir);
+            discover_products(input_dir, &discovery)?
+        }
+        (None, None) => {
+            return Err(AppError::MissingArgument {
+                arg: "--input-dir or --input-list".to_string(),
+            }
+            .into());
+        }
+    };
 
+    info!("Found {} products", products.len());
+    info!("Output directory: {:?}", output_dir);
+
+    let params = ProcessingParams {
+        assemble_slices: args.assemble_slices,
+        validate_inputs: args.validate,
+        discovery,
+        ..processing_params(opts, &setup)?
+    };
+    let report = process_products_to_path(&products, output_dir, &params, true)?;
+
+    info!("Batch processing complete!");
+    info!("Processed: {}", report.processed);
+    info!("Skipped: {}", report.skipped);
+    info!("Errors: {}", report.errors);
     Ok(())
 }
+
+pub fn run(args: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let command = match args.command {
+        Some(command) => command,
+        // Flags without a subcommand keep their original meaning
+        None if args.batch || args.input_dir.is_some() => Command::Batch(BatchArgs {
+            input_dir: Some(args.input_dir.ok_or(AppError::MissingArgument {
+                arg: "--input-dir".to_string(),
+            })?),
+            input_list: None,
+            output_dir: args.output_dir.ok_or(AppError::MissingArgument {
+                arg: "--output-dir".to_string(),
+            })?,
+            assemble_slices: args.assemble_slices,
+            validate: args.validate,
+            discovery: DiscoveryArgs::default(),
+            options: args.options,
+        }),
+        None => Command::Process(ProcessArgs {
+            input: args.input.ok_or(AppError::MissingArgument {
+                arg: "--input".to_string(),
+            })?,
+            output: args.output.ok_or(AppError::MissingArgument {
+                arg: "--output".to_string(),
+            })?,
+            options: args.options,
+        }),
+    };
+    match command {
+        Command::Process(process) => run_process(process),
+        Command::Batch(batch) => run_batch(batch),
+        Command::Info(info) => run_info(info),
+        Command::Validate(validate) => run_validate(validate),
+        Command::Change(change) => run_change(change),
+        Command::Stack(stack) => run_stack(stack),
+        Command::Tiles(tiles) => run_tiles(tiles),
+        Command::Chips(chips) => run_chips(chips),
+        Command::Serve(serve) => run_serve(serve),
+    }
+}
@@ -1,6 +1,17 @@
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-use crate::types::{OutputFormat, SyntheticRgbMode};
+use crate::core::processing::autoscale::AutoscaleParams;
+use crate::core::processing::colormap::ColormapOptions;
+use crate::core::processing::save::SaveOptions;
+use crate::error::Result;
+use crate::io::aoi::Aoi;
+use crate::io::discovery::DiscoveryOptions;
+use crate::io::writers::encode::EncodeOptions;
+use crate::io::sentinel1::ReadOptions;
+use crate::core::processing::ships::ShipOptions;
+use crate::core::processing::water::WaterOptions;
+use crate::types::{BitDepth, Colormap, ExprScale, OutputFormat, ProductMode, SyntheticRgbMode};
 use crate::{AutoscaleStrategy, BitDepthArg, InputFormat, Polarization};
 
 /// Processing parameters suitable for config files and GUI presets
@@ -11,7 +22,10 @@ pub struct ProcessingParams {
     pub bit_depth: BitDepthArg,
     pub polarization: Polarization,
     pub autoscale: AutoscaleStrategy,
-    /// Synthetic RGB mode for multiband JPEG outputs; ignored otherwise
+    /// Percentile, gamma, CLAHE and dB-floor overrides for the autoscale stage
+    #[serde(default)]
+    pub autoscale_params: AutoscaleParams,
+    /// Synthetic RGB mode for multiband JPEG, PNG and WebP outputs; ignored otherwise
     pub synrgb_mode: SyntheticRgbMode,
     /// Target long side in pixels; None means original size
     pub size: Option<usize>,
@@ -21,6 +35,70 @@ pub struct ProcessingParams {
     pub target_crs: Option<String>,
     /// Optional resampling algorithm name (nearest, bilinear, cubic)
     pub resample_alg: Option<String>,
+    /// Optional area of interest (lon/lat bbox or GeoJSON polygons); only that window is read
+    #[serde(default)]
+    pub aoi: Option<Aoi>,
+    /// If true, pixels outside the AOI geometry are set to nodata
+    #[serde(default)]
+    pub mask_outside_aoi: bool,
+    /// Output ground resolution in meters (target CRS units when reprojecting);
+    /// when set, `size` is ignored
+    #[serde(default)]
+    pub pixel_spacing: Option<f64>,
+    /// Snap output pixels to multiples of `pixel_spacing` so different dates share a grid
+    #[serde(default)]
+    pub target_aligned_pixels: bool,
+    /// Optional band-math expression (e.g. "10*log10(VH/VV)"); replaces `polarization`
+    #[serde(default)]
+    pub expression: Option<String>,
+    /// Units of the `expression` result: linear intensity is converted to dB, dB and native
+    /// index values are stretched as-is
+    #[serde(default)]
+    pub expression_scale: ExprScale,
+    /// Output product; `Water` and `Ships` work on a single polarization
+    #[serde(default)]
+    pub product: ProductMode,
+    /// Thresholding options for the water product
+    #[serde(default)]
+    pub water: WaterOptions,
+    /// CA-CFAR and clustering options for the ships product
+    #[serde(default)]
+    pub ships: ShipOptions,
+    /// Batch only: mosaic consecutive slices of one datatake into a single product
+    #[serde(default)]
+    pub assemble_slices: bool,
+    /// Batch only: check every product against its manifest (file sizes and MD5) first and
+    /// count corrupted ones as errors instead of processing them
+    #[serde(default)]
+    pub validate_inputs: bool,
+    /// Batch only: how products are found under the input directory (recursion, glob
+    /// patterns, product-name filters)
+    #[serde(default)]
+    pub discovery: DiscoveryOptions,
+    /// Earlier output (raster or JSON sidecar) whose recorded clip window is reused as a
+    /// `Fixed` autoscale, so outputs share one stretch; overrides `autoscale`
+    #[serde(default)]
+    pub autoscale_reference: Option<PathBuf>,
+    /// Render single-band outputs through this built-in color ramp (RGB JPEG, RGBA PNG/WebP
+    /// or paletted TIFF)
+    #[serde(default)]
+    pub colormap: Option<Colormap>,
+    /// Custom JSON or CPT color ramp; takes precedence over `colormap`
+    #[serde(default)]
+    pub colormap_file: Option<PathBuf>,
+    /// With a colormap, also write `<output>.legend.jpg` labelled with the clip limits
+    #[serde(default)]
+    pub colormap_legend: bool,
+    /// Encoder settings for the lossy image formats (JPEG quality, progressive mode,
+    /// subsampling and oversize fallback; WebP quality and lossless mode)
+    #[serde(default)]
+    pub encode: EncodeOptions,
+    /// Also package JPEG/PNG outputs with a Google Earth `GroundOverlay` as `<output>.kmz`
+    #[serde(default)]
+    pub kmz: bool,
+    /// Also write the data footprint as `<output>.footprint.geojson` and `.footprint.wkt`
+    #[serde(default)]
+    pub footprint: bool,
 }
 
 impl Default for ProcessingParams {
@@ -31,11 +109,88 @@ impl Default for ProcessingParams {
             bit_depth: BitDepthArg::U8,
             polarization: Polarization::Vv,
             autoscale: AutoscaleStrategy::Clahe,
+            autoscale_params: AutoscaleParams::default(),
             synrgb_mode: SyntheticRgbMode::Default,
             size: None,
             pad: false,
             target_crs: None,
             resample_alg: Some("lanczos".to_string()),
+            aoi: None,
+            mask_outside_aoi: false,
+            pixel_spacing: None,
+            target_aligned_pixels: false,
+            expression: None,
+            expression_scale: ExprScale::Linear,
+            product: ProductMode::Image,
+            water: WaterOptions::default(),
+            ships: ShipOptions::default(),
+            assemble_slices: false,
+            validate_inputs: false,
+            discovery: DiscoveryOptions::default(),
+            autoscale_reference: None,
+            colormap: None,
+            colormap_file: None,
+            colormap_legend: false,
+            encode: EncodeOptions::default(),
+            kmz: false,
+            footprint: false,
+        }
+    }
+}
+
+impl ProcessingParams {
+    /// Reader options derived from these parameters
+    pub fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            aoi: self.aoi.clone(),
+            mask_outside_aoi: self.mask_outside_aoi,
+            pixel_spacing: self.pixel_spacing,
+            target_aligned_pixels: self.target_aligned_pixels,
+        }
+    }
+
+    /// Colormap rendering requested by `colormap_file` or `colormap`, if any
+    pub fn colormap_options(&self) -> Result<Option<ColormapOptions>> {
+        ColormapOptions::resolve(
+            self.colormap,
+            self.colormap_file.as_deref(),
+            self.colormap_legend,
+        )
+    }
+
+    /// Output settings for the save stage (fails when the colormap file cannot be read)
+    pub fn save_options(&self) -> Result<SaveOptions> {
+        Ok(SaveOptions {
+            format: self.format,
+            bit_depth: match self.bit_depth {
+                BitDepthArg::U8 => BitDepth::U8,
+                BitDepthArg::U16 => BitDepth::U16,
+            },
+            target_size: self.target_size(),
+            pad: self.pad,
+            strategy: self.autoscale,
+            autoscale_params: self.autoscale_params,
+            colormap: self.colormap_options()?,
+            encode: self.encode,
+        })
+    }
+
+    /// Long-side target size, or None when a fixed pixel spacing drives the output shape
+    pub fn target_size(&self) -> Option<usize> {
+        if self.pixel_spacing.is_some() {
+            None
+        } else {
+            self.size
+        }
+    }
+
+    /// Extension of the image written for these parameters; water masks are always GeoTIFF
+    pub fn output_extension(&self) -> &'static str {
+        match (self.product, self.format) {
+            (ProductMode::Water, _) | (_, OutputFormat::TIFF) => "tiff",
+            (_, OutputFormat::JPEG) => "jpg",
+            (_, OutputFormat::PNG) => "png",
+            (_, OutputFormat::WebP) => "webp",
         }
     }
 }
@@ -1,166 +1,539 @@
 use ndarray::Array2;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
+use crate::core::processing::mask::ValidMask;
+use crate::error::{Error, Result};
 use crate::{AutoscaleStrategy, BitDepth};
 
-/// Simple statistics and percentile estimates computed from a streaming histogram.
-struct HistogramStats {
-    valid_count: usize,
-    min_db: f64,
-    max_db: f64,
-    mean_db: f64,
-    std_db: f64,
-    median_db: f64,
-    p01: f64,
-    p02: f64,
-    p05: f64,
-    p10: f64,
-    p25: f64,
-    p75: f64,
-    p90: f64,
-    p95: f64,
-    p98: f64,
-    p99: f64,
+/// Lower edge of the fixed dB domain covered by [`DbHistogram`]
+pub const HIST_DB_MIN: f64 = -100.0;
+/// Width of the fixed dB domain covered by [`DbHistogram`] (dB)
+pub const HIST_DB_SPAN: usize = 200;
+/// Histogram resolution in bins per dB
+pub const HIST_BINS_PER_DB: usize = 64;
+const HIST_NUM_BINS: usize = HIST_DB_SPAN * HIST_BINS_PER_DB;
+
+/// Pixels per parallel work item. Multiple of 64 so chunks map onto whole `ValidMask` words.
+pub(crate) const PAR_CHUNK: usize = 64 * 1024;
+
+/// Entries in the gamma lookup table over the normalized 0..1 domain
+const GAMMA_LUT_SIZE: usize = 1 << 16;
+
+/// Pixels at or below this level (dB) are treated as nodata unless overridden
+pub const DEFAULT_DB_FLOOR: f64 = -50.0;
+
+/// Tuning knobs for the autoscale stage. Unset percentiles and gamma keep each strategy's
+/// own choice, so `AutoscaleParams::default()` reproduces the built-in stretches.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoscaleParams {
+    /// Lower clip percentile (0..100); replaces the strategy's lower cut
+    pub low_percentile: Option<f64>,
+    /// Upper clip percentile (0..100); replaces the strategy's upper cut
+    pub high_percentile: Option<f64>,
+    /// Gamma applied after clipping; replaces the strategy's gamma
+    pub gamma: Option<f64>,
+    /// CLAHE tile columns
+    pub clahe_tiles_x: usize,
+    /// CLAHE tile rows
+    pub clahe_tiles_y: usize,
+    /// CLAHE clip limit as a multiple of the mean bin count
+    pub clahe_clip_limit: f64,
+    /// CLAHE histogram bins per tile
+    pub clahe_bins: usize,
+    /// Pixels at or below this level (dB) are nodata
+    pub db_floor: f64,
 }
 
-#[inline]
-fn approx_eq(a: f64, b: f64) -> bool {
-    (a - b).abs() < 1e-9_f64
+impl Default for AutoscaleParams {
+    fn default() -> Self {
+        Self {
+            low_percentile: None,
+            high_percentile: None,
+            gamma: None,
+            clahe_tiles_x: 8,
+            clahe_tiles_y: 8,
+            clahe_clip_limit: 2.0,
+            clahe_bins: 256,
+            db_floor: DEFAULT_DB_FLOOR,
+        }
+    }
 }
 
-/// Compute robust percentiles and basic stats without materializing/sorting all values.
-/// Two-pass approach:
-///   1) Find min/max and Welford mean/std in a single pass over valid pixels
-///   2) Build a fixed-bin histogram over [min,max] and invert CDF for requested percentiles
-fn compute_histogram_stats(db: &Array2<f64>, valid_mask: &[bool]) -> HistogramStats {
-    // First pass: min/max + Welford mean/std
-    let mut count: u64 = 0;
-    let mut min_db = f64::INFINITY;
-    let mut max_db = f64::NEG_INFINITY;
-    let mut mean = 0.0_f64;
-    let mut m2 = 0.0_f64; // Sum of squares of differences from the current mean
-
-    for ((i, j), &v) in db.indexed_iter() {
-        if valid_mask[i * db.ncols() + j] {
-            count += 1;
-            if v < min_db { min_db = v; }
-            if v > max_db { max_db = v; }
-
-            // Welford's online algorithm
-            let delta = v - mean;
-            mean += delta / (count as f64);
-            let delta2 = v - mean;
-            m2 += delta * delta2;
+impl AutoscaleParams {
+    /// Reject out-of-range percentiles, gamma and CLAHE settings
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |arg: &'static str, value: String| Err(Error::InvalidArgument { arg, value });
+        for (arg, p) in [
+            ("low_percentile", self.low_percentile),
+            ("high_percentile", self.high_percentile),
+        ] {
+            if let Some(p) = p
+                && !(0.0..=100.0).contains(&p)
+            {
+                return invalid(arg, format!("{} (must be within 0..100)", p));
+            }
+        }
+        if let (Some(lo), Some(hi)) = (self.low_percentile, self.high_percentile)
+            && lo >= hi
+        {
+            return invalid(
+                "high_percentile",
+                format!("{} (must exceed low_percentile {})", hi, lo),
+            );
+        }
+        if let Some(g) = self.gamma
+            && (!g.is_finite() || g <= 0.0)
+        {
+            return invalid("gamma", format!("{} (must be positive)", g));
+        }
+        if self.clahe_tiles_x == 0 || self.clahe_tiles_y == 0 {
+            return invalid(
+                "clahe_tiles",
+                format!(
+                    "{}x{} (needs at least one tile per axis)",
+                    self.clahe_tiles_x, self.clahe_tiles_y
+                ),
+            );
+        }
+        if !self.clahe_clip_limit.is_finite() || self.clahe_clip_limit <= 0.0 {
+            return invalid(
+                "clahe_clip_limit",
+                format!("{} (must be positive)", self.clahe_clip_limit),
+            );
+        }
+        if self.clahe_bins < 2 {
+            return invalid("clahe_bins", format!("{} (needs at least 2 bins)", self.clahe_bins));
         }
+        if !self.db_floor.is_finite() {
+            return invalid("db_floor", self.db_floor.to_string());
+        }
+        Ok(())
     }
 
-    if count == 0 {
-        return HistogramStats {
-            valid_count: 0,
-            min_db: 0.0,
-            max_db: 0.0,
-            mean_db: 0.0,
-            std_db: 0.0,
-            median_db: 0.0,
-            p01: 0.0,
-            p02: 0.0,
-            p05: 0.0,
-            p10: 0.0,
-            p25: 0.0,
-            p75: 0.0,
-            p90: 0.0,
-            p95: 0.0,
-            p98: 0.0,
-            p99: 0.0,
+    /// `window` with the user percentiles and gamma applied (a `Fixed` window keeps its own),
+    /// widened to the histogram's minimum span
+    fn tune(
+        &self,
+        window: StretchWindow,
+        stats: &HistogramStats,
+        strategy: AutoscaleStrategy,
+    ) -> StretchWindow {
+        let window = if matches!(strategy, AutoscaleStrategy::Fixed { .. }) {
+            window
+        } else {
+            StretchWindow {
+                min_db: self
+                    .low_percentile
+                    .map_or(window.min_db, |p| stats.histogram.percentile(p / 100.0)),
+                max_db: self
+                    .high_percentile
+                    .map_or(window.max_db, |p| stats.histogram.percentile(p / 100.0)),
+                gamma: self.gamma.unwrap_or(window.gamma),
+            }
         };
+        window.with_min_span(stats.min_window)
     }
+}
+
+/// Clip window and gamma that mapped one band onto the output range. Values are dB for
+/// intensity products and the operation's own units for native-scale indices.
+///
+/// Valid pixels at or below `min_db` map to 0, the same integer as nodata, so they cannot be
+/// told apart in the scaled output; only the validity mask (alpha, NaN sidecars) separates them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StretchWindow {
+    pub min_db: f64,
+    pub max_db: f64,
+    pub gamma: f64,
+}
 
-    let std_db = if count > 1 { (m2 / (count as f64)).sqrt() } else { 0.0 };
+/// Shape of the curve from window values to output integers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StretchTransfer {
+    /// Affine: `value = offset + scale * v`
+    Linear,
+    /// Clip, normalize, then raise to `gamma`
+    Power,
+    /// Linear clip followed by CLAHE; only the window is recoverable, not per-pixel values
+    Clahe,
+}
 
-    // Handle degenerate case: all values are equal
-    if (max_db - min_db).abs() < f64::EPSILON {
-        return HistogramStats {
-            valid_count: count as usize,
-            min_db,
-            max_db,
-            mean_db: mean,
-            std_db,
-            median_db: min_db,
-            p01: min_db,
-            p02: min_db,
-            p05: min_db,
-            p10: min_db,
-            p25: min_db,
-            p75: max_db,
-            p90: max_db,
-            p95: max_db,
-            p98: max_db,
-            p99: max_db,
-        };
+impl StretchTransfer {
+    /// Transfer curve `strategy` applies through `window`
+    pub fn for_strategy(strategy: AutoscaleStrategy, window: &StretchWindow) -> Self {
+        if strategy == AutoscaleStrategy::Clahe {
+            StretchTransfer::Clahe
+        } else if (window.gamma - 1.0).abs() > 1e-9 {
+            StretchTransfer::Power
+        } else {
+            StretchTransfer::Linear
+        }
+    }
+}
+
+impl std::fmt::Display for StretchTransfer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StretchTransfer::Linear => write!(f, "linear"),
+            StretchTransfer::Power => write!(f, "power"),
+            StretchTransfer::Clahe => write!(f, "clahe"),
+        }
+    }
+}
+
+impl StretchWindow {
+    /// This window as the mapping applies it: a span narrower than `min_window` (including an
+    /// inverted one) is widened upwards from `min_db`, the same divisor the stretch uses, so
+    /// [`Self::scale_offset`] and [`Self::invert`] describe the written integers exactly
+    pub fn with_min_span(self, min_window: f64) -> Self {
+        StretchWindow {
+            max_db: self.min_db + (self.max_db - self.min_db).max(min_window),
+            ..self
+        }
+    }
+
+    /// GDAL-style `(scale, offset)` with `value = offset + scale * v` for outputs whose
+    /// largest integer is `max_value`. Exact only for a linear transfer; integers are
+    /// truncated, so recovered values sit up to one step below the original. `v = 0` gives
+    /// `min_db`, although 0 is also the nodata value.
+    pub fn scale_offset(&self, max_value: f64) -> (f64, f64) {
+        ((self.max_db - self.min_db) / max_value, self.min_db)
+    }
+
+    /// Window value of output integer `v`, undoing the gamma curve (not CLAHE)
+    pub fn invert(&self, v: f64, max_value: f64) -> f64 {
+        let n = (v / max_value).clamp(0.0, 1.0).powf(1.0 / self.gamma);
+        self.min_db + (self.max_db - self.min_db) * n
     }
+}
+
+/// Fixed-domain dB histogram with running moments.
+///
+/// Bins cover [`HIST_DB_MIN`, `HIST_DB_MIN + HIST_DB_SPAN`] at `1 / HIST_BINS_PER_DB` dB;
+/// values outside land in the edge bins while min/max stay exact. Because the domain does
+/// not depend on the data, partial histograms from parallel chunks merge by simple addition,
+/// which lets the dB conversion and histogram share a single pass.
+///
+/// Values that are not in dB (e.g. bounded vegetation indices) use [`DbHistogram::with_range`],
+/// which spreads the same number of bins over a caller-supplied domain.
+#[derive(Debug, Clone)]
+pub struct DbHistogram {
+    bins: Vec<u64>,
+    origin: f64,
+    bins_per_unit: f64,
+    min_window: f64,
+    count: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    sum_sq: f64,
+}
 
-    // Second pass: histogram over [min,max]
-    const NUM_BINS: usize = 4096;
-    let mut hist: [u64; NUM_BINS] = [0; NUM_BINS];
-    let span = max_db - min_db;
-    let inv_span = 1.0 / span;
+impl Default for DbHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    for ((i, j), &v) in db.indexed_iter() {
-        if !valid_mask[i * db.ncols() + j] {
-            continue;
+impl DbHistogram {
+    pub fn new() -> Self {
+        Self {
+            bins: vec![0u64; HIST_NUM_BINS],
+            origin: HIST_DB_MIN,
+            bins_per_unit: HIST_BINS_PER_DB as f64,
+            min_window: 1.0,
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            sum: 0.0,
+            sum_sq: 0.0,
         }
-        // Map v ∈ [min,max] into bin 0..NUM_BINS-1 (inclusive)
-        let t = ((v - min_db) * inv_span).clamp(0.0, 1.0);
-        let mut idx = (t * (NUM_BINS as f64)) as usize;
-        if idx >= NUM_BINS { idx = NUM_BINS - 1; }
-        hist[idx] += 1;
     }
 
-    // Helper to invert CDF and estimate percentile value using linear interpolation within the bin
-    let estimate_percentile = |p: f64| -> f64 {
-        let n = count; // number of valid samples
-        // Match previous behavior roughly: idx = floor(n * p), then clamp to [0, n-1]
+    /// Histogram over `[min, max]` in the values' own units. Partial histograms built with
+    /// the same bounds merge like [`DbHistogram::new`] ones.
+    pub fn with_range(min: f64, max: f64) -> Self {
+        let span = if max > min { max - min } else { 1.0 };
+        Self {
+            origin: min,
+            bins_per_unit: HIST_NUM_BINS as f64 / span,
+            min_window: span * 1e-3,
+            ..Self::new()
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, v: f32) {
+        let v = v as f64;
+        self.count += 1;
+        if v < self.min { self.min = v; }
+        if v > self.max { self.max = v; }
+        self.sum += v;
+        self.sum_sq += v * v;
+        let idx = self.bin_index(v);
+        self.bins[idx] += 1;
+    }
+
+    /// Combine two partial histograms (rayon reduce step)
+    pub fn merge(mut self, other: Self) -> Self {
+        debug_assert!(self.origin == other.origin && self.bins_per_unit == other.bins_per_unit);
+        for (a, b) in self.bins.iter_mut().zip(other.bins.iter()) {
+            *a += *b;
+        }
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        self.sum_sq += other.sum_sq;
+        self
+    }
+
+    #[inline]
+    fn bin_index(&self, v: f64) -> usize {
+        let idx = ((v - self.origin) * self.bins_per_unit).floor();
+        if idx <= 0.0 {
+            0
+        } else if idx >= (HIST_NUM_BINS - 1) as f64 {
+            HIST_NUM_BINS - 1
+        } else {
+            idx as usize
+        }
+    }
+
+    /// Lower edge of bin `idx`
+    pub fn bin_start(&self, idx: usize) -> f64 {
+        self.origin + idx as f64 / self.bins_per_unit
+    }
+
+    /// Width of one bin
+    pub fn bin_width(&self) -> f64 {
+        1.0 / self.bins_per_unit
+    }
+
+    /// Smallest clip window worth stretching: 1 dB on the default domain, a thousandth of
+    /// the span for [`DbHistogram::with_range`]
+    pub fn min_window(&self) -> f64 {
+        self.min_window
+    }
+
+    pub fn bins(&self) -> &[u64] {
+        &self.bins
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min_db(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.min }
+    }
+
+    pub fn max_db(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.max }
+    }
+
+    pub fn mean_db(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.sum / self.count as f64 }
+    }
+
+    /// Population standard deviation
+    pub fn std_db(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        let n = self.count as f64;
+        let mean = self.sum / n;
+        (self.sum_sq / n - mean * mean).max(0.0).sqrt()
+    }
+
+    /// Estimate the `p` quantile (0..1) by inverting the CDF with linear interpolation
+    /// inside the bin; the result is clamped to the exact [min, max].
+    pub fn percentile(&self, p: f64) -> f64 {
+        let n = self.count;
+        if n == 0 {
+            return 0.0;
+        }
+        // idx = floor(n * p), clamped to [0, n-1]
         let mut target = (p * (n as f64)).floor() as u64;
         if target >= n { target = n - 1; }
 
         let mut cumsum: u64 = 0;
-        for (b, &h) in hist.iter().enumerate() {
+        for (b, &h) in self.bins.iter().enumerate() {
             let next = cumsum + h;
             if target < next {
-                let within = target.saturating_sub(cumsum);
-                let frac = if h > 0 { (within as f64) / (h as f64) } else { 0.0 };
-                let bin_width = span / (NUM_BINS as f64);
-                let bin_start = min_db + (b as f64) * bin_width;
-                return bin_start + frac * bin_width;
+                let frac = (target - cumsum) as f64 / h as f64;
+                let v = self.bin_start(b) + frac * self.bin_width();
+                return v.max(self.min).min(self.max);
             }
             cumsum = next;
         }
-        // Fallback (should not happen): return max
-        max_db
-    };
+        self.max
+    }
+}
 
-    HistogramStats {
-        valid_count: count as usize,
-        min_db,
-        max_db,
-        mean_db: mean,
-        std_db,
-        median_db: estimate_percentile(0.5),
-        p01: estimate_percentile(0.01),
-        p02: estimate_percentile(0.02),
-        p05: estimate_percentile(0.05),
-        p10: estimate_percentile(0.10),
-        p25: estimate_percentile(0.25),
-        p75: estimate_percentile(0.75),
-        p90: estimate_percentile(0.90),
-        p95: estimate_percentile(0.95),
-        p98: estimate_percentile(0.98),
-        p99: estimate_percentile(0.99),
+/// Basic statistics and percentile estimates derived from a [`DbHistogram`].
+#[derive(Debug, Clone)]
+pub struct HistogramStats {
+    pub valid_count: usize,
+    pub min_db: f64,
+    pub max_db: f64,
+    pub mean_db: f64,
+    pub std_db: f64,
+    pub median_db: f64,
+    pub p01: f64,
+    pub p02: f64,
+    pub p05: f64,
+    pub p10: f64,
+    pub p25: f64,
+    pub p75: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p98: f64,
+    pub p99: f64,
+    /// Lower bound for clip windows, see [`DbHistogram::min_window`]
+    pub min_window: f64,
+    pub histogram: DbHistogram,
+}
+
+impl HistogramStats {
+    pub fn from_histogram(histogram: DbHistogram) -> Self {
+        let h = &histogram;
+        Self {
+            valid_count: h.count() as usize,
+            min_db: h.min_db(),
+            max_db: h.max_db(),
+            mean_db: h.mean_db(),
+            std_db: h.std_db(),
+            median_db: h.percentile(0.5),
+            p01: h.percentile(0.01),
+            p02: h.percentile(0.02),
+            p05: h.percentile(0.05),
+            p10: h.percentile(0.10),
+            p25: h.percentile(0.25),
+            p75: h.percentile(0.75),
+            p90: h.percentile(0.90),
+            p95: h.percentile(0.95),
+            p98: h.percentile(0.98),
+            p99: h.percentile(0.99),
+            min_window: h.min_window(),
+            histogram,
+        }
     }
 }
 
+/// Build a [`DbHistogram`] over the valid pixels of an existing dB image.
+/// Chunks are accumulated in parallel and merged; see `process_scalar_data_fused`
+/// for the variant that builds it during dB conversion.
+pub fn build_db_histogram(db: &Array2<f32>, valid_mask: &ValidMask) -> DbHistogram {
+    let db = db.as_standard_layout();
+    let src = db.as_slice().expect("standard layout array is contiguous");
+    src.par_chunks(PAR_CHUNK)
+        .enumerate()
+        .fold(DbHistogram::new, |mut hist, (ci, chunk)| {
+            let base = ci * PAR_CHUNK;
+            for (k, &v) in chunk.iter().enumerate() {
+                if valid_mask.get(base + k) {
+                    hist.push(v);
+                }
+            }
+            hist
+        })
+        .reduce(DbHistogram::new, DbHistogram::merge)
+}
+
+/// Compute robust percentiles and basic stats without materializing/sorting all values.
+pub fn compute_histogram_stats(db: &Array2<f32>, valid_mask: &ValidMask) -> HistogramStats {
+    HistogramStats::from_histogram(build_db_histogram(db, valid_mask))
+}
+
+/// Map valid dB pixels through a clip window and gamma curve onto `0..=max_val`, in parallel.
+/// Non-unit gammas use a lookup table over the normalized domain instead of a per-pixel `powf`.
+fn map_db_window(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    low_clip: f64,
+    high_clip: f64,
+    gamma: f64,
+    max_val: f64,
+    min_window: f64,
+) -> Vec<u16> {
+    let db = db.as_standard_layout();
+    let src = db.as_slice().expect("standard layout array is contiguous");
+
+    let low = low_clip as f32;
+    let high = high_clip as f32;
+    let inv_range = (1.0 / (high_clip - low_clip).max(min_window)) as f32;
+    let max_f = max_val as f32;
+
+    let lut: Option<Vec<u16>> = if (gamma - 1.0).abs() > 1e-9 {
+        let last = (GAMMA_LUT_SIZE - 1) as f64;
+        Some(
+            (0..GAMMA_LUT_SIZE)
+                .map(|i| ((i as f64 / last).powf(gamma) * max_val).clamp(0.0, max_val) as u16)
+                .collect(),
+        )
+    } else {
+        None
+    };
+    let lut_last = (GAMMA_LUT_SIZE - 1) as f32;
+
+    let mut out = vec![0u16; src.len()];
+    out.par_chunks_mut(PAR_CHUNK)
+        .zip(src.par_chunks(PAR_CHUNK))
+        .enumerate()
+        .for_each(|(ci, (dst, chunk))| {
+            let base = ci * PAR_CHUNK;
+            for (k, (o, &v)) in dst.iter_mut().zip(chunk.iter()).enumerate() {
+                if !valid_mask.get(base + k) {
+                    continue;
+                }
+                let n = (v.max(low).min(high) - low) * inv_range;
+                *o = match &lut {
+                    Some(lut) => lut[((n * lut_last) as usize).min(GAMMA_LUT_SIZE - 1)],
+                    None => (n * max_f).clamp(0.0, max_f) as u16,
+                };
+            }
+        });
+    out
+}
+
+/// Normalize valid pixels to 0..1 over the [low, high] window (invalid pixels become 0)
+fn normalize_db_window(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    low_clip: f64,
+    high_clip: f64,
+    min_window: f64,
+) -> Array2<f32> {
+    let (rows, cols) = db.dim();
+    let db = db.as_standard_layout();
+    let src = db.as_slice().expect("standard layout array is contiguous");
+
+    let low = low_clip as f32;
+    let high = high_clip as f32;
+    let inv_range = (1.0 / (high_clip - low_clip).max(min_window)) as f32;
+
+    let mut norm = vec![0f32; src.len()];
+    norm.par_chunks_mut(PAR_CHUNK)
+        .zip(src.par_chunks(PAR_CHUNK))
+        .enumerate()
+        .for_each(|(ci, (dst, chunk))| {
+            let base = ci * PAR_CHUNK;
+            for (k, (o, &v)) in dst.iter_mut().zip(chunk.iter()).enumerate() {
+                if valid_mask.get(base + k) {
+                    *o = (v.max(low).min(high) - low) * inv_range;
+                }
+            }
+        });
+    Array2::from_shape_vec((rows, cols), norm).expect("norm length should match (rows*cols)")
+}
+
 #[inline]
-fn insertion_sort_in_place(values: &mut [f64]) {
+fn insertion_sort_in_place(values: &mut [f32]) {
     for i in 1..values.len() {
         let key = values[i];
         let mut j = i;
@@ -174,15 +547,15 @@ fn insertion_sort_in_place(values: &mut [f64]) {
 
 #[inline]
 fn local_median_and_range_3x3(
-    db: &Array2<f64>,
-    valid_mask: &[bool],
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
     row: usize,
     col: usize,
 ) -> Option<(f64, f64)> {
     let rows = db.nrows();
     let cols = db.ncols();
 
-    let mut buf: [f64; 9] = [0.0; 9];
+    let mut buf: [f32; 9] = [0.0; 9];
     let mut count: usize = 0;
 
     let r0 = row.saturating_sub(1);
@@ -192,8 +565,7 @@ fn local_median_and_range_3x3(
 
     for r in r0..=r1 {
         for c in c0..=c1 {
-            let idx = r * cols + c;
-            if valid_mask[idx] {
+            if valid_mask.get(r * cols + c) {
                 buf[count] = db[(r, c)];
                 count += 1;
             }
@@ -207,24 +579,24 @@ fn local_median_and_range_3x3(
     let slice = &mut buf[..count];
     insertion_sort_in_place(slice);
 
-    let median = slice[count / 2];
-    let range = slice[count - 1] - slice[0];
+    let median = slice[count / 2] as f64;
+    let range = (slice[count - 1] - slice[0]) as f64;
     Some((median, range))
 }
 
 /// Contrast Limited Adaptive Histogram Equalization (CLAHE) on a normalized 0..1 image.
 /// - Splits the image into `tiles_x` x `tiles_y` tiles
-/// - Builds per-tile histograms with `num_bins` bins
+/// - Builds per-tile histograms with `num_bins` bins (tiles in parallel)
 /// - Clips each histogram at `clip_limit` (relative multiplier of average count)
-/// - Computes CDFs and performs bilinear interpolation of the CDF value for each pixel
+/// - Computes CDFs and performs bilinear interpolation of the CDF value for each pixel (rows in parallel)
 fn clahe_equalize_normalized(
-    norm: &Array2<f64>,
-    valid_mask: &[bool],
+    norm: &Array2<f32>,
+    valid_mask: &ValidMask,
     tiles_x: usize,
     tiles_y: usize,
     clip_limit: f64,
     num_bins: usize,
-) -> Array2<f64> {
+) -> Array2<f32> {
 
     let rows = norm.nrows();
     let cols = norm.ncols();
@@ -232,44 +604,40 @@ fn clahe_equalize_normalized(
         return norm.clone();
     }
 
-    let tile_h = (rows + tiles_y - 1) / tiles_y;
-    let tile_w = (cols + tiles_x - 1) / tiles_x;
+    let norm = norm.as_standard_layout();
+    let src = norm.as_slice().expect("standard layout array is contiguous");
 
-    // Precompute per-tile CDFs
-    let mut cdfs: Vec<Vec<f64>> = Vec::with_capacity(tiles_x * tiles_y);
-    cdfs.resize_with(tiles_x * tiles_y, || vec![0.0; num_bins]);
-
-    let avg_count_per_bin = |tile_rows: usize, tile_cols: usize| -> f64 {
-        let tile_pixels = (tile_rows * tile_cols) as f64;
-        tile_pixels / (num_bins as f64)
-    };
+    let tile_h = rows.div_ceil(tiles_y);
+    let tile_w = cols.div_ceil(tiles_x);
+    let bin_scale = (num_bins - 1) as f32;
 
-    for ty in 0..tiles_y {
-        let r0 = ty * tile_h;
-        let r1 = ((ty + 1) * tile_h).min(rows);
-        let tile_rows = r1 - r0;
-        for tx in 0..tiles_x {
-            let c0 = tx * tile_w;
+    // Precompute per-tile CDFs
+    let cdfs: Vec<Vec<f32>> = (0..tiles_x * tiles_y)
+        .into_par_iter()
+        .map(|t| {
+            let ty = t / tiles_x;
+            let tx = t % tiles_x;
+            // Trailing tiles can fall past the edge for tiny images; keep them empty
+            let r0 = (ty * tile_h).min(rows);
+            let r1 = ((ty + 1) * tile_h).min(rows);
+            let c0 = (tx * tile_w).min(cols);
             let c1 = ((tx + 1) * tile_w).min(cols);
-            let tile_cols = c1 - c0;
 
             let mut hist = vec![0u32; num_bins];
 
             // Build histogram for this tile
             for r in r0..r1 {
                 for c in c0..c1 {
-                    if valid_mask[r * cols + c] {
-                        let v = norm[(r, c)].clamp(0.0, 1.0);
-                        let mut bin = (v * (num_bins as f64 - 1.0)).round() as isize;
-                        if bin < 0 { bin = 0; }
-                        if bin as usize >= num_bins { bin = (num_bins - 1) as isize; }
-                        hist[bin as usize] += 1;
+                    let idx = r * cols + c;
+                    if valid_mask.get(idx) {
+                        let bin = (src[idx].clamp(0.0, 1.0) * bin_scale).round() as usize;
+                        hist[bin.min(num_bins - 1)] += 1;
                     }
                 }
             }
 
             // Clip histogram
-            let avg = avg_count_per_bin(tile_rows, tile_cols);
+            let avg = ((r1 - r0) * (c1 - c0)) as f64 / (num_bins as f64);
             let clip_threshold = (clip_limit * avg).max(1.0);
             let mut excess: f64 = 0.0;
             for h in &mut hist {
@@ -293,31 +661,31 @@ fn clahe_equalize_normalized(
 
             // Compute CDF normalized to 0..1
             let total: f64 = hist.iter().map(|&x| x as f64).sum::<f64>().max(1.0);
-            let mut cdf = vec![0.0f64; num_bins];
             let mut acc = 0.0f64;
-            for i in 0..num_bins {
-                acc += hist[i] as f64;
-                cdf[i] = (acc / total).clamp(0.0, 1.0);
-            }
-            cdfs[ty * tiles_x + tx] = cdf;
-        }
-    }
+            hist.iter()
+                .map(|&h| {
+                    acc += h as f64;
+                    (acc / total).clamp(0.0, 1.0) as f32
+                })
+                .collect()
+        })
+        .collect();
 
     // Helper to sample bilinearly from neighboring tile CDFs
-    let sample_cdf = |r: usize, c: usize, val: f64| -> f64 {
-        let rf = r as f64 / tile_h as f64 - 0.5;
-        let cf = c as f64 / tile_w as f64 - 0.5;
+    let sample_cdf = |r: usize, c: usize, val: f32| -> f32 {
+        let rf = r as f32 / tile_h as f32 - 0.5;
+        let cf = c as f32 / tile_w as f32 - 0.5;
         let ty = rf.floor().max(0.0) as isize;
         let tx = cf.floor().max(0.0) as isize;
-        let dy = rf - ty as f64;
-        let dx = cf - tx as f64;
+        let dy = rf - ty as f32;
+        let dx = cf - tx as f32;
 
         let ty0 = ty.clamp(0, tiles_y as isize - 1) as usize;
         let tx0 = tx.clamp(0, tiles_x as isize - 1) as usize;
         let ty1 = ((ty + 1).clamp(0, tiles_y as isize - 1)) as usize;
         let tx1 = ((tx + 1).clamp(0, tiles_x as isize - 1)) as usize;
 
-        let bin_pos = (val.clamp(0.0, 1.0) * (num_bins as f64 - 1.0)).round() as usize;
+        let bin_pos = ((val.clamp(0.0, 1.0) * bin_scale).round() as usize).min(num_bins - 1);
 
         let cdf00 = cdfs[ty0 * tiles_x + tx0][bin_pos];
         let cdf01 = cdfs[ty0 * tiles_x + tx1][bin_pos];
@@ -329,19 +697,17 @@ fn clahe_equalize_normalized(
         top * (1.0 - dy) + bottom * dy
     };
 
-    let mut out = Array2::<f64>::zeros((rows, cols));
-    for r in 0..rows {
-        for c in 0..cols {
-            if valid_mask[r * cols + c] {
-                let v = norm[(r, c)];
-                out[(r, c)] = sample_cdf(r, c, v);
-            } else {
-                out[(r, c)] = 0.0;
+    let mut out = vec![0f32; rows * cols];
+    out.par_chunks_mut(cols).enumerate().for_each(|(r, row)| {
+        let base = r * cols;
+        for (c, o) in row.iter_mut().enumerate() {
+            if valid_mask.get(base + c) {
+                *o = sample_cdf(r, c, src[base + c]);
             }
         }
-    }
+    });
 
-    out
+    Array2::from_shape_vec((rows, cols), out).expect("clahe output length should match (rows*cols)")
 }
 
 /// Scale U16 to U8, used for resizing U16 images to U8
@@ -349,13 +715,13 @@ pub fn scale_u16_to_u8(data: &[u16]) -> Vec<u8> {
     if data.is_empty() {
         return Vec::new();
     }
-    let min = *data.iter().min().unwrap() as f32;
-    let max = *data.iter().max().unwrap() as f32;
+    let min = *data.par_iter().min().unwrap() as f32;
+    let max = *data.par_iter().max().unwrap() as f32;
 
     // Avoid division by zero if min == max
     let scale = if max > min { 255.0 / (max - min) } else { 1.0 };
 
-    data.iter()
+    data.par_iter()
         .map(|&x| {
             let val = ((x as f32 - min) * scale).round();
             val.clamp(0.0, 255.0) as u8
@@ -366,17 +732,47 @@ pub fn scale_u16_to_u8(data: &[u16]) -> Vec<u8> {
 /// Autoscale a dB image to the desired bit depth using SAR-specific techniques
 /// Uses robust statistics and adaptive contrast enhancement for better SAR image quality
 pub fn autoscale_db_image(
-    db: &Array2<f64>,
-    valid_mask: &[bool],
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
     bit_depth: BitDepth,
 ) -> Vec<u16> {
-    // Fast O(N) stats and percentiles
     let stats = compute_histogram_stats(db, valid_mask);
+    autoscale_db_image_with_stats(db, valid_mask, &stats, bit_depth, &AutoscaleParams::default())
+}
 
+/// [`autoscale_db_image`] with precomputed statistics (e.g. from the fused dB pass)
+pub fn autoscale_db_image_with_stats(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    stats: &HistogramStats,
+    bit_depth: BitDepth,
+    params: &AutoscaleParams,
+) -> Vec<u16> {
     if stats.valid_count == 0 {
         return vec![0u16; db.len()];
     }
 
+    let max_val = match bit_depth {
+        BitDepth::U8 => 255.0,
+        BitDepth::U16 => 65535.0,
+    };
+
+    let window = params.tune(standard_window(stats), stats, AutoscaleStrategy::Standard);
+
+    // Apply scaling with gamma correction
+    map_db_window(
+        db,
+        valid_mask,
+        window.min_db,
+        window.max_db,
+        window.gamma,
+        max_val,
+        stats.min_window,
+    )
+}
+
+/// Clip window of the Standard strategy, chosen from the scene's dynamic range and IQR
+fn standard_window(stats: &HistogramStats) -> StretchWindow {
     let min_db = stats.min_db;
     let max_db = stats.max_db;
     let mean_db = stats.mean_db;
@@ -387,11 +783,6 @@ pub fn autoscale_db_image(
     let p75 = stats.p75;
     let p98 = stats.p98;
 
-    let max_val = match bit_depth {
-        BitDepth::U8 => 255.0,
-        BitDepth::U16 => 65535.0,
-    };
-
     let dynamic_range = max_db - min_db;
     let iqr = p75 - p25;
 
@@ -426,47 +817,148 @@ pub fn autoscale_db_image(
     // Ensure valid range
     let low_clip = low_clip.max(min_db);
     let high_clip = high_clip.min(max_db);
-    let range = (high_clip - low_clip).max(1.0);
 
     debug!(
         "SAR autoscale: clipping to [{:.1}, {:.1}] dB, gamma={:.2}",
         low_clip, high_clip, gamma
     );
 
-    // Apply scaling with gamma correction
-    db.indexed_iter()
-        .map(|(idx, &v)| {
-            if valid_mask[idx.0 * db.ncols() + idx.1] {
-                let clipped = v.max(low_clip).min(high_clip);
-                let normalized = ((clipped - low_clip) / range).powf(gamma);
-                (normalized * max_val).clamp(0.0, max_val) as u16
-            } else {
-                0u16
-            }
-        })
-        .collect()
+    StretchWindow {
+        min_db: low_clip,
+        max_db: high_clip,
+        gamma,
+    }
 }
 
 /// Advanced SAR autoscaling with local contrast enhancement and speckle handling
 /// This provides multiple strategies for different SAR image characteristics
 pub fn autoscale_db_image_advanced(
-    db: &Array2<f64>,
-    valid_mask: &[bool],
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
     bit_depth: BitDepth,
     strategy: AutoscaleStrategy, // robust, adaptive, equalized, tamed, CLAHE, default
+) -> Vec<u16> {
+    let stats = compute_histogram_stats(db, valid_mask);
+    autoscale_db_image_advanced_with_stats(
+        db,
+        valid_mask,
+        &stats,
+        bit_depth,
+        strategy,
+        &AutoscaleParams::default(),
+    )
+}
+
+/// [`autoscale_db_image_advanced`] with precomputed statistics (e.g. from the fused dB pass)
+pub fn autoscale_db_image_advanced_with_stats(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    stats: &HistogramStats,
+    bit_depth: BitDepth,
+    strategy: AutoscaleStrategy,
+    params: &AutoscaleParams,
 ) -> Vec<u16> {
     let max_val = match bit_depth {
         BitDepth::U8 => 255.0,
         BitDepth::U16 => 65535.0,
     };
 
-    // Fast O(N) stats and percentiles
-    let stats = compute_histogram_stats(db, valid_mask);
-
     if stats.valid_count == 0 {
         return vec![0u16; db.len()];
     }
 
+    let window = params.tune(strategy_window(stats, strategy), stats, strategy);
+    let (low_clip, high_clip, gamma) = (window.min_db, window.max_db, window.gamma);
+    // Local enhancement is disabled for every strategy (non-physical in dB); use CLAHE instead
+    let use_local_enhancement = false;
+
+    debug!(
+        "Advanced SAR scaling: strategy={}, range=[{:.1}, {:.1}] dB, gamma={:.2}, local_enh={}",
+        strategy, low_clip, high_clip, gamma, use_local_enhancement
+    );
+
+    // Special path: CLAHE
+    if matches!(strategy, AutoscaleStrategy::Clahe) {
+        debug!(
+            "Applying CLAHE with window [{:.1}, {:.1}] dB over normalized domain",
+            low_clip, high_clip
+        );
+
+        // Normalize to 0..1 using the chosen window
+        let norm = normalize_db_window(db, valid_mask, low_clip, high_clip, stats.min_window);
+        let equalized = clahe_equalize_normalized(
+            &norm,
+            valid_mask,
+            params.clahe_tiles_x,
+            params.clahe_tiles_y,
+            params.clahe_clip_limit,
+            params.clahe_bins,
+        );
+        let eq = equalized
+            .as_slice()
+            .expect("clahe output is contiguous");
+
+        let max_f = max_val as f32;
+        let mut result = vec![0u16; eq.len()];
+        result
+            .par_chunks_mut(PAR_CHUNK)
+            .zip(eq.par_chunks(PAR_CHUNK))
+            .enumerate()
+            .for_each(|(ci, (dst, chunk))| {
+                let base = ci * PAR_CHUNK;
+                for (k, (o, &n)) in dst.iter_mut().zip(chunk.iter()).enumerate() {
+                    if valid_mask.get(base + k) {
+                        *o = (n.clamp(0.0, 1.0) * max_f) as u16;
+                    }
+                }
+            });
+        return result;
+    }
+
+    if use_local_enhancement {
+        // Apply local contrast enhancement for better detail visibility
+        debug!("Applying local contrast enhancement");
+        let range = (high_clip - low_clip).max(stats.min_window);
+        let cols = db.ncols();
+        let mut result = Vec::with_capacity(db.len());
+        for ((i, j), &v) in db.indexed_iter() {
+            if !valid_mask.get(i * cols + j) {
+                result.push(0u16);
+                continue;
+            }
+            let v = v as f64;
+
+            let (local_median, local_range) = match local_median_and_range_3x3(db, valid_mask, i, j) {
+                Some((m, r)) => (m, r),
+                None => {
+                    let clipped = v.max(low_clip).min(high_clip);
+                    let normalized = ((clipped - low_clip) / range).powf(gamma);
+                    result.push((normalized * max_val).clamp(0.0, max_val) as u16);
+                    continue;
+                }
+            };
+
+            let local_factor = if local_range > 0.0 {
+                1.0 + 0.1 * (v - local_median) / local_range
+            } else {
+                1.0
+            };
+
+            let adjusted_v = v * local_factor;
+            let clipped = adjusted_v.max(low_clip).min(high_clip);
+            let normalized = ((clipped - low_clip) / range).powf(gamma);
+            result.push((normalized * max_val).clamp(0.0, max_val) as u16);
+        }
+        return result;
+    }
+
+    // Standard scaling
+    debug!("Applying scaling without local enhancement");
+    map_db_window(db, valid_mask, low_clip, high_clip, gamma, max_val, stats.min_window)
+}
+
+/// Clip window of the histogram-driven strategies (and the explicit `Fixed` window)
+fn strategy_window(stats: &HistogramStats, strategy: AutoscaleStrategy) -> StretchWindow {
     let min_db = stats.min_db;
     let max_db = stats.max_db;
     let mean_db = stats.mean_db;
@@ -488,20 +980,20 @@ pub fn autoscale_db_image_advanced(
     );
 
     // Choose scaling parameters based on strategy and image characteristics
-    let (low_clip, high_clip, gamma, use_local_enhancement) = match strategy {
+    let (low_clip, high_clip, gamma) = match strategy {
         AutoscaleStrategy::Robust => {
             debug!("Robust SAR scaling");
             // Robust statistics approach - handles outliers well
             let outlier_threshold = 2.5 * iqr;
             let low = (p25 - outlier_threshold).max(p01).max(min_db);
             let high = (p75 + outlier_threshold).min(p99).min(max_db);
-            (low, high, 1.0, false)
+            (low, high, 1.0)
         }
         AutoscaleStrategy::Adaptive => {
             debug!("Adaptive SAR scaling");
             // Adaptive based on image characteristics
-            let skew_factor = (mean_db - median_db) / std_db.abs().max(1.0);
-            let tail_heaviness = (p99 - p95) / (p95 - p75).max(1.0);
+            let skew_factor = (mean_db - median_db) / std_db.abs().max(stats.min_window);
+            let tail_heaviness = (p99 - p95) / (p95 - p75).max(stats.min_window);
 
             let (low_pct, high_pct, gamma_adj) = if skew_factor.abs() > 0.5 {
                 // Skewed distribution - adjust percentiles
@@ -519,160 +1011,102 @@ pub fn autoscale_db_image_advanced(
             };
 
             // Use histogram-estimated percentiles
-            let low = if approx_eq(low_pct, 0.10) { stats.p10 }
-                      else if approx_eq(low_pct, 0.02) { stats.p02 }
-                      else if approx_eq(low_pct, 0.05) { stats.p05 }
-                      else if approx_eq(low_pct, 0.25) { stats.p25 }
-                      else if approx_eq(low_pct, 0.75) { stats.p75 }
-                      else if approx_eq(low_pct, 0.95) { stats.p95 }
-                      else if approx_eq(low_pct, 0.99) { stats.p99 }
-                      else { stats.p05 };
-            let high = if approx_eq(high_pct, 0.90) { stats.p90 }
-                       else if approx_eq(high_pct, 0.98) { stats.p98 }
-                       else if approx_eq(high_pct, 0.95) { stats.p95 }
-                       else if approx_eq(high_pct, 0.75) { stats.p75 }
-                       else if approx_eq(high_pct, 0.99) { stats.p99 }
-                       else { stats.p95 };
+            let low = stats.histogram.percentile(low_pct);
+            let high = stats.histogram.percentile(high_pct);
             // Disable local enhancement (non-physical in dB). For local contrast, use CLAHE.
-            (low, high, gamma_adj, false)
+            (low, high, gamma_adj)
         }
         AutoscaleStrategy::Equalized => {
             debug!("Equalized SAR scaling");
             // Histogram equalization approach
-            (p01, p99, 1.0, false)
+            (p01, p99, 1.0)
         }
         AutoscaleStrategy::Clahe => {
             debug!("CLAHE SAR scaling");
             // Use robust window for initial normalization prior to CLAHE
-            (p01, p99, 1.0, false)
+            (p01, p99, 1.0)
         }
         AutoscaleStrategy::Tamed => {
             debug!("Tamed SAR scaling");
             // Use with p25 and p99 for synRGB only
-            (p25, p99, 1.0, false)
+            (p25, p99, 1.0)
         }
         AutoscaleStrategy::Standard => {
             debug!("Standard SAR scaling");
-            (p05, p95, 1.0, false)
+            (p05, p95, 1.0)
         }
         AutoscaleStrategy::Default => {
             debug!("Default SAR scaling");
-            (p05, p95, 1.0, false)
+            (p05, p95, 1.0)
         }
-    };
-
-    let range = (high_clip - low_clip).max(1.0);
-
-    debug!(
-        "Advanced SAR scaling: strategy={}, range=[{:.1}, {:.1}] dB, gamma={:.2}, local_enh={}",
-        strategy, low_clip, high_clip, gamma, use_local_enhancement
-    );
-
-    // Special path: CLAHE
-    if matches!(strategy, AutoscaleStrategy::Clahe) {
-        debug!(
-            "Applying CLAHE with window [{:.1}, {:.1}] dB over normalized domain",
-            low_clip, high_clip
-        );
-
-        let rows = db.nrows();
-        let cols = db.ncols();
-
-        // Normalize to 0..1 using the chosen window
-        let mut norm = Array2::<f64>::zeros((rows, cols));
-        for ((i, j), &v) in db.indexed_iter() {
-            if valid_mask[i * cols + j] {
-                let clipped = v.max(low_clip).min(high_clip);
-                let n = (clipped - low_clip) / range;
-                norm[(i, j)] = n;
-            } else {
-                norm[(i, j)] = 0.0;
-            }
+        AutoscaleStrategy::Fixed {
+            min_db,
+            max_db,
+            gamma,
+        } => {
+            debug!("Fixed SAR scaling");
+            (min_db, max_db, gamma)
         }
+    };
 
-        let equalized = clahe_equalize_normalized(&norm, valid_mask, 8, 8, 2.0, 256);
-
-        let mut result = Vec::with_capacity(rows * cols);
-        let max_val = match bit_depth {
-            BitDepth::U8 => 255.0,
-            BitDepth::U16 => 65535.0,
-        };
-        for ((i, j), &n) in equalized.indexed_iter() {
-            if valid_mask[i * cols + j] {
-                result.push((n.clamp(0.0, 1.0) * max_val) as u16);
-            } else {
-                result.push(0u16);
-            }
-        }
-        return result;
+    StretchWindow {
+        min_db: low_clip,
+        max_db: high_clip,
+        gamma,
     }
+}
 
-    // Apply scaling for other strategies
-    let mut result = Vec::with_capacity(db.len());
-
-    if use_local_enhancement {
-        // Apply local contrast enhancement for better detail visibility
-        debug!("Applying local contrast enhancement");
-        let cols = db.ncols();
-        for ((i, j), &v) in db.indexed_iter() {
-            if !valid_mask[i * cols + j] {
-                result.push(0u16);
-                continue;
-            }
-
-            let (local_median, local_range) = match local_median_and_range_3x3(db, valid_mask, i, j) {
-                Some((m, r)) => (m, r),
-                None => {
-                    let clipped = v.max(low_clip).min(high_clip);
-                    let normalized = ((clipped - low_clip) / range).powf(gamma);
-                    result.push((normalized * max_val).clamp(0.0, max_val) as u16);
-                    continue;
-                }
-            };
-
-            let local_factor = if local_range > 0.0 {
-                1.0 + 0.1 * (v - local_median) / local_range
-            } else {
-                1.0
-            };
-
-            let adjusted_v = v * local_factor;
-            let clipped = adjusted_v.max(low_clip).min(high_clip);
-            let normalized = ((clipped - low_clip) / range).powf(gamma);
-            result.push((normalized * max_val).clamp(0.0, max_val) as u16);
-        }
+/// Clip window the autoscale pipeline applies for `strategy` and `params`. `native` selects
+/// the native-scale path, where Standard uses the plain percentile window of the advanced path.
+pub fn stretch_window(
+    stats: &HistogramStats,
+    strategy: AutoscaleStrategy,
+    native: bool,
+    params: &AutoscaleParams,
+) -> StretchWindow {
+    let window = if strategy == AutoscaleStrategy::Standard && !native {
+        standard_window(stats)
     } else {
-        // Standard scaling
-        debug!("Applying scaling without local enhancement");
-        for ((i, j), &v) in db.indexed_iter() {
-            if valid_mask[i * db.ncols() + j] {
-                let clipped = v.max(low_clip).min(high_clip);
-                let normalized = ((clipped - low_clip) / range).powf(gamma);
-                result.push((normalized * max_val).clamp(0.0, max_val) as u16);
-            } else {
-                result.push(0u16);
-            }
-        }
-    }
-
-    result
+        strategy_window(stats, strategy)
+    };
+    params.tune(window, stats, strategy)
 }
 
 /// Convenience wrapper to return Vec<u8> or Vec<u16> as needed
 pub fn autoscale_db_image_to_bitdepth(
-    db: &Array2<f64>,
-    valid_mask: &[bool],
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
     bit_depth: BitDepth,
+) -> (Vec<u8>, Option<Vec<u16>>) {
+    let stats = compute_histogram_stats(db, valid_mask);
+    autoscale_db_image_to_bitdepth_with_stats(
+        db,
+        valid_mask,
+        &stats,
+        bit_depth,
+        &AutoscaleParams::default(),
+    )
+}
+
+/// [`autoscale_db_image_to_bitdepth`] with precomputed statistics
+pub fn autoscale_db_image_to_bitdepth_with_stats(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    stats: &HistogramStats,
+    bit_depth: BitDepth,
+    params: &AutoscaleParams,
 ) -> (Vec<u8>, Option<Vec<u16>>) {
     match bit_depth {
         BitDepth::U8 => {
-            let v: Vec<u16> = autoscale_db_image(db, valid_mask, BitDepth::U8);
+            let v: Vec<u16> =
+                autoscale_db_image_with_stats(db, valid_mask, stats, BitDepth::U8, params);
             let u8_data = scale_u16_to_u8(&v);
             debug!("autoscale_db_image_to_bitdepth: U8");
             (u8_data, None)
         }
         BitDepth::U16 => {
-            let v: Vec<u16> = autoscale_db_image(db, valid_mask, BitDepth::U16);
+            let v: Vec<u16> =
+                autoscale_db_image_with_stats(db, valid_mask, stats, BitDepth::U16, params);
             debug!("autoscale_db_image_to_bitdepth: U16");
             (vec![], Some(v))
         }
@@ -681,22 +1115,49 @@ pub fn autoscale_db_image_to_bitdepth(
 
 /// Convenience wrapper to return Vec<u8> or Vec<u16> as needed
 pub fn autoscale_db_image_to_bitdepth_advanced(
-    db: &Array2<f64>,
-    valid_mask: &[bool],
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    bit_depth: BitDepth,
+    strategy: AutoscaleStrategy,
+) -> (Vec<u8>, Option<Vec<u16>>) {
+    let stats = compute_histogram_stats(db, valid_mask);
+    autoscale_db_image_to_bitdepth_advanced_with_stats(
+        db,
+        valid_mask,
+        &stats,
+        bit_depth,
+        strategy,
+        &AutoscaleParams::default(),
+    )
+}
+
+/// [`autoscale_db_image_to_bitdepth_advanced`] with precomputed statistics
+pub fn autoscale_db_image_to_bitdepth_advanced_with_stats(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    stats: &HistogramStats,
     bit_depth: BitDepth,
     strategy: AutoscaleStrategy,
+    params: &AutoscaleParams,
 ) -> (Vec<u8>, Option<Vec<u16>>) {
     match bit_depth {
         BitDepth::U8 => {
-            let v: Vec<u16> =
-                autoscale_db_image_advanced(db, valid_mask, BitDepth::U8, strategy);
-            let u8_data = scale_u16_to_u8(&v);
+            let v: Vec<u16> = autoscale_db_image_advanced_with_stats(
+                db, valid_mask, stats, BitDepth::U8, strategy, params,
+            );
+            // A fixed window must map identically across scenes; skip the per-image re-stretch
+            let u8_data = if matches!(strategy, AutoscaleStrategy::Fixed { .. }) {
+                v.into_par_iter().map(|x| x as u8).collect()
+            } else {
+                scale_u16_to_u8(&v)
+            };
             debug!("autoscale_db_image_to_bitdepth: U8");
             (u8_data, None)
         }
         BitDepth::U16 => {
-            let v: Vec<u16> =
-                autoscale_db_image_advanced(db, valid_mask, BitDepth::U16, strategy);
+            let v: Vec<u16> = autoscale_db_image_advanced_with_stats(
+                db, valid_mask, stats, BitDepth::U16, strategy, params,
+            );
             debug!("autoscale_db_image_to_bitdepth: U16");
             (vec![], Some(v))
         }
@@ -708,9 +1169,25 @@ pub fn autoscale_db_image_to_bitdepth_advanced(
 /// Cross-pol (VH/HV): use a slightly higher lower cut to lift signal.
 /// Always map to U8 as synRGB expects U8 inputs and applies channel gammas itself.
 pub fn autoscale_db_image_tamed_synrgb_u8(
-    db: &Array2<f64>,
-    valid_mask: &[bool],
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    is_copol: bool,
+) -> Vec<u8> {
+    autoscale_db_image_tamed_synrgb_u8_with_params(
+        db,
+        valid_mask,
+        is_copol,
+        &AutoscaleParams::default(),
+    )
+}
+
+/// [`autoscale_db_image_tamed_synrgb_u8`] where user percentiles replace the band-specific
+/// cuts (gamma stays 1; the synRGB channel mix applies its own)
+pub fn autoscale_db_image_tamed_synrgb_u8_with_params(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
     is_copol: bool,
+    params: &AutoscaleParams,
 ) -> Vec<u8> {
     let stats = compute_histogram_stats(db, valid_mask);
     if stats.valid_count == 0 {
@@ -725,18 +1202,144 @@ pub fn autoscale_db_image_tamed_synrgb_u8(
         // Cross-pol (VH/HV): generally weaker; raise floor slightly
         (stats.p05, stats.p99)
     };
+    let low_clip = params
+        .low_percentile
+        .map_or(low_clip, |p| stats.histogram.percentile(p / 100.0));
+    let high_clip = params
+        .high_percentile
+        .map_or(high_clip, |p| stats.histogram.percentile(p / 100.0));
+
+    map_db_window(db, valid_mask, low_clip, high_clip, 1.0, 255.0, stats.min_window)
+        .into_par_iter()
+        .map(|v| v as u8)
+        .collect()
+}
 
-    let range = (high_clip - low_clip).max(1.0);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic values spread over `[lo, hi)` (xorshift, no RNG dependency)
+    fn samples(n: usize, lo: f32, hi: f32) -> Vec<f32> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                lo + (hi - lo) * ((state >> 40) as f32 / (1u64 << 24) as f32)
+            })
+            .collect()
+    }
 
-    db.indexed_iter()
-        .map(|(idx, &v)| {
-            if valid_mask[idx.0 * db.ncols() + idx.1] {
-                let clipped = v.max(low_clip).min(high_clip);
-                let normalized = (clipped - low_clip) / range;
-                (normalized * 255.0).clamp(0.0, 255.0) as u8
-            } else {
-                0u8
-            }
-        })
-        .collect()
+    fn sorted_percentile(sorted: &[f32], p: f64) -> f64 {
+        let idx = ((p * sorted.len() as f64).floor() as usize).min(sorted.len() - 1);
+        sorted[idx] as f64
+    }
+
+    fn assert_percentiles_match(histogram: &DbHistogram, values: &[f32]) {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for p in [0.0, 0.01, 0.02, 0.1, 0.25, 0.5, 0.75, 0.9, 0.98, 0.99, 1.0] {
+            let expected = sorted_percentile(&sorted, p);
+            let actual = histogram.percentile(p);
+            assert!(
+                (actual - expected).abs() <= histogram.bin_width(),
+                "p={}: histogram {} vs sorted {}",
+                p,
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn db_percentiles_match_sorted_within_a_bin() {
+        let values = samples(50_000, -35.0, 5.0);
+        let mut histogram = DbHistogram::new();
+        values.iter().for_each(|&v| histogram.push(v));
+        assert_eq!(histogram.count(), values.len() as u64);
+        assert_percentiles_match(&histogram, &values);
+    }
+
+    #[test]
+    fn ranged_percentiles_match_sorted_within_a_bin() {
+        let values = samples(20_000, -1.0, 1.0);
+        let mut histogram = DbHistogram::with_range(-1.0, 1.0);
+        values.iter().for_each(|&v| histogram.push(v));
+        assert_percentiles_match(&histogram, &values);
+    }
+
+    #[test]
+    fn merged_chunks_equal_single_pass() {
+        let values = samples(10_000, -30.0, 0.0);
+        let mut whole = DbHistogram::new();
+        values.iter().for_each(|&v| whole.push(v));
+        let merged = values
+            .chunks(1_000)
+            .map(|chunk| {
+                let mut h = DbHistogram::new();
+                chunk.iter().for_each(|&v| h.push(v));
+                h
+            })
+            .reduce(DbHistogram::merge)
+            .unwrap();
+        assert_eq!(merged.bins(), whole.bins());
+        assert_eq!(merged.count(), whole.count());
+        for p in [0.02, 0.5, 0.98] {
+            assert_eq!(merged.percentile(p), whole.percentile(p));
+        }
+    }
+
+    #[test]
+    fn narrow_window_inverts_like_the_mapping() {
+        // All values within 0.3 dB: the stretch divides by the 1 dB minimum span, and the
+        // recorded window must say so for scale/offset and the inverse to hold
+        let values: Vec<f32> = (0..64).map(|i| -12.0 + 0.3 * i as f32 / 63.0).collect();
+        let db = Array2::from_shape_vec((8, 8), values.clone()).unwrap();
+        let mask = ValidMask::all_valid(values.len());
+        let stats = compute_histogram_stats(&db, &mask);
+        let params = AutoscaleParams::default();
+        let strategy = AutoscaleStrategy::Robust;
+        let window = stretch_window(&stats, strategy, false, &params);
+        assert!((window.max_db - window.min_db - stats.min_window).abs() < 1e-12);
+
+        let max_value = 65535.0;
+        let scaled = autoscale_db_image_advanced_with_stats(
+            &db, &mask, &stats, BitDepth::U16, strategy, &params,
+        );
+        let (scale, offset) = window.scale_offset(max_value);
+        let step = (window.max_db - window.min_db) / max_value;
+        for (&v, &q) in values.iter().zip(&scaled) {
+            let v = (v as f64).clamp(window.min_db, window.max_db);
+            assert!((offset + scale * q as f64 - v).abs() <= step + 1e-6);
+            assert!((window.invert(q as f64, max_value) - v).abs() <= step + 1e-6);
+        }
+        assert_eq!(scaled[0], 0, "min_db maps onto the nodata value");
+    }
+
+    #[test]
+    fn inverted_window_is_widened_from_min() {
+        let window = StretchWindow {
+            min_db: -5.0,
+            max_db: -8.0,
+            gamma: 1.0,
+        };
+        let widened = window.with_min_span(1.0);
+        assert_eq!((widened.min_db, widened.max_db), (-5.0, -4.0));
+        assert_eq!(widened.with_min_span(1.0), widened);
+        assert_eq!(widened.invert(255.0, 255.0), -4.0);
+    }
+
+    #[test]
+    fn percentile_is_clamped_to_exact_extremes() {
+        let mut histogram = DbHistogram::new();
+        for v in [-12.3f32, -12.3, -4.7] {
+            histogram.push(v);
+        }
+        assert_eq!(histogram.percentile(0.0), -12.3f32 as f64);
+        let top = histogram.percentile(1.0);
+        assert!(top <= -4.7f32 as f64 && top > -4.7 - histogram.bin_width());
+        assert_eq!(DbHistogram::new().percentile(0.5), 0.0);
+    }
 }
@@ -0,0 +1,286 @@
+//! Bi-temporal change detection between two co-gridded intensity images.
+//! Provides the per-pixel change measures, the overlap of two aligned geotransforms and
+//! a thresholded increase/decrease mask.
+use ndarray::{Array2, Zip, s};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::processing::autoscale::{DbHistogram, PAR_CHUNK};
+use crate::core::processing::colormap::ColormapOptions;
+use crate::core::processing::pipeline::MIN_VALID_INTENSITY;
+use crate::core::processing::water::otsu_threshold;
+use crate::types::ChangeMethod;
+
+/// Mask value for pixels without significant change
+pub const CHANGE_NONE: u8 = 0;
+/// Mask value for backscatter increase (after > before)
+pub const CHANGE_INCREASE: u8 = 1;
+/// Mask value for backscatter decrease
+pub const CHANGE_DECREASE: u8 = 2;
+/// Mask value (and GeoTIFF nodata) where either date has no valid data
+pub const CHANGE_NODATA: u8 = 255;
+
+/// Options for change detection on top of the shared `ProcessingParams`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeOptions {
+    pub method: ChangeMethod,
+    /// Write a thresholded change mask next to the change raster
+    pub mask: bool,
+    /// Absolute change threshold in the method's units; None picks one with Otsu on |change|
+    pub threshold: Option<f64>,
+    /// Also render the first band as a colormapped `<output>_preview.jpg` over ±p98 |change|
+    #[serde(default)]
+    pub colormap: Option<ColormapOptions>,
+}
+
+impl Default for ChangeOptions {
+    fn default() -> Self {
+        Self {
+            method: ChangeMethod::LogRatio,
+            mask: false,
+            threshold: None,
+            colormap: None,
+        }
+    }
+}
+
+/// Pixel window `(x_off, y_off, width, height)` into one raster
+pub type GridWindow = (usize, usize, usize, usize);
+
+/// Overlap of two north-up rasters that share pixel size and alignment (e.g. warped with
+/// the same CRS, `pixel_spacing` and target-aligned pixels). Returns the window into each
+/// raster and the geotransform of the overlap, or None if they are not aligned or disjoint.
+pub fn common_grid_window(
+    gt_a: [f64; 6],
+    (rows_a, cols_a): (usize, usize),
+    gt_b: [f64; 6],
+    (rows_b, cols_b): (usize, usize),
+) -> Option<(GridWindow, GridWindow, [f64; 6])> {
+    let (px, py) = (gt_a[1], gt_a[5]);
+    let tol = 1e-6 * px.abs().max(py.abs());
+    if gt_a[2] != 0.0 || gt_a[4] != 0.0 || gt_b[2] != 0.0 || gt_b[4] != 0.0 {
+        return None;
+    }
+    if (gt_b[1] - px).abs() > tol || (gt_b[5] - py).abs() > tol {
+        return None;
+    }
+    // Origin of b in a's pixel coordinates; aligned grids give whole pixels
+    let fx = (gt_b[0] - gt_a[0]) / px;
+    let fy = (gt_b[3] - gt_a[3]) / py;
+    let (dx, dy) = (fx.round(), fy.round());
+    if (fx - dx).abs() > 0.01 || (fy - dy).abs() > 0.01 {
+        return None;
+    }
+    let (dx, dy) = (dx as i64, dy as i64);
+
+    let x0 = dx.max(0);
+    let y0 = dy.max(0);
+    let x1 = (cols_a as i64).min(dx + cols_b as i64);
+    let y1 = (rows_a as i64).min(dy + rows_b as i64);
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+    let (w, h) = ((x1 - x0) as usize, (y1 - y0) as usize);
+    let win_a = (x0 as usize, y0 as usize, w, h);
+    let win_b = ((x0 - dx) as usize, (y0 - dy) as usize, w, h);
+    let mut gt = gt_a;
+    gt[0] += x0 as f64 * px;
+    gt[3] += y0 as f64 * py;
+    Some((win_a, win_b, gt))
+}
+
+/// Owned copy of `window` from `data`
+pub fn crop_window(data: &Array2<f32>, (x, y, w, h): GridWindow) -> Array2<f32> {
+    data.slice(s![y..y + h, x..x + w]).to_owned()
+}
+
+/// Per-pixel change from `before` to `after` intensity; NaN where either date is invalid.
+/// - LogRatio: 10·log10(after / before) in dB
+/// - Difference: after − before on linear intensity
+/// - NormalizedDiff: (after − before) / (after + before) on linear intensity
+pub fn change_arrays(before: &Array2<f32>, after: &Array2<f32>, method: ChangeMethod) -> Array2<f32> {
+    let mut out = Array2::<f32>::zeros(before.dim());
+    Zip::from(&mut out)
+        .and(before)
+        .and(after)
+        .par_for_each(|o, &b, &a| {
+            let valid = |v: f32| v.is_finite() && v > MIN_VALID_INTENSITY;
+            *o = if !valid(b) || !valid(a) {
+                f32::NAN
+            } else {
+                match method {
+                    ChangeMethod::LogRatio => 10.0 * (a / b).log10(),
+                    ChangeMethod::Difference => a - b,
+                    ChangeMethod::NormalizedDiff => (a - b) / (a + b),
+                }
+            };
+        });
+    out
+}
+
+/// Histogram of |change| over the observed range (None when there is no change at all)
+fn abs_change_histogram(change: &Array2<f32>) -> Option<DbHistogram> {
+    let data = change.as_standard_layout();
+    let src = data.as_slice().expect("standard layout array is contiguous");
+    let max = src
+        .par_iter()
+        .filter(|v| v.is_finite())
+        .map(|v| v.abs() as f64)
+        .reduce(|| 0.0, f64::max);
+    if max <= 0.0 {
+        return None;
+    }
+    let hist = src
+        .par_chunks(PAR_CHUNK)
+        .fold(
+            || DbHistogram::with_range(0.0, max),
+            |mut h, chunk| {
+                for &v in chunk.iter().filter(|v| v.is_finite()) {
+                    h.push(v.abs());
+                }
+                h
+            },
+        )
+        .reduce(|| DbHistogram::with_range(0.0, max), DbHistogram::merge);
+    Some(hist)
+}
+
+/// Threshold on |change| separating changed from unchanged pixels (Otsu over the observed range)
+pub fn auto_change_threshold(change: &Array2<f32>) -> Option<f64> {
+    otsu_threshold(&abs_change_histogram(change)?)
+}
+
+/// Symmetric display range ±limit for colormapped change previews: the 98th percentile of
+/// |change|, so a few extreme pixels do not wash out the ramp
+pub fn change_display_limit(change: &Array2<f32>) -> Option<f64> {
+    let limit = abs_change_histogram(change)?.percentile(0.98);
+    (limit > 0.0).then_some(limit)
+}
+
+/// 8-bit preview values for `change` over [-limit, limit]: 1..=255 for valid pixels and 0
+/// for nodata, ready for a colormap palette
+pub fn change_preview_values(change: &Array2<f32>, limit: f64) -> Vec<u8> {
+    let data = change.as_standard_layout();
+    let src = data.as_slice().expect("standard layout array is contiguous");
+    src.par_iter()
+        .map(|&v| {
+            if !v.is_finite() {
+                return 0;
+            }
+            let t = ((v as f64 + limit) / (2.0 * limit)).clamp(0.0, 1.0);
+            (t * 255.0).round().max(1.0) as u8
+        })
+        .collect()
+}
+
+/// Change mask and (increase, decrease) pixel counts for |change| >= `threshold`
+pub fn change_mask(change: &Array2<f32>, threshold: f64) -> (Vec<u8>, usize, usize) {
+    let data = change.as_standard_layout();
+    let src = data.as_slice().expect("standard layout array is contiguous");
+    let t = threshold as f32;
+    let mut mask = vec![CHANGE_NODATA; src.len()];
+    let (inc, dec) = mask
+        .par_chunks_mut(PAR_CHUNK)
+        .zip(src.par_chunks(PAR_CHUNK))
+        .map(|(dst, chunk)| {
+            let (mut inc, mut dec) = (0usize, 0usize);
+            for (o, &v) in dst.iter_mut().zip(chunk.iter()) {
+                if !v.is_finite() {
+                    continue;
+                }
+                *o = if v >= t {
+                    inc += 1;
+                    CHANGE_INCREASE
+                } else if v <= -t {
+                    dec += 1;
+                    CHANGE_DECREASE
+                } else {
+                    CHANGE_NONE
+                };
+            }
+            (inc, dec)
+        })
+        .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+    (mask, inc, dec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn change_measures_on_known_values() {
+        let before = array![[1.0f32, 0.1], [0.5, 0.0]];
+        let after = array![[10.0f32, 0.05], [0.5, 1.0]];
+
+        let log_ratio = change_arrays(&before, &after, ChangeMethod::LogRatio);
+        let difference = change_arrays(&before, &after, ChangeMethod::Difference);
+        let normalized = change_arrays(&before, &after, ChangeMethod::NormalizedDiff);
+        let expected = [
+            (log_ratio, [10.0, -3.0103, 0.0]),
+            (difference, [9.0, -0.05, 0.0]),
+            (normalized, [9.0 / 11.0, -1.0 / 3.0, 0.0]),
+        ];
+        for (change, want) in expected {
+            let got = [change[[0, 0]], change[[0, 1]], change[[1, 0]]];
+            assert!(got.iter().zip(want).all(|(&g, w)| close(g, w)), "{:?} vs {:?}", got, want);
+            // A zero (invalid) intensity on either date is nodata
+            assert!(change[[1, 1]].is_nan());
+        }
+    }
+
+    #[test]
+    fn aligned_grids_overlap() {
+        let gt_a = [1000.0, 10.0, 0.0, 5000.0, 0.0, -10.0];
+        // b starts 3 columns right and 2 rows down of a
+        let gt_b = [1030.0, 10.0, 0.0, 4980.0, 0.0, -10.0];
+        let (win_a, win_b, gt) = common_grid_window(gt_a, (8, 10), gt_b, (8, 10)).unwrap();
+        assert_eq!(win_a, (3, 2, 7, 6));
+        assert_eq!(win_b, (0, 0, 7, 6));
+        assert_eq!(gt, gt_b);
+
+        // Swapped: the window moves to b's side
+        let (win_b, win_a, _) = common_grid_window(gt_b, (8, 10), gt_a, (8, 10)).unwrap();
+        assert_eq!((win_a, win_b), ((3, 2, 7, 6), (0, 0, 7, 6)));
+    }
+
+    #[test]
+    fn misaligned_or_disjoint_grids_have_no_window() {
+        let gt_a = [1000.0, 10.0, 0.0, 5000.0, 0.0, -10.0];
+        let shifted = [1004.0, 10.0, 0.0, 5000.0, 0.0, -10.0];
+        let coarser = [1000.0, 20.0, 0.0, 5000.0, 0.0, -20.0];
+        let rotated = [1000.0, 10.0, 0.5, 5000.0, 0.0, -10.0];
+        let disjoint = [1100.0, 10.0, 0.0, 5000.0, 0.0, -10.0];
+        for gt_b in [shifted, coarser, rotated, disjoint] {
+            assert_eq!(common_grid_window(gt_a, (8, 10), gt_b, (8, 10)), None, "{:?}", gt_b);
+        }
+    }
+
+    #[test]
+    fn threshold_separates_changed_pixels() {
+        // 90 pixels of small change around ±0.5 dB and 10 of large change around ±6 dB
+        let values: Vec<f32> = (0..100)
+            .map(|i| {
+                let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+                let magnitude = if i < 90 { 0.3 + (i % 5) as f32 * 0.1 } else { 6.0 };
+                sign * magnitude
+            })
+            .chain([f32::NAN])
+            .collect();
+        let change = Array2::from_shape_vec((1, 101), values).unwrap();
+        let t = auto_change_threshold(&change).unwrap();
+        assert!(t > 0.7 && t < 6.0, "threshold {}", t);
+
+        let (mask, inc, dec) = change_mask(&change, t);
+        assert_eq!((inc, dec), (5, 5));
+        assert_eq!(mask[100], CHANGE_NODATA);
+        assert_eq!(mask[0], CHANGE_NONE);
+
+        assert_eq!(auto_change_threshold(&Array2::zeros((2, 2))), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Fixed-size training chips on a map grid: chip placement on a global, overlap-aware grid
+//! anchored at the CRS origin, nodata coverage and extraction with fill outside the image.
+use serde::{Deserialize, Serialize};
+
+use crate::core::processing::mask::ValidMask;
+use crate::error::{Error, Result};
+use crate::types::ChipValues;
+
+/// Options for chip generation on top of the shared `ProcessingParams`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChipOptions {
+    /// Chip side in pixels
+    pub size: usize,
+    /// Pixels shared by neighbouring chips; the grid stride is `size - overlap`
+    pub overlap: usize,
+    /// Chips with a smaller fraction of valid pixels (0..=1) are dropped
+    pub min_coverage: f64,
+    /// File stem template; `{product}`, `{pol}`, `{date}`, `{row}` and `{col}` are replaced
+    pub name_template: String,
+    pub values: ChipValues,
+}
+
+impl Default for ChipOptions {
+    fn default() -> Self {
+        Self {
+            size: 256,
+            overlap: 0,
+            min_coverage: 0.5,
+            name_template: "{product}_{pol}_r{row}_c{col}".to_string(),
+            values: ChipValues::Float,
+        }
+    }
+}
+
+impl ChipOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.size == 0 {
+            return Err(Error::ZeroSize { size: self.size });
+        }
+        if self.overlap >= self.size {
+            return Err(Error::InvalidArgument {
+                arg: "overlap",
+                value: format!(
+                    "{} (must be smaller than the chip size {})",
+                    self.overlap, self.size
+                ),
+            });
+        }
+        if !(0.0..=1.0).contains(&self.min_coverage) {
+            return Err(Error::InvalidArgument {
+                arg: "min_coverage",
+                value: format!("{} (must be within 0..=1)", self.min_coverage),
+            });
+        }
+        if !self.name_template.contains("{row}") || !self.name_template.contains("{col}") {
+            return Err(Error::InvalidArgument {
+                arg: "name_template",
+                value: format!("{} (needs {{row}} and {{col}})", self.name_template),
+            });
+        }
+        Ok(())
+    }
+
+    /// Distance between neighbouring chip origins, in pixels
+    pub fn stride(&self) -> usize {
+        self.size - self.overlap
+    }
+
+    /// File stem for the chip at `row`/`col` of the grid
+    pub fn chip_name(&self, product: &str, pol: &str, date: &str, row: i64, col: i64) -> String {
+        self.name_template
+            .replace("{product}", product)
+            .replace("{pol}", pol)
+            .replace("{date}", date)
+            .replace("{row}", &row.to_string())
+            .replace("{col}", &col.to_string())
+    }
+}
+
+/// One chip position. The grid is anchored at the CRS origin, so the same `row`/`col` mark
+/// the same ground footprint in every product warped onto the same target-aligned grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ChipWindow {
+    /// Grid row, counted southwards from the CRS origin
+    pub row: i64,
+    /// Grid column, counted eastwards from the CRS origin
+    pub col: i64,
+    /// Image pixel offset of the chip's top-left corner; negative when it starts outside
+    pub row_off: i64,
+    pub col_off: i64,
+}
+
+impl ChipWindow {
+    /// North-up geotransform of the chip inside an image with geotransform `gt`
+    pub fn geotransform(&self, gt: [f64; 6]) -> [f64; 6] {
+        [
+            gt[0] + self.col_off as f64 * gt[1],
+            gt[1],
+            0.0,
+            gt[3] + self.row_off as f64 * gt[5],
+            0.0,
+            gt[5],
+        ]
+    }
+}
+
+/// Every chip of `options` that overlaps a `cols` x `rows` image with north-up geotransform
+/// `gt`. The image must sit on whole pixels of its CRS (target-aligned pixels).
+pub fn chip_windows(
+    gt: [f64; 6],
+    cols: usize,
+    rows: usize,
+    options: &ChipOptions,
+) -> Vec<ChipWindow> {
+    let size = options.size as i64;
+    let stride = options.stride() as i64;
+    // Image origin in global pixel indices, counted from the CRS origin
+    let origin_col = (gt[0] / gt[1]).round() as i64;
+    let origin_row = (gt[3] / gt[5]).round() as i64;
+    let span = |origin: i64, len: usize| {
+        let first = (origin - size).div_euclid(stride) + 1;
+        let last = (origin + len as i64 - 1).div_euclid(stride);
+        first..=last
+    };
+    let mut windows = Vec::new();
+    for row in span(origin_row, rows) {
+        for col in span(origin_col, cols) {
+            windows.push(ChipWindow {
+                row,
+                col,
+                row_off: row * stride - origin_row,
+                col_off: col * stride - origin_col,
+            });
+        }
+    }
+    windows
+}
+
+/// Fraction of the chip's `size` x `size` pixels that are valid in `mask`; pixels outside
+/// the image count as nodata
+pub fn chip_coverage(
+    mask: &ValidMask,
+    cols: usize,
+    rows: usize,
+    window: &ChipWindow,
+    size: usize,
+) -> f64 {
+    let mut valid = 0usize;
+    for r in 0..size as i64 {
+        let y = window.row_off + r;
+        if y < 0 || y >= rows as i64 {
+            continue;
+        }
+        for c in 0..size as i64 {
+            let x = window.col_off + c;
+            if x >= 0 && x < cols as i64 && mask.get(y as usize * cols + x as usize) {
+                valid += 1;
+            }
+        }
+    }
+    valid as f64 / (size * size) as f64
+}
+
+/// Copy the chip out of row-major `data`; pixels outside the image or invalid in `mask` get
+/// `fill`
+pub fn extract_chip<T: Copy>(
+    data: &[T],
+    mask: &ValidMask,
+    cols: usize,
+    rows: usize,
+    window: &ChipWindow,
+    size: usize,
+    fill: T,
+) -> Vec<T> {
+    let mut chip = vec![fill; size * size];
+    for r in 0..size {
+        let y = window.row_off + r as i64;
+        if y < 0 || y >= rows as i64 {
+            continue;
+        }
+        for c in 0..size {
+            let x = window.col_off + c as i64;
+            if x < 0 || x >= cols as i64 {
+                continue;
+            }
+            let idx = y as usize * cols + x as usize;
+            if mask.get(idx) {
+                chip[r * size + c] = data[idx];
+            }
+        }
+    }
+    chip
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(size: usize, overlap: usize) -> ChipOptions {
+        ChipOptions { size, overlap, ..ChipOptions::default() }
+    }
+
+    fn window(row: i64, col: i64, row_off: i64, col_off: i64) -> ChipWindow {
+        ChipWindow { row, col, row_off, col_off }
+    }
+
+    #[test]
+    fn grid_is_anchored_at_the_crs_origin_for_negative_origins() {
+        // 10 m pixels; the image starts at global column -3 and row -2
+        let gt = [-30.0, 10.0, 0.0, 20.0, 0.0, -10.0];
+        let windows = chip_windows(gt, 5, 4, &options(4, 0));
+        assert_eq!(
+            windows,
+            vec![
+                window(-1, -1, -2, -1),
+                window(-1, 0, -2, 3),
+                window(0, -1, 2, -1),
+                window(0, 0, 2, 3),
+            ]
+        );
+        assert_eq!(windows[0].geotransform(gt), [-40.0, 10.0, 0.0, 40.0, 0.0, -10.0]);
+    }
+
+    #[test]
+    fn overlap_shortens_the_stride() {
+        let windows = chip_windows([0.0, 1.0, 0.0, 0.0, 0.0, -1.0], 6, 1, &options(4, 2));
+        let cols: Vec<_> =
+            windows.iter().filter(|w| w.row == 0).map(|w| (w.col, w.col_off)).collect();
+        assert_eq!(cols, vec![(-1, -2), (0, 0), (1, 2), (2, 4)]);
+        let rows: Vec<_> =
+            windows.iter().filter(|w| w.col == 0).map(|w| (w.row, w.row_off)).collect();
+        assert_eq!(rows, vec![(-1, -2), (0, 0)]);
+    }
+
+    #[test]
+    fn partial_edge_chips_are_filled() {
+        let data: Vec<i32> = (1..=9).collect();
+        let mut mask = ValidMask::all_valid(9);
+        mask.set(4, false);
+        let edge = window(0, 0, -1, 1);
+        let chip = extract_chip(&data, &mask, 3, 3, &edge, 3, 0);
+        assert_eq!(chip, vec![0, 0, 0, 2, 3, 0, 0, 6, 0]);
+        assert!((chip_coverage(&mask, 3, 3, &edge, 3) - 3.0 / 9.0).abs() < 1e-12);
+
+        let inside = window(0, 0, 0, 0);
+        let chip = extract_chip(&data, &mask, 3, 3, &inside, 3, 0);
+        assert_eq!(chip, vec![1, 2, 3, 4, 0, 6, 7, 8, 9]);
+        assert!((chip_coverage(&mask, 3, 3, &inside, 3) - 8.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn options_are_validated() {
+        assert!(options(4, 3).validate().is_ok());
+        assert!(options(0, 0).validate().is_err());
+        assert!(options(4, 4).validate().is_err());
+        assert!(ChipOptions { min_coverage: 1.5, ..options(4, 0) }.validate().is_err());
+        let bad_name = ChipOptions { name_template: "{product}".into(), ..options(4, 0) };
+        assert!(bad_name.validate().is_err());
+    }
+}
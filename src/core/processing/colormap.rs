@@ -0,0 +1,388 @@
+//! Color ramps for single-band renderings.
+//! Maps autoscaled 8-bit values through a 256-entry palette (built-in ramps or custom JSON/CPT
+//! files) and draws a small legend bar labelled with the stretch's clip limits.
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::core::processing::autoscale::{PAR_CHUNK, StretchWindow};
+use crate::error::{Error, Result};
+use crate::types::Colormap;
+
+/// RGB entry per 8-bit value; entry 0 is the nodata color
+pub type Palette = [[u8; 3]; 256];
+
+/// Color of value 0, which the autoscale stage also uses for nodata
+pub const PALETTE_NODATA_RGB: [u8; 3] = [0, 0, 0];
+
+/// One color at a normalized ramp position (0 = low clip, 1 = high clip)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorStop {
+    pub position: f64,
+    pub rgb: [u8; 3],
+}
+
+/// Piecewise-linear color ramp
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorRamp {
+    pub name: String,
+    pub stops: Vec<ColorStop>,
+}
+
+/// Colormap rendering options for single-band outputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColormapOptions {
+    pub ramp: ColorRamp,
+    /// Also write `<output>.legend.jpg` with the clip limits
+    pub legend: bool,
+}
+
+impl ColormapOptions {
+    /// Options for a custom ramp `file` (preferred) or a built-in `colormap`; None when
+    /// neither is given
+    pub fn resolve(
+        colormap: Option<Colormap>,
+        file: Option<&Path>,
+        legend: bool,
+    ) -> Result<Option<Self>> {
+        let ramp = match (file, colormap) {
+            (Some(path), _) => ColorRamp::from_file(path)?,
+            (None, Some(colormap)) => ColorRamp::builtin(colormap),
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Self { ramp, legend }))
+    }
+}
+
+/// Custom ramp file: `{"name": "...", "stops": [[position, r, g, b], ...]}`
+#[derive(Deserialize)]
+struct RampFile {
+    name: Option<String>,
+    stops: Vec<[f64; 4]>,
+}
+
+impl ColorRamp {
+    /// Built-in ramp, sampled from the matplotlib / ColorBrewer definitions
+    pub fn builtin(colormap: Colormap) -> Self {
+        let rgb: &[[u8; 3]] = match colormap {
+            Colormap::Viridis => &[
+                [68, 1, 84],
+                [71, 45, 123],
+                [59, 82, 139],
+                [44, 114, 142],
+                [33, 145, 140],
+                [40, 174, 128],
+                [94, 201, 98],
+                [173, 220, 48],
+                [253, 231, 37],
+            ],
+            Colormap::Magma => &[
+                [0, 0, 4],
+                [28, 16, 68],
+                [79, 18, 123],
+                [129, 37, 129],
+                [181, 54, 122],
+                [229, 80, 100],
+                [251, 135, 97],
+                [254, 194, 135],
+                [252, 253, 191],
+            ],
+            Colormap::RdBu => &[
+                [103, 0, 31],
+                [178, 24, 43],
+                [214, 96, 77],
+                [244, 165, 130],
+                [253, 219, 199],
+                [247, 247, 247],
+                [209, 229, 240],
+                [146, 197, 222],
+                [67, 147, 195],
+                [33, 102, 172],
+                [5, 48, 97],
+            ],
+            Colormap::Terrain => {
+                return Self {
+                    name: colormap.to_string(),
+                    stops: [
+                        (0.0, [51, 51, 153]),
+                        (0.15, [0, 153, 255]),
+                        (0.25, [0, 204, 102]),
+                        (0.5, [255, 255, 153]),
+                        (0.75, [128, 92, 84]),
+                        (1.0, [255, 255, 255]),
+                    ]
+                    .into_iter()
+                    .map(|(position, rgb)| ColorStop { position, rgb })
+                    .collect(),
+                };
+            }
+        };
+        let last = (rgb.len() - 1) as f64;
+        Self {
+            name: colormap.to_string(),
+            stops: rgb
+                .iter()
+                .enumerate()
+                .map(|(i, &rgb)| ColorStop {
+                    position: i as f64 / last,
+                    rgb,
+                })
+                .collect(),
+        }
+    }
+
+    /// Load a custom ramp from a JSON file (`{"stops": [[position, r, g, b], ...]}`) or a
+    /// GMT-style `.cpt` file (`z0 r g b z1 r g b` per line, RGB color model only). Positions
+    /// or z-values are rescaled so the first stop maps to the low clip and the last to the high.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let invalid = |reason: String| Error::InvalidArgument {
+            arg: "colormap_file",
+            value: format!("{} ({})", path.display(), reason),
+        };
+        let is_cpt = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("cpt"));
+        let (name, stops) = if is_cpt {
+            (None, parse_cpt(&text).map_err(invalid)?)
+        } else {
+            let file: RampFile =
+                serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+            let stops = file
+                .stops
+                .iter()
+                .map(|&[position, r, g, b]| {
+                    Ok::<_, String>((position, [channel(r)?, channel(g)?, channel(b)?]))
+                })
+                .collect::<std::result::Result<Vec<_>, String>>()
+                .map_err(invalid)?;
+            (file.name, stops)
+        };
+        let name = name.unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "custom".to_string())
+        });
+        Self::from_stops(name, stops).map_err(invalid)
+    }
+
+    /// Ramp from `(position, rgb)` pairs in any increasing units, rescaled to 0..1
+    fn from_stops(name: String, stops: Vec<(f64, [u8; 3])>) -> std::result::Result<Self, String> {
+        if stops.len() < 2 {
+            return Err("a ramp needs at least two stops".to_string());
+        }
+        if stops.iter().any(|(p, _)| !p.is_finite()) {
+            return Err("stop positions must be finite".to_string());
+        }
+        if stops.windows(2).any(|w| w[1].0 < w[0].0) {
+            return Err("stop positions must be increasing".to_string());
+        }
+        let (first, last) = (stops[0].0, stops[stops.len() - 1].0);
+        if last <= first {
+            return Err("the first and last stop must differ".to_string());
+        }
+        Ok(Self {
+            name,
+            stops: stops
+                .into_iter()
+                .map(|(p, rgb)| ColorStop {
+                    position: (p - first) / (last - first),
+                    rgb,
+                })
+                .collect(),
+        })
+    }
+
+    /// Interpolated color at ramp position `t` (clamped to 0..1)
+    pub fn sample(&self, t: f64) -> [u8; 3] {
+        let t = t.clamp(0.0, 1.0);
+        let upper = self
+            .stops
+            .iter()
+            .position(|s| s.position >= t)
+            .unwrap_or(self.stops.len() - 1);
+        if upper == 0 {
+            return self.stops[0].rgb;
+        }
+        let (a, b) = (&self.stops[upper - 1], &self.stops[upper]);
+        let span = b.position - a.position;
+        let f = if span > 0.0 { (t - a.position) / span } else { 1.0 };
+        let mut rgb = [0u8; 3];
+        for (c, out) in rgb.iter_mut().enumerate() {
+            let v = a.rgb[c] as f64 + (b.rgb[c] as f64 - a.rgb[c] as f64) * f;
+            *out = v.round().clamp(0.0, 255.0) as u8;
+        }
+        rgb
+    }
+
+    /// 256-entry palette: value `v` takes the ramp color at `v / 255`, except 0, which stays
+    /// [`PALETTE_NODATA_RGB`] like in grayscale outputs
+    pub fn palette(&self) -> Palette {
+        let mut lut = [PALETTE_NODATA_RGB; 256];
+        for (v, entry) in lut.iter_mut().enumerate().skip(1) {
+            *entry = self.sample(v as f64 / 255.0);
+        }
+        lut
+    }
+}
+
+fn channel(v: f64) -> std::result::Result<u8, String> {
+    if (0.0..=255.0).contains(&v) {
+        Ok(v.round() as u8)
+    } else {
+        Err(format!("color component {} is outside 0..255", v))
+    }
+}
+
+/// Stops from the slice lines of a GMT color palette table
+fn parse_cpt(text: &str) -> std::result::Result<Vec<(f64, [u8; 3])>, String> {
+    let mut stops: Vec<(f64, [u8; 3])> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            if line.contains("COLOR_MODEL") && !line.to_ascii_uppercase().contains("RGB") {
+                return Err("only the RGB color model is supported".to_string());
+            }
+            continue;
+        }
+        // Background/foreground/NaN colors have no position on the ramp
+        if line.is_empty() || line.starts_with(['B', 'F', 'N']) {
+            continue;
+        }
+        let values: Vec<f64> = line
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .filter(|t| !t.is_empty())
+            .map_while(|t| t.parse::<f64>().ok())
+            .collect();
+        if values.len() < 8 {
+            return Err(format!("cannot read slice line '{}'", line));
+        }
+        for (z, rgb) in [(values[0], &values[1..4]), (values[4], &values[5..8])] {
+            let rgb = [channel(rgb[0])?, channel(rgb[1])?, channel(rgb[2])?];
+            if stops.last() != Some(&(z, rgb)) {
+                stops.push((z, rgb));
+            }
+        }
+    }
+    Ok(stops)
+}
+
+/// Interleaved RGB for 8-bit `values` through `palette`
+pub fn apply_palette(values: &[u8], palette: &Palette) -> Vec<u8> {
+    let mut rgb = vec![0u8; values.len() * 3];
+    rgb.par_chunks_mut(PAR_CHUNK * 3)
+        .zip(values.par_chunks(PAR_CHUNK))
+        .for_each(|(dst, src)| {
+            for (px, &v) in dst.chunks_exact_mut(3).zip(src) {
+                px.copy_from_slice(&palette[v as usize]);
+            }
+        });
+    rgb
+}
+
+const GLYPH_W: usize = 5;
+const GLYPH_H: usize = 7;
+const GLYPH_SCALE: usize = 2;
+const GLYPH_ADVANCE: usize = (GLYPH_W + 1) * GLYPH_SCALE;
+
+/// 5x7 bitmap rows (bit 4 = leftmost column) for the characters legend labels use
+fn glyph(c: char) -> [u8; GLYPH_H] {
+    match c {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        'd' => [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        _ => [0; GLYPH_H],
+    }
+}
+
+fn text_width(text: &str) -> usize {
+    (text.chars().count() * GLYPH_ADVANCE).saturating_sub(GLYPH_SCALE)
+}
+
+fn draw_text(rgb: &mut [u8], width: usize, x0: usize, y0: usize, text: &str) {
+    let height = rgb.len() / (width * 3);
+    for (i, c) in text.chars().enumerate() {
+        let gx = x0 + i * GLYPH_ADVANCE;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_W {
+                if bits & (0x10 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..GLYPH_SCALE {
+                    for dx in 0..GLYPH_SCALE {
+                        let (x, y) = (gx + col * GLYPH_SCALE + dx, y0 + row * GLYPH_SCALE + dy);
+                        if x < width && y < height {
+                            rgb[(y * width + x) * 3..][..3].fill(0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Legend image for an output rendered with `palette` over `window`: the color bar with
+/// ticks at the low clip, midpoint and high clip, labelled with their window values (gamma
+/// undone) followed by `unit`. Returns `(cols, rows, rgb)`.
+pub fn render_legend(
+    palette: &Palette,
+    window: &StretchWindow,
+    unit: &str,
+) -> (usize, usize, Vec<u8>) {
+    const MARGIN: usize = 24;
+    const BAR_W: usize = 256;
+    const BAR_H: usize = 24;
+    const TICK_H: usize = 6;
+    let cols = BAR_W + 2 * MARGIN;
+    let (bar_top, label_top) = (10, 10 + BAR_H + TICK_H + 4);
+    let rows = label_top + GLYPH_H * GLYPH_SCALE + 10;
+    let mut rgb = vec![255u8; cols * rows * 3];
+    let mut set = |x: usize, y: usize, c: [u8; 3]| {
+        rgb[(y * cols + x) * 3..][..3].copy_from_slice(&c);
+    };
+
+    for y in bar_top..bar_top + BAR_H {
+        for x in 0..BAR_W {
+            // Entry 0 is the nodata color; the bar shows the ramp itself
+            set(MARGIN + x, y, palette[x.max(1)]);
+        }
+    }
+    for x in MARGIN - 1..=MARGIN + BAR_W {
+        set(x, bar_top - 1, [0; 3]);
+        set(x, bar_top + BAR_H, [0; 3]);
+    }
+    for y in bar_top - 1..=bar_top + BAR_H {
+        set(MARGIN - 1, y, [0; 3]);
+        set(MARGIN + BAR_W, y, [0; 3]);
+    }
+
+    let ticks = [0.0, 0.5, 1.0];
+    for t in ticks {
+        let x = MARGIN + (t * (BAR_W - 1) as f64).round() as usize;
+        for y in bar_top + BAR_H..bar_top + BAR_H + TICK_H {
+            set(x, y, [0; 3]);
+            set(x.saturating_sub(1), y, [0; 3]);
+        }
+    }
+    for t in ticks {
+        let label = format!("{:.1}{}", window.invert(t * 255.0, 255.0), unit);
+        let center = MARGIN + (t * (BAR_W - 1) as f64).round() as usize;
+        let x = center
+            .saturating_sub(text_width(&label) / 2)
+            .min(cols.saturating_sub(text_width(&label)));
+        draw_text(&mut rgb, cols, x, label_top, &label);
+    }
+    (cols, rows, rgb)
+}
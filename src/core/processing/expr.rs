@@ -0,0 +1,702 @@
+//! Band-math expressions over polarization channels, e.g. `10*log10(VH/VV)` or
+//! `(VV-VH)/(VV+VH)`. An expression is parsed once into an AST and then evaluated
+//! a whole array at a time (rayon-parallel per node), so new indices can be tried
+//! without adding a `PolarizationOperation`.
+//!
+//! Grammar (usual precedence, `^` binds tighter than unary minus and is right-associative):
+//! numbers, the bands `VV`/`VH`/`HH`/`HV` (case-insensitive), `+ - * / ^`, parentheses and
+//! the functions `log10`, `log`/`ln`, `exp`, `sqrt`, `abs`, `db` (10·log10), `lin` (10^(x/10)),
+//! `min`, `max` and `pow`.
+use std::fmt;
+use std::str::FromStr;
+
+use ndarray::{Array2, Zip};
+use thiserror::Error;
+
+/// Errors raised while parsing or evaluating a band-math expression
+#[derive(Debug, Error)]
+pub enum ExprError {
+    #[error("unexpected {found} at position {pos}")]
+    Unexpected { pos: usize, found: String },
+    #[error("unknown identifier `{0}` (expected VV, VH, HH or HV)")]
+    UnknownIdentifier(String),
+    #[error("unknown function `{0}`")]
+    UnknownFunction(String),
+    #[error("function `{name}` takes {expected} argument(s), got {got}")]
+    Arity {
+        name: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    #[error("expression does not reference any polarization band")]
+    NoBands,
+    #[error("band {0} is not available in this product")]
+    MissingBand(Band),
+    #[error("band {band} has shape {got:?}, expected {expected:?}")]
+    ShapeMismatch {
+        band: Band,
+        got: (usize, usize),
+        expected: (usize, usize),
+    },
+}
+
+/// Polarization channel referenced by an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Band {
+    Vv,
+    Vh,
+    Hh,
+    Hv,
+}
+
+impl Band {
+    fn from_ident(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "vv" => Some(Band::Vv),
+            "vh" => Some(Band::Vh),
+            "hh" => Some(Band::Hh),
+            "hv" => Some(Band::Hv),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used for reader polarization hints
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Band::Vv => "vv",
+            Band::Vh => "vh",
+            Band::Hh => "hh",
+            Band::Hv => "hv",
+        }
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str().to_ascii_uppercase())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Log10,
+    Ln,
+    Exp,
+    Sqrt,
+    Abs,
+    Db,
+    Lin,
+    Min,
+    Max,
+    Pow,
+}
+
+impl Func {
+    fn from_ident(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "log10" => Some(Func::Log10),
+            "log" | "ln" => Some(Func::Ln),
+            "exp" => Some(Func::Exp),
+            "sqrt" => Some(Func::Sqrt),
+            "abs" => Some(Func::Abs),
+            "db" => Some(Func::Db),
+            "lin" => Some(Func::Lin),
+            "min" => Some(Func::Min),
+            "max" => Some(Func::Max),
+            "pow" => Some(Func::Pow),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Func::Log10 => "log10",
+            Func::Ln => "ln",
+            Func::Exp => "exp",
+            Func::Sqrt => "sqrt",
+            Func::Abs => "abs",
+            Func::Db => "db",
+            Func::Lin => "lin",
+            Func::Min => "min",
+            Func::Max => "max",
+            Func::Pow => "pow",
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Func::Min | Func::Max | Func::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn unary(&self) -> fn(f32) -> f32 {
+        match self {
+            Func::Log10 => f32::log10,
+            Func::Ln => f32::ln,
+            Func::Exp => f32::exp,
+            Func::Sqrt => f32::sqrt,
+            Func::Abs => f32::abs,
+            Func::Db => |x| 10.0 * x.log10(),
+            Func::Lin => |x| 10f32.powf(x / 10.0),
+            Func::Min | Func::Max | Func::Pow => unreachable!("binary function"),
+        }
+    }
+
+    fn binary(&self) -> fn(f32, f32) -> f32 {
+        match self {
+            Func::Min => f32::min,
+            Func::Max => f32::max,
+            Func::Pow => f32::powf,
+            _ => unreachable!("unary function"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl BinOp {
+    fn apply(&self) -> fn(f32, f32) -> f32 {
+        match self {
+            BinOp::Add => |a, b| a + b,
+            BinOp::Sub => |a, b| a - b,
+            BinOp::Mul => |a, b| a * b,
+            BinOp::Div => |a, b| a / b,
+            BinOp::Pow => f32::powf,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Number(f32),
+    Band(Band),
+    Neg(Box<Node>),
+    Binary(BinOp, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(v) => format!("number `{}`", v),
+            Token::Ident(s) => format!("`{}`", s),
+            Token::Op(c) => format!("`{}`", c),
+            Token::LParen => "`(`".to_string(),
+            Token::RParen => "`)`".to_string(),
+            Token::Comma => "`,`".to_string(),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, ExprError> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            // Exponent: 1e-3, 2.5E4
+            if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+                let mut j = i + 1;
+                if j < bytes.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+                    j += 1;
+                }
+                if j < bytes.len() && bytes[j].is_ascii_digit() {
+                    i = j;
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text = &src[start..i];
+            let value = text.parse::<f32>().map_err(|_| ExprError::Unexpected {
+                pos: start,
+                found: format!("number `{}`", text),
+            })?;
+            tokens.push((start, Token::Number(value)));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Ident(src[start..i].to_string())));
+        } else {
+            let token = match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Op(c),
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                ',' => Token::Comma,
+                _ => {
+                    return Err(ExprError::Unexpected {
+                        pos: i,
+                        found: format!("character `{}`", c),
+                    });
+                }
+            };
+            tokens.push((i, token));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser over the token stream
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn unexpected(&self) -> ExprError {
+        match self.tokens.get(self.pos) {
+            Some((pos, tok)) => ExprError::Unexpected {
+                pos: *pos,
+                found: tok.describe(),
+            },
+            None => ExprError::Unexpected {
+                pos: self.end,
+                found: "end of expression".to_string(),
+            },
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ExprError> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Node, ExprError> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek() {
+            let op = if *op == '+' { BinOp::Add } else { BinOp::Sub };
+            self.pos += 1;
+            let rhs = self.term()?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<Node, ExprError> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek() {
+            let op = if *op == '*' { BinOp::Mul } else { BinOp::Div };
+            self.pos += 1;
+            let rhs = self.unary()?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    // unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Result<Node, ExprError> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                Ok(Node::Neg(Box::new(self.unary()?)))
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    // power := atom ('^' unary)?
+    fn power(&mut self) -> Result<Node, ExprError> {
+        let base = self.atom()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.pos += 1;
+            let exp = self.unary()?;
+            return Ok(Node::Binary(BinOp::Pow, Box::new(base), Box::new(exp)));
+        }
+        Ok(base)
+    }
+
+    // atom := number | band | func '(' expr (',' expr)* ')' | '(' expr ')'
+    fn atom(&mut self) -> Result<Node, ExprError> {
+        match self.peek().cloned() {
+            Some(Token::Number(v)) => {
+                self.pos += 1;
+                Ok(Node::Number(v))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let inner = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if self.peek() == Some(&Token::LParen) {
+                    let func = Func::from_ident(&name)
+                        .ok_or_else(|| ExprError::UnknownFunction(name.clone()))?;
+                    self.pos += 1;
+                    let mut args = vec![self.expr()?];
+                    while self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                        args.push(self.expr()?);
+                    }
+                    self.expect(Token::RParen)?;
+                    if args.len() != func.arity() {
+                        return Err(ExprError::Arity {
+                            name: func.name(),
+                            expected: func.arity(),
+                            got: args.len(),
+                        });
+                    }
+                    Ok(Node::Call(func, args))
+                } else {
+                    Band::from_ident(&name)
+                        .map(Node::Band)
+                        .ok_or(ExprError::UnknownIdentifier(name))
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+/// Intermediate result: constants stay scalar until they meet an array
+enum Value<'a> {
+    Scalar(f32),
+    Borrowed(&'a Array2<f32>),
+    Owned(Array2<f32>),
+}
+
+impl Value<'_> {
+    fn map(self, f: fn(f32) -> f32) -> Value<'static> {
+        match self {
+            Value::Scalar(a) => Value::Scalar(f(a)),
+            Value::Borrowed(a) => Value::Owned(Zip::from(a).par_map_collect(|&x| f(x))),
+            Value::Owned(mut a) => {
+                a.par_mapv_inplace(f);
+                Value::Owned(a)
+            }
+        }
+    }
+
+    fn zip_with(self, rhs: Value<'_>, f: fn(f32, f32) -> f32) -> Value<'static> {
+        match (self, rhs) {
+            (Value::Scalar(a), Value::Scalar(b)) => Value::Scalar(f(a, b)),
+            (Value::Scalar(a), Value::Borrowed(b)) => {
+                Value::Owned(Zip::from(b).par_map_collect(|&y| f(a, y)))
+            }
+            (Value::Scalar(a), Value::Owned(mut b)) => {
+                b.par_mapv_inplace(|y| f(a, y));
+                Value::Owned(b)
+            }
+            (Value::Borrowed(a), Value::Scalar(b)) => {
+                Value::Owned(Zip::from(a).par_map_collect(|&x| f(x, b)))
+            }
+            (Value::Owned(mut a), Value::Scalar(b)) => {
+                a.par_mapv_inplace(|x| f(x, b));
+                Value::Owned(a)
+            }
+            (Value::Owned(mut a), Value::Borrowed(b)) => {
+                Zip::from(&mut a).and(b).par_for_each(|x, &y| *x = f(*x, y));
+                Value::Owned(a)
+            }
+            (Value::Owned(mut a), Value::Owned(b)) => {
+                Zip::from(&mut a).and(&b).par_for_each(|x, &y| *x = f(*x, y));
+                Value::Owned(a)
+            }
+            (Value::Borrowed(a), Value::Owned(mut b)) => {
+                Zip::from(&mut b).and(a).par_for_each(|y, &x| *y = f(x, *y));
+                Value::Owned(b)
+            }
+            (Value::Borrowed(a), Value::Borrowed(b)) => {
+                Value::Owned(Zip::from(a).and(b).par_map_collect(|&x, &y| f(x, y)))
+            }
+        }
+    }
+}
+
+/// A parsed band-math expression
+#[derive(Debug, Clone)]
+pub struct BandExpr {
+    source: String,
+    root: Node,
+}
+
+impl BandExpr {
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.len(),
+        };
+        let root = parser.expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(parser.unexpected());
+        }
+        let expr = BandExpr {
+            source: source.trim().to_string(),
+            root,
+        };
+        if expr.bands().is_empty() {
+            return Err(ExprError::NoBands);
+        }
+        Ok(expr)
+    }
+
+    /// Expression text as given (trimmed); recorded in output metadata
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Distinct bands referenced by the expression, sorted
+    pub fn bands(&self) -> Vec<Band> {
+        fn walk(node: &Node, out: &mut Vec<Band>) {
+            match node {
+                Node::Number(_) => {}
+                Node::Band(b) => {
+                    if !out.contains(b) {
+                        out.push(*b);
+                    }
+                }
+                Node::Neg(inner) => walk(inner, out),
+                Node::Binary(_, lhs, rhs) => {
+                    walk(lhs, out);
+                    walk(rhs, out);
+                }
+                Node::Call(_, args) => args.iter().for_each(|a| walk(a, out)),
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.root, &mut out);
+        out.sort();
+        out
+    }
+
+    /// Reader polarization hint covering every referenced band
+    pub fn reader_hint(&self) -> &'static str {
+        match self.bands().as_slice() {
+            [single] => single.as_str(),
+            _ => "all_pairs",
+        }
+    }
+
+    /// Evaluate over the arrays returned by `lookup`. All bands must share one shape.
+    /// Division by zero and logs of non-positive values yield non-finite pixels, which the
+    /// pipelines treat as nodata.
+    pub fn evaluate<'a, F>(&self, lookup: F) -> Result<Array2<f32>, ExprError>
+    where
+        F: Fn(Band) -> Option<&'a Array2<f32>>,
+    {
+        let mut shape: Option<(usize, usize)> = None;
+        for band in self.bands() {
+            let data = lookup(band).ok_or(ExprError::MissingBand(band))?;
+            match shape {
+                None => shape = Some(data.dim()),
+                Some(expected) if expected != data.dim() => {
+                    return Err(ExprError::ShapeMismatch {
+                        band,
+                        got: data.dim(),
+                        expected,
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        let shape = shape.ok_or(ExprError::NoBands)?;
+
+        fn eval<'a, F>(node: &Node, lookup: &F) -> Result<Value<'a>, ExprError>
+        where
+            F: Fn(Band) -> Option<&'a Array2<f32>>,
+        {
+            Ok(match node {
+                Node::Number(v) => Value::Scalar(*v),
+                Node::Band(b) => Value::Borrowed(lookup(*b).ok_or(ExprError::MissingBand(*b))?),
+                Node::Neg(inner) => eval(inner, lookup)?.map(|x| -x),
+                Node::Binary(op, lhs, rhs) => {
+                    let lhs = eval(lhs, lookup)?;
+                    let rhs = eval(rhs, lookup)?;
+                    lhs.zip_with(rhs, op.apply())
+                }
+                Node::Call(func, args) if func.arity() == 1 => {
+                    eval(&args[0], lookup)?.map(func.unary())
+                }
+                Node::Call(func, args) => {
+                    let a = eval(&args[0], lookup)?;
+                    let b = eval(&args[1], lookup)?;
+                    a.zip_with(b, func.binary())
+                }
+            })
+        }
+
+        Ok(match eval(&self.root, &lookup)? {
+            Value::Scalar(v) => Array2::from_elem(shape, v),
+            Value::Borrowed(a) => a.clone(),
+            Value::Owned(a) => a,
+        })
+    }
+}
+
+impl FromStr for BandExpr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BandExpr::parse(s)
+    }
+}
+
+impl fmt::Display for BandExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// Evaluate `source` on single-pixel VV/VH bands
+    fn eval(source: &str, vv: f32, vh: f32) -> f32 {
+        let (vv, vh) = (array![[vv]], array![[vh]]);
+        let expr = BandExpr::parse(source).unwrap();
+        let out = expr
+            .evaluate(|band| match band {
+                Band::Vv => Some(&vv),
+                Band::Vh => Some(&vh),
+                _ => None,
+            })
+            .unwrap();
+        out[[0, 0]]
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(eval("VV + VH * 2", 1.0, 3.0), 7.0);
+        assert_eq!(eval("(VV + VH) * 2", 1.0, 3.0), 8.0);
+        assert_eq!(eval("VV - VH - 1", 5.0, 1.0), 3.0);
+        assert_eq!(eval("VV / VH / 2", 8.0, 2.0), 2.0);
+        assert_eq!(eval("VV * 2 ^ 3 ^ 2", 1.0, 0.0), 512.0);
+        assert_eq!(eval("(VV - VH) / (VV + VH)", 3.0, 1.0), 0.5);
+    }
+
+    #[test]
+    fn unary_minus() {
+        // `^` binds tighter than unary minus
+        assert_eq!(eval("-VV ^ 2", 3.0, 0.0), -9.0);
+        assert_eq!(eval("(-VV) ^ 2", 3.0, 0.0), 9.0);
+        assert_eq!(eval("VV - -VH", 1.0, 2.0), 3.0);
+        assert_eq!(eval("VV ^ -1", 4.0, 0.0), 0.25);
+        assert_eq!(eval("+VV * -2", 4.0, 0.0), -8.0);
+    }
+
+    #[test]
+    fn logarithms_and_functions() {
+        assert!((eval("log10(VV)", 100.0, 0.0) - 2.0).abs() < 1e-6);
+        assert!((eval("10*log10(VH/VV)", 10.0, 1.0) + 10.0).abs() < 1e-5);
+        assert!((eval("db(VH) - 10*LOG10(vh)", 0.0, 0.3)).abs() < 1e-5);
+        assert!((eval("lin(db(VV))", 0.25, 0.0) - 0.25).abs() < 1e-6);
+        assert_eq!(eval("max(VV, VH) - min(VV, VH)", 2.0, 5.0), 3.0);
+        assert_eq!(eval("pow(VV, 2) + abs(-VH)", 3.0, 1.0), 10.0);
+        assert!(eval("log10(VV)", -1.0, 0.0).is_nan());
+    }
+
+    #[test]
+    fn division_by_zero_is_non_finite() {
+        assert_eq!(eval("VV / VH", 1.0, 0.0), f32::INFINITY);
+        assert!(eval("(VV - VH) / (VV + VH)", 0.0, 0.0).is_nan());
+        assert_eq!(eval("10*log10(VH/VV)", 0.0, 1.0), f32::INFINITY);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            BandExpr::parse("VV + XX"),
+            Err(ExprError::UnknownIdentifier(name)) if name == "XX"
+        ));
+        assert!(matches!(
+            BandExpr::parse("foo(VV)"),
+            Err(ExprError::UnknownFunction(name)) if name == "foo"
+        ));
+        assert!(matches!(
+            BandExpr::parse("max(VV)"),
+            Err(ExprError::Arity { name: "max", expected: 2, got: 1 })
+        ));
+        assert!(matches!(BandExpr::parse("1 + 2"), Err(ExprError::NoBands)));
+        assert!(matches!(
+            BandExpr::parse("VV +"),
+            Err(ExprError::Unexpected { pos: 4, .. })
+        ));
+        assert!(matches!(
+            BandExpr::parse("(VV"),
+            Err(ExprError::Unexpected { pos: 3, .. })
+        ));
+        assert!(matches!(
+            BandExpr::parse("VV % 2"),
+            Err(ExprError::Unexpected { pos: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn bands_and_missing_band() {
+        let expr = BandExpr::parse("hv / (HH + hv)").unwrap();
+        assert_eq!(expr.bands(), vec![Band::Hh, Band::Hv]);
+        assert_eq!(expr.reader_hint(), "all_pairs");
+        assert_eq!(BandExpr::parse("db(VH)").unwrap().reader_hint(), "vh");
+
+        let hh = array![[1.0f32, 2.0]];
+        let result = expr.evaluate(|band| (band == Band::Hh).then_some(&hh));
+        assert!(matches!(result, Err(ExprError::MissingBand(Band::Hv))));
+    }
+
+    #[test]
+    fn shape_mismatch_and_constant_broadcast() {
+        let vv = array![[1.0f32, 2.0], [3.0, 4.0]];
+        let vh = array![[1.0f32, 2.0]];
+        let expr = BandExpr::parse("VV + VH").unwrap();
+        let result = expr.evaluate(|band| match band {
+            Band::Vv => Some(&vv),
+            Band::Vh => Some(&vh),
+            _ => None,
+        });
+        assert!(matches!(
+            result,
+            Err(ExprError::ShapeMismatch { band: Band::Vh, got: (1, 2), expected: (2, 2) })
+        ));
+
+        let out = BandExpr::parse("VV * 0 + 2").unwrap().evaluate(|_| Some(&vv)).unwrap();
+        assert_eq!(out, array![[2.0f32, 2.0], [2.0, 2.0]]);
+    }
+}
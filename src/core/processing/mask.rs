@@ -0,0 +1,139 @@
+/// Packed per-pixel validity mask (one bit per pixel, row-major).
+///
+/// Replaces the former `Vec<bool>` mask: 8x less memory and word-aligned
+/// chunks can be written concurrently by the fused dB pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidMask {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl ValidMask {
+    /// All-invalid mask for `len` pixels
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0u64; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// All-valid mask for `len` pixels
+    pub fn all_valid(len: usize) -> Self {
+        let mut mask = Self {
+            words: vec![u64::MAX; len.div_ceil(64)],
+            len,
+        };
+        mask.clear_tail();
+        mask
+    }
+
+    /// Build from a packed word vector produced elsewhere (e.g. parallel kernels)
+    pub fn from_words(words: Vec<u64>, len: usize) -> Self {
+        debug_assert_eq!(words.len(), len.div_ceil(64));
+        let mut mask = Self { words, len };
+        mask.clear_tail();
+        mask
+    }
+
+    /// Build from an unpacked boolean slice
+    pub fn from_bools(values: &[bool]) -> Self {
+        let mut mask = Self::new(values.len());
+        for (i, &v) in values.iter().enumerate() {
+            if v {
+                mask.words[i / 64] |= 1u64 << (i % 64);
+            }
+        }
+        mask
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> bool {
+        (self.words[idx / 64] >> (idx % 64)) & 1 == 1
+    }
+
+    #[inline]
+    pub fn set(&mut self, idx: usize, valid: bool) {
+        let bit = 1u64 << (idx % 64);
+        if valid {
+            self.words[idx / 64] |= bit;
+        } else {
+            self.words[idx / 64] &= !bit;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of valid pixels
+    pub fn count_valid(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Packed words (bit `i % 64` of word `i / 64` is pixel `i`)
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Unpack into one `bool` per pixel
+    pub fn to_bools(&self) -> Vec<bool> {
+        (0..self.len).map(|i| self.get(i)).collect()
+    }
+
+    // Keep bits past `len` zero so `count_valid` stays exact
+    fn clear_tail(&mut self) {
+        let rem = self.len % 64;
+        if rem != 0
+            && let Some(last) = self.words.last_mut()
+        {
+            *last &= (1u64 << rem) - 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_cross_word_boundaries() {
+        let mut mask = ValidMask::new(130);
+        assert_eq!(mask.words().len(), 3);
+        for idx in [0, 63, 64, 127, 128, 129] {
+            mask.set(idx, true);
+        }
+        assert_eq!(mask.count_valid(), 6);
+        assert_eq!(mask.words()[0], 1 | (1 << 63));
+        assert_eq!(mask.words()[1], 1 | (1 << 63));
+        assert_eq!(mask.words()[2], 0b11);
+        assert!(mask.get(64) && !mask.get(65) && !mask.get(126));
+
+        mask.set(64, false);
+        assert!(!mask.get(64));
+        assert_eq!(mask.count_valid(), 5);
+    }
+
+    #[test]
+    fn tail_bits_stay_clear() {
+        let mask = ValidMask::all_valid(70);
+        assert_eq!(mask.count_valid(), 70);
+        assert_eq!(mask.words()[1], (1 << 6) - 1);
+
+        let mask = ValidMask::from_words(vec![u64::MAX, u64::MAX], 65);
+        assert_eq!(mask.count_valid(), 65);
+        assert!(ValidMask::all_valid(0).is_empty());
+    }
+
+    #[test]
+    fn bools_round_trip() {
+        let values: Vec<bool> = (0..200).map(|i| i % 3 == 0 || i % 64 == 63).collect();
+        let mask = ValidMask::from_bools(&values);
+        assert_eq!(mask.len(), values.len());
+        assert_eq!(mask.to_bools(), values);
+        assert_eq!(mask.count_valid(), values.iter().filter(|&&v| v).count());
+    }
+}
@@ -1,7 +1,16 @@
 pub mod autoscale;
+pub mod change;
+pub mod chips;
+pub mod colormap;
+pub mod expr;
+pub mod mask;
 pub mod ops;
 pub mod padding;
 pub mod pipeline;
 pub mod resize;
 pub mod save;
+pub mod ships;
 pub mod synthetic_rgb;
+pub mod temporal;
+pub mod tiles;
+pub mod water;
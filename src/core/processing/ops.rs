@@ -42,3 +42,132 @@ pub fn log_ratio_arrays(a: &Array2<f32>, b: &Array2<f32>) -> Array2<f32> {
         });
     result
 }
+
+/// Radar Vegetation Index (dual-pol): 4·cross / (co + cross); NaN where there is no signal
+pub fn rvi_arrays(co: &Array2<f32>, cross: &Array2<f32>) -> Array2<f32> {
+    let mut result = Array2::zeros(co.dim());
+    Zip::from(co)
+        .and(cross)
+        .and(&mut result)
+        .for_each(|&a_val, &b_val, res| {
+            let denom = a_val + b_val;
+            if denom > 1e-10 { *res = 4.0 * b_val / denom; } else { *res = f32::NAN; }
+        });
+    result
+}
+
+/// Cross-pol ratio in dB: 10·log10(cross / co); NaN where either channel is empty
+pub fn cross_pol_db_arrays(co: &Array2<f32>, cross: &Array2<f32>) -> Array2<f32> {
+    let mut result = Array2::zeros(co.dim());
+    Zip::from(co)
+        .and(cross)
+        .and(&mut result)
+        .for_each(|&a_val, &b_val, res| {
+            if a_val > 1e-10 && b_val > 1e-10 {
+                *res = 10.0 * (b_val / a_val).log10();
+            } else {
+                *res = f32::NAN;
+            }
+        });
+    result
+}
+
+/// Depolarization ratio (linear): cross / co; NaN where either channel is empty
+pub fn depol_ratio_arrays(co: &Array2<f32>, cross: &Array2<f32>) -> Array2<f32> {
+    let mut result = Array2::zeros(co.dim());
+    Zip::from(co)
+        .and(cross)
+        .and(&mut result)
+        .for_each(|&a_val, &b_val, res| {
+            if a_val > 1e-10 && b_val > 1e-10 { *res = b_val / a_val; } else { *res = f32::NAN; }
+        });
+    result
+}
+
+/// Dual-pol SAR Vegetation Index: ((co_max - co + cross) / √2) · ((co + cross) / co) · cross,
+/// with co_max the scene maximum of the co-pol channel; dB conversion occurs downstream
+pub fn dpsvi_arrays(co: &Array2<f32>, cross: &Array2<f32>) -> Array2<f32> {
+    let co_max = co
+        .iter()
+        .copied()
+        .filter(|v| v.is_finite())
+        .fold(0.0f32, f32::max);
+    let mut result = Array2::zeros(co.dim());
+    Zip::from(co)
+        .and(cross)
+        .and(&mut result)
+        .for_each(|&a_val, &b_val, res| {
+            if a_val > 1e-10 {
+                *res = (co_max - a_val + b_val) * std::f32::consts::FRAC_1_SQRT_2
+                    * ((a_val + b_val) / a_val)
+                    * b_val;
+            } else {
+                *res = 0.0;
+            }
+        });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn close(a: f32, b: f64) -> bool {
+        (a as f64 - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn rvi_on_known_values() {
+        let co = array![[0.4f32, 0.1, 0.0]];
+        let cross = array![[0.1f32, 0.1, 0.0]];
+        let rvi = rvi_arrays(&co, &cross);
+        assert!(close(rvi[[0, 0]], 0.8));
+        assert!(close(rvi[[0, 1]], 2.0));
+        assert!(rvi[[0, 2]].is_nan());
+    }
+
+    #[test]
+    fn cross_pol_db_on_known_values() {
+        let co = array![[0.4f32, 0.1, 0.0, 0.2]];
+        let cross = array![[0.1f32, 0.1, 0.1, 0.0]];
+        let db = cross_pol_db_arrays(&co, &cross);
+        assert!(close(db[[0, 0]], 10.0 * 0.25f64.log10()));
+        assert!(close(db[[0, 1]], 0.0));
+        assert!(db[[0, 2]].is_nan());
+        assert!(db[[0, 3]].is_nan());
+    }
+
+    #[test]
+    fn depol_ratio_on_known_values() {
+        let co = array![[0.4f32, 0.1, 0.0, 0.2]];
+        let cross = array![[0.1f32, 0.1, 0.1, 0.0]];
+        let ratio = depol_ratio_arrays(&co, &cross);
+        assert!(close(ratio[[0, 0]], 0.25));
+        assert!(close(ratio[[0, 1]], 1.0));
+        assert!(ratio[[0, 2]].is_nan());
+        assert!(ratio[[0, 3]].is_nan());
+    }
+
+    #[test]
+    fn dpsvi_on_known_values() {
+        // co_max = 0.4
+        let co = array![[0.4f32, 0.1, 0.0]];
+        let cross = array![[0.1f32, 0.1, 0.1]];
+        let dpsvi = dpsvi_arrays(&co, &cross);
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        assert!(close(dpsvi[[0, 0]], 0.1 * s * 1.25 * 0.1));
+        assert!(close(dpsvi[[0, 1]], 0.4 * s * 2.0 * 0.1));
+        assert_eq!(dpsvi[[0, 2]], 0.0);
+    }
+
+    #[test]
+    fn dpsvi_ignores_non_finite_co_pol_in_the_maximum() {
+        // co_max = 0.2; the NaN pixel has no valid co-pol and maps to 0
+        let co = array![[f32::NAN, 0.2]];
+        let cross = array![[0.1f32, 0.1]];
+        let dpsvi = dpsvi_arrays(&co, &cross);
+        assert_eq!(dpsvi[[0, 0]], 0.0);
+        assert!(close(dpsvi[[0, 1]], 0.1 * std::f64::consts::FRAC_1_SQRT_2 * 1.5 * 0.1));
+    }
+}
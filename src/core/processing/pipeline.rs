@@ -1,67 +1,232 @@
 use ndarray::Array2;
+use rayon::prelude::*;
 
 use crate::core::processing::autoscale::{
-    autoscale_db_image_to_bitdepth, autoscale_db_image_to_bitdepth_advanced,
+    AutoscaleParams, DEFAULT_DB_FLOOR, DbHistogram, HistogramStats, PAR_CHUNK, StretchWindow,
+    autoscale_db_image_to_bitdepth_advanced_with_stats, autoscale_db_image_to_bitdepth_with_stats,
+    stretch_window,
 };
-use crate::types::{AutoscaleStrategy, BitDepth};
+use crate::core::processing::mask::ValidMask;
+use crate::types::{AutoscaleStrategy, BitDepth, ProcessingOperation};
 
-pub fn process_scalar_data_inplace(processed: &Array2<f32>) -> (Array2<f64>, Vec<bool>) {
+/// Linear intensity equivalent of the default dB validity floor, for analyses on linear data
+pub(crate) const MIN_VALID_INTENSITY: f32 = 1e-5;
+
+pub fn process_scalar_data_inplace(processed: &Array2<f32>) -> (Array2<f32>, ValidMask) {
+    let (db_data, valid_mask, _) = process_scalar_data_fused(processed);
+    (db_data, valid_mask)
+}
+
+/// Single fused pass over the source: dB conversion, validity mask and dB histogram.
+/// Work is split into word-aligned chunks so each rayon task owns its slice of the
+/// output, its mask words and a partial histogram that is merged at the end.
+pub fn process_scalar_data_fused(
+    processed: &Array2<f32>,
+) -> (Array2<f32>, ValidMask, DbHistogram) {
+    process_scalar_data_fused_with_floor(processed, DEFAULT_DB_FLOOR)
+}
+
+/// [`process_scalar_data_fused`] with pixels at or below `db_floor` (dB) masked as nodata
+pub fn process_scalar_data_fused_with_floor(
+    processed: &Array2<f32>,
+    db_floor: f64,
+) -> (Array2<f32>, ValidMask, DbHistogram) {
+    let db_floor = db_floor as f32;
     let (rows, cols) = processed.dim();
     let len = rows * cols;
 
-    // Prefer contiguous slice iteration to minimize bounds checks
-    if let Some(src) = processed.as_slice() {
-        let mut db_vec: Vec<f64> = Vec::with_capacity(len);
-        let mut valid_mask: Vec<bool> = Vec::with_capacity(len);
-
-        // Single pass over source data: compute dB and validity mask
-        for &v in src.iter() {
-            let magnitude = (v as f64).max(1e-10);
-            let db_val = 10.0 * magnitude.log10();
-            db_vec.push(db_val);
-            valid_mask.push(db_val > -50.0);
-        }
-
-        let db_data = Array2::from_shape_vec((rows, cols), db_vec)
-            .expect("db_vec length should match (rows*cols)");
-        return (db_data, valid_mask);
-    }
+    let src = processed.as_standard_layout();
+    let src = src.as_slice().expect("standard layout array is contiguous");
+
+    let mut db_vec = vec![0f32; len];
+    let mut words = vec![0u64; len.div_ceil(64)];
+
+    let hist = src
+        .par_chunks(PAR_CHUNK)
+        .zip(db_vec.par_chunks_mut(PAR_CHUNK))
+        .zip(words.par_chunks_mut(PAR_CHUNK / 64))
+        .fold(DbHistogram::new, |mut hist, ((chunk, db_out), mask_words)| {
+            for (k, (&v, out)) in chunk.iter().zip(db_out.iter_mut()).enumerate() {
+                let db_val = 10.0 * v.max(1e-10).log10();
+                *out = db_val;
+                if db_val > db_floor {
+                    mask_words[k / 64] |= 1u64 << (k % 64);
+                    hist.push(db_val);
+                }
+            }
+            hist
+        })
+        .reduce(DbHistogram::new, DbHistogram::merge);
+
+    let db_data = Array2::from_shape_vec((rows, cols), db_vec)
+        .expect("db_vec length should match (rows*cols)");
+    (db_data, ValidMask::from_words(words, len), hist)
+}
 
-    // Fallback: indexed iteration
-    let mut db_data = Array2::<f64>::zeros((rows, cols));
-    let mut valid_mask = Vec::with_capacity(len);
-    for ((i, j), &val) in processed.indexed_iter() {
-        let magnitude = (val as f64).max(1e-10);
-        let db_val = 10.0 * magnitude.log10();
-        db_data[[i, j]] = db_val;
-        valid_mask.push(db_val > -50.0);
+/// Mask and histogram for values that are already on their final scale (no dB conversion).
+/// Non-finite pixels are nodata; the histogram spans the observed finite range.
+pub fn process_native_data_fused(
+    processed: &Array2<f32>,
+) -> (Array2<f32>, ValidMask, DbHistogram) {
+    let data = processed.as_standard_layout().into_owned();
+    let src = data.as_slice().expect("standard layout array is contiguous");
+    let len = src.len();
+
+    let (min, max) = src
+        .par_iter()
+        .filter(|v| v.is_finite())
+        .fold(
+            || (f64::INFINITY, f64::NEG_INFINITY),
+            |(lo, hi), &v| (lo.min(v as f64), hi.max(v as f64)),
+        )
+        .reduce(
+            || (f64::INFINITY, f64::NEG_INFINITY),
+            |a, b| (a.0.min(b.0), a.1.max(b.1)),
+        );
+    let (min, max) = if min <= max { (min, max) } else { (0.0, 1.0) };
+
+    let mut words = vec![0u64; len.div_ceil(64)];
+    let hist = src
+        .par_chunks(PAR_CHUNK)
+        .zip(words.par_chunks_mut(PAR_CHUNK / 64))
+        .fold(
+            || DbHistogram::with_range(min, max),
+            |mut hist, (chunk, mask_words)| {
+                for (k, &v) in chunk.iter().enumerate() {
+                    if v.is_finite() {
+                        mask_words[k / 64] |= 1u64 << (k % 64);
+                        hist.push(v);
+                    }
+                }
+                hist
+            },
+        )
+        .reduce(|| DbHistogram::with_range(min, max), DbHistogram::merge);
+
+    (data, ValidMask::from_words(words, len), hist)
+}
+
+/// Route to the dB or native-scale pipeline depending on what `operation` produces
+pub fn process_operation_data_pipeline(
+    processed: &Array2<f32>,
+    bit_depth: BitDepth,
+    strategy: AutoscaleStrategy,
+    operation: ProcessingOperation,
+) -> (Array2<f32>, ValidMask, Vec<u8>, Option<Vec<u16>>) {
+    let (data, valid_mask, scaled_u8, scaled_u16, _) = process_operation_data_pipeline_with_window(
+        processed,
+        bit_depth,
+        strategy,
+        &AutoscaleParams::default(),
+        operation,
+    );
+    (data, valid_mask, scaled_u8, scaled_u16)
+}
+
+/// [`process_operation_data_pipeline`] with tuning `params`, also returning the clip window
+/// it applied
+pub fn process_operation_data_pipeline_with_window(
+    processed: &Array2<f32>,
+    bit_depth: BitDepth,
+    strategy: AutoscaleStrategy,
+    params: &AutoscaleParams,
+    operation: ProcessingOperation,
+) -> (Array2<f32>, ValidMask, Vec<u8>, Option<Vec<u16>>, StretchWindow) {
+    if operation.skips_db_conversion() {
+        process_native_data_pipeline_with_window(processed, bit_depth, strategy, params)
+    } else {
+        process_scalar_data_pipeline_with_window(processed, bit_depth, strategy, params)
     }
-    (db_data, valid_mask)
+}
+
+/// Autoscale index values as-is. The dB-tuned heuristics of the Standard strategy do not
+/// apply here, so it falls back to the plain p05..p95 window of the advanced path.
+pub fn process_native_data_pipeline(
+    processed: &Array2<f32>,
+    bit_depth: BitDepth,
+    strategy: AutoscaleStrategy,
+) -> (Array2<f32>, ValidMask, Vec<u8>, Option<Vec<u16>>) {
+    let (data, valid_mask, scaled_u8, scaled_u16, _) = process_native_data_pipeline_with_window(
+        processed,
+        bit_depth,
+        strategy,
+        &AutoscaleParams::default(),
+    );
+    (data, valid_mask, scaled_u8, scaled_u16)
+}
+
+/// [`process_native_data_pipeline`] with tuning `params`, also returning the clip window it
+/// applied. The dB floor does not apply to native-scale values.
+pub fn process_native_data_pipeline_with_window(
+    processed: &Array2<f32>,
+    bit_depth: BitDepth,
+    strategy: AutoscaleStrategy,
+    params: &AutoscaleParams,
+) -> (Array2<f32>, ValidMask, Vec<u8>, Option<Vec<u16>>, StretchWindow) {
+    let (data, valid_mask, hist) = process_native_data_fused(processed);
+    let stats = HistogramStats::from_histogram(hist);
+    let window = stretch_window(&stats, strategy, true, params);
+    let (scaled_u8, scaled_u16) = autoscale_db_image_to_bitdepth_advanced_with_stats(
+        &data,
+        &valid_mask,
+        &stats,
+        bit_depth,
+        strategy,
+        params,
+    );
+    (data, valid_mask, scaled_u8, scaled_u16, window)
 }
 
 pub fn process_scalar_data_pipeline(
     processed: &Array2<f32>,
     bit_depth: BitDepth,
     strategy: AutoscaleStrategy,
-) -> (Array2<f64>, Vec<bool>, Vec<u8>, Option<Vec<u16>>) {
-    let (db_data, valid_mask) = process_scalar_data_inplace(processed);
+) -> (Array2<f32>, ValidMask, Vec<u8>, Option<Vec<u16>>) {
+    let (db_data, valid_mask, scaled_u8, scaled_u16, _) = process_scalar_data_pipeline_with_window(
+        processed,
+        bit_depth,
+        strategy,
+        &AutoscaleParams::default(),
+    );
+    (db_data, valid_mask, scaled_u8, scaled_u16)
+}
+
+/// [`process_scalar_data_pipeline`] with tuning `params`, also returning the clip window it
+/// applied
+pub fn process_scalar_data_pipeline_with_window(
+    processed: &Array2<f32>,
+    bit_depth: BitDepth,
+    strategy: AutoscaleStrategy,
+    params: &AutoscaleParams,
+) -> (Array2<f32>, ValidMask, Vec<u8>, Option<Vec<u16>>, StretchWindow) {
+    let (db_data, valid_mask, hist) =
+        process_scalar_data_fused_with_floor(processed, params.db_floor);
+    let stats = HistogramStats::from_histogram(hist);
+    let window = stretch_window(&stats, strategy, false, params);
 
     let (scaled_u8, scaled_u16) = match strategy {
-        AutoscaleStrategy::Standard => {
-            autoscale_db_image_to_bitdepth(&db_data, &valid_mask, bit_depth)
-        }
+        AutoscaleStrategy::Standard => autoscale_db_image_to_bitdepth_with_stats(
+            &db_data,
+            &valid_mask,
+            &stats,
+            bit_depth,
+            params,
+        ),
         AutoscaleStrategy::Robust
         | AutoscaleStrategy::Adaptive
         | AutoscaleStrategy::Equalized
         | AutoscaleStrategy::Clahe
         | AutoscaleStrategy::Tamed
-        | AutoscaleStrategy::Default => autoscale_db_image_to_bitdepth_advanced(
+        | AutoscaleStrategy::Default
+        | AutoscaleStrategy::Fixed { .. } => autoscale_db_image_to_bitdepth_advanced_with_stats(
             &db_data,
             &valid_mask,
+            &stats,
             bit_depth,
             strategy,
+            params,
         ),
     };
 
-    (db_data, valid_mask, scaled_u8, scaled_u16)
+    (db_data, valid_mask, scaled_u8, scaled_u16, window)
 }
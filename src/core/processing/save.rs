@@ -1,38 +1,74 @@
+use gdal::Metadata;
 use ndarray::Array2;
 use std::path::Path;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::core::processing::pipeline::process_scalar_data_pipeline;
-use crate::core::processing::autoscale::autoscale_db_image_tamed_synrgb_u8;
+use crate::core::processing::change::{
+    CHANGE_NODATA, ChangeOptions, auto_change_threshold, change_display_limit, change_mask,
+    change_preview_values,
+};
+use crate::core::processing::pipeline::{
+    process_operation_data_pipeline_with_window, process_scalar_data_fused_with_floor,
+    process_scalar_data_pipeline_with_window,
+};
+use crate::core::processing::autoscale::{
+    AutoscaleParams, StretchWindow, autoscale_db_image_tamed_synrgb_u8_with_params,
+};
+use crate::core::processing::colormap::{ColormapOptions, apply_palette, render_legend};
+use crate::core::processing::mask::ValidMask;
 use crate::core::processing::resize::resize_image_data_with_meta;
+use crate::core::processing::ships::{ShipOptions, ShipTarget, detect_ships};
 use crate::core::processing::synthetic_rgb::create_synthetic_rgb_by_mode_and_strategy;
-use crate::io::writers::jpeg::{write_gray_jpeg, write_rgb_jpeg};
+use crate::core::processing::water::{WATER_NODATA, WaterOptions, WaterResult, detect_water};
+use crate::io::footprint::Footprint;
+use crate::io::geolocation::{PixelGeolocation, pixel_ground_size};
+use crate::io::writers::encode::{EncodeOptions, JpegOptions};
+use crate::io::writers::jpeg::{
+    check_jpeg_dimensions, write_gray_jpeg_with_options, write_rgb_jpeg,
+    write_rgb_jpeg_with_options,
+};
+use crate::io::writers::kml::{GroundOverlay, OverlayPlacement, kml_description, write_kmz};
 use crate::io::writers::metadata::{
-    create_jpeg_metadata_sidecar_with_overrides, create_jpeg_metadata_sidecar_with_overrides_and_extras, embed_tiff_metadata,
+    create_jpeg_metadata_sidecar_with_overrides, create_jpeg_metadata_sidecar_with_overrides_and_extras,
+    create_metadata_sidecar_with_json_extras, embed_tiff_metadata, extract_metadata_fields,
 };
+use crate::io::writers::png::{rgb_planes, write_gray_png, write_gray_png_u16, write_rgb_png};
 use crate::io::writers::tiff::{
-    write_tiff_multiband_u8, write_tiff_multiband_u16, write_tiff_u8, write_tiff_u16,
+    write_tiff_bands_f32, write_tiff_jpeg_tiled, write_tiff_multiband_u8, write_tiff_multiband_u16,
+    write_tiff_paletted, write_tiff_u8, write_tiff_u16,
 };
+use crate::io::writers::vector::{write_footprint_geojson, write_ships_csv, write_ships_geojson};
+use crate::io::writers::webp::{write_gray_webp, write_rgb_webp};
 use crate::io::writers::worldfile::{write_prj_file, write_world_file};
 use crate::types::{
-    AutoscaleStrategy, BitDepth, OutputFormat, PolarizationOperation, ProcessingOperation, SyntheticRgbMode,
+    AutoscaleStrategy, BitDepth, ExprScale, OutputFormat, PolarizationOperation, ProcessingOperation, SyntheticRgbMode,
 };
 
 // resize_image_data moved to crate::core::processing::resize
 
-pub fn save_processed_image(
-    processed: &Array2<f32>,
-    output: &Path,
-    format: OutputFormat,
-    bit_depth: BitDepth,
-    target_size: Option<usize>,
-    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
-    pad: bool,
+/// Copy of `meta` stamped with the autoscale strategy and tuning, the per-band clip windows
+/// used and the integer range they were mapped onto
+fn with_autoscale(
+    meta: &crate::io::sentinel1::SafeMetadata,
     strategy: AutoscaleStrategy,
-    operation: ProcessingOperation,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Map operation enum to metadata label when needed
-    let operation_label: Option<String> = match operation {
+    params: &AutoscaleParams,
+    windows: Vec<StretchWindow>,
+    bit_depth: BitDepth,
+) -> crate::io::sentinel1::SafeMetadata {
+    let mut meta = meta.clone();
+    meta.autoscale = Some(strategy);
+    meta.autoscale_params = Some(*params);
+    meta.autoscale_windows = windows;
+    meta.autoscale_max_value = Some(match bit_depth {
+        BitDepth::U8 => 255.0,
+        BitDepth::U16 => 65535.0,
+    });
+    meta
+}
+
+/// Metadata label of `operation` (None for plain single-band outputs)
+fn operation_label(operation: ProcessingOperation) -> Option<String> {
+    match operation {
         ProcessingOperation::SingleBand => None,
         ProcessingOperation::MultibandVvVh => Some("multiband_vv_vh".to_string()),
         ProcessingOperation::MultibandHhHv => Some("multiband_hh_hv".to_string()),
@@ -45,11 +81,212 @@ pub fn save_processed_image(
         ProcessingOperation::PolarOp(PolarizationOperation::LogRatio) => {
             Some("log_ratio".to_string())
         }
+        ProcessingOperation::PolarOp(PolarizationOperation::Rvi) => Some("rvi".to_string()),
+        ProcessingOperation::PolarOp(PolarizationOperation::CrossPolDb) => {
+            Some("cross_pol_db".to_string())
+        }
+        ProcessingOperation::PolarOp(PolarizationOperation::DepolRatio) => {
+            Some("depol_ratio".to_string())
+        }
+        ProcessingOperation::PolarOp(PolarizationOperation::Dpsvi) => Some("dpsvi".to_string()),
+        ProcessingOperation::Expression { scale: ExprScale::Linear } => {
+            Some("expression".to_string())
+        }
+        ProcessingOperation::Expression { scale } => Some(format!("expression_{}", scale)),
+    }
+}
+
+/// Alpha channel for PNG/WebP outputs: pixels valid in every mask are opaque (255), the
+/// rest transparent (0). The mask goes through the same resize and padding as the image, so
+/// padding comes out transparent too.
+fn resized_alpha(
+    masks: &[&ValidMask],
+    cols: usize,
+    rows: usize,
+    target_size: Option<usize>,
+    pad: bool,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let alpha: Vec<u8> = (0..cols * rows)
+        .map(|i| if masks.iter().all(|m| m.get(i)) { 255 } else { 0 })
+        .collect();
+    let (_, _, resized, _, _, _, _, _) =
+        resize_image_data_with_meta(&alpha, None, cols, rows, target_size, BitDepth::U8, pad)?;
+    // Resampling softens the mask edge; keep it binary so no half-transparent fringe remains
+    Ok(resized.into_iter().map(|a| if a >= 128 { 255 } else { 0 }).collect())
+}
+
+/// World file, .prj and JSON sidecar next to a PNG/WebP/JPEG image
+fn write_image_sidecars(
+    output: &Path,
+    meta: &crate::io::sentinel1::SafeMetadata,
+    operation_label: Option<&str>,
+    gt_override: Option<[f64; 6]>,
+    proj_override: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(gt) = gt_override {
+        write_world_file(output, gt)?;
+    }
+    if let Some(p) = proj_override {
+        write_prj_file(output, p)?;
+    }
+    create_jpeg_metadata_sidecar_with_overrides(
+        output,
+        meta,
+        operation_label,
+        gt_override,
+        proj_override,
+    )
+}
+
+/// Stand-in for a JPEG beyond the format's size limit. Returns `false` when `cols` x `rows`
+/// fits a JPEG and nothing was written. Otherwise writes `data` (gray, or interleaved RGB when
+/// `rgb` is set) as a tiled, JPEG-compressed GeoTIFF at `<output>.tif` when
+/// `jpeg.tiff_fallback` is set, or fails with the size error.
+fn write_oversized_jpeg_as_tiff(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    data: &[u8],
+    rgb: bool,
+    jpeg: &JpegOptions,
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+    operation_label: Option<&str>,
+    gt_override: Option<[f64; 6]>,
+    proj_override: Option<&str>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let Err(e) = check_jpeg_dimensions(cols, rows) else {
+        return Ok(false);
+    };
+    if !jpeg.tiff_fallback {
+        return Err(e.into());
+    }
+    let fallback = output.with_extension("tif");
+    warn!(
+        "{}x{} exceeds the JPEG size limit; writing a tiled JPEG-compressed GeoTIFF {:?} instead",
+        cols, rows, fallback
+    );
+    let mut ds = if rgb {
+        let [r, g, b] = rgb_planes(data);
+        write_tiff_jpeg_tiled(&fallback, cols, rows, &[&r, &g, &b], jpeg.quality)?
+    } else {
+        write_tiff_jpeg_tiled(&fallback, cols, rows, &[data], jpeg.quality)?
     };
+    if let Some(meta) = metadata {
+        embed_tiff_metadata(&mut ds, meta, operation_label, gt_override, proj_override)?;
+    }
+    Ok(true)
+}
+
+/// Output settings shared by [`save_processed_image`] and
+/// [`save_processed_multiband_image_sequential`]
+#[derive(Debug, Clone)]
+pub struct SaveOptions {
+    pub format: OutputFormat,
+    /// Integer depth of TIFF and PNG outputs; JPEG, WebP and colormapped outputs are 8-bit
+    pub bit_depth: BitDepth,
+    /// Long side in pixels; None keeps the reader's size
+    pub target_size: Option<usize>,
+    /// Zero-pad to square after resizing
+    pub pad: bool,
+    pub strategy: AutoscaleStrategy,
+    pub autoscale_params: AutoscaleParams,
+    /// Render single-band outputs through a color ramp, plus a legend when
+    /// `colormap.legend` is set; ignored by multiband outputs
+    pub colormap: Option<ColormapOptions>,
+    /// Encoder settings for the lossy formats
+    pub encode: EncodeOptions,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::TIFF,
+            bit_depth: BitDepth::U8,
+            target_size: None,
+            pad: false,
+            strategy: AutoscaleStrategy::Clahe,
+            autoscale_params: AutoscaleParams::default(),
+            colormap: None,
+            encode: EncodeOptions::default(),
+        }
+    }
+}
+
+/// Geotransform and projection of an output resized from `cols` x `rows` to `final_cols` x
+/// `final_rows` and padded by `pad_left`/`pad_top`: the pixel size grows by the resize factor
+/// and the origin moves out by the padding. None where `metadata` has no georeferencing.
+fn output_georef(
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+    (cols, rows): (usize, usize),
+    (final_cols, final_rows): (usize, usize),
+    (scale_x, scale_y): (f64, f64),
+    (pad_left, pad_top): (usize, usize),
+) -> (Option<[f64; 6]>, Option<&str>) {
+    let gt = metadata.and_then(|m| m.geotransform).map(|mut gt| {
+        if scale_x > 0.0 {
+            gt[1] *= cols as f64 / final_cols as f64;
+        }
+        if scale_y > 0.0 {
+            gt[5] *= rows as f64 / final_rows as f64;
+        }
+        gt[0] -= pad_left as f64 * gt[1];
+        gt[3] -= pad_top as f64 * gt[5];
+        gt
+    });
+    (gt, metadata.and_then(|m| m.projection.as_deref()))
+}
+
+/// Stretch a single-band intensity image to `options.format` and write it with its metadata
+/// (embedded in TIFFs; world file, .prj and JSON sidecar otherwise). With
+/// `options.colormap`, the 8-bit stretch becomes an RGB JPEG, an RGBA PNG/WebP or a paletted
+/// GeoTIFF (value 0 keeps the nodata color), plus `<output>.legend.jpg` with the clip limits
+/// when `colormap.legend` is set.
+pub fn save_processed_image(
+    processed: &Array2<f32>,
+    output: &Path,
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+    operation: ProcessingOperation,
+    options: &SaveOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match &options.colormap {
+        Some(colormap) => {
+            save_colormapped_image(processed, output, metadata, operation, colormap, options)
+        }
+        None => save_stretched_image(processed, output, metadata, operation, options),
+    }
+}
+
+fn save_stretched_image(
+    processed: &Array2<f32>,
+    output: &Path,
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+    operation: ProcessingOperation,
+    options: &SaveOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let SaveOptions {
+        format,
+        bit_depth,
+        target_size,
+        pad,
+        strategy,
+        ref autoscale_params,
+        ref encode,
+        ..
+    } = *options;
+    let operation_label = operation_label(operation);
     match format {
         OutputFormat::TIFF => {
-            let (db_data, _, scaled_u8, scaled_u16) =
-                process_scalar_data_pipeline(processed, bit_depth, strategy);
+            let (db_data, _, scaled_u8, scaled_u16, window) =
+                process_operation_data_pipeline_with_window(
+                    processed,
+                    bit_depth,
+                    strategy,
+                    autoscale_params,
+                    operation,
+                );
+            let stamped = metadata
+                .map(|m| with_autoscale(m, strategy, autoscale_params, vec![window], bit_depth));
+            let metadata = stamped.as_ref();
             let shape = db_data.dim();
             let (rows, cols) = shape;
 
@@ -64,27 +301,13 @@ pub fn save_processed_image(
                     pad,
                 )?;
 
-            // Compute updated geotransform if metadata available
-            let mut gt_override: Option<[f64; 6]> = None;
-            let mut proj_override: Option<String> = None;
-            if let Some(meta) = metadata {
-                if let Some(mut gt) = meta.geotransform {
-                    // Adjust pixel size by inverse of scaling
-                    if scale_x > 0.0 {
-                        gt[1] = gt[1] * (cols as f64 / final_cols as f64);
-                    }
-                    if scale_y > 0.0 {
-                        gt[5] = gt[5] * (rows as f64 / final_rows as f64);
-                    }
-                    // Shift origin for padding
-                    gt[0] = gt[0] - (pad_left as f64) * gt[1];
-                    gt[3] = gt[3] - (pad_top as f64) * gt[5];
-                    gt_override = Some(gt);
-                }
-                if let Some(p) = &meta.projection {
-                    proj_override = Some(p.clone());
-                }
-            }
+            let (gt_override, proj_override) = output_georef(
+                metadata,
+                (cols, rows),
+                (final_cols, final_rows),
+                (scale_x, scale_y),
+                (pad_left, pad_top),
+            );
 
             match bit_depth {
                 BitDepth::U8 => {
@@ -95,7 +318,7 @@ pub fn save_processed_image(
                             meta,
                             operation_label.as_deref(),
                             gt_override,
-                            proj_override.as_deref(),
+                            proj_override,
                         )?;
                     }
                     info!("save_processed_image: U8 TIFF saved with metadata");
@@ -109,7 +332,7 @@ pub fn save_processed_image(
                             meta,
                             operation_label.as_deref(),
                             gt_override,
-                            proj_override.as_deref(),
+                            proj_override,
                         )?;
                     }
                     info!("save_processed_image: U16 TIFF saved with metadata");
@@ -117,8 +340,17 @@ pub fn save_processed_image(
             }
         }
         OutputFormat::JPEG => {
-            let (db_data, _, scaled_u8, _) =
-                process_scalar_data_pipeline(processed, BitDepth::U8, strategy);
+            let (db_data, _, scaled_u8, _, window) =
+                process_operation_data_pipeline_with_window(
+                    processed,
+                    BitDepth::U8,
+                    strategy,
+                    autoscale_params,
+                    operation,
+                );
+            let stamped = metadata
+                .map(|m| with_autoscale(m, strategy, autoscale_params, vec![window], BitDepth::U8));
+            let metadata = stamped.as_ref();
             let shape = db_data.dim();
             let (rows, cols) = shape;
 
@@ -133,76 +365,282 @@ pub fn save_processed_image(
                     pad,
                 )?;
 
-            write_gray_jpeg(output, final_cols, final_rows, &final_u8)?;
+            let (gt_override, proj_override) = output_georef(
+                metadata,
+                (cols, rows),
+                (final_cols, final_rows),
+                (scale_x, scale_y),
+                (pad_left, pad_top),
+            );
 
+            if write_oversized_jpeg_as_tiff(
+                output,
+                final_cols,
+                final_rows,
+                &final_u8,
+                false,
+                &encode.jpeg,
+                metadata,
+                operation_label.as_deref(),
+                gt_override,
+                proj_override,
+            )? {
+                return Ok(());
+            }
+            write_gray_jpeg_with_options(output, final_cols, final_rows, &final_u8, &encode.jpeg)?;
             if let Some(meta) = metadata {
-                let mut gt_override: Option<[f64; 6]> = None;
-                let mut proj_override: Option<String> = None;
-                if let Some(mut gt) = meta.geotransform {
-                    if scale_x > 0.0 {
-                        gt[1] = gt[1] * (cols as f64 / final_cols as f64);
-                    }
-                    if scale_y > 0.0 {
-                        gt[5] = gt[5] * (rows as f64 / final_rows as f64);
-                    }
-                    gt[0] = gt[0] - (pad_left as f64) * gt[1];
-                    gt[3] = gt[3] - (pad_top as f64) * gt[5];
-                    write_world_file(output, gt)?;
-                    gt_override = Some(gt);
+                write_image_sidecars(
+                    output,
+                    meta,
+                    operation_label.as_deref(),
+                    gt_override,
+                    proj_override,
+                )?;
+            }
+
+            info!("save_processed_image: JPEG saved with metadata sidecar");
+        }
+        OutputFormat::PNG | OutputFormat::WebP => {
+            // WebP is 8-bit only; PNG keeps the requested depth
+            let bit_depth = if format == OutputFormat::WebP {
+                if bit_depth == BitDepth::U16 {
+                    warn!("save_processed_image: WebP outputs are 8-bit; U16 bit depth ignored");
                 }
-                if let Some(p) = &meta.projection {
-                    write_prj_file(output, p)?;
-                    proj_override = Some(p.clone());
+                BitDepth::U8
+            } else {
+                bit_depth
+            };
+            let (db_data, valid_mask, scaled_u8, scaled_u16, window) =
+                process_operation_data_pipeline_with_window(
+                    processed,
+                    bit_depth,
+                    strategy,
+                    autoscale_params,
+                    operation,
+                );
+            let stamped = metadata
+                .map(|m| with_autoscale(m, strategy, autoscale_params, vec![window], bit_depth));
+            let metadata = stamped.as_ref();
+            let (rows, cols) = db_data.dim();
+            drop(db_data);
+
+            let (final_cols, final_rows, final_u8, final_u16, scale_x, scale_y, pad_left, pad_top) =
+                resize_image_data_with_meta(
+                    &scaled_u8,
+                    scaled_u16.as_deref(),
+                    cols,
+                    rows,
+                    target_size,
+                    bit_depth,
+                    pad,
+                )?;
+            let alpha = resized_alpha(&[&valid_mask], cols, rows, target_size, pad)?;
+
+            match (format, bit_depth) {
+                (OutputFormat::PNG, BitDepth::U16) => write_gray_png_u16(
+                    output,
+                    final_cols,
+                    final_rows,
+                    &final_u16.unwrap(),
+                    Some(&alpha),
+                )?,
+                (OutputFormat::PNG, _) => {
+                    write_gray_png(output, final_cols, final_rows, &final_u8, Some(&alpha))?
                 }
-                create_jpeg_metadata_sidecar_with_overrides(
+                _ => write_gray_webp(
+                    output,
+                    final_cols,
+                    final_rows,
+                    &final_u8,
+                    Some(&alpha),
+                    &encode.webp,
+                )?,
+            }
+
+            if let Some(meta) = metadata {
+                let (gt_override, proj_override) = output_georef(
+                    metadata,
+                    (cols, rows),
+                    (final_cols, final_rows),
+                    (scale_x, scale_y),
+                    (pad_left, pad_top),
+                );
+                write_image_sidecars(
                     output,
                     meta,
                     operation_label.as_deref(),
                     gt_override,
-                    proj_override.as_deref(),
+                    proj_override,
                 )?;
             }
 
-            info!("save_processed_image: JPEG saved with metadata sidecar");
+            info!("save_processed_image: {:?} saved with alpha and metadata sidecar", format);
         }
     }
     Ok(())
 }
 
+fn save_colormapped_image(
+    processed: &Array2<f32>,
+    output: &Path,
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+    operation: ProcessingOperation,
+    colormap: &ColormapOptions,
+    options: &SaveOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let SaveOptions {
+        format,
+        bit_depth,
+        target_size,
+        pad,
+        strategy,
+        ref autoscale_params,
+        ref encode,
+        ..
+    } = *options;
+    if bit_depth == BitDepth::U16 {
+        warn!("save_processed_image: colormap outputs are 8-bit; U16 bit depth ignored");
+    }
+    let operation_label = operation_label(operation);
+    let (db_data, valid_mask, scaled_u8, _, window) = process_operation_data_pipeline_with_window(
+        processed,
+        BitDepth::U8,
+        strategy,
+        autoscale_params,
+        operation,
+    );
+    let stamped = metadata.map(|m| {
+        let mut m = with_autoscale(m, strategy, autoscale_params, vec![window], BitDepth::U8);
+        m.colormap = Some(colormap.ramp.name.clone());
+        m
+    });
+    let metadata = stamped.as_ref();
+    let (rows, cols) = db_data.dim();
+
+    let (final_cols, final_rows, final_u8, _, scale_x, scale_y, pad_left, pad_top) =
+        resize_image_data_with_meta(&scaled_u8, None, cols, rows, target_size, BitDepth::U8, pad)?;
+
+    let (gt_override, proj_override) = output_georef(
+        metadata,
+        (cols, rows),
+        (final_cols, final_rows),
+        (scale_x, scale_y),
+        (pad_left, pad_top),
+    );
+
+    let palette = colormap.ramp.palette();
+    match format {
+        OutputFormat::TIFF => {
+            let mut ds = write_tiff_paletted(output, final_cols, final_rows, &final_u8, &palette)?;
+            if let Some(meta) = metadata {
+                embed_tiff_metadata(
+                    &mut ds,
+                    meta,
+                    operation_label.as_deref(),
+                    gt_override,
+                    proj_override,
+                )?;
+            }
+        }
+        OutputFormat::JPEG | OutputFormat::PNG | OutputFormat::WebP => {
+            let rgb = apply_palette(&final_u8, &palette);
+            let as_tiff = format == OutputFormat::JPEG
+                && write_oversized_jpeg_as_tiff(
+                    output,
+                    final_cols,
+                    final_rows,
+                    &rgb,
+                    true,
+                    &encode.jpeg,
+                    metadata,
+                    operation_label.as_deref(),
+                    gt_override,
+                    proj_override,
+                )?;
+            if as_tiff {
+                // Metadata is embedded in the GeoTIFF; no world file or sidecar
+            } else if format == OutputFormat::JPEG {
+                write_rgb_jpeg_with_options(output, final_cols, final_rows, &rgb, &encode.jpeg)?;
+            } else {
+                let alpha = resized_alpha(&[&valid_mask], cols, rows, target_size, pad)?;
+                if format == OutputFormat::PNG {
+                    write_rgb_png(output, final_cols, final_rows, &rgb, Some(&alpha))?;
+                } else {
+                    write_rgb_webp(
+                        output,
+                        final_cols,
+                        final_rows,
+                        &rgb,
+                        Some(&alpha),
+                        &encode.webp,
+                    )?;
+                }
+            }
+            if let (false, Some(meta)) = (as_tiff, metadata) {
+                write_image_sidecars(
+                    output,
+                    meta,
+                    operation_label.as_deref(),
+                    gt_override,
+                    proj_override,
+                )?;
+            }
+        }
+    }
+
+    if colormap.legend {
+        let unit = match operation {
+            ProcessingOperation::PolarOp(PolarizationOperation::CrossPolDb)
+            | ProcessingOperation::Expression { scale: ExprScale::Db } => " dB",
+            op if op.skips_db_conversion() => "",
+            _ => " dB",
+        };
+        let (legend_cols, legend_rows, legend) = render_legend(&palette, &window, unit);
+        write_rgb_jpeg(&output.with_extension("legend.jpg"), legend_cols, legend_rows, &legend)?;
+    }
+
+    info!(
+        "save_processed_image: {:?} rendered with colormap {}",
+        format, colormap.ramp.name
+    );
+    Ok(())
+}
+
+/// Stretch a VV/VH or HH/HV pair to `options.format`. TIFF keeps both bands; JPEG, PNG and
+/// WebP get the `syn_mode` synthetic RGB composite, and PNG and WebP add an alpha channel that
+/// is opaque where both bands are valid.
 pub fn save_processed_multiband_image_sequential(
     processed1: &Array2<f32>,
     processed2: &Array2<f32>,
     output: &Path,
-    format: OutputFormat,
-    bit_depth: BitDepth,
-    target_size: Option<usize>,
     metadata: Option<&crate::io::sentinel1::SafeMetadata>,
-    pad: bool,
-    strategy: AutoscaleStrategy,
     operation: ProcessingOperation,
     syn_mode: SyntheticRgbMode,
+    options: &SaveOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let operation_label: Option<String> = match operation {
-        ProcessingOperation::SingleBand => None,
-        ProcessingOperation::MultibandVvVh => Some("multiband_vv_vh".to_string()),
-        ProcessingOperation::MultibandHhHv => Some("multiband_hh_hv".to_string()),
-        ProcessingOperation::PolarOp(PolarizationOperation::Sum) => Some("sum".to_string()),
-        ProcessingOperation::PolarOp(PolarizationOperation::Diff) => Some("difference".to_string()),
-        ProcessingOperation::PolarOp(PolarizationOperation::Ratio) => Some("ratio".to_string()),
-        ProcessingOperation::PolarOp(PolarizationOperation::NDiff) => {
-            Some("normalized_diff".to_string())
-        }
-        ProcessingOperation::PolarOp(PolarizationOperation::LogRatio) => {
-            Some("log_ratio".to_string())
-        }
-    };
+    let SaveOptions {
+        format,
+        bit_depth,
+        target_size,
+        pad,
+        strategy,
+        ref autoscale_params,
+        ref encode,
+        ..
+    } = *options;
+    let operation_label = operation_label(operation);
     match format {
         OutputFormat::TIFF => {
             let shape = processed1.dim();
             let (rows, cols) = shape;
 
-            let (db_data, valid_mask, scaled_u8, scaled_u16) =
-                process_scalar_data_pipeline(processed1, bit_depth, strategy);
+            let (db_data, valid_mask, scaled_u8, scaled_u16, window1) =
+                process_scalar_data_pipeline_with_window(
+                    processed1,
+                    bit_depth,
+                    strategy,
+                    autoscale_params,
+                );
 
             let (final_cols, final_rows, final_u8, final_u16, scale_x, scale_y, pad_left, pad_top) =
                 resize_image_data_with_meta(
@@ -215,33 +653,31 @@ pub fn save_processed_multiband_image_sequential(
                     pad,
                 )?;
 
-            // Compute updated geotransform if metadata available
-            let mut gt_override: Option<[f64; 6]> = None;
-            let mut proj_override: Option<String> = None;
-            if let Some(meta) = metadata {
-                if let Some(mut gt) = meta.geotransform {
-                    if scale_x > 0.0 {
-                        gt[1] = gt[1] * (cols as f64 / final_cols as f64);
-                    }
-                    if scale_y > 0.0 {
-                        gt[5] = gt[5] * (rows as f64 / final_rows as f64);
-                    }
-                    gt[0] = gt[0] - (pad_left as f64) * gt[1];
-                    gt[3] = gt[3] - (pad_top as f64) * gt[5];
-                    gt_override = Some(gt);
-                }
-                if let Some(p) = &meta.projection {
-                    proj_override = Some(p.clone());
-                }
-            }
+            let (gt_override, proj_override) = output_georef(
+                metadata,
+                (cols, rows),
+                (final_cols, final_rows),
+                (scale_x, scale_y),
+                (pad_left, pad_top),
+            );
 
             match bit_depth {
                 BitDepth::U8 => {
                     drop(db_data);
                     drop(valid_mask);
 
-                    let (_, _, scaled_u8, _) =
-                        process_scalar_data_pipeline(processed2, bit_depth, strategy);
+                    let (_, _, scaled_u8, _, window2) =
+                        process_scalar_data_pipeline_with_window(
+                            processed2,
+                            bit_depth,
+                            strategy,
+                            autoscale_params,
+                        );
+                    let stamped = metadata.map(|m| {
+                        let windows = vec![window1, window2];
+                        with_autoscale(m, strategy, autoscale_params, windows, bit_depth)
+                    });
+                    let metadata = stamped.as_ref();
 
                     let (_, _, final_u8_band2, _, _sx2, _sy2, _pl2, _pt2) =
                         resize_image_data_with_meta(
@@ -267,7 +703,7 @@ pub fn save_processed_multiband_image_sequential(
                             meta,
                             operation_label.as_deref(),
                             gt_override,
-                            proj_override.as_deref(),
+                            proj_override,
                         )?;
                     }
                     info!(
@@ -279,8 +715,18 @@ pub fn save_processed_multiband_image_sequential(
                     drop(db_data);
                     drop(valid_mask);
 
-                    let (_, _, _, scaled_u16) =
-                        process_scalar_data_pipeline(processed2, bit_depth, strategy);
+                    let (_, _, _, scaled_u16, window2) =
+                        process_scalar_data_pipeline_with_window(
+                            processed2,
+                            bit_depth,
+                            strategy,
+                            autoscale_params,
+                        );
+                    let stamped = metadata.map(|m| {
+                        let windows = vec![window1, window2];
+                        with_autoscale(m, strategy, autoscale_params, windows, bit_depth)
+                    });
+                    let metadata = stamped.as_ref();
 
                     let (_, _, _, final_u16, _sx2, _sy2, _pl2, _pt2) = resize_image_data_with_meta(
                         &vec![],
@@ -305,7 +751,7 @@ pub fn save_processed_multiband_image_sequential(
                             meta,
                             operation_label.as_deref(),
                             gt_override,
-                            proj_override.as_deref(),
+                            proj_override,
                         )?;
                     }
                     info!(
@@ -314,15 +760,27 @@ pub fn save_processed_multiband_image_sequential(
                 }
             }
         }
-        OutputFormat::JPEG => {
-            info!("Creating syntetic RGB JPEG from VV | HH (Red) and VH | HV (Green) bands");
+        OutputFormat::JPEG | OutputFormat::PNG | OutputFormat::WebP => {
+            info!(
+                "Creating syntetic RGB {:?} from VV | HH (Red) and VH | HV (Green) bands",
+                format
+            );
 
-            let (db_data, valid_mask, scaled_u8, _) =
-                process_scalar_data_pipeline(processed1, BitDepth::U8, strategy);
+            let (db_data, valid_mask, scaled_u8, _, _) = process_scalar_data_pipeline_with_window(
+                processed1,
+                BitDepth::U8,
+                strategy,
+                autoscale_params,
+            );
 
             // If Tamed for synRGB, recompute band1 U8 using band-specific tamed autoscale
             let input_u8_band1: Vec<u8> = if matches!(strategy, AutoscaleStrategy::Tamed) {
-                autoscale_db_image_tamed_synrgb_u8(&db_data, &valid_mask, true)
+                autoscale_db_image_tamed_synrgb_u8_with_params(
+                    &db_data,
+                    &valid_mask,
+                    true,
+                    autoscale_params,
+                )
             } else {
                 scaled_u8
             };
@@ -340,12 +798,21 @@ pub fn save_processed_multiband_image_sequential(
                     pad,
                 )?;
 
-            let (db2, valid2, scaled_u8_b2, _) =
-                process_scalar_data_pipeline(processed2, BitDepth::U8, strategy);
+            let (db2, valid2, scaled_u8_b2, _, _) = process_scalar_data_pipeline_with_window(
+                processed2,
+                BitDepth::U8,
+                strategy,
+                autoscale_params,
+            );
 
             // If Tamed for synRGB, recompute band2 U8 using band-specific tamed autoscale
             let input_u8_band2: Vec<u8> = if matches!(strategy, AutoscaleStrategy::Tamed) {
-                autoscale_db_image_tamed_synrgb_u8(&db2, &valid2, false)
+                autoscale_db_image_tamed_synrgb_u8_with_params(
+                    &db2,
+                    &valid2,
+                    false,
+                    autoscale_params,
+                )
             } else {
                 scaled_u8_b2
             };
@@ -367,26 +834,65 @@ pub fn save_processed_multiband_image_sequential(
                 &final_u8_band2,
             );
 
-            write_rgb_jpeg(output, final_cols, final_rows, &rgb_data)?;
+            // Colour synthesis mixes the bands, so only the strategy is recorded
+            let stamped = metadata
+                .map(|m| with_autoscale(m, strategy, autoscale_params, Vec::new(), BitDepth::U8));
+            let (gt_override, proj_override) = output_georef(
+                stamped.as_ref(),
+                (cols, rows),
+                (final_cols, final_rows),
+                (scale_x, scale_y),
+                (pad_left, pad_top),
+            );
 
-            if let Some(meta) = metadata {
-                let mut gt_override: Option<[f64; 6]> = None;
-                let mut proj_override: Option<String> = None;
-                if let Some(mut gt) = meta.geotransform {
-                    if scale_x > 0.0 {
-                        gt[1] = gt[1] * (cols as f64 / final_cols as f64);
+            match format {
+                OutputFormat::PNG | OutputFormat::WebP => {
+                    let alpha =
+                        resized_alpha(&[&valid_mask, &valid2], cols, rows, target_size, pad)?;
+                    if format == OutputFormat::PNG {
+                        write_rgb_png(output, final_cols, final_rows, &rgb_data, Some(&alpha))?;
+                    } else {
+                        write_rgb_webp(
+                            output,
+                            final_cols,
+                            final_rows,
+                            &rgb_data,
+                            Some(&alpha),
+                            &encode.webp,
+                        )?;
                     }
-                    if scale_y > 0.0 {
-                        gt[5] = gt[5] * (rows as f64 / final_rows as f64);
+                }
+                _ => {
+                    if write_oversized_jpeg_as_tiff(
+                        output,
+                        final_cols,
+                        final_rows,
+                        &rgb_data,
+                        true,
+                        &encode.jpeg,
+                        stamped.as_ref(),
+                        operation_label.as_deref(),
+                        gt_override,
+                        proj_override,
+                    )? {
+                        return Ok(());
                     }
-                    gt[0] = gt[0] - (pad_left as f64) * gt[1];
-                    gt[3] = gt[3] - (pad_top as f64) * gt[5];
+                    write_rgb_jpeg_with_options(
+                        output,
+                        final_cols,
+                        final_rows,
+                        &rgb_data,
+                        &encode.jpeg,
+                    )?;
+                }
+            }
+
+            if let Some(meta) = stamped.as_ref() {
+                if let Some(gt) = gt_override {
                     write_world_file(output, gt)?;
-                    gt_override = Some(gt);
                 }
-                if let Some(p) = &meta.projection {
+                if let Some(p) = proj_override {
                     write_prj_file(output, p)?;
-                    proj_override = Some(p.clone());
                 }
                 // Attach synthetic_rgb_mode to JPEG sidecar
                 create_jpeg_metadata_sidecar_with_overrides_and_extras(
@@ -394,13 +900,315 @@ pub fn save_processed_multiband_image_sequential(
                     meta,
                     operation_label.as_deref(),
                     gt_override,
-                    proj_override.as_deref(),
+                    proj_override,
                     Some(&[("synthetic_rgb_mode", syn_mode.to_string())]),
                 )?;
             }
 
-            info!("Syntetic RGB JPEG saved with metadata sidecar");
+            info!("Syntetic RGB {:?} saved with metadata sidecar", format);
         }
     }
     Ok(())
 }
+
+/// Threshold a single-band intensity image into a water mask and write it as a GeoTIFF
+/// (1 = water, 0 = land, 255 = nodata). Threshold and area statistics are embedded as
+/// `WATER_*` TIFF metadata and written to the `<output>.json` sidecar under `water`.
+/// The mask keeps the reader's resolution; no resizing or padding is applied. Pixels below
+/// `params.db_floor` are nodata, as in the image outputs.
+pub fn save_water_mask(
+    processed: &Array2<f32>,
+    output: &Path,
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+    options: &WaterOptions,
+    params: &AutoscaleParams,
+) -> Result<WaterResult, Box<dyn std::error::Error>> {
+    let (db_data, valid_mask, hist) =
+        process_scalar_data_fused_with_floor(processed, params.db_floor);
+    let result = detect_water(&db_data, &valid_mask, &hist, options)
+        .ok_or("Cannot estimate a water threshold: image has no valid backscatter range")?;
+
+    let mut ds = write_tiff_u8(output, result.cols, result.rows, &result.mask)?;
+    ds.rasterband(1)?.set_no_data_value(Some(WATER_NODATA as f64))?;
+
+    let pixel_area_m2 = metadata
+        .and_then(|m| pixel_ground_size(m, m.geotransform, result.cols, result.rows))
+        .map(|(dx, dy)| dx * dy);
+    let area_km2 = |pixels: usize| pixel_area_m2.map(|a| pixels as f64 * a / 1e6);
+    let stats = serde_json::json!({
+        "method": options.method.to_string(),
+        "threshold_db": result.threshold_db,
+        "global_threshold_db": result.global_threshold_db,
+        "tile_refinement": options.tile_refinement,
+        "db_floor": params.db_floor,
+        "tiles_total": result.tiles_total,
+        "tiles_bimodal": result.tiles_bimodal,
+        "water_pixels": result.water_pixels,
+        "valid_pixels": result.valid_pixels,
+        "water_fraction": result.water_fraction(),
+        "pixel_area_m2": pixel_area_m2,
+        "water_area_km2": area_km2(result.water_pixels),
+        "valid_area_km2": area_km2(result.valid_pixels),
+    });
+
+    if let Some(meta) = metadata {
+        embed_tiff_metadata(&mut ds, meta, Some("water"), None, None)?;
+        ds.set_metadata_item("WATER_METHOD", &options.method.to_string(), "")?;
+        ds.set_metadata_item("WATER_THRESHOLD_DB", &result.threshold_db.to_string(), "")?;
+        ds.set_metadata_item("WATER_FRACTION", &result.water_fraction().to_string(), "")?;
+        if let Some(km2) = area_km2(result.water_pixels) {
+            ds.set_metadata_item("WATER_AREA_KM2", &km2.to_string(), "")?;
+        }
+        create_metadata_sidecar_with_json_extras(output, meta, Some("water"), &[("water", stats)])?;
+    }
+
+    info!(
+        "save_water_mask: threshold {:.2} dB, water fraction {:.4}",
+        result.threshold_db,
+        result.water_fraction()
+    );
+    Ok(result)
+}
+
+/// Run CA-CFAR ship detection on a single-band intensity image and write the targets to
+/// `<output>.ships.geojson` and `<output>.ships.csv`. `geolocation` maps target centroids
+/// to lon/lat; without it only pixel coordinates are reported.
+pub fn save_ship_detections(
+    processed: &Array2<f32>,
+    output: &Path,
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+    geolocation: Option<&PixelGeolocation>,
+    options: &ShipOptions,
+) -> Result<Vec<ShipTarget>, Box<dyn std::error::Error>> {
+    let (rows, cols) = processed.dim();
+    let pixel_size = metadata.and_then(|m| pixel_ground_size(m, m.geotransform, cols, rows));
+    let mut targets = detect_ships(processed, pixel_size, options);
+    if let Some(geo) = geolocation {
+        for t in targets.iter_mut() {
+            if let Some((lon, lat)) = geo.lonlat(t.col, t.row) {
+                t.lon = Some(lon);
+                t.lat = Some(lat);
+            }
+        }
+    }
+
+    write_ships_geojson(&output.with_extension("ships.geojson"), &targets, metadata)?;
+    write_ships_csv(&output.with_extension("ships.csv"), &targets)?;
+    Ok(targets)
+}
+
+/// Write the data footprint next to `output` as `<output>.footprint.geojson` (a Feature with
+/// bbox and product properties) and `<output>.footprint.wkt`
+pub fn save_footprint(
+    output: &Path,
+    footprint: &Footprint,
+    metadata: Option<&crate::io::sentinel1::SafeMetadata>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_footprint_geojson(&output.with_extension("footprint.geojson"), footprint, metadata)?;
+    std::fs::write(output.with_extension("footprint.wkt"), footprint.to_wkt())?;
+    Ok(())
+}
+
+/// Package the JPEG/PNG quicklook at `output` with a Google Earth `GroundOverlay` into
+/// `<output>.kmz`. `geolocation` locates the image corners: an unrotated lon/lat grid becomes a
+/// `LatLonBox`, anything else (swaths in radar geometry, projected grids) a `gx:LatLonQuad`.
+/// The description lists platform, orbits, pass direction, polarization and acquisition time.
+pub fn save_kmz(
+    output: &Path,
+    metadata: &crate::io::sentinel1::SafeMetadata,
+    geolocation: Option<&PixelGeolocation>,
+    operation: ProcessingOperation,
+) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    if !output.is_file() {
+        return Err(format!("KMZ needs the JPEG/PNG image at {:?}", output).into());
+    }
+    let geolocation = geolocation.ok_or("KMZ needs a geotransform or GCPs to place the image")?;
+    let placement =
+        OverlayPlacement::from_geolocation(geolocation, metadata.samples, metadata.lines)
+            .ok_or("Could not geolocate the image corners for the KMZ")?;
+    let fields = extract_metadata_fields(metadata, operation_label(operation).as_deref());
+    let image_href = output
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("overlay.jpg")
+        .to_string();
+    let name = metadata.product_id.clone().unwrap_or_else(|| {
+        output.file_stem().and_then(|s| s.to_str()).unwrap_or("SARPRO").to_string()
+    });
+    let overlay = GroundOverlay {
+        name,
+        description: kml_description(&fields),
+        image_href,
+        placement,
+        time_span: Some((
+            metadata.acquisition_start.clone(),
+            metadata.acquisition_stop.clone(),
+        ))
+        .filter(|(start, _)| !start.is_empty()),
+    };
+
+    let kmz_path = output.with_extension("kmz");
+    write_kmz(&kmz_path, &overlay, output)?;
+    info!("Google Earth overlay written to {:?}", kmz_path);
+    Ok(kmz_path)
+}
+
+/// Statistics of a written change product
+#[derive(Debug, Clone)]
+pub struct ChangeSummary {
+    /// Threshold used for the mask (None when no mask was requested or none could be estimated)
+    pub threshold: Option<f64>,
+    pub increased_pixels: usize,
+    pub decreased_pixels: usize,
+    /// Path of the change mask, if written
+    pub mask_path: Option<std::path::PathBuf>,
+    /// Path of the colormapped preview, if written
+    pub preview_path: Option<std::path::PathBuf>,
+}
+
+/// Write co-gridded change bands (one per polarization, NaN nodata) as a Float32 GeoTIFF.
+/// `metadata` describes the output grid (the later acquisition); `before` is recorded as
+/// `CHANGE_BEFORE_*`. With `options.mask`, the first band is thresholded into
+/// `<output>_mask.tif` (0 none, 1 increase, 2 decrease, 255 nodata).
+pub fn save_change_raster(
+    bands: &[(String, Array2<f32>)],
+    output: &Path,
+    metadata: &crate::io::sentinel1::SafeMetadata,
+    before: &crate::io::sentinel1::SafeMetadata,
+    options: &ChangeOptions,
+) -> Result<ChangeSummary, Box<dyn std::error::Error>> {
+    let (rows, cols) = bands.first().ok_or("No change bands to write")?.1.dim();
+    let std_bands: Vec<Array2<f32>> = bands
+        .iter()
+        .map(|(_, b)| b.as_standard_layout().into_owned())
+        .collect();
+    let slices: Vec<&[f32]> = std_bands
+        .iter()
+        .map(|b| b.as_slice().expect("standard layout array is contiguous"))
+        .collect();
+
+    let label = match options.method {
+        crate::types::ChangeMethod::LogRatio => "change_log_ratio",
+        crate::types::ChangeMethod::Difference => "change_difference",
+        crate::types::ChangeMethod::NormalizedDiff => "change_normalized_diff",
+    };
+    let items = [
+        ("CHANGE_METHOD", options.method.to_string()),
+        ("CHANGE_BEFORE_PRODUCT", before.product_id.clone().unwrap_or_default()),
+        ("CHANGE_BEFORE_START", before.acquisition_start.clone()),
+        ("CHANGE_AFTER_PRODUCT", metadata.product_id.clone().unwrap_or_default()),
+        ("CHANGE_AFTER_START", metadata.acquisition_start.clone()),
+    ];
+
+    let mut ds = write_tiff_bands_f32(output, cols, rows, &slices)?;
+    for (i, (pol, _)) in bands.iter().enumerate() {
+        ds.rasterband(i + 1)?.set_description(pol)?;
+    }
+    embed_tiff_metadata(&mut ds, metadata, Some(label), None, None)?;
+    for (k, v) in &items {
+        ds.set_metadata_item(k, v, "")?;
+    }
+
+    let mut summary = ChangeSummary {
+        threshold: None,
+        increased_pixels: 0,
+        decreased_pixels: 0,
+        mask_path: None,
+        preview_path: None,
+    };
+    if options.mask {
+        let primary = &bands[0].1;
+        summary.threshold = options.threshold.or_else(|| auto_change_threshold(primary));
+        match summary.threshold {
+            Some(t) => {
+                let (mask, inc, dec) = change_mask(primary, t);
+                let stem = output.file_stem().and_then(|s| s.to_str()).unwrap_or("change");
+                let mask_path = output.with_file_name(format!("{}_mask.tif", stem));
+                let mut mds = write_tiff_u8(&mask_path, cols, rows, &mask)?;
+                mds.rasterband(1)?.set_no_data_value(Some(CHANGE_NODATA as f64))?;
+                embed_tiff_metadata(&mut mds, metadata, Some(label), None, None)?;
+                for (k, v) in &items {
+                    mds.set_metadata_item(k, v, "")?;
+                }
+                mds.set_metadata_item("CHANGE_THRESHOLD", &t.to_string(), "")?;
+                mds.set_metadata_item("CHANGE_BAND", &bands[0].0, "")?;
+                summary.increased_pixels = inc;
+                summary.decreased_pixels = dec;
+                summary.mask_path = Some(mask_path);
+            }
+            None => warn!("save_change_raster: no change threshold could be estimated; mask skipped"),
+        }
+    }
+
+    if let Some(colormap) = &options.colormap {
+        let primary = &bands[0].1;
+        match change_display_limit(primary) {
+            Some(limit) => {
+                let stem = output.file_stem().and_then(|s| s.to_str()).unwrap_or("change");
+                let preview_path = output.with_file_name(format!("{}_preview.jpg", stem));
+                let palette = colormap.ramp.palette();
+                let values = change_preview_values(primary, limit);
+                write_rgb_jpeg(&preview_path, cols, rows, &apply_palette(&values, &palette))?;
+                if let Some(gt) = metadata.geotransform {
+                    write_world_file(&preview_path, gt)?;
+                }
+                if let Some(p) = &metadata.projection {
+                    write_prj_file(&preview_path, p)?;
+                }
+                if colormap.legend {
+                    let window = StretchWindow {
+                        min_db: -limit,
+                        max_db: limit,
+                        gamma: 1.0,
+                    };
+                    let unit = match options.method {
+                        crate::types::ChangeMethod::LogRatio => " dB",
+                        _ => "",
+                    };
+                    let (legend_cols, legend_rows, legend) = render_legend(&palette, &window, unit);
+                    write_rgb_jpeg(
+                        &preview_path.with_extension("legend.jpg"),
+                        legend_cols,
+                        legend_rows,
+                        &legend,
+                    )?;
+                }
+                summary.preview_path = Some(preview_path);
+            }
+            None => warn!("save_change_raster: no change to render; preview skipped"),
+        }
+    }
+
+    let stats = serde_json::json!({
+        "method": options.method.to_string(),
+        "bands": bands.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>(),
+        "before": {
+            "product_id": before.product_id,
+            "acquisition_start": before.acquisition_start,
+            "acquisition_stop": before.acquisition_stop,
+            "orbit_number": before.orbit_number,
+        },
+        "after": {
+            "product_id": metadata.product_id,
+            "acquisition_start": metadata.acquisition_start,
+            "acquisition_stop": metadata.acquisition_stop,
+            "orbit_number": metadata.orbit_number,
+        },
+        "threshold": summary.threshold,
+        "increased_pixels": summary.increased_pixels,
+        "decreased_pixels": summary.decreased_pixels,
+        "mask": summary.mask_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+        "preview": summary.preview_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+        "preview_colormap": options.colormap.as_ref().map(|c| c.ramp.name.as_str()),
+    });
+    create_metadata_sidecar_with_json_extras(output, metadata, Some(label), &[("change", stats)])?;
+
+    info!(
+        "save_change_raster: {} band(s) {}x{}, threshold {:?}",
+        bands.len(),
+        cols,
+        rows,
+        summary.threshold
+    );
+    Ok(summary)
+}
@@ -0,0 +1,500 @@
+//! Ship / vessel detection with a two-parameter CA-CFAR detector.
+//! Each pixel is compared with the mean and standard deviation of a square background ring
+//! around it (a guard window keeps the target itself out of the estimate); pixels exceeding
+//! `mean + k * std`, with `k` set by the false alarm rate, are detections. Neighbouring
+//! detections are clustered into targets (8-connectivity).
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::core::processing::pipeline::MIN_VALID_INTENSITY;
+
+/// Output rows handled per rayon task; each task builds summed-area tables for its strip
+const STRIP_ROWS: usize = 64;
+/// Minimum share of the background ring that must hold valid pixels (scene edges, nodata)
+const MIN_BACKGROUND_FRACTION: f64 = 0.5;
+
+/// CA-CFAR and clustering parameters; window sizes are half-widths in pixels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShipOptions {
+    /// Guard half-width: pixels excluded around the cell under test
+    pub guard: usize,
+    /// Background half-width: outer edge of the clutter ring (must exceed `guard`)
+    pub background: usize,
+    /// Probability of false alarm per pixel
+    pub pfa: f64,
+    /// Clusters with fewer detected pixels are dropped
+    pub min_pixels: usize,
+    /// Clusters with more detected pixels are dropped (land, ambiguities, bright coastlines)
+    pub max_pixels: usize,
+}
+
+impl Default for ShipOptions {
+    fn default() -> Self {
+        Self {
+            guard: 4,
+            background: 12,
+            pfa: 1e-6,
+            min_pixels: 2,
+            max_pixels: 5000,
+        }
+    }
+}
+
+/// One detected target
+#[derive(Debug, Clone, Serialize)]
+pub struct ShipTarget {
+    pub id: usize,
+    /// Intensity-weighted centroid in output pixel coordinates (pixel centers at +0.5)
+    pub col: f64,
+    pub row: f64,
+    /// WGS84 centroid, when the raster can be geolocated
+    pub lon: Option<f64>,
+    pub lat: Option<f64>,
+    /// Detected pixels in the cluster
+    pub pixels: usize,
+    /// Major / minor axis from the cluster's second moments (meters); None without pixel size
+    pub length_m: Option<f64>,
+    pub width_m: Option<f64>,
+    /// Brightest pixel of the cluster (dB)
+    pub peak_db: f64,
+}
+
+#[inline]
+fn is_valid(v: f32) -> bool {
+    v.is_finite() && v > MIN_VALID_INTENSITY
+}
+
+/// Inverse standard normal CDF (Acklam's rational approximation, |error| < 1.2e-9)
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Run the CA-CFAR detector over a linear intensity image.
+/// Returns row-major indices of detected pixels in ascending order.
+pub fn cfar_detect(intensity: &Array2<f32>, options: &ShipOptions) -> Vec<usize> {
+    let (rows, cols) = intensity.dim();
+    let data = intensity.as_standard_layout();
+    let src = data.as_slice().expect("standard layout array is contiguous");
+
+    let g = options.guard;
+    let b = options.background.max(g + 1);
+    let k = normal_quantile(1.0 - options.pfa.clamp(1e-12, 0.5));
+    let ring = ((2 * b + 1).pow(2) - (2 * g + 1).pow(2)) as f64;
+    let min_n = ring * MIN_BACKGROUND_FRACTION;
+    info!(
+        "CA-CFAR: guard {} px, background {} px, pfa {:e} (k = {:.2})",
+        g, b, options.pfa, k
+    );
+
+    let strips: Vec<usize> = (0..rows).step_by(STRIP_ROWS).collect();
+    let hits: Vec<usize> = strips
+        .into_par_iter()
+        .flat_map_iter(|r0| {
+            let r1 = (r0 + STRIP_ROWS).min(rows);
+            let top = r0.saturating_sub(b);
+            let h = (r1 + b).min(rows) - top;
+            let w1 = cols + 1;
+
+            // Summed-area tables of valid count, intensity and squared intensity
+            let mut n = vec![0f64; (h + 1) * w1];
+            let mut s = vec![0f64; (h + 1) * w1];
+            let mut q = vec![0f64; (h + 1) * w1];
+            for i in 0..h {
+                let (mut rn, mut rs, mut rq) = (0.0, 0.0, 0.0);
+                for j in 0..cols {
+                    let v = src[(top + i) * cols + j];
+                    if is_valid(v) {
+                        let v = v as f64;
+                        rn += 1.0;
+                        rs += v;
+                        rq += v * v;
+                    }
+                    let idx = (i + 1) * w1 + j + 1;
+                    n[idx] = n[idx - w1] + rn;
+                    s[idx] = s[idx - w1] + rs;
+                    q[idx] = q[idx - w1] + rq;
+                }
+            }
+            // Box sum over local rows [a, c) and columns [d, e)
+            let rect = |t: &[f64], (a, c, d, e): (usize, usize, usize, usize)| {
+                t[c * w1 + e] - t[a * w1 + e] - t[c * w1 + d] + t[a * w1 + d]
+            };
+            let window = |lr: usize, c: usize, half: usize| {
+                (
+                    lr.saturating_sub(half),
+                    (lr + half + 1).min(h),
+                    c.saturating_sub(half),
+                    (c + half + 1).min(cols),
+                )
+            };
+
+            let mut out = Vec::new();
+            for r in r0..r1 {
+                let lr = r - top;
+                for c in 0..cols {
+                    let v = src[r * cols + c];
+                    if !is_valid(v) {
+                        continue;
+                    }
+                    let (outer, inner) = (window(lr, c, b), window(lr, c, g));
+                    let nb = rect(&n, outer) - rect(&n, inner);
+                    if nb < min_n {
+                        continue;
+                    }
+                    let mean = (rect(&s, outer) - rect(&s, inner)) / nb;
+                    let var = (rect(&q, outer) - rect(&q, inner)) / nb - mean * mean;
+                    if var > 0.0 && v as f64 - mean > k * var.sqrt() {
+                        out.push(r * cols + c);
+                    }
+                }
+            }
+            out
+        })
+        .collect();
+
+    debug!("CA-CFAR: {} detected pixels", hits.len());
+    hits
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Cluster detected pixels (8-connectivity) into targets. `pixel_size` is the ground size
+/// (x, y) of one pixel in meters and enables the length/width estimate.
+pub fn cluster_targets(
+    intensity: &Array2<f32>,
+    detections: &[usize],
+    pixel_size: Option<(f64, f64)>,
+    options: &ShipOptions,
+) -> Vec<ShipTarget> {
+    let cols = intensity.ncols();
+    let data = intensity.as_standard_layout();
+    let src = data.as_slice().expect("standard layout array is contiguous");
+
+    let position: HashMap<usize, usize> =
+        detections.iter().enumerate().map(|(i, &p)| (p, i)).collect();
+    let mut parent: Vec<usize> = (0..detections.len()).collect();
+    for (i, &p) in detections.iter().enumerate() {
+        let (r, c) = (p / cols, p % cols);
+        // Neighbours that precede `p` in row-major order
+        let mut prev = Vec::with_capacity(4);
+        if c > 0 {
+            prev.push(p - 1);
+        }
+        if r > 0 {
+            prev.push(p - cols);
+            if c > 0 {
+                prev.push(p - cols - 1);
+            }
+            if c + 1 < cols {
+                prev.push(p - cols + 1);
+            }
+        }
+        for np in prev {
+            if let Some(&j) = position.get(&np) {
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                if ri != rj {
+                    parent[ri.max(rj)] = ri.min(rj);
+                }
+            }
+        }
+    }
+
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    let mut slot: HashMap<usize, usize> = HashMap::new();
+    for (i, &p) in detections.iter().enumerate() {
+        let root = find(&mut parent, i);
+        let idx = *slot.entry(root).or_insert_with(|| {
+            members.push(Vec::new());
+            members.len() - 1
+        });
+        members[idx].push(p);
+    }
+
+    let mut targets = Vec::new();
+    for pixels in members {
+        if pixels.len() < options.min_pixels || pixels.len() > options.max_pixels {
+            continue;
+        }
+        let (mut w, mut wc, mut wr, mut peak) = (0.0, 0.0, 0.0, 0f32);
+        for &p in &pixels {
+            let v = src[p];
+            let (r, c) = ((p / cols) as f64 + 0.5, (p % cols) as f64 + 0.5);
+            w += v as f64;
+            wc += v as f64 * c;
+            wr += v as f64 * r;
+            peak = peak.max(v);
+        }
+        let (col, row) = (wc / w, wr / w);
+
+        let axes = pixel_size.map(|(dx, dy)| {
+            let n = pixels.len() as f64;
+            let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+            let (mx, my) = (
+                pixels.iter().map(|&p| (p % cols) as f64).sum::<f64>() / n,
+                pixels.iter().map(|&p| (p / cols) as f64).sum::<f64>() / n,
+            );
+            for &p in &pixels {
+                let x = ((p % cols) as f64 - mx) * dx;
+                let y = ((p / cols) as f64 - my) * dy;
+                sxx += x * x;
+                syy += y * y;
+                sxy += x * y;
+            }
+            let (sxx, syy, sxy) = (sxx / n, syy / n, sxy / n);
+            let half_trace = (sxx + syy) / 2.0;
+            let spread = ((sxx - syy).powi(2) / 4.0 + sxy * sxy).sqrt();
+            // A uniform bar of n pixels of size d has variance d²(n²-1)/12 along its axis
+            let cell = dx * dy;
+            (
+                (12.0 * (half_trace + spread) + cell).sqrt(),
+                (12.0 * (half_trace - spread).max(0.0) + cell).sqrt(),
+            )
+        });
+
+        targets.push(ShipTarget {
+            id: targets.len() + 1,
+            col,
+            row,
+            lon: None,
+            lat: None,
+            pixels: pixels.len(),
+            length_m: axes.map(|a| a.0),
+            width_m: axes.map(|a| a.1),
+            peak_db: 10.0 * (peak.max(1e-10) as f64).log10(),
+        });
+    }
+    info!("Ship detection: {} targets", targets.len());
+    targets
+}
+
+/// CA-CFAR detection followed by clustering
+pub fn detect_ships(
+    intensity: &Array2<f32>,
+    pixel_size: Option<(f64, f64)>,
+    options: &ShipOptions,
+) -> Vec<ShipTarget> {
+    let detections = cfar_detect(intensity, options);
+    cluster_targets(intensity, &detections, pixel_size, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic speckle-like clutter in [lo, hi) (xorshift, no RNG dependency)
+    fn clutter(rows: usize, cols: usize, lo: f32, hi: f32) -> Array2<f32> {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        Array2::from_shape_fn((rows, cols), |_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            lo + (hi - lo) * ((state >> 40) as f32 / (1u64 << 24) as f32)
+        })
+    }
+
+    /// Direct CA-CFAR without summed-area tables, for cross-checking
+    fn brute_force_cfar(intensity: &Array2<f32>, options: &ShipOptions) -> Vec<usize> {
+        let (rows, cols) = intensity.dim();
+        let (g, b) = (options.guard as isize, options.background.max(options.guard + 1) as isize);
+        let k = normal_quantile(1.0 - options.pfa);
+        let ring = ((2 * b + 1).pow(2) - (2 * g + 1).pow(2)) as f64;
+        let mut out = Vec::new();
+        for r in 0..rows {
+            for c in 0..cols {
+                let v = intensity[[r, c]];
+                if !is_valid(v) {
+                    continue;
+                }
+                let (mut n, mut s, mut q) = (0.0, 0.0, 0.0);
+                for dr in -b..=b {
+                    for dc in -b..=b {
+                        let (rr, cc) = (r as isize + dr, c as isize + dc);
+                        let inside = rr >= 0 && cc >= 0 && rr < rows as isize && cc < cols as isize;
+                        if !inside || (dr.abs() <= g && dc.abs() <= g) {
+                            continue;
+                        }
+                        let x = intensity[[rr as usize, cc as usize]];
+                        if is_valid(x) {
+                            let x = x as f64;
+                            n += 1.0;
+                            s += x;
+                            q += x * x;
+                        }
+                    }
+                }
+                if n < ring * MIN_BACKGROUND_FRACTION {
+                    continue;
+                }
+                let mean = s / n;
+                let var = q / n - mean * mean;
+                if var > 0.0 && v as f64 - mean > k * var.sqrt() {
+                    out.push(r * cols + c);
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn normal_quantile_known_values() {
+        let cases = [
+            (0.5, 0.0),
+            (0.841_344_746_068_543, 1.0),
+            (0.975, 1.959_963_984_540_054),
+            (0.01, -2.326_347_874_040_841),
+            (1.0 - 1e-6, 4.753_424_308_822_899),
+        ];
+        for (p, z) in cases {
+            assert!((normal_quantile(p) - z).abs() < 1e-8, "p={}", p);
+            assert!((normal_quantile(1.0 - p) + z).abs() < 1e-8, "1-p for p={}", p);
+        }
+    }
+
+    #[test]
+    fn point_target_is_detected_alone() {
+        let mut image = clutter(100, 100, 0.05, 0.15);
+        for (r, c) in [(40, 60), (40, 61), (41, 60), (41, 61)] {
+            image[[r, c]] = 10.0;
+        }
+        let options = ShipOptions::default();
+        let detections = cfar_detect(&image, &options);
+        assert_eq!(detections, vec![4060, 4061, 4160, 4161]);
+
+        let targets = cluster_targets(&image, &detections, Some((10.0, 10.0)), &options);
+        assert_eq!(targets.len(), 1);
+        let target = &targets[0];
+        assert_eq!(target.pixels, 4);
+        assert!((target.col - 61.0).abs() < 1e-9 && (target.row - 41.0).abs() < 1e-9);
+        assert!((target.peak_db - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn summed_area_tables_match_brute_force() {
+        // Several strips, image edges, nodata holes and a loose pfa so clutter peaks fire
+        let mut image = clutter(150, 40, 0.0, 1.0);
+        for r in 60..70 {
+            for c in 10..14 {
+                image[[r, c]] = 0.0;
+            }
+        }
+        image[[3, 3]] = f32::NAN;
+        let options = ShipOptions {
+            guard: 1,
+            background: 3,
+            pfa: 0.05,
+            ..ShipOptions::default()
+        };
+        let detections = cfar_detect(&image, &options);
+        assert!(!detections.is_empty());
+        assert_eq!(detections, brute_force_cfar(&image, &options));
+    }
+
+    #[test]
+    fn union_find_compresses_paths() {
+        let mut parent = vec![0, 0, 1, 2, 4];
+        assert_eq!(find(&mut parent, 3), 0);
+        assert_eq!(find(&mut parent, 4), 4);
+        assert!(parent[3] < 3);
+    }
+
+    #[test]
+    fn clusters_use_eight_connectivity() {
+        let image = Array2::from_elem((8, 8), 1.0f32);
+        let px = |r: usize, c: usize| r * 8 + c;
+        // A "U" whose arms only meet on the bottom row, a diagonal pair and a lone pixel
+        let mut detections = vec![
+            px(0, 0),
+            px(0, 2),
+            px(1, 0),
+            px(1, 1),
+            px(1, 2),
+            px(3, 5),
+            px(4, 6),
+            px(7, 0),
+        ];
+        detections.sort_unstable();
+        let options = ShipOptions {
+            min_pixels: 1,
+            ..ShipOptions::default()
+        };
+        let targets = cluster_targets(&image, &detections, None, &options);
+        let sizes: Vec<usize> = targets.iter().map(|t| t.pixels).collect();
+        assert_eq!(sizes, vec![5, 2, 1]);
+        assert!((targets[1].col - 6.0).abs() < 1e-9 && (targets[1].row - 4.0).abs() < 1e-9);
+        assert!(targets.iter().all(|t| t.length_m.is_none()));
+
+        let options = ShipOptions {
+            min_pixels: 2,
+            max_pixels: 4,
+            ..ShipOptions::default()
+        };
+        let kept = cluster_targets(&image, &detections, None, &options);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].pixels, 2);
+    }
+
+    #[test]
+    fn bar_length_and_width() {
+        let image = Array2::from_elem((3, 20), 1.0f32);
+        let detections: Vec<usize> = (20 + 5..20 + 15).collect();
+        let options = ShipOptions::default();
+        let targets = cluster_targets(&image, &detections, Some((10.0, 10.0)), &options);
+        assert_eq!(targets.len(), 1);
+        assert!((targets[0].length_m.unwrap() - 100.0).abs() < 1e-9);
+        assert!((targets[0].width_m.unwrap() - 10.0).abs() < 1e-9);
+    }
+}
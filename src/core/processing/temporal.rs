@@ -0,0 +1,117 @@
+//! Per-pixel statistics across the dates of a co-gridded time series.
+use rayon::prelude::*;
+
+use crate::core::processing::pipeline::MIN_VALID_INTENSITY;
+use crate::types::TemporalStat;
+
+/// Compute `stats` over `dates` (one slice per date, all of the same length).
+/// Returns one vector per statistic; pixels without valid samples are NaN.
+/// Mean, std and CV are computed on the values as given (linear intensity for stacks).
+pub fn temporal_stats(dates: &[&[f32]], stats: &[TemporalStat]) -> Vec<Vec<f32>> {
+    let len = dates.first().map(|d| d.len()).unwrap_or(0);
+    let n_stats = stats.len();
+    if n_stats == 0 {
+        return Vec::new();
+    }
+    let mut interleaved = vec![f32::NAN; len * n_stats];
+    interleaved
+        .par_chunks_mut(n_stats)
+        .enumerate()
+        .for_each_init(Vec::new, |buf: &mut Vec<f32>, (i, dst)| {
+            buf.clear();
+            buf.extend(
+                dates
+                    .iter()
+                    .map(|d| d[i])
+                    .filter(|v| v.is_finite() && *v > MIN_VALID_INTENSITY),
+            );
+            if buf.is_empty() {
+                return;
+            }
+            let n = buf.len() as f64;
+            let mean = buf.iter().map(|&v| v as f64).sum::<f64>() / n;
+            let var = buf.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / n;
+            if stats.contains(&TemporalStat::Median) {
+                buf.sort_unstable_by(|a, b| a.total_cmp(b));
+            }
+            for (o, stat) in dst.iter_mut().zip(stats) {
+                *o = match stat {
+                    TemporalStat::Median => {
+                        let m = buf.len() / 2;
+                        if m * 2 == buf.len() {
+                            (buf[m - 1] + buf[m]) / 2.0
+                        } else {
+                            buf[m]
+                        }
+                    }
+                    TemporalStat::Mean => mean as f32,
+                    TemporalStat::Std => var.sqrt() as f32,
+                    TemporalStat::Min => buf.iter().copied().fold(f32::INFINITY, f32::min),
+                    TemporalStat::Max => buf.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                    TemporalStat::Cv => (var.sqrt() / mean) as f32,
+                };
+            }
+        });
+
+    (0..n_stats)
+        .map(|k| interleaved.iter().skip(k).step_by(n_stats).copied().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [TemporalStat; 6] = [
+        TemporalStat::Median,
+        TemporalStat::Mean,
+        TemporalStat::Std,
+        TemporalStat::Min,
+        TemporalStat::Max,
+        TemporalStat::Cv,
+    ];
+
+    fn close(a: f32, b: f64) -> bool {
+        (a as f64 - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn known_values_per_pixel() {
+        // Pixel 0: 1, 2, 3, 4 (even count); pixel 1: 0.5, 0.5, 0.5, 2.5
+        let dates: [&[f32]; 4] = [&[2.0, 0.5], &[4.0, 0.5], &[1.0, 2.5], &[3.0, 0.5]];
+        let out = temporal_stats(&dates, &ALL);
+        assert_eq!(out.len(), ALL.len());
+        let std0 = 1.25f64.sqrt();
+        let std1 = 0.75f64.sqrt();
+        let expected = [[2.5, 0.5], [2.5, 1.0], [std0, std1], [1.0, 0.5], [4.0, 2.5]];
+        for (values, want) in out.iter().zip(expected) {
+            assert!(close(values[0], want[0]) && close(values[1], want[1]));
+        }
+        assert!(close(out[5][0], std0 / 2.5) && close(out[5][1], std1 / 1.0));
+    }
+
+    #[test]
+    fn invalid_samples_are_skipped() {
+        // Pixel 0 keeps 3 valid of 5 (odd median); pixel 1 has no valid sample
+        let dates: [&[f32]; 5] = [
+            &[5.0, 0.0],
+            &[f32::NAN, f32::NAN],
+            &[1.0, f32::INFINITY],
+            &[0.0, -1.0],
+            &[3.0, 1e-6],
+        ];
+        let out = temporal_stats(&dates, &[TemporalStat::Median, TemporalStat::Min]);
+        assert_eq!(out[0][0], 3.0);
+        assert_eq!(out[1][0], 1.0);
+        assert!(out[0][1].is_nan() && out[1][1].is_nan());
+    }
+
+    #[test]
+    fn requested_order_and_empty_inputs() {
+        let dates: [&[f32]; 2] = [&[1.0], &[3.0]];
+        let out = temporal_stats(&dates, &[TemporalStat::Max, TemporalStat::Mean]);
+        assert_eq!(out, vec![vec![3.0], vec![2.0]]);
+        assert!(temporal_stats(&dates, &[]).is_empty());
+        assert_eq!(temporal_stats(&[], &[TemporalStat::Mean]), vec![Vec::<f32>::new()]);
+    }
+}
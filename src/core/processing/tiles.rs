@@ -0,0 +1,260 @@
+//! Web Mercator (EPSG:3857) XYZ tiling: tile grid math, zoom selection and resampling of a
+//! georeferenced 8-bit image into fixed-size tiles with an alpha channel.
+
+/// Half the side of the square EPSG:3857 world, in meters
+pub const WEB_MERCATOR_HALF_EXTENT: f64 = 20_037_508.342_789_244;
+/// Sphere radius of EPSG:3857, in meters
+const WEB_MERCATOR_RADIUS: f64 = 6_378_137.0;
+/// Deepest zoom level SARPRO cuts (≈ 2 cm pixels at 256 px tiles)
+pub const MAX_TILE_ZOOM: u8 = 24;
+
+/// Side of one tile at zoom `z`, in meters
+pub fn tile_span(z: u8) -> f64 {
+    2.0 * WEB_MERCATOR_HALF_EXTENT / (1u64 << z) as f64
+}
+
+/// EPSG:3857 coordinates to longitude/latitude in degrees
+pub fn mercator_to_lonlat(x: f64, y: f64) -> (f64, f64) {
+    let lon = (x / WEB_MERCATOR_RADIUS).to_degrees();
+    let lat = (y / WEB_MERCATOR_RADIUS).sinh().atan().to_degrees();
+    (lon, lat)
+}
+
+/// Shallowest zoom whose pixels are at least as fine as `pixel_size` meters
+pub fn native_zoom(pixel_size: f64, tile_size: usize) -> u8 {
+    let z = (2.0 * WEB_MERCATOR_HALF_EXTENT / (tile_size as f64 * pixel_size.abs())).log2().ceil();
+    z.clamp(0.0, MAX_TILE_ZOOM as f64) as u8
+}
+
+/// Deepest zoom at which `bounds` (`[min_x, min_y, max_x, max_y]`) still fit in one tile
+pub fn overview_zoom(bounds: [f64; 4]) -> u8 {
+    let extent = (bounds[2] - bounds[0]).max(bounds[3] - bounds[1]);
+    let z = (2.0 * WEB_MERCATOR_HALF_EXTENT / extent).log2().floor();
+    z.clamp(0.0, MAX_TILE_ZOOM as f64) as u8
+}
+
+/// Autoscaled 8-bit image on a north-up EPSG:3857 grid
+pub struct MercatorImage<'a> {
+    pub cols: usize,
+    pub rows: usize,
+    /// Samples per pixel: 1 (gray) or 3 (interleaved RGB)
+    pub channels: usize,
+    pub data: &'a [u8],
+    /// 255 where the pixel holds data, 0 for nodata
+    pub alpha: &'a [u8],
+    pub geotransform: [f64; 6],
+}
+
+/// One rendered tile: samples laid out like the source image plus a 0/255 alpha channel
+pub struct Tile {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+    pub data: Vec<u8>,
+    pub alpha: Vec<u8>,
+}
+
+impl MercatorImage<'_> {
+    /// `[min_x, min_y, max_x, max_y]` in meters
+    pub fn bounds(&self) -> [f64; 4] {
+        let gt = self.geotransform;
+        let x1 = gt[0] + self.cols as f64 * gt[1];
+        let y1 = gt[3] + self.rows as f64 * gt[5];
+        [gt[0].min(x1), gt[3].min(y1), gt[0].max(x1), gt[3].max(y1)]
+    }
+
+    /// Inclusive tile index ranges `(x0, y0, x1, y1)` covering the image at zoom `z`
+    pub fn tile_range(&self, z: u8) -> (u32, u32, u32, u32) {
+        let [min_x, min_y, max_x, max_y] = self.bounds();
+        let span = tile_span(z);
+        let last = ((1u64 << z) - 1) as f64;
+        // Edges that fall exactly on a tile boundary do not pull in the next tile
+        let eps = span * 1e-9;
+        let index = |v: f64| (v / span).floor().clamp(0.0, last) as u32;
+        (
+            index(min_x + WEB_MERCATOR_HALF_EXTENT),
+            index(WEB_MERCATOR_HALF_EXTENT - max_y),
+            index(max_x + WEB_MERCATOR_HALF_EXTENT - eps),
+            index(WEB_MERCATOR_HALF_EXTENT - min_y - eps),
+        )
+    }
+
+    /// Resample tile `z/x/y` at `tile_size` pixels. Tile pixels finer than the image take the
+    /// image pixel under their centre; coarser ones average the valid image pixels they cover
+    /// and stay transparent unless at least half of those are valid. Returns `None` for a
+    /// tile without any valid pixel.
+    pub fn render_tile(&self, z: u8, x: u32, y: u32, tile_size: usize) -> Option<Tile> {
+        let span = tile_span(z);
+        let res = span / tile_size as f64;
+        let gt = self.geotransform;
+        let left = -WEB_MERCATOR_HALF_EXTENT + x as f64 * span;
+        let top = WEB_MERCATOR_HALF_EXTENT - y as f64 * span;
+        let col_ranges = axis_ranges(left, res, tile_size, gt[0], gt[1], self.cols);
+        let row_ranges = axis_ranges(top, -res, tile_size, gt[3], gt[5], self.rows);
+        if col_ranges.iter().all(Option::is_none) || row_ranges.iter().all(Option::is_none) {
+            return None;
+        }
+
+        let ch = self.channels;
+        let mut data = vec![0u8; tile_size * tile_size * ch];
+        let mut alpha = vec![0u8; tile_size * tile_size];
+        let mut sums = vec![0u64; ch];
+        let mut any = false;
+        for (j, rows) in row_ranges.iter().enumerate() {
+            let Some((y0, y1)) = *rows else { continue };
+            for (i, cols) in col_ranges.iter().enumerate() {
+                let Some((x0, x1)) = *cols else { continue };
+                sums.fill(0);
+                let mut valid = 0u64;
+                for sy in y0..y1 {
+                    for sx in x0..x1 {
+                        let idx = sy * self.cols + sx;
+                        if self.alpha[idx] != 0 {
+                            valid += 1;
+                            for (c, sum) in sums.iter_mut().enumerate() {
+                                *sum += self.data[idx * ch + c] as u64;
+                            }
+                        }
+                    }
+                }
+                let total = ((y1 - y0) * (x1 - x0)) as u64;
+                if valid == 0 || valid * 2 < total {
+                    continue;
+                }
+                let o = j * tile_size + i;
+                alpha[o] = 255;
+                for (c, sum) in sums.iter().enumerate() {
+                    data[o * ch + c] = ((sum + valid / 2) / valid) as u8;
+                }
+                any = true;
+            }
+        }
+        any.then_some(Tile { z, x, y, data, alpha })
+    }
+
+    /// `(x, y)` of every tile of zoom `z` that overlaps the image, row by row
+    pub fn tile_indices(&self, z: u8) -> Vec<(u32, u32)> {
+        let (x0, y0, x1, y1) = self.tile_range(z);
+        (y0..=y1).flat_map(|y| (x0..=x1).map(move |x| (x, y))).collect()
+    }
+}
+
+/// Source index range `[lo, hi)` covered by each of `n` output cells of size `step` starting
+/// at `start`, on an image axis with origin `origin`, pixel size `pixel` and `len` pixels.
+/// A cell narrower than an image pixel takes the pixel under its centre; `None` lies outside.
+fn axis_ranges(
+    start: f64,
+    step: f64,
+    n: usize,
+    origin: f64,
+    pixel: f64,
+    len: usize,
+) -> Vec<Option<(usize, usize)>> {
+    (0..n)
+        .map(|i| {
+            let a = (start + i as f64 * step - origin) / pixel;
+            let b = (start + (i + 1) as f64 * step - origin) / pixel;
+            let (a, b) = if a <= b { (a, b) } else { (b, a) };
+            // Image pixels whose centres fall inside the cell
+            let lo = (a - 0.5).ceil().max(0.0);
+            let hi = (b - 0.5).ceil().min(len as f64);
+            if hi > lo {
+                Some((lo as usize, hi as usize))
+            } else {
+                let c = ((a + b) * 0.5).floor();
+                (c >= 0.0 && c < len as f64).then(|| (c as usize, c as usize + 1))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H: f64 = WEB_MERCATOR_HALF_EXTENT;
+
+    /// 2×2 image covering exactly the north-west quadrant, i.e. tile 1/0/0
+    fn quadrant<'a>(data: &'a [u8], alpha: &'a [u8]) -> MercatorImage<'a> {
+        MercatorImage {
+            cols: 2,
+            rows: 2,
+            channels: 1,
+            data,
+            alpha,
+            geotransform: [-H, H / 2.0, 0.0, H, 0.0, -H / 2.0],
+        }
+    }
+
+    #[test]
+    fn edges_on_tile_boundaries_do_not_pull_in_the_next_tile() {
+        let image = quadrant(&[0; 4], &[255; 4]);
+        assert_eq!(image.tile_range(0), (0, 0, 0, 0));
+        assert_eq!(image.tile_range(1), (0, 0, 0, 0));
+        assert_eq!(image.tile_range(2), (0, 0, 1, 1));
+        assert_eq!(image.tile_indices(2), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn coarse_cells_average_valid_pixels() {
+        let image = quadrant(&[10, 20, 30, 40], &[255; 4]);
+        let tile = image.render_tile(1, 0, 0, 1).unwrap();
+        assert_eq!((tile.data[0], tile.alpha[0]), (25, 255));
+
+        // Half of the covered pixels valid: averaged over those, rounding half up
+        let image = quadrant(&[10, 21, 99, 99], &[255, 255, 0, 0]);
+        let tile = image.render_tile(1, 0, 0, 1).unwrap();
+        assert_eq!((tile.data[0], tile.alpha[0]), (16, 255));
+    }
+
+    #[test]
+    fn coarse_cells_below_half_valid_stay_transparent() {
+        let image = quadrant(&[10, 20, 30, 40], &[255, 0, 0, 0]);
+        assert!(image.render_tile(1, 0, 0, 1).is_none());
+
+        // Same image cut finer: the valid pixel shows up on its own
+        let tile = image.render_tile(1, 0, 0, 2).unwrap();
+        assert_eq!(tile.alpha, vec![255, 0, 0, 0]);
+        assert_eq!(tile.data[0], 10);
+    }
+
+    #[test]
+    fn fine_cells_take_the_pixel_under_their_centre() {
+        let image = quadrant(&[1, 2, 3, 4], &[255; 4]);
+        let tile = image.render_tile(1, 0, 0, 4).unwrap();
+        assert_eq!(tile.data, vec![1, 1, 2, 2, 1, 1, 2, 2, 3, 3, 4, 4, 3, 3, 4, 4]);
+        assert!(tile.alpha.iter().all(|&a| a == 255));
+    }
+
+    #[test]
+    fn tiles_off_the_image_are_empty() {
+        let image = quadrant(&[1, 2, 3, 4], &[255; 4]);
+        assert!(image.render_tile(1, 1, 1, 4).is_none());
+    }
+
+    #[test]
+    fn axis_ranges_cover_and_clip() {
+        assert_eq!(
+            axis_ranges(0.0, 2.0, 3, 0.0, 1.0, 5),
+            vec![Some((0, 2)), Some((2, 4)), Some((4, 5))]
+        );
+        // Descending axis (north-up rows)
+        assert_eq!(axis_ranges(0.0, -2.0, 2, 0.0, -1.0, 3), vec![Some((0, 2)), Some((2, 3))]);
+        assert_eq!(axis_ranges(10.0, 1.0, 2, 0.0, 1.0, 5), vec![None, None]);
+        assert_eq!(axis_ranges(-1.0, 1.0, 1, 0.0, 1.0, 5), vec![None]);
+    }
+
+    #[test]
+    fn zooms_are_clamped() {
+        let pixel = tile_span(10) / 256.0;
+        assert_eq!(native_zoom(pixel * 1.01, 256), 10);
+        assert_eq!(native_zoom(pixel * 0.99, 256), 11);
+        assert_eq!(native_zoom(-pixel * 1.01, 256), 10);
+        assert_eq!(native_zoom(1e-6, 256), MAX_TILE_ZOOM);
+        assert_eq!(native_zoom(1e9, 256), 0);
+
+        assert_eq!(overview_zoom([0.0, 0.0, 1e-6, 1e-6]), MAX_TILE_ZOOM);
+        assert_eq!(overview_zoom([-H, -H, H, H]), 0);
+        assert_eq!(overview_zoom([-4.0 * H, -H, 4.0 * H, H]), 0);
+    }
+}
@@ -0,0 +1,406 @@
+//! Open water / flood extent from a single dB backscatter image.
+//! Calm water is a specular reflector and forms the dark mode of the histogram; a global
+//! Otsu or Kittler-Illingworth threshold separates it from land. Optionally the threshold
+//! is re-estimated from tiles whose histograms are clearly bimodal around the global value
+//! (split-based thresholding), which keeps small water fractions from being swamped by land.
+use ndarray::{Array2, s};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::core::processing::autoscale::{DbHistogram, PAR_CHUNK};
+use crate::core::processing::mask::ValidMask;
+use crate::types::WaterThreshold;
+
+/// Mask value for water pixels
+pub const WATER: u8 = 1;
+/// Mask value for valid non-water pixels
+pub const LAND: u8 = 0;
+/// Mask value (and GeoTIFF nodata) for pixels without valid backscatter
+pub const WATER_NODATA: u8 = 255;
+
+/// Ashman's D above which a tile counts as bimodal
+const BIMODAL_ASHMAN_D: f64 = 2.0;
+/// Minimum share of a tile's valid pixels on each side of the threshold
+const BIMODAL_MIN_CLASS_FRACTION: f64 = 0.1;
+/// Fewer selected tiles than this keeps the global threshold
+const MIN_SELECTED_TILES: usize = 3;
+
+/// Options for water mapping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaterOptions {
+    pub method: WaterThreshold,
+    /// Re-estimate the threshold from bimodal tiles only
+    pub tile_refinement: bool,
+    /// Tile edge in pixels for the bimodality search
+    pub tile_size: usize,
+}
+
+impl Default for WaterOptions {
+    fn default() -> Self {
+        Self {
+            method: WaterThreshold::Otsu,
+            tile_refinement: false,
+            tile_size: 256,
+        }
+    }
+}
+
+/// Water mask and the statistics that produced it
+#[derive(Debug, Clone)]
+pub struct WaterResult {
+    /// Row-major mask: [`WATER`], [`LAND`] or [`WATER_NODATA`]
+    pub mask: Vec<u8>,
+    pub cols: usize,
+    pub rows: usize,
+    /// Threshold applied (dB); pixels below it are water
+    pub threshold_db: f64,
+    /// Threshold from the whole-image histogram (dB)
+    pub global_threshold_db: f64,
+    /// Tiles examined / tiles found bimodal (both 0 without tile refinement)
+    pub tiles_total: usize,
+    pub tiles_bimodal: usize,
+    pub water_pixels: usize,
+    pub valid_pixels: usize,
+}
+
+impl WaterResult {
+    pub fn water_fraction(&self) -> f64 {
+        if self.valid_pixels == 0 {
+            0.0
+        } else {
+            self.water_pixels as f64 / self.valid_pixels as f64
+        }
+    }
+}
+
+/// Occupied bin range and cumulative (count, sum, sum of squares) at bin centers
+struct Cumulative {
+    first: usize,
+    n: Vec<f64>,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+}
+
+impl Cumulative {
+    fn new(hist: &DbHistogram) -> Option<Self> {
+        let bins = hist.bins();
+        let first = bins.iter().position(|&c| c > 0)?;
+        let last = bins.iter().rposition(|&c| c > 0)?;
+        if last <= first {
+            return None;
+        }
+        let half = hist.bin_width() / 2.0;
+        let len = last - first + 1;
+        let mut out = Cumulative {
+            first,
+            n: Vec::with_capacity(len),
+            sum: Vec::with_capacity(len),
+            sum_sq: Vec::with_capacity(len),
+        };
+        let (mut n, mut sum, mut sum_sq) = (0.0, 0.0, 0.0);
+        for (i, &c) in bins[first..=last].iter().enumerate() {
+            let x = hist.bin_start(first + i) + half;
+            let c = c as f64;
+            n += c;
+            sum += c * x;
+            sum_sq += c * x * x;
+            out.n.push(n);
+            out.sum.push(sum);
+            out.sum_sq.push(sum_sq);
+        }
+        Some(out)
+    }
+
+    /// Threshold between bin `k` and `k + 1`
+    fn split(&self, hist: &DbHistogram, k: usize) -> f64 {
+        hist.bin_start(self.first + k + 1)
+    }
+}
+
+/// Otsu's threshold: maximizes the between-class variance
+pub fn otsu_threshold(hist: &DbHistogram) -> Option<f64> {
+    let c = Cumulative::new(hist)?;
+    let total_n = *c.n.last()?;
+    let total_sum = *c.sum.last()?;
+    let mut best = (f64::NEG_INFINITY, 0usize);
+    for k in 0..c.n.len() - 1 {
+        let n0 = c.n[k];
+        let n1 = total_n - n0;
+        if n0 == 0.0 || n1 == 0.0 {
+            continue;
+        }
+        let m0 = c.sum[k] / n0;
+        let m1 = (total_sum - c.sum[k]) / n1;
+        let between = n0 * n1 * (m0 - m1) * (m0 - m1);
+        if between > best.0 {
+            best = (between, k);
+        }
+    }
+    best.0.is_finite().then(|| c.split(hist, best.1))
+}
+
+/// Kittler-Illingworth minimum-error threshold: fits two Gaussians and minimizes
+/// J(t) = 1 + 2 (P0 ln σ0 + P1 ln σ1) - 2 (P0 ln P0 + P1 ln P1)
+pub fn kittler_illingworth_threshold(hist: &DbHistogram) -> Option<f64> {
+    let c = Cumulative::new(hist)?;
+    let total_n = *c.n.last()?;
+    let total_sum = *c.sum.last()?;
+    let total_sq = *c.sum_sq.last()?;
+    // Variance floor: a class narrower than one bin has no meaningful spread
+    let min_var = hist.bin_width() * hist.bin_width();
+    let mut best = (f64::INFINITY, 0usize);
+    for k in 0..c.n.len() - 1 {
+        let n0 = c.n[k];
+        let n1 = total_n - n0;
+        if n0 < 2.0 || n1 < 2.0 {
+            continue;
+        }
+        let m0 = c.sum[k] / n0;
+        let m1 = (total_sum - c.sum[k]) / n1;
+        let v0 = (c.sum_sq[k] / n0 - m0 * m0).max(min_var);
+        let v1 = ((total_sq - c.sum_sq[k]) / n1 - m1 * m1).max(min_var);
+        let p0 = n0 / total_n;
+        let p1 = n1 / total_n;
+        // ln σ = 0.5 ln σ²
+        let j = 1.0 + (p0 * v0.ln() + p1 * v1.ln()) - 2.0 * (p0 * p0.ln() + p1 * p1.ln());
+        if j < best.0 {
+            best = (j, k);
+        }
+    }
+    best.0.is_finite().then(|| c.split(hist, best.1))
+}
+
+fn threshold_with(method: WaterThreshold, hist: &DbHistogram) -> Option<f64> {
+    match method {
+        WaterThreshold::Otsu => otsu_threshold(hist),
+        WaterThreshold::Ki => kittler_illingworth_threshold(hist),
+    }
+}
+
+/// Class moments on either side of `t` for one tile: (n, sum, sum_sq) below and above
+fn tile_split_moments(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    (r0, r1, c0, c1): (usize, usize, usize, usize),
+    t: f32,
+) -> [(f64, f64, f64); 2] {
+    let cols = db.ncols();
+    let mut acc = [(0.0, 0.0, 0.0); 2];
+    for (i, row) in db.slice(s![r0..r1, c0..c1]).outer_iter().enumerate() {
+        let base = (r0 + i) * cols + c0;
+        for (j, &v) in row.iter().enumerate() {
+            if !valid_mask.get(base + j) {
+                continue;
+            }
+            let side = &mut acc[usize::from(v >= t)];
+            let v = v as f64;
+            side.0 += 1.0;
+            side.1 += v;
+            side.2 += v * v;
+        }
+    }
+    acc
+}
+
+fn is_bimodal(moments: &[(f64, f64, f64); 2]) -> bool {
+    let [(n0, s0, q0), (n1, s1, q1)] = *moments;
+    let total = n0 + n1;
+    if total == 0.0
+        || n0 / total < BIMODAL_MIN_CLASS_FRACTION
+        || n1 / total < BIMODAL_MIN_CLASS_FRACTION
+    {
+        return false;
+    }
+    let (m0, m1) = (s0 / n0, s1 / n1);
+    let v0 = (q0 / n0 - m0 * m0).max(0.0);
+    let v1 = (q1 / n1 - m1 * m1).max(0.0);
+    let spread = (v0 + v1).sqrt();
+    spread > 0.0 && std::f64::consts::SQRT_2 * (m1 - m0).abs() / spread >= BIMODAL_ASHMAN_D
+}
+
+/// Threshold `db` into a water mask. `hist` is the histogram of the valid dB pixels
+/// (e.g. from `process_scalar_data_fused`). Returns None when no threshold can be
+/// estimated (empty or single-valued image).
+pub fn detect_water(
+    db: &Array2<f32>,
+    valid_mask: &ValidMask,
+    hist: &DbHistogram,
+    options: &WaterOptions,
+) -> Option<WaterResult> {
+    let (rows, cols) = db.dim();
+    let global = threshold_with(options.method, hist)?;
+    info!("Water: global {:?} threshold {:.2} dB", options.method, global);
+
+    let mut threshold = global;
+    let (mut tiles_total, mut tiles_bimodal) = (0, 0);
+    if options.tile_refinement {
+        let ts = options.tile_size.max(16);
+        let tiles: Vec<(usize, usize, usize, usize)> = (0..rows)
+            .step_by(ts)
+            .flat_map(|r0| {
+                (0..cols)
+                    .step_by(ts)
+                    .map(move |c0| (r0, (r0 + ts).min(rows), c0, (c0 + ts).min(cols)))
+            })
+            .collect();
+        tiles_total = tiles.len();
+
+        let t = global as f32;
+        let (selected, refined_hist) = tiles
+            .par_iter()
+            .filter(|&&tile| is_bimodal(&tile_split_moments(db, valid_mask, tile, t)))
+            .fold(
+                || (0usize, DbHistogram::new()),
+                |(count, mut h), &(r0, r1, c0, c1)| {
+                    for i in r0..r1 {
+                        for j in c0..c1 {
+                            if valid_mask.get(i * cols + j) {
+                                h.push(db[[i, j]]);
+                            }
+                        }
+                    }
+                    (count + 1, h)
+                },
+            )
+            .reduce(
+                || (0usize, DbHistogram::new()),
+                |(a, ha), (b, hb)| (a + b, ha.merge(hb)),
+            );
+        tiles_bimodal = selected;
+
+        if selected >= MIN_SELECTED_TILES
+            && let Some(t) = threshold_with(options.method, &refined_hist)
+        {
+            threshold = t;
+        }
+        debug!(
+            "Water: {}/{} bimodal tiles, threshold {:.2} dB",
+            tiles_bimodal, tiles_total, threshold
+        );
+    }
+
+    let db_std = db.as_standard_layout();
+    let src = db_std.as_slice().expect("standard layout array is contiguous");
+    let t = threshold as f32;
+    let mut mask = vec![WATER_NODATA; src.len()];
+    let (water_pixels, valid_pixels) = mask
+        .par_chunks_mut(PAR_CHUNK)
+        .zip(src.par_chunks(PAR_CHUNK))
+        .enumerate()
+        .map(|(ci, (dst, chunk))| {
+            let base = ci * PAR_CHUNK;
+            let (mut water, mut valid) = (0usize, 0usize);
+            for (k, (o, &v)) in dst.iter_mut().zip(chunk.iter()).enumerate() {
+                if valid_mask.get(base + k) {
+                    valid += 1;
+                    if v < t {
+                        water += 1;
+                        *o = WATER;
+                    } else {
+                        *o = LAND;
+                    }
+                }
+            }
+            (water, valid)
+        })
+        .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+    Some(WaterResult {
+        mask,
+        cols,
+        rows,
+        threshold_db: threshold,
+        global_threshold_db: global,
+        tiles_total,
+        tiles_bimodal,
+        water_pixels,
+        valid_pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Symmetric triangular distribution of `n * n` samples centred on `mean` with half-width
+    /// `half`, built from a grid so the test is deterministic
+    fn triangular(mean: f32, half: f32, n: usize) -> Vec<f32> {
+        let mut out = Vec::with_capacity(n * n);
+        for i in 0..n {
+            for j in 0..n {
+                let u = (i as f32 + 0.5) / n as f32 + (j as f32 + 0.5) / n as f32 - 1.0;
+                out.push(mean + half * u);
+            }
+        }
+        out
+    }
+
+    fn histogram(values: &[f32]) -> DbHistogram {
+        let mut hist = DbHistogram::new();
+        values.iter().for_each(|&v| hist.push(v));
+        hist
+    }
+
+    #[test]
+    fn symmetric_bimodal_splits_at_midpoint() {
+        // Overlapping classes on [-28, -12] and [-16, 0]; by symmetry both criteria are
+        // optimal at the midpoint -14 dB
+        let mut values = triangular(-20.0, 8.0, 200);
+        values.extend(triangular(-8.0, 8.0, 200));
+        let hist = histogram(&values);
+        let otsu = otsu_threshold(&hist).unwrap();
+        let ki = kittler_illingworth_threshold(&hist).unwrap();
+        assert!((otsu + 14.0).abs() < 0.1, "otsu {}", otsu);
+        assert!((ki + 14.0).abs() < 0.1, "ki {}", ki);
+    }
+
+    #[test]
+    fn separated_classes_split_in_the_gap() {
+        // Every split in the gap separates the classes equally well; the first one wins
+        let water = triangular(-22.0, 2.0, 50);
+        let land = triangular(-6.0, 2.0, 100);
+        let hist = histogram(&[water.as_slice(), land.as_slice()].concat());
+        for t in [otsu_threshold(&hist), kittler_illingworth_threshold(&hist)] {
+            let t = t.unwrap();
+            assert!(water.iter().all(|&v| (v as f64) < t), "threshold {}", t);
+            assert!(land.iter().all(|&v| (v as f64) >= t), "threshold {}", t);
+            assert!(t - hist.bin_width() < -20.0, "threshold {}", t);
+        }
+    }
+
+    #[test]
+    fn minority_water_moves_ki_towards_water() {
+        // Equal spreads, 1:4 priors: the minimum-error threshold moves towards the smaller
+        // (water) class, while staying between the class means
+        let mut values = triangular(-20.0, 8.0, 100);
+        values.extend(triangular(-8.0, 8.0, 200));
+        let ki = kittler_illingworth_threshold(&histogram(&values)).unwrap();
+        assert!(ki > -20.0 && ki < -14.0, "ki {}", ki);
+    }
+
+    #[test]
+    fn degenerate_histograms_have_no_threshold() {
+        assert_eq!(otsu_threshold(&DbHistogram::new()), None);
+        let flat = histogram(&[-10.0; 100]);
+        assert_eq!(otsu_threshold(&flat), None);
+        assert_eq!(kittler_illingworth_threshold(&flat), None);
+    }
+
+    #[test]
+    fn detect_water_masks_low_backscatter() {
+        // 4x4 image: left half water (-22 dB), right half land (-6 dB), one nodata pixel
+        let db = Array2::from_shape_fn((4, 4), |(_, c)| if c < 2 { -22.0 } else { -6.0 });
+        let mut valid_mask = ValidMask::all_valid(16);
+        valid_mask.set(15, false);
+        let hist = histogram(&db.iter().take(15).copied().collect::<Vec<_>>());
+
+        let result = detect_water(&db, &valid_mask, &hist, &WaterOptions::default()).unwrap();
+        assert!(result.threshold_db > -22.0 && result.threshold_db <= -6.0);
+        assert_eq!(result.valid_pixels, 15);
+        assert_eq!(result.water_pixels, 8);
+        assert_eq!(&result.mask[..4], &[WATER, WATER, LAND, LAND]);
+        assert_eq!(result.mask[15], WATER_NODATA);
+        assert!((result.water_fraction() - 8.0 / 15.0).abs() < 1e-12);
+    }
+}
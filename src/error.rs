@@ -33,6 +33,9 @@ pub enum Error {
         available: String,
     },
 
+    #[error("Expression error: {0}")]
+    Expression(#[from] crate::core::processing::expr::ExprError),
+
     #[error("Processing error: {0}")]
     Processing(String),
 
@@ -2,7 +2,7 @@ use super::models::{SarproGui, SizeMode};
 use crate::{AutoscaleStrategy, Polarization, PolarizationOperation};
 use crate::{BitDepth, OutputFormat};
 use crate::types::SyntheticRgbMode;
-use eframe::egui::{Align, Color32, ComboBox, Frame, Layout, RichText, Ui};
+use eframe::egui::{Align, Color32, ComboBox, Frame, Layout, RichText, Slider, Ui};
 
 const COMPONENT_HEIGHT: f32 = 80.0;
 const COMPONENT_WIDTH: f32 = 120.0;
@@ -145,6 +145,8 @@ impl FormatOptionsComponent {
                         .show_ui(ui, |ui| {
                             ui.selectable_value(&mut app.output_format, OutputFormat::TIFF, "TIFF");
                             ui.selectable_value(&mut app.output_format, OutputFormat::JPEG, "JPEG");
+                            ui.selectable_value(&mut app.output_format, OutputFormat::PNG, "PNG");
+                            ui.selectable_value(&mut app.output_format, OutputFormat::WebP, "WebP");
                         });
 
                     // Update output path extension if format changed
@@ -170,10 +172,10 @@ impl FormatOptionsComponent {
                         .selected_text(format!("{:?}", app.bit_depth))
                         .show_ui(ui, |ui| {
                             ui.selectable_value(&mut app.bit_depth, BitDepth::U8, "8-bit");
-                            // Disable 16-bit option when JPEG format is selected
-                            if app.output_format == OutputFormat::JPEG {
+                            // Disable 16-bit option when an 8-bit only format is selected
+                            if matches!(app.output_format, OutputFormat::JPEG | OutputFormat::WebP) {
                                 ui.add_enabled_ui(false, |ui| {
-                                    ui.selectable_value(&mut app.bit_depth, BitDepth::U16, "16-bit (not available for JPEG)");
+                                    ui.selectable_value(&mut app.bit_depth, BitDepth::U16, "16-bit (not available for JPEG/WebP)");
                                 });
                             } else {
                                 ui.selectable_value(&mut app.bit_depth, BitDepth::U16, "16-bit");
@@ -194,8 +196,8 @@ impl FormatOptionsComponent {
                             ui.selectable_value(&mut app.polarization, Polarization::Vh, "VH");
                             ui.selectable_value(&mut app.polarization, Polarization::Hh, "HH");
                             ui.selectable_value(&mut app.polarization, Polarization::Hv, "HV");
-                            // Disable Multiband option when JPEG format is selected
-                            if app.output_format == OutputFormat::JPEG {
+                            // Multiband becomes synthetic RGB for every format but TIFF
+                            if app.output_format != OutputFormat::TIFF {
                                 // ui.add_enabled_ui(false, |ui| {
                                     ui.selectable_value(
                                         &mut app.polarization,
@@ -236,12 +238,32 @@ impl FormatOptionsComponent {
                                 Polarization::OP(PolarizationOperation::LogRatio),
                                 "Log Ratio",
                             );
+                            ui.selectable_value(
+                                &mut app.polarization,
+                                Polarization::OP(PolarizationOperation::Rvi),
+                                "RVI",
+                            );
+                            ui.selectable_value(
+                                &mut app.polarization,
+                                Polarization::OP(PolarizationOperation::CrossPolDb),
+                                "Cross-pol Ratio (dB)",
+                            );
+                            ui.selectable_value(
+                                &mut app.polarization,
+                                Polarization::OP(PolarizationOperation::DepolRatio),
+                                "Depolarization Ratio",
+                            );
+                            ui.selectable_value(
+                                &mut app.polarization,
+                                Polarization::OP(PolarizationOperation::Dpsvi),
+                                "DPSVI",
+                            );
                         });
                 });
             });
 
-            // Synthetic RGB mode: only relevant when JPEG + Multiband
-            if app.output_format == OutputFormat::JPEG && matches!(app.polarization, Polarization::Multiband) {
+            // Synthetic RGB mode: only relevant when JPEG/PNG/WebP + Multiband
+            if app.output_format != OutputFormat::TIFF && matches!(app.polarization, Polarization::Multiband) {
                 ui.add_space(10.0);
                 ui.horizontal(|ui| {
                     ui.label("synRGB mode:");
@@ -279,7 +301,7 @@ impl FormatOptionsComponent {
                     "Looks for HV (Horizontal-Vertical) polarization. The output is grayscale."
                 },
                 Polarization::Multiband => {
-                    if app.output_format == OutputFormat::JPEG {
+                    if app.output_format != OutputFormat::TIFF {
                         "Multiband (synthetic RGB). Choose mode in the dropdown below. Default uses R=VV|HH, G=VH|HV, B=ratio with gamma/LUTs. Use Tamed or CLAHE for contrast. *Note: All use Default at the moment, to be implemented in v0.3.x"
                     } else {
                         "Multiband. The output is grayscale. Use with Tamed or Clahe autoscale strategy to isolate the ground from the water."
@@ -300,6 +322,18 @@ impl FormatOptionsComponent {
                 Polarization::OP(PolarizationOperation::LogRatio) => {
                     "Log Ratio (log(band1 / band2)). The output is grayscale. Try with Tamed or Clahe autoscale strategy for maximum contrast."
                 },
+                Polarization::OP(PolarizationOperation::Rvi) => {
+                    "Radar Vegetation Index 4·band2 / (band1 + band2). Scaled as an index, without dB conversion. The output is grayscale."
+                },
+                Polarization::OP(PolarizationOperation::CrossPolDb) => {
+                    "Cross-pol ratio 10·log10(band2 / band1) in dB. The output is grayscale."
+                },
+                Polarization::OP(PolarizationOperation::DepolRatio) => {
+                    "Depolarization ratio band2 / band1 (linear). Scaled as an index, without dB conversion. The output is grayscale."
+                },
+                Polarization::OP(PolarizationOperation::Dpsvi) => {
+                    "Dual-pol SAR Vegetation Index. The output is grayscale. Try with Clahe autoscale strategy for maximum contrast."
+                },
             };
             ui.label(
                 RichText::new(polarization_info)
@@ -454,6 +488,9 @@ impl OptionsComponent {
                 AutoscaleStrategy::Default => {
                     "Default advanced scaling (same as Adaptive). Automatically analyzes image characteristics and applies optimal scaling parameters. Recommended."
                 }
+                AutoscaleStrategy::Fixed { .. } => {
+                    "Fixed dB clip window and gamma (from a preset), identical for every scene. Use for time series and batches that must share one stretch."
+                }
             };
 
             ui.label(
@@ -462,6 +499,12 @@ impl OptionsComponent {
                     .size(11.0)
             );
 
+            ui.add_space(5.0);
+
+            ui.collapsing("Autoscale tuning", |ui| {
+                Self::render_autoscale_tuning(ui, app);
+            });
+
             ui.add_space(12.0);
 
             // Target CRS option
@@ -508,6 +551,54 @@ impl OptionsComponent {
             );
         });
     }
+
+    /// Sliders for the percentile, gamma, CLAHE and dB-floor overrides; an unchecked override
+    /// leaves the strategy's own value in place
+    fn render_autoscale_tuning(ui: &mut Ui, app: &mut SarproGui) {
+        let tuning = &mut app.autoscale_params;
+
+        for (label, value, default, range) in [
+            ("Low percentile:", &mut tuning.low_percentile, 2.0, 0.0..=50.0),
+            ("High percentile:", &mut tuning.high_percentile, 98.0, 50.0..=100.0),
+            ("Gamma:", &mut tuning.gamma, 1.0, 0.1..=3.0),
+        ] {
+            ui.horizontal(|ui| {
+                let mut enabled = value.is_some();
+                ui.checkbox(&mut enabled, label);
+                if enabled {
+                    ui.add(Slider::new(value.get_or_insert(default), range));
+                } else {
+                    *value = None;
+                }
+            });
+        }
+
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            ui.label("CLAHE tiles (x, y):");
+            ui.add(Slider::new(&mut tuning.clahe_tiles_x, 1..=32));
+            ui.add(Slider::new(&mut tuning.clahe_tiles_y, 1..=32));
+        });
+        ui.horizontal(|ui| {
+            ui.label("CLAHE clip limit:");
+            ui.add(Slider::new(&mut tuning.clahe_clip_limit, 1.0..=10.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("CLAHE bins:");
+            ui.add(Slider::new(&mut tuning.clahe_bins, 16..=4096).logarithmic(true));
+        });
+        ui.horizontal(|ui| {
+            ui.label("dB floor:");
+            ui.add(Slider::new(&mut tuning.db_floor, -80.0..=-10.0));
+        });
+
+        ui.label(
+            RichText::new("Percentiles and gamma replace the selected strategy's own cuts (not used by Fixed). CLAHE settings apply to the CLAHE strategy. Pixels below the dB floor are treated as no-data.")
+                .color(Color32::from_gray(120))
+                .size(11.0)
+        );
+    }
 }
 
 pub struct FooterComponent;
@@ -1,6 +1,7 @@
 use crate::gui::logging::{GuiLogLayer, LogEntry};
 use crate::{AutoscaleStrategy, InputFormat, Polarization, PolarizationOperation};
 use crate::{BitDepth, OutputFormat};
+use crate::core::processing::autoscale::AutoscaleParams;
 use crate::types::SyntheticRgbMode;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
@@ -49,6 +50,7 @@ pub struct SarproGui {
     pub bit_depth: BitDepth,
     pub polarization: Polarization,
     pub autoscale: AutoscaleStrategy,
+    pub autoscale_params: AutoscaleParams,
     pub synrgb_mode: SyntheticRgbMode,
 
     // Reprojection parameters
@@ -97,6 +99,7 @@ impl Default for SarproGui {
             bit_depth: BitDepth::U8,
             polarization: Polarization::Vv,
             autoscale: AutoscaleStrategy::Clahe,
+            autoscale_params: AutoscaleParams::default(),
             synrgb_mode: SyntheticRgbMode::Default,
             target_crs: "EPSG:32630".to_string(),
             resample_alg: "lanczos".to_string(),
@@ -214,6 +217,7 @@ impl SarproGui {
             bit_depth: BitDepth,
             polarization: Polarization,
             autoscale: AutoscaleStrategy,
+            autoscale_params: AutoscaleParams,
             synrgb_mode: SyntheticRgbMode,
             target_crs: String,
             resample_alg: String,
@@ -230,6 +234,7 @@ impl SarproGui {
             bit_depth: self.bit_depth,
             polarization: self.polarization,
             autoscale: self.autoscale,
+            autoscale_params: self.autoscale_params,
             synrgb_mode: self.synrgb_mode,
             target_crs: self.target_crs.clone(),
             resample_alg: self.resample_alg.clone(),
@@ -296,6 +301,8 @@ impl SarproGui {
                 bit_depth: BitDepth,
                 polarization: Polarization,
                 autoscale: AutoscaleStrategy,
+                #[serde(default)]
+                autoscale_params: AutoscaleParams,
                 synrgb_mode: SyntheticRgbMode,
                 target_crs: String,
                 resample_alg: String,
@@ -324,6 +331,7 @@ impl SarproGui {
             self.bit_depth = preset.bit_depth;
             self.polarization = preset.polarization;
             self.autoscale = preset.autoscale;
+            self.autoscale_params = preset.autoscale_params;
             self.synrgb_mode = preset.synrgb_mode;
             self.target_crs = preset.target_crs;
             self.resample_alg = preset.resample_alg;
@@ -343,8 +351,9 @@ impl SarproGui {
     pub fn generate_cli_command(&self) -> String {
         let mut cmd = String::from("cargo run --release --bin sarpro --");
 
-        // Add input/output paths depending on the mode to avoid mixing single and batch flags
+        // Subcommand and input/output paths depending on the mode
         if self.batch_mode {
+            cmd.push_str(" batch");
             if let Some(input_dir) = &self.input_dir_path {
                 cmd.push_str(&format!(" --input-dir {:?}", input_dir));
             }
@@ -352,6 +361,7 @@ impl SarproGui {
                 cmd.push_str(&format!(" --output-dir {:?}", output_dir));
             }
         } else {
+            cmd.push_str(" process");
             if let Some(input_path) = &self.input_path {
                 cmd.push_str(&format!(" --input {:?}", input_path));
             }
@@ -377,6 +387,10 @@ impl SarproGui {
             Polarization::OP(PolarizationOperation::Ratio) => "ratio",
             Polarization::OP(PolarizationOperation::NDiff) => "n-diff",
             Polarization::OP(PolarizationOperation::LogRatio) => "log-ratio",
+            Polarization::OP(PolarizationOperation::Rvi) => "rvi",
+            Polarization::OP(PolarizationOperation::CrossPolDb) => "cross-pol-db",
+            Polarization::OP(PolarizationOperation::DepolRatio) => "depol-ratio",
+            Polarization::OP(PolarizationOperation::Dpsvi) => "dpsvi",
         };
         cmd.push_str(&format!(" --polarization {}", polarization_cli).to_lowercase());
 
@@ -389,11 +403,48 @@ impl SarproGui {
             AutoscaleStrategy::Clahe => "clahe",
             AutoscaleStrategy::Tamed => "tamed",
             AutoscaleStrategy::Default => "default",
+            AutoscaleStrategy::Fixed { min_db, max_db, gamma } => {
+                cmd.push_str(&format!(
+                    " --fixed-range {},{} --fixed-gamma {}",
+                    min_db, max_db, gamma
+                ));
+                ""
+            }
         };
-        cmd.push_str(&format!(" --autoscale {}", autoscale_cli));
+        if !autoscale_cli.is_empty() {
+            cmd.push_str(&format!(" --autoscale {}", autoscale_cli));
+        }
+
+        // Add autoscale tuning only where it differs from the defaults
+        let tuning = &self.autoscale_params;
+        let defaults = AutoscaleParams::default();
+        if let Some(p) = tuning.low_percentile {
+            cmd.push_str(&format!(" --low-percentile {}", p));
+        }
+        if let Some(p) = tuning.high_percentile {
+            cmd.push_str(&format!(" --high-percentile {}", p));
+        }
+        if let Some(g) = tuning.gamma {
+            cmd.push_str(&format!(" --gamma {}", g));
+        }
+        if tuning.clahe_tiles_x != defaults.clahe_tiles_x {
+            cmd.push_str(&format!(" --clahe-tiles-x {}", tuning.clahe_tiles_x));
+        }
+        if tuning.clahe_tiles_y != defaults.clahe_tiles_y {
+            cmd.push_str(&format!(" --clahe-tiles-y {}", tuning.clahe_tiles_y));
+        }
+        if tuning.clahe_clip_limit != defaults.clahe_clip_limit {
+            cmd.push_str(&format!(" --clahe-clip-limit {}", tuning.clahe_clip_limit));
+        }
+        if tuning.clahe_bins != defaults.clahe_bins {
+            cmd.push_str(&format!(" --clahe-bins {}", tuning.clahe_bins));
+        }
+        if tuning.db_floor != defaults.db_floor {
+            cmd.push_str(&format!(" --db-floor {}", tuning.db_floor));
+        }
 
         // Add synthetic RGB mode when applicable
-        if self.output_format == OutputFormat::JPEG && matches!(self.polarization, Polarization::Multiband) {
+        if self.output_format != OutputFormat::TIFF && matches!(self.polarization, Polarization::Multiband) {
             let mode_cli = match self.synrgb_mode {
                 SyntheticRgbMode::Default => "default",
                 SyntheticRgbMode::RgbRatio => "rgb-ratio",
@@ -420,9 +471,6 @@ impl SarproGui {
         cmd.push_str(&format!(" --size {}", size_str));
 
         // Add boolean flags
-        if self.batch_mode {
-            cmd.push_str(" --batch");
-        }
         if self.pad {
             cmd.push_str(" --pad");
         }
@@ -82,7 +82,7 @@ impl SarproGui {
 
     pub fn select_output_file(&mut self) {
         if let Some(path) = rfd::FileDialog::new()
-            .add_filter("Image files", &["tiff", "jpg", "jpeg"])
+            .add_filter("Image files", &["tiff", "jpg", "jpeg", "png", "webp"])
             .save_file()
         {
             // Strip any extension from the user-selected path
@@ -120,6 +120,8 @@ impl SarproGui {
             let extension = match self.output_format {
                 OutputFormat::TIFF => "tiff",
                 OutputFormat::JPEG => "jpg",
+                OutputFormat::PNG => "png",
+                OutputFormat::WebP => "webp",
             };
 
             let new_path = path_without_extension.with_extension(extension);
@@ -162,6 +164,8 @@ impl SarproGui {
 
         // Autoscale strategy now passed as typed enum directly
         trace!("Autoscale strategy: {:?}", autoscale);
+        trace!("Autoscale tuning: {:?}", self.autoscale_params);
+        self.autoscale_params.validate()?;
 
         // Convert polarization enum to string for SafeReader
         let polarization_str = match polarization {
@@ -263,6 +267,16 @@ impl SarproGui {
             }
         };
 
+        let save_options = crate::core::processing::save::SaveOptions {
+            format,
+            bit_depth,
+            target_size,
+            pad,
+            strategy: autoscale,
+            autoscale_params: self.autoscale_params,
+            ..Default::default()
+        };
+
         // Refactored polarization handling to reduce repetition
         match polarization {
             Polarization::Vv | Polarization::Vh | Polarization::Hh | Polarization::Hv => {
@@ -284,13 +298,9 @@ impl SarproGui {
                 crate::core::processing::save::save_processed_image(
                     processed,
                     output.as_path(),
-                    format,
-                    bit_depth,
-                    target_size,
                     Some(reader.metadata()),
-                    pad,
-                    autoscale,
                     crate::types::ProcessingOperation::SingleBand,
+                    &save_options,
                 )
             }
             Polarization::Multiband => {
@@ -323,14 +333,10 @@ impl SarproGui {
                         &vv_processed,
                         &vh_processed,
                         output.as_path(),
-                        format,
-                        bit_depth,
-                        target_size,
                         Some(reader.metadata()),
-                        pad,
-                        autoscale,
                         crate::types::ProcessingOperation::MultibandVvVh,
                         self.synrgb_mode,
+                        &save_options,
                     )
                 } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
                     // Use HH/HV pair
@@ -360,14 +366,10 @@ impl SarproGui {
                         &hh_processed,
                         &hv_processed,
                         output.as_path(),
-                        format,
-                        bit_depth,
-                        target_size,
                         Some(reader.metadata()),
-                        pad,
-                        autoscale,
                         crate::types::ProcessingOperation::MultibandHhHv,
                         self.synrgb_mode,
+                        &save_options,
                     )
                 } else {
                     let available = reader.get_available_polarizations();
@@ -394,6 +396,14 @@ impl SarproGui {
                         Polarization::OP(PolarizationOperation::LogRatio) => {
                             reader.log_ratio_data()?
                         }
+                        Polarization::OP(PolarizationOperation::Rvi) => reader.rvi_data()?,
+                        Polarization::OP(PolarizationOperation::CrossPolDb) => {
+                            reader.cross_pol_db_data()?
+                        }
+                        Polarization::OP(PolarizationOperation::DepolRatio) => {
+                            reader.depol_ratio_data()?
+                        }
+                        Polarization::OP(PolarizationOperation::Dpsvi) => reader.dpsvi_data()?,
                         _ => unreachable!(),
                     }
                 } else if reader.hh_data().is_ok() && reader.hv_data().is_ok() {
@@ -412,6 +422,16 @@ impl SarproGui {
                         Polarization::OP(PolarizationOperation::LogRatio) => {
                             reader.log_ratio_hh_hv_data()?
                         }
+                        Polarization::OP(PolarizationOperation::Rvi) => reader.rvi_hh_hv_data()?,
+                        Polarization::OP(PolarizationOperation::CrossPolDb) => {
+                            reader.cross_pol_db_hh_hv_data()?
+                        }
+                        Polarization::OP(PolarizationOperation::DepolRatio) => {
+                            reader.depol_ratio_hh_hv_data()?
+                        }
+                        Polarization::OP(PolarizationOperation::Dpsvi) => {
+                            reader.dpsvi_hh_hv_data()?
+                        }
                         _ => unreachable!(),
                     }
                 } else {
@@ -433,16 +453,12 @@ impl SarproGui {
                 crate::core::processing::save::save_processed_image(
                     &processed,
                     output.as_path(),
-                    format,
-                    bit_depth,
-                    target_size,
                     Some(reader.metadata()),
-                    pad,
-                    autoscale,
                     crate::types::ProcessingOperation::PolarOp(match polarization {
                         Polarization::OP(op) => op,
                         _ => unreachable!(),
                     }),
+                    &save_options,
                 )
             }
         }
@@ -473,6 +489,7 @@ impl SarproGui {
         let bit_depth = self.bit_depth;
         let polarization = self.polarization;
         let autoscale = self.autoscale; // Pass the actual autoscale strategy
+        let autoscale_params = self.autoscale_params;
         let size_mode = self.size_mode; // <-- FIX: clone actual size_mode
         let custom_size = self.custom_size.clone(); // <-- FIX: clone actual custom_size
         let batch_mode = self.batch_mode;
@@ -510,6 +527,7 @@ impl SarproGui {
                 bit_depth,
                 polarization,
                 autoscale, // Use the actual autoscale strategy from GUI
+                autoscale_params,
                 target_crs,
                 resample_alg,
                 size_mode,   // <-- FIX: use actual size_mode
@@ -602,6 +620,8 @@ impl SarproGui {
                                             match self.output_format {
                                                 OutputFormat::TIFF => "tiff",
                                                 OutputFormat::JPEG => "jpg",
+                                                OutputFormat::PNG => "png",
+                                                OutputFormat::WebP => "webp",
                                             }
                                         );
                                         let output_path = output_dir.join(&output_name);
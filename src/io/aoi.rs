@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::Path;
+
+use crate::io::sentinel1::SafeError;
+
+/// Area of interest in WGS84 lon/lat
+///
+/// Either a plain bounding box or one or more polygons (each a list of rings,
+/// exterior first, holes after) as loaded from GeoJSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Aoi {
+    BBox {
+        min_lon: f64,
+        min_lat: f64,
+        max_lon: f64,
+        max_lat: f64,
+    },
+    Polygon {
+        polygons: Vec<Vec<Vec<[f64; 2]>>>,
+    },
+}
+
+impl Aoi {
+    /// Parse `min_lon,min_lat,max_lon,max_lat`
+    pub fn from_bbox_str(s: &str) -> Result<Self, SafeError> {
+        let parts: Vec<f64> = s
+            .split(',')
+            .map(|p| p.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| SafeError::Parse(format!("Invalid AOI bbox '{}': expected min_lon,min_lat,max_lon,max_lat", s)))?;
+        if parts.len() != 4 {
+            return Err(SafeError::Parse(format!(
+                "Invalid AOI bbox '{}': expected 4 values, got {}",
+                s,
+                parts.len()
+            )));
+        }
+        let (min_lon, min_lat, max_lon, max_lat) = (parts[0], parts[1], parts[2], parts[3]);
+        if !(min_lon < max_lon && min_lat < max_lat) {
+            return Err(SafeError::Parse(format!(
+                "Invalid AOI bbox '{}': min must be smaller than max",
+                s
+            )));
+        }
+        if min_lon < -180.0 || max_lon > 180.0 || min_lat < -90.0 || max_lat > 90.0 {
+            return Err(SafeError::Parse(format!(
+                "Invalid AOI bbox '{}': outside lon/lat range",
+                s
+            )));
+        }
+        Ok(Aoi::BBox { min_lon, min_lat, max_lon, max_lat })
+    }
+
+    /// Load polygons from a GeoJSON file (Polygon, MultiPolygon, Feature or FeatureCollection)
+    pub fn from_geojson_file<P: AsRef<Path>>(path: P) -> Result<Self, SafeError> {
+        let text = std::fs::read_to_string(path.as_ref())?;
+        Self::from_geojson_str(&text)
+    }
+
+    /// Parse polygons from GeoJSON text
+    pub fn from_geojson_str(text: &str) -> Result<Self, SafeError> {
+        let v: Value = serde_json::from_str(text)
+            .map_err(|e| SafeError::Parse(format!("Invalid AOI GeoJSON: {}", e)))?;
+        let mut polygons = Vec::new();
+        collect_polygons(&v, &mut polygons)?;
+        if polygons.is_empty() {
+            return Err(SafeError::Parse(
+                "AOI GeoJSON contains no Polygon or MultiPolygon geometry".to_string(),
+            ));
+        }
+        Ok(Aoi::Polygon { polygons })
+    }
+
+    /// Bounding box as [min_lon, min_lat, max_lon, max_lat]
+    pub fn bounds(&self) -> [f64; 4] {
+        match self {
+            Aoi::BBox { min_lon, min_lat, max_lon, max_lat } => {
+                [*min_lon, *min_lat, *max_lon, *max_lat]
+            }
+            Aoi::Polygon { polygons } => {
+                let mut b = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+                for ring in polygons.iter().flatten() {
+                    for p in ring {
+                        b[0] = b[0].min(p[0]);
+                        b[1] = b[1].min(p[1]);
+                        b[2] = b[2].max(p[0]);
+                        b[3] = b[3].max(p[1]);
+                    }
+                }
+                b
+            }
+        }
+    }
+
+    /// True if (lon, lat) is inside the bbox, or inside any polygon (even-odd rule, so holes are excluded)
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        match self {
+            Aoi::BBox { min_lon, min_lat, max_lon, max_lat } => {
+                lon >= *min_lon && lon <= *max_lon && lat >= *min_lat && lat <= *max_lat
+            }
+            Aoi::Polygon { polygons } => polygons.iter().any(|rings| {
+                let mut inside = false;
+                for ring in rings {
+                    if ring_crosses(ring, lon, lat) {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }),
+        }
+    }
+
+    /// GeoJSON FeatureCollection of the AOI (used as a gdalwarp cutline)
+    pub fn to_geojson(&self) -> Value {
+        let geometry = match self {
+            Aoi::BBox { min_lon, min_lat, max_lon, max_lat } => json!({
+                "type": "Polygon",
+                "coordinates": [[
+                    [min_lon, min_lat],
+                    [max_lon, min_lat],
+                    [max_lon, max_lat],
+                    [min_lon, max_lat],
+                    [min_lon, min_lat]
+                ]]
+            }),
+            Aoi::Polygon { polygons } => json!({
+                "type": "MultiPolygon",
+                "coordinates": polygons
+            }),
+        };
+        json!({
+            "type": "FeatureCollection",
+            "features": [{ "type": "Feature", "properties": {}, "geometry": geometry }]
+        })
+    }
+
+    /// Short human-readable label for metadata
+    pub fn label(&self) -> String {
+        let b = self.bounds();
+        match self {
+            Aoi::BBox { .. } => format!("BBOX({}, {}, {}, {})", b[0], b[1], b[2], b[3]),
+            Aoi::Polygon { polygons } => format!(
+                "POLYGON(n={}, bbox={}, {}, {}, {})",
+                polygons.len(),
+                b[0],
+                b[1],
+                b[2],
+                b[3]
+            ),
+        }
+    }
+}
+
+// Ray casting: does a horizontal ray from (x, y) cross this ring an odd number of times
+fn ring_crosses(ring: &[[f64; 2]], x: f64, y: f64) -> bool {
+    let n = ring.len();
+    if n < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = n - 1;
+    for i in 0..n {
+        let (xi, yi) = (ring[i][0], ring[i][1]);
+        let (xj, yj) = (ring[j][0], ring[j][1]);
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn parse_ring(v: &Value) -> Result<Vec<[f64; 2]>, SafeError> {
+    let pts = v
+        .as_array()
+        .ok_or_else(|| SafeError::Parse("AOI GeoJSON: ring is not an array".to_string()))?;
+    pts.iter()
+        .map(|p| {
+            let arr = p.as_array();
+            match arr.and_then(|a| Some([a.first()?.as_f64()?, a.get(1)?.as_f64()?])) {
+                Some(xy) => Ok(xy),
+                None => Err(SafeError::Parse(
+                    "AOI GeoJSON: position must be [lon, lat]".to_string(),
+                )),
+            }
+        })
+        .collect()
+}
+
+fn parse_polygon(v: &Value) -> Result<Vec<Vec<[f64; 2]>>, SafeError> {
+    v.as_array()
+        .ok_or_else(|| SafeError::Parse("AOI GeoJSON: polygon is not an array".to_string()))?
+        .iter()
+        .map(parse_ring)
+        .collect()
+}
+
+fn collect_polygons(v: &Value, out: &mut Vec<Vec<Vec<[f64; 2]>>>) -> Result<(), SafeError> {
+    match v.get("type").and_then(|t| t.as_str()) {
+        Some("FeatureCollection") => {
+            if let Some(features) = v.get("features").and_then(|f| f.as_array()) {
+                for f in features {
+                    collect_polygons(f, out)?;
+                }
+            }
+        }
+        Some("Feature") => {
+            if let Some(g) = v.get("geometry") {
+                collect_polygons(g, out)?;
+            }
+        }
+        Some("GeometryCollection") => {
+            if let Some(geoms) = v.get("geometries").and_then(|g| g.as_array()) {
+                for g in geoms {
+                    collect_polygons(g, out)?;
+                }
+            }
+        }
+        Some("Polygon") => {
+            if let Some(c) = v.get("coordinates") {
+                out.push(parse_polygon(c)?);
+            }
+        }
+        Some("MultiPolygon") => {
+            if let Some(polys) = v.get("coordinates").and_then(|c| c.as_array()) {
+                for p in polys {
+                    out.push(parse_polygon(p)?);
+                }
+            }
+        }
+        // Points/lines cannot bound an area; ignore them
+        _ => {}
+    }
+    Ok(())
+}
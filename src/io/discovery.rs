@@ -0,0 +1,463 @@
+//! Batch input discovery: SAFE directories and zipped products under an archive directory
+//! (optionally recursive, e.g. year/month/day trees) or from a list file, narrowed by glob
+//! patterns and by the fields encoded in Sentinel-1 product names.
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use glob::{MatchOptions, Pattern};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+use tracing::{debug, warn};
+use zip::ZipArchive;
+
+use crate::io::sentinel1::SafeError;
+
+/// Fields of a Sentinel-1 product name such as
+/// `S1A_IW_GRDH_1SDV_20230101T053015_20230101T053040_046574_059508_ABCD.SAFE`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductName {
+    /// `S1A`, `S1B`, `S1C`, ...
+    pub platform: String,
+    /// `IW`, `EW`, `WV`, or the stripmap beam `S1`..`S6`
+    pub mode: String,
+    /// Product type and resolution class, e.g. `GRDH` or `SLC_`
+    pub product_type: String,
+    /// Polarization code: `SV`, `SH`, `DV`, `DH` or a single channel such as `VV`
+    pub polarization: String,
+    pub start: NaiveDateTime,
+    pub stop: NaiveDateTime,
+    pub absolute_orbit: u64,
+    pub data_take: String,
+}
+
+impl ProductName {
+    /// Parse a product name with or without the `.SAFE`/`.zip` suffixes. The name layout has
+    /// fixed field widths, which also covers `SLC_`-style types padded with an underscore.
+    pub fn parse(name: &str) -> Option<Self> {
+        let field = |from: usize, to: usize| name.get(from..to);
+        let time = |from: usize| {
+            NaiveDateTime::parse_from_str(field(from, from + 15)?, "%Y%m%dT%H%M%S").ok()
+        };
+        if !name.starts_with("S1") || name.len() < 67 {
+            return None;
+        }
+        Some(Self {
+            platform: field(0, 3)?.to_string(),
+            mode: field(4, 6)?.to_string(),
+            product_type: field(7, 11)?.to_string(),
+            polarization: field(14, 16)?.to_string(),
+            start: time(17)?,
+            stop: time(33)?,
+            absolute_orbit: field(49, 55)?.parse().ok()?,
+            data_take: field(56, 62)?.to_string(),
+        })
+    }
+
+    /// Polarizations the product carries according to its polarization code
+    pub fn polarizations(&self) -> Vec<&'static str> {
+        match self.polarization.as_str() {
+            "SV" | "VV" => vec!["VV"],
+            "SH" | "HH" => vec!["HH"],
+            "DV" => vec!["VV", "VH"],
+            "DH" => vec!["HH", "HV"],
+            "VH" => vec!["VH"],
+            "HV" => vec!["HV"],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Filters on product-name fields (and the manifest pass direction). Empty lists and `None`
+/// accept everything; list entries are alternatives, except `polarizations`, which must all
+/// be present.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductFilter {
+    /// e.g. `["S1A", "S1C"]`
+    pub platforms: Vec<String>,
+    /// e.g. `["IW"]`; `SM` matches the stripmap beams `S1`..`S6`
+    pub modes: Vec<String>,
+    /// Polarizations every product must have, e.g. `["VV", "VH"]`
+    pub polarizations: Vec<String>,
+    /// First acquisition date kept (by sensing start, inclusive)
+    pub start_date: Option<NaiveDate>,
+    /// Last acquisition date kept (inclusive)
+    pub end_date: Option<NaiveDate>,
+    /// Inclusive absolute-orbit ranges
+    pub orbits: Vec<(u64, u64)>,
+    /// `ASCENDING` or `DESCENDING`; read from `manifest.safe` since names do not carry it
+    pub pass_direction: Option<String>,
+}
+
+impl ProductFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Parse an absolute-orbit filter: `46574` or the inclusive range `46500-46600`
+    pub fn parse_orbit_range(s: &str) -> Result<(u64, u64), SafeError> {
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<u64>()
+                .map_err(|_| SafeError::Parse(format!("invalid orbit '{}'", s)))
+        };
+        match s.split_once('-') {
+            Some((lo, hi)) => {
+                let (lo, hi) = (parse(lo)?, parse(hi)?);
+                if lo > hi {
+                    return Err(SafeError::Parse(format!("empty orbit range '{}'", s)));
+                }
+                Ok((lo, hi))
+            }
+            None => parse(s).map(|orbit| (orbit, orbit)),
+        }
+    }
+
+    /// True when the product at `path` passes every filter. Products whose name cannot be
+    /// parsed only pass an empty filter.
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let Some(name) = ProductName::parse(&product_stem(path)) else {
+            debug!("Filtered out {:?}: not a Sentinel-1 product name", path);
+            return false;
+        };
+        let any_of = |list: &[String], value: &str| {
+            list.is_empty() || list.iter().any(|v| v.eq_ignore_ascii_case(value))
+        };
+        let stripmap = name.mode.starts_with('S') && name.mode[1..].parse::<u8>().is_ok();
+        let mode_ok = any_of(&self.modes, &name.mode)
+            || (stripmap && self.modes.iter().any(|m| m.eq_ignore_ascii_case("SM")));
+        let pols = name.polarizations();
+        let date = name.start.date();
+        let keep = any_of(&self.platforms, &name.platform)
+            && mode_ok
+            && self
+                .polarizations
+                .iter()
+                .all(|p| pols.iter().any(|have| have.eq_ignore_ascii_case(p)))
+            && self.start_date.is_none_or(|d| date >= d)
+            && self.end_date.is_none_or(|d| date <= d)
+            && (self.orbits.is_empty()
+                || self
+                    .orbits
+                    .iter()
+                    .any(|&(lo, hi)| (lo..=hi).contains(&name.absolute_orbit)));
+        if !keep {
+            return false;
+        }
+        match &self.pass_direction {
+            None => true,
+            Some(wanted) => match read_pass_direction(path) {
+                Ok(Some(pass)) => pass.eq_ignore_ascii_case(wanted),
+                Ok(None) => false,
+                Err(e) => {
+                    warn!("Filtered out {:?}: cannot read pass direction: {}", path, e);
+                    false
+                }
+            },
+        }
+    }
+}
+
+/// Where and how to look for products
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryOptions {
+    /// Descend into subdirectories; a directory holding `manifest.safe` (or named `*.SAFE`)
+    /// is a product and is not searched further. Without it every immediate subdirectory is
+    /// a candidate, as in [`crate::api::iterate_safe_products`].
+    pub recursive: bool,
+    /// Glob patterns, of which a product must match at least one: against its file name, or
+    /// against its path relative to the input directory when the pattern contains `/`
+    pub patterns: Vec<String>,
+    pub filter: ProductFilter,
+}
+
+impl DiscoveryOptions {
+    /// Keep the `products` that match the patterns and the filter; `base` is the directory
+    /// relative patterns are matched from
+    pub fn select(&self, products: Vec<PathBuf>, base: &Path) -> Result<Vec<PathBuf>, SafeError> {
+        let patterns: Vec<Pattern> = self
+            .patterns
+            .iter()
+            .map(|p| {
+                Pattern::new(p).map_err(|e| SafeError::Parse(format!("pattern '{}': {}", p, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let matches_pattern = |path: &Path| {
+            let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            let relative = path.strip_prefix(base).unwrap_or(path);
+            patterns.iter().any(|p| {
+                if p.as_str().contains('/') {
+                    p.matches_path_with(relative, options)
+                } else {
+                    p.matches_with(&name, options)
+                }
+            })
+        };
+        Ok(products
+            .into_iter()
+            .filter(|p| patterns.is_empty() || matches_pattern(p))
+            .filter(|p| self.filter.matches(p))
+            .collect())
+    }
+}
+
+/// Products under `input_dir`, sorted by path
+pub fn discover_products(
+    input_dir: &Path,
+    options: &DiscoveryOptions,
+) -> Result<Vec<PathBuf>, SafeError> {
+    let mut found = Vec::new();
+    walk(input_dir, options.recursive, &mut HashSet::new(), &mut found)?;
+    found.sort();
+    options.select(found, input_dir)
+}
+
+/// Products listed in a text file, one path per line; blank lines and `#` comments are
+/// skipped and relative paths are taken from the current directory
+pub fn read_product_list(list: &Path) -> Result<Vec<PathBuf>, SafeError> {
+    Ok(fs::read_to_string(list)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Collect the products under `dir`. `visited` holds the canonical directories already
+/// searched, so symlink loops and trees linked in twice are searched once.
+fn walk(
+    dir: &Path,
+    recursive: bool,
+    visited: &mut HashSet<PathBuf>,
+    found: &mut Vec<PathBuf>,
+) -> Result<(), SafeError> {
+    if !visited.insert(fs::canonicalize(dir)?) {
+        debug!("Skipping {:?}: already searched", dir);
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let is_product = path.join("manifest.safe").is_file()
+                || path.extension().is_some_and(|e| e.eq_ignore_ascii_case("safe"));
+            if recursive && !is_product {
+                walk(&path, true, visited, found)?;
+            } else {
+                found.push(path);
+            }
+        } else if is_safe_zip(&path) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// True for a `.zip` file, the form products are distributed in
+pub fn is_safe_zip(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+/// File name without the `.zip` and `.SAFE` suffixes
+fn product_stem(path: &Path) -> String {
+    let mut name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    for suffix in [".zip", ".safe"] {
+        if name.to_ascii_lowercase().ends_with(suffix) {
+            name.truncate(name.len() - suffix.len());
+        }
+    }
+    name
+}
+
+/// Pass direction (`ASCENDING`/`DESCENDING`) from the manifest of a SAFE directory or zip
+pub fn read_pass_direction(path: &Path) -> Result<Option<String>, SafeError> {
+    let manifest = if is_safe_zip(path) {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let name = archive
+            .file_names()
+            .find(|n| n.ends_with("manifest.safe") && n.matches('/').count() <= 1)
+            .map(str::to_string)
+            .ok_or(SafeError::MissingField("manifest.safe"))?;
+        let mut text = String::new();
+        archive.by_name(&name)?.read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(path.join("manifest.safe"))?
+    };
+
+    let mut reader = Reader::from_str(&manifest);
+    reader.trim_text(true);
+    let mut in_pass = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => in_pass = e.local_name().as_ref() == b"pass",
+            Event::Text(e) if in_pass => return Ok(Some(e.unescape()?.trim().to_uppercase())),
+            Event::End(_) => in_pass = false,
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// A zipped product unpacked into a temporary directory that is removed on drop
+pub struct UnpackedSafe {
+    /// The `.SAFE` directory inside the temporary directory
+    pub path: PathBuf,
+    _dir: TempDir,
+}
+
+/// Unpack a zipped product so it can be read like a SAFE directory
+pub fn unpack_safe_zip(zip: &Path) -> Result<UnpackedSafe, SafeError> {
+    let dir = tempfile::tempdir()?;
+    ZipArchive::new(File::open(zip)?)?.extract(dir.path())?;
+    let path = if dir.path().join("manifest.safe").is_file() {
+        dir.path().to_path_buf()
+    } else {
+        fs::read_dir(dir.path())?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|p| p.join("manifest.safe").is_file())
+            .ok_or(SafeError::MissingField("manifest.safe"))?
+    };
+    debug!("Unpacked {:?} to {:?}", zip, path);
+    Ok(UnpackedSafe { path, _dir: dir })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRD: &str = "S1A_IW_GRDH_1SDV_20230101T053015_20230101T053040_046574_059508_ABCD";
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn product(name: &str) -> PathBuf {
+        PathBuf::from(format!("/archive/{}.SAFE", name))
+    }
+
+    #[test]
+    fn product_names_are_split_at_fixed_offsets() {
+        let grd = ProductName::parse(GRD).unwrap();
+        assert_eq!(grd.platform, "S1A");
+        assert_eq!(grd.mode, "IW");
+        assert_eq!(grd.product_type, "GRDH");
+        assert_eq!(grd.polarization, "DV");
+        assert_eq!(grd.polarizations(), vec!["VV", "VH"]);
+        assert_eq!(grd.start.to_string(), "2023-01-01 05:30:15");
+        assert_eq!(grd.stop.to_string(), "2023-01-01 05:30:40");
+        assert_eq!(grd.absolute_orbit, 46574);
+        assert_eq!(grd.data_take, "059508");
+        assert_eq!(ProductName::parse(&format!("{}.SAFE", GRD)), Some(grd));
+
+        // `SLC_` keeps its padding underscore and the fields after it stay in place
+        let slc = ProductName::parse(
+            "S1B_EW_SLC__1SSH_20210615T120000_20210615T120030_027401_0345AB_1F2E",
+        )
+        .unwrap();
+        assert_eq!((slc.mode.as_str(), slc.product_type.as_str()), ("EW", "SLC_"));
+        assert_eq!(slc.polarization, "SH");
+        assert_eq!(slc.polarizations(), vec!["HH"]);
+        assert_eq!(slc.absolute_orbit, 27401);
+        assert_eq!(slc.data_take, "0345AB");
+
+        for beam in 1..=6 {
+            let name = format!(
+                "S1C_S{}_GRDF_1SDH_20250301T000000_20250301T000020_001234_00ABCD_0000",
+                beam
+            );
+            let stripmap = ProductName::parse(&name).unwrap();
+            assert_eq!(stripmap.mode, format!("S{}", beam));
+            assert_eq!(stripmap.polarizations(), vec!["HH", "HV"]);
+        }
+
+        assert_eq!(ProductName::parse(&GRD[..60]), None);
+        assert_eq!(ProductName::parse(&GRD.replace("S1A", "S2A")), None);
+        assert_eq!(ProductName::parse(&GRD.replace("T053015", "T053099")), None);
+    }
+
+    #[test]
+    fn orbit_ranges_are_inclusive() {
+        assert_eq!(ProductFilter::parse_orbit_range("46574").unwrap(), (46574, 46574));
+        assert_eq!(ProductFilter::parse_orbit_range(" 46500 - 46600 ").unwrap(), (46500, 46600));
+        assert!(ProductFilter::parse_orbit_range("46600-46500").is_err());
+        assert!(ProductFilter::parse_orbit_range("46500-").is_err());
+        assert!(ProductFilter::parse_orbit_range("abc").is_err());
+
+        let filter = |orbits: Vec<(u64, u64)>| ProductFilter {
+            orbits,
+            ..ProductFilter::default()
+        };
+        assert!(filter(vec![(46574, 46574)]).matches(&product(GRD)));
+        assert!(filter(vec![(46500, 46574)]).matches(&product(GRD)));
+        assert!(filter(vec![(1, 2), (46574, 46600)]).matches(&product(GRD)));
+        assert!(!filter(vec![(46575, 46600)]).matches(&product(GRD)));
+    }
+
+    #[test]
+    fn date_bounds_are_inclusive() {
+        let filter = |start: Option<&str>, end: Option<&str>| ProductFilter {
+            start_date: start.map(date),
+            end_date: end.map(date),
+            ..ProductFilter::default()
+        };
+        assert!(filter(Some("2023-01-01"), Some("2023-01-01")).matches(&product(GRD)));
+        assert!(filter(Some("2022-12-31"), None).matches(&product(GRD)));
+        assert!(filter(None, Some("2023-01-01")).matches(&product(GRD)));
+        assert!(!filter(Some("2023-01-02"), None).matches(&product(GRD)));
+        assert!(!filter(None, Some("2022-12-31")).matches(&product(GRD)));
+    }
+
+    #[test]
+    fn filters_check_every_name_field() {
+        let filter = ProductFilter {
+            platforms: vec!["s1a".to_string(), "S1C".to_string()],
+            modes: vec!["SM".to_string()],
+            polarizations: vec!["HH".to_string()],
+            ..ProductFilter::default()
+        };
+        let stripmap = "S1C_S3_GRDH_1SDH_20250301T000000_20250301T000020_001234_00ABCD_0000";
+        assert!(filter.matches(&product(stripmap)));
+        assert!(filter.matches(&PathBuf::from(format!("{}.zip", stripmap))));
+        assert!(!filter.matches(&product(GRD)));
+        assert!(!filter.matches(&product(&stripmap.replace("S1C", "S1B"))));
+        assert!(!filter.matches(&product(&stripmap.replace("1SDH", "1SSV"))));
+        assert!(!filter.matches(&product("not_a_product")));
+        assert!(ProductFilter::default().matches(&product("not_a_product")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loops_are_walked_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let day = dir.path().join("2023/01/01");
+        fs::create_dir_all(day.join(format!("{}.SAFE", GRD))).unwrap();
+        File::create(day.join("S1A_other.zip")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), day.join("loop")).unwrap();
+        std::os::unix::fs::symlink(&day, dir.path().join("latest")).unwrap();
+
+        let options = DiscoveryOptions {
+            recursive: true,
+            ..DiscoveryOptions::default()
+        };
+        let found = discover_products(dir.path(), &options).unwrap();
+        assert_eq!(found.len(), 2, "{:?}", found);
+        assert!(found.iter().any(|p| p.ends_with(format!("{}.SAFE", GRD))));
+        assert!(found.iter().any(|p| p.ends_with("S1A_other.zip")));
+    }
+}
@@ -0,0 +1,240 @@
+//! Data footprints in WGS84 lon/lat: the outline of the valid pixels (or of the GCP grid),
+//! split at the antimeridian, with GeoJSON/WKT encodings and AOI intersection tests.
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::io::aoi::Aoi;
+
+/// Footprint polygons as closed exterior rings of `[lon, lat]`. A footprint that crosses the
+/// antimeridian is split into one polygon on each side of it (RFC 7946, section 3.1.9).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Footprint {
+    pub polygons: Vec<Vec<[f64; 2]>>,
+}
+
+impl Footprint {
+    /// Footprint of one outline given as consecutive `[lon, lat]` vertices (closed or not).
+    /// Longitudes are unwrapped along the ring, so an outline that jumps from +179° to -179°
+    /// is recognised as crossing the antimeridian and split there.
+    pub fn from_ring(ring: &[[f64; 2]]) -> Option<Self> {
+        let mut unwrapped: Vec<[f64; 2]> = Vec::with_capacity(ring.len() + 1);
+        for &[lon, lat] in ring {
+            if !lon.is_finite() || !lat.is_finite() {
+                continue;
+            }
+            let lon = match unwrapped.last() {
+                Some(prev) => lon + 360.0 * ((prev[0] - lon) / 360.0).round(),
+                None => lon,
+            };
+            unwrapped.push([lon, lat]);
+        }
+        if unwrapped.len() < 3 {
+            return None;
+        }
+
+        let (min_lon, max_lon) = unwrapped
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p[0]), hi.max(p[0])));
+        let polygons = if max_lon > 180.0 {
+            vec![
+                clip_at(&unwrapped, 180.0, true),
+                shift(clip_at(&unwrapped, 180.0, false), -360.0),
+            ]
+        } else if min_lon < -180.0 {
+            vec![
+                clip_at(&unwrapped, -180.0, false),
+                shift(clip_at(&unwrapped, -180.0, true), 360.0),
+            ]
+        } else {
+            vec![unwrapped]
+        };
+        let polygons: Vec<Vec<[f64; 2]>> = polygons
+            .into_iter()
+            .filter(|ring| ring.len() >= 3)
+            .map(close_ring)
+            .collect();
+        (!polygons.is_empty()).then_some(Self { polygons })
+    }
+
+    /// True when the footprint was split at the antimeridian
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.polygons.len() > 1
+    }
+
+    /// `[west, south, east, north]`. For a footprint split at the antimeridian `west` is
+    /// larger than `east`, as in GeoJSON bounding boxes.
+    pub fn bounds(&self) -> [f64; 4] {
+        let mut b = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+        for p in self.polygons.iter().flatten() {
+            b[1] = b[1].min(p[1]);
+            b[3] = b[3].max(p[1]);
+        }
+        if self.crosses_antimeridian() {
+            // West: smallest longitude of the eastern-hemisphere part; east: largest of the
+            // western part
+            for p in self.polygons.iter().flatten() {
+                if p[0] > 0.0 {
+                    b[0] = b[0].min(p[0]);
+                } else {
+                    b[2] = b[2].max(p[0]);
+                }
+            }
+        } else {
+            for p in self.polygons.iter().flatten() {
+                b[0] = b[0].min(p[0]);
+                b[2] = b[2].max(p[0]);
+            }
+        }
+        b
+    }
+
+    /// True if (lon, lat) lies inside one of the polygons
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        self.polygons.iter().any(|ring| point_in_ring(ring, lon, lat))
+    }
+
+    /// True when the footprint and `aoi` overlap: a vertex of one lies inside the other or
+    /// their edges cross
+    pub fn intersects_aoi(&self, aoi: &Aoi) -> bool {
+        let aoi_rings: Vec<Vec<[f64; 2]>> = match aoi {
+            Aoi::BBox {
+                min_lon,
+                min_lat,
+                max_lon,
+                max_lat,
+            } => vec![vec![
+                [*min_lon, *min_lat],
+                [*max_lon, *min_lat],
+                [*max_lon, *max_lat],
+                [*min_lon, *max_lat],
+                [*min_lon, *min_lat],
+            ]],
+            Aoi::Polygon { polygons } => polygons.iter().flatten().cloned().collect(),
+        };
+        if self.polygons.iter().flatten().any(|p| aoi.contains(p[0], p[1])) {
+            return true;
+        }
+        if aoi_rings.iter().flatten().any(|p| self.contains(p[0], p[1])) {
+            return true;
+        }
+        self.polygons.iter().any(|ring| {
+            aoi_rings.iter().any(|other| {
+                ring.windows(2).any(|a| {
+                    other
+                        .windows(2)
+                        .any(|b| segments_cross(a[0], a[1], b[0], b[1]))
+                })
+            })
+        })
+    }
+
+    /// GeoJSON geometry: a Polygon, or a MultiPolygon when split at the antimeridian
+    pub fn to_geojson(&self) -> Value {
+        if let [ring] = self.polygons.as_slice() {
+            json!({ "type": "Polygon", "coordinates": [ring] })
+        } else {
+            let coords: Vec<Vec<&Vec<[f64; 2]>>> = self.polygons.iter().map(|r| vec![r]).collect();
+            json!({ "type": "MultiPolygon", "coordinates": coords })
+        }
+    }
+
+    /// WKT `POLYGON` or `MULTIPOLYGON` in lon/lat order
+    pub fn to_wkt(&self) -> String {
+        let ring = |r: &Vec<[f64; 2]>| {
+            let coords: Vec<String> = r.iter().map(|p| format!("{} {}", p[0], p[1])).collect();
+            format!("({})", coords.join(", "))
+        };
+        if let [r] = self.polygons.as_slice() {
+            format!("POLYGON ({})", ring(r))
+        } else {
+            let parts: Vec<String> =
+                self.polygons.iter().map(|r| format!("({})", ring(r))).collect();
+            format!("MULTIPOLYGON ({})", parts.join(", "))
+        }
+    }
+}
+
+/// Outline of the valid pixels of a `cols` x `rows` raster in pixel coordinates (col, row),
+/// traced from the first and last valid pixel of every `step`-th row. This follows the data
+/// edge exactly for shapes with one valid span per row, such as Sentinel-1 swaths in radar
+/// geometry or warped onto a map grid. None when no pixel is valid.
+pub fn valid_outline(
+    cols: usize,
+    rows: usize,
+    step: usize,
+    is_valid: impl Fn(usize, usize) -> bool,
+) -> Option<Vec<[f64; 2]>> {
+    let step = step.max(1);
+    let span = |row: usize| {
+        let first = (0..cols).find(|&c| is_valid(row, c))?;
+        let last = (first..cols).rev().find(|&c| is_valid(row, c))?;
+        Some((first, last))
+    };
+    let mut sampled: Vec<usize> = (0..rows).step_by(step).collect();
+    if rows > 0 && sampled.last() != Some(&(rows - 1)) {
+        sampled.push(rows - 1);
+    }
+    let spans: Vec<(usize, usize, usize)> = sampled
+        .into_iter()
+        .filter_map(|row| span(row).map(|(first, last)| (row, first, last)))
+        .collect();
+    let (top, bottom) = (spans.first()?, spans.last()?);
+
+    // Left edge downwards, right edge upwards, on pixel edges
+    let mut ring = vec![[top.1 as f64, top.0 as f64]];
+    ring.extend(spans.iter().map(|&(row, first, _)| [first as f64, row as f64 + 0.5]));
+    ring.push([bottom.1 as f64, bottom.0 as f64 + 1.0]);
+    ring.push([bottom.2 as f64 + 1.0, bottom.0 as f64 + 1.0]);
+    ring.extend(spans.iter().rev().map(|&(row, _, last)| [last as f64 + 1.0, row as f64 + 0.5]));
+    ring.push([top.2 as f64 + 1.0, top.0 as f64]);
+    Some(ring)
+}
+
+/// Part of `ring` on one side of the meridian `lon` (west side when `west` is set)
+fn clip_at(ring: &[[f64; 2]], lon: f64, west: bool) -> Vec<[f64; 2]> {
+    let inside = |p: &[f64; 2]| if west { p[0] <= lon } else { p[0] >= lon };
+    let mut out = Vec::new();
+    for (i, cur) in ring.iter().enumerate() {
+        let prev = &ring[(i + ring.len() - 1) % ring.len()];
+        if inside(cur) != inside(prev) {
+            let t = (lon - prev[0]) / (cur[0] - prev[0]);
+            out.push([lon, prev[1] + t * (cur[1] - prev[1])]);
+        }
+        if inside(cur) {
+            out.push(*cur);
+        }
+    }
+    out
+}
+
+fn shift(ring: Vec<[f64; 2]>, dlon: f64) -> Vec<[f64; 2]> {
+    ring.into_iter().map(|[lon, lat]| [lon + dlon, lat]).collect()
+}
+
+fn close_ring(mut ring: Vec<[f64; 2]>) -> Vec<[f64; 2]> {
+    if ring.first() != ring.last() {
+        ring.push(ring[0]);
+    }
+    ring
+}
+
+// Even-odd ray casting
+fn point_in_ring(ring: &[[f64; 2]], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let ([x1, y1], [x2, y2]) = (w[0], w[1]);
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn segments_cross(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> bool {
+    let orient = |p: [f64; 2], q: [f64; 2], r: [f64; 2]| {
+        (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0])
+    };
+    let (d1, d2) = (orient(c, d, a), orient(c, d, b));
+    let (d3, d4) = (orient(a, b, c), orient(a, b, d));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
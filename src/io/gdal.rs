@@ -176,6 +176,44 @@ impl GdalSarReader {
         Ok(array)
     }
 
+    /// Read a pixel window `(x_off, y_off)` + `(width, height)` of a band, resampled to
+    /// `(out_cols, out_rows)`. Only the window is fetched from disk.
+    pub fn read_window(
+        &self,
+        index: usize,
+        offset: (usize, usize),
+        window: (usize, usize),
+        out_cols: usize,
+        out_rows: usize,
+        e_resample_alg: Option<ResampleAlg>,
+    ) -> Result<Array2<f32>, GdalError> {
+        if index == 0 || index > self.metadata.bands {
+            return Err(GdalError::UnsupportedFormat(format!(
+                "Band index {} out of range",
+                index
+            )));
+        }
+        if offset.0 + window.0 > self.metadata.size_x || offset.1 + window.1 > self.metadata.size_y
+        {
+            return Err(GdalError::UnsupportedFormat(format!(
+                "Window {:?}+{:?} exceeds raster {}x{}",
+                offset, window, self.metadata.size_x, self.metadata.size_y
+            )));
+        }
+        let band = self.dataset.rasterband(index)?;
+        let buf = band.read_as::<f32>(
+            (offset.0 as isize, offset.1 as isize),
+            window,
+            (out_cols, out_rows),
+            e_resample_alg,
+        )?;
+        let data_vec = buf.data().to_vec();
+        let array = Array2::from_shape_vec((out_rows, out_cols), data_vec).map_err(|_| {
+            GdalError::DimensionMismatch(window.0, window.1, out_cols, out_rows)
+        })?;
+        Ok(array)
+    }
+
     /// Read all bands into a vector of f64 ndarrays
     pub fn _read_all_bands(&self) -> Result<Vec<Array2<f32>>, GdalError> {
         let mut result = Vec::with_capacity(self.metadata.bands);
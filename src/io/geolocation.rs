@@ -0,0 +1,235 @@
+use gdal::Dataset;
+use gdal::spatial_ref::{AxisMappingStrategy, CoordTransform, SpatialRef};
+
+use crate::io::aoi::Aoi;
+use crate::io::sentinel1::SafeMetadata;
+
+/// Pixel step used when scanning the raster for AOI coverage
+const WINDOW_SEARCH_STEP: usize = 16;
+
+/// Meters per degree of latitude (WGS84 mean), used to express metric spacing in degrees
+pub const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Ground size (x, y) in meters of one pixel of a `cols` x `rows` raster.
+///
+/// Georeferenced rasters use the geotransform (degrees are converted at the raster's
+/// center latitude); pixel-space rasters use the annotation pixel spacing, which the reader
+/// keeps in step with any resampling.
+pub fn pixel_ground_size(
+    meta: &SafeMetadata,
+    geotransform: Option<[f64; 6]>,
+    cols: usize,
+    rows: usize,
+) -> Option<(f64, f64)> {
+    match geotransform {
+        Some(gt) if gt != [0.0, 1.0, 0.0, 0.0, 0.0, 1.0] => {
+            let dx = gt[1].hypot(gt[4]);
+            let dy = gt[2].hypot(gt[5]);
+            let geographic = meta
+                .projection
+                .as_deref()
+                .and_then(|p| gdal::spatial_ref::SpatialRef::from_definition(p).ok())
+                .map(|srs| srs.is_geographic())
+                .unwrap_or(false);
+            if geographic {
+                let center_lat = gt[3] + gt[4] * cols as f64 / 2.0 + gt[5] * rows as f64 / 2.0;
+                let cos_lat = center_lat.to_radians().cos().abs();
+                Some((dx * METERS_PER_DEGREE * cos_lat, dy * METERS_PER_DEGREE))
+            } else {
+                Some((dx, dy))
+            }
+        }
+        _ => Some((meta.pixel_spacing_range?, meta.pixel_spacing_azimuth?)),
+    }
+}
+
+/// Regular geolocation grid built from a product's GCPs (pixel, line) -> (lon, lat).
+///
+/// Sentinel-1 GRD measurement TIFFs carry the annotation geolocation grid as GCPs
+/// laid out on a regular pixel/line lattice; bilinear interpolation inside a cell
+/// is accurate to a few meters for GRD geometry.
+#[derive(Debug, Clone)]
+pub struct GcpGrid {
+    pixels: Vec<f64>,
+    lines: Vec<f64>,
+    /// Row-major over (line, pixel)
+    lon: Vec<f64>,
+    lat: Vec<f64>,
+}
+
+impl GcpGrid {
+    /// Build from (pixel, line, lon, lat) tuples. Returns None when the points
+    /// do not form a complete lattice with at least 2x2 nodes.
+    pub fn from_points(points: &[(f64, f64, f64, f64)]) -> Option<Self> {
+        let dedup = |mut v: Vec<f64>| {
+            v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            v.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+            v
+        };
+        let pixels = dedup(points.iter().map(|p| p.0).collect());
+        let lines = dedup(points.iter().map(|p| p.1).collect());
+        if pixels.len() < 2 || lines.len() < 2 || pixels.len() * lines.len() != points.len() {
+            return None;
+        }
+
+        let find = |axis: &[f64], v: f64| axis.iter().position(|&a| (a - v).abs() < 1e-6);
+        let mut lon = vec![f64::NAN; points.len()];
+        let mut lat = vec![f64::NAN; points.len()];
+        for &(p, l, x, y) in points {
+            let idx = find(&lines, l)? * pixels.len() + find(&pixels, p)?;
+            lon[idx] = x;
+            lat[idx] = y;
+        }
+        if lon.iter().any(|v| v.is_nan()) {
+            return None;
+        }
+        Some(Self { pixels, lines, lon, lat })
+    }
+
+    /// Read GCPs from a GDAL dataset
+    pub fn from_dataset(ds: &Dataset) -> Option<Self> {
+        let points: Vec<(f64, f64, f64, f64)> = ds
+            .gcps()
+            .iter()
+            .map(|g| (g.pixel(), g.line(), g.x(), g.y()))
+            .collect();
+        Self::from_points(&points)
+    }
+
+    // Cell index and fractional offset along one axis (extrapolates past the outer nodes)
+    #[inline]
+    fn locate(axis: &[f64], v: f64) -> (usize, f64) {
+        let i = match axis.partition_point(|&a| a <= v) {
+            0 => 0,
+            n => (n - 1).min(axis.len() - 2),
+        };
+        (i, (v - axis[i]) / (axis[i + 1] - axis[i]))
+    }
+
+    /// Interpolated (lon, lat) at a fractional pixel/line position
+    pub fn lonlat(&self, pixel: f64, line: f64) -> (f64, f64) {
+        let (ci, tx) = Self::locate(&self.pixels, pixel);
+        let (ri, ty) = Self::locate(&self.lines, line);
+        let n = self.pixels.len();
+        let i00 = ri * n + ci;
+        let i01 = i00 + 1;
+        let i10 = i00 + n;
+        let i11 = i10 + 1;
+        let interp = |v: &[f64]| {
+            let top = v[i00] * (1.0 - tx) + v[i01] * tx;
+            let bottom = v[i10] * (1.0 - tx) + v[i11] * tx;
+            top * (1.0 - ty) + bottom * ty
+        };
+        (interp(&self.lon), interp(&self.lat))
+    }
+
+    /// (lon, lat) of the outer grid nodes, clockwise in image space from the first pixel of
+    /// the first line: the swath outline the geolocation grid describes
+    pub fn perimeter(&self) -> Vec<[f64; 2]> {
+        let (n, m) = (self.pixels.len(), self.lines.len());
+        let node = |r: usize, c: usize| [self.lon[r * n + c], self.lat[r * n + c]];
+        let mut ring: Vec<[f64; 2]> = (0..n).map(|c| node(0, c)).collect();
+        ring.extend((1..m).map(|r| node(r, n - 1)));
+        ring.extend((0..n - 1).rev().map(|c| node(m - 1, c)));
+        ring.extend((1..m - 1).rev().map(|r| node(r, 0)));
+        ring
+    }
+
+    /// Source pixel window `(x_off, y_off, width, height)` covering the AOI bounds,
+    /// clamped to a `size_x` x `size_y` raster. None if the AOI misses the product.
+    pub fn pixel_window(
+        &self,
+        aoi: &Aoi,
+        size_x: usize,
+        size_y: usize,
+    ) -> Option<(usize, usize, usize, usize)> {
+        let [min_lon, min_lat, max_lon, max_lat] = aoi.bounds();
+        let mut x0 = usize::MAX;
+        let mut y0 = usize::MAX;
+        let mut x1 = 0usize;
+        let mut y1 = 0usize;
+        for l in (0..size_y).step_by(WINDOW_SEARCH_STEP) {
+            for p in (0..size_x).step_by(WINDOW_SEARCH_STEP) {
+                let (lon, lat) = self.lonlat(p as f64, l as f64);
+                if lon >= min_lon && lon <= max_lon && lat >= min_lat && lat <= max_lat {
+                    x0 = x0.min(p);
+                    y0 = y0.min(l);
+                    x1 = x1.max(p);
+                    y1 = y1.max(l);
+                }
+            }
+        }
+        if x0 == usize::MAX {
+            return None;
+        }
+        // Pad by one search step so the AOI edge is not clipped between samples
+        let x0 = x0.saturating_sub(WINDOW_SEARCH_STEP);
+        let y0 = y0.saturating_sub(WINDOW_SEARCH_STEP);
+        let x1 = (x1 + WINDOW_SEARCH_STEP).min(size_x);
+        let y1 = (y1 + WINDOW_SEARCH_STEP).min(size_y);
+        Some((x0, y0, x1 - x0, y1 - y0))
+    }
+}
+
+/// Output pixel -> WGS84 lon/lat for a raster produced by the reader.
+pub enum PixelGeolocation {
+    /// Map-projected output: affine geotransform plus a transform of its CRS to WGS84
+    /// (None when the CRS is already geographic)
+    Affine {
+        geotransform: [f64; 6],
+        to_wgs84: Option<CoordTransform>,
+    },
+    /// Radar-geometry output: GCP grid in source pixel/line, reached through the read
+    /// window offset and the output-to-source scale
+    Grid {
+        grid: GcpGrid,
+        offset: (f64, f64),
+        scale: (f64, f64),
+    },
+}
+
+impl PixelGeolocation {
+    /// Geotransform-based geolocation; `projection` is the geotransform's CRS (WKT or EPSG:xxxx)
+    pub fn from_geotransform(geotransform: [f64; 6], projection: &str) -> Option<Self> {
+        let src = SpatialRef::from_definition(projection).ok()?;
+        let to_wgs84 = if src.is_geographic() {
+            None
+        } else {
+            let mut src = src;
+            src.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+            let mut dst = SpatialRef::from_epsg(4326).ok()?;
+            dst.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+            Some(CoordTransform::new(&src, &dst).ok()?)
+        };
+        Some(Self::Affine {
+            geotransform,
+            to_wgs84,
+        })
+    }
+
+    /// (lon, lat) at a fractional output pixel position (col, row)
+    pub fn lonlat(&self, col: f64, row: f64) -> Option<(f64, f64)> {
+        match self {
+            Self::Affine {
+                geotransform: gt,
+                to_wgs84,
+            } => {
+                let x = gt[0] + col * gt[1] + row * gt[2];
+                let y = gt[3] + col * gt[4] + row * gt[5];
+                match to_wgs84 {
+                    None => Some((x, y)),
+                    Some(ct) => {
+                        let (mut xs, mut ys, mut zs) = ([x], [y], [0.0]);
+                        ct.transform_coords(&mut xs, &mut ys, &mut zs).ok()?;
+                        Some((xs[0], ys[0]))
+                    }
+                }
+            }
+            Self::Grid {
+                grid,
+                offset,
+                scale,
+            } => Some(grid.lonlat(offset.0 + col * scale.0, offset.1 + row * scale.1)),
+        }
+    }
+}
@@ -7,4 +7,19 @@ pub use sentinel1::{ProductType, SafeError, SafeMetadata, SafeReader};
 pub mod gdal;
 pub use gdal::{GdalError, GdalMetadata, GdalSarReader};
 
+pub mod aoi;
+pub use aoi::Aoi;
+
+pub mod discovery;
+pub use discovery::{DiscoveryOptions, ProductFilter, ProductName};
+
+pub mod footprint;
+pub use footprint::Footprint;
+
+pub mod geolocation;
+pub use geolocation::{GcpGrid, PixelGeolocation};
+
+pub mod slices;
+pub use slices::{SliceGroup, assemble_slices, group_slices};
+
 pub mod writers;
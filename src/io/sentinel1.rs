@@ -2,17 +2,25 @@ use chrono;
 use ndarray::Array2;
 use quick_xml::Reader;
 use quick_xml::events::Event;
+use rayon::prelude::*;
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use tracing::info;
 use tracing::warn;
 
+use crate::core::processing::autoscale::{AutoscaleParams, StretchWindow};
+use crate::core::processing::expr::{Band, BandExpr, ExprError};
+use crate::io::aoi::Aoi;
+use crate::io::footprint::{Footprint, valid_outline};
 use crate::io::gdal::GdalSarReader;
-use gdal::Dataset;
+use crate::io::geolocation::{GcpGrid, METERS_PER_DEGREE, PixelGeolocation};
+use crate::types::AutoscaleStrategy;
+use gdal::{Dataset, Gcp};
 use gdal::raster::Buffer;
 use gdal::raster::ResampleAlg;
 use std::process::Command;
+use serde::Serialize;
 use serde_json::Value;
 
 /// Errors encountered when reading SAFE archives
@@ -32,8 +40,13 @@ pub enum SafeError {
     Parse(String),
     #[error("Provided SLC measurement file is not a TIFF file: {0}")]
     NotTiff(String),
+    #[error("ZIP archive error: {0}")]
+    Zip(#[from] zip::result::ZipError),
 }
 
+/// Rows sampled along the data edge when tracing a footprint
+const FOOTPRINT_ROW_SAMPLES: usize = 512;
+
 /// Sentinel-1 product types
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ProductType {
@@ -48,8 +61,36 @@ pub enum TargetCrsArg {
     Custom(String),
 }
 
+/// Relative orbit from the absolute orbit number (175-orbit repeat cycle per satellite)
+pub fn relative_orbit_from_absolute(platform: &str, absolute_orbit: u64) -> Option<u32> {
+    let offset = match platform.to_ascii_uppercase().as_str() {
+        "S1A" | "SENTINEL-1A" => 73,
+        "S1B" | "SENTINEL-1B" => 27,
+        _ => return None,
+    };
+    if absolute_orbit < offset {
+        return None;
+    }
+    Some(((absolute_orbit - offset) % 175 + 1) as u32)
+}
+
+/// Read-time controls shared by the `*_with_read_options` open variants
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// Optional area of interest; only the covering window is read (or warped)
+    pub aoi: Option<Aoi>,
+    /// Set pixels outside the AOI geometry to 0 (nodata)
+    pub mask_outside_aoi: bool,
+    /// Target ground pixel spacing in meters; takes precedence over `target_size`.
+    /// Applied as `-tr` when warping (degrees are derived for geographic CRSs),
+    /// otherwise derived from the annotation range/azimuth pixel spacing.
+    pub pixel_spacing: Option<f64>,
+    /// Align output pixels to multiples of the pixel spacing (`-tap`); warped reads only
+    pub target_aligned_pixels: bool,
+}
+
 /// Metadata extracted from SAFE
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SafeMetadata {
     // Basic product information
     pub instrument: String,
@@ -57,6 +98,8 @@ pub struct SafeMetadata {
     pub acquisition_start: String,
     pub acquisition_stop: String,
     pub orbit_number: u64,
+    /// Relative orbit (track) number, from the manifest or derived from the absolute orbit
+    pub relative_orbit: Option<u32>,
     pub polarizations: Vec<String>,
     pub lines: usize,
     pub samples: usize,
@@ -77,12 +120,26 @@ pub struct SafeMetadata {
     pub crs: Option<String>,
     pub pixel_spacing_range: Option<f64>,
     pub pixel_spacing_azimuth: Option<f64>,
+    /// AOI label when the product was subset
+    pub aoi: Option<String>,
+    /// AOI window in source pixels [x_off, y_off, width, height] (unwarped reads only)
+    pub aoi_window: Option<[usize; 4]>,
+    /// GCPs in output pixel/line of a radar-geometry AOI subset, georeferencing it in place
+    /// of a geotransform (the GCP CRS is `projection`)
+    #[serde(skip)]
+    pub gcps: Vec<Gcp>,
+    /// Requested output pixel spacing in meters, if any
+    pub target_pixel_spacing: Option<f64>,
+    /// Band-math expression the output was computed from, if any
+    pub expression: Option<String>,
 
     // Acquisition details
     pub instrument_mode: Option<String>,
     pub pass_direction: Option<String>,
     pub data_take_id: Option<String>,
     pub product_id: Option<String>,
+    /// Product IDs of the slices this product was assembled from (empty for a single product)
+    pub slices: Vec<String>,
 
     // Processing parameters
     pub processing_level: Option<String>,
@@ -91,6 +148,16 @@ pub struct SafeMetadata {
     pub noise_estimate: Option<f64>,
     pub processing_center: Option<String>,
     pub software_version: Option<String>,
+    /// Autoscale strategy of the written output, if it was scaled to integers
+    pub autoscale: Option<AutoscaleStrategy>,
+    /// Clip window applied per output band, so scaled values can be mapped back to dB
+    pub autoscale_windows: Vec<StretchWindow>,
+    /// Largest integer of the scaled output (255 for U8, 65535 for U16)
+    pub autoscale_max_value: Option<f64>,
+    /// Percentile, gamma, CLAHE and dB-floor tuning the output was scaled with
+    pub autoscale_params: Option<AutoscaleParams>,
+    /// Color ramp a single-band output was rendered with, if any
+    pub colormap: Option<String>,
 
     // Image characteristics
     pub pixel_data_type: Option<String>,
@@ -110,6 +177,19 @@ pub struct SafeMetadata {
     pub conversion_timestamp: String,
 }
 
+/// One entry of the `dataObjectSection` of `manifest.safe`
+#[derive(Debug, Clone, Serialize)]
+pub struct DataObject {
+    pub id: String,
+    /// Schema the object follows, e.g. `s1Level1MeasurementSchema` or `s1Level1ProductSchema`
+    pub rep_id: String,
+    /// Path relative to the SAFE directory, without the leading `./`
+    pub href: String,
+    pub size: Option<u64>,
+    /// Lower-case hex MD5 digest
+    pub md5: Option<String>,
+}
+
 /// Reader for Sentinel-1 SAFE archives
 pub struct SafeReader {
     pub base_path: PathBuf,
@@ -137,6 +217,25 @@ impl SafeReader {
         target_crs: Option<TargetCrsArg>,
         resample_alg: Option<ResampleAlg>,
         target_size: Option<usize>,
+    ) -> Result<Self, SafeError> {
+        Self::open_with_read_options(
+            safe_dir,
+            polarization,
+            target_crs,
+            resample_alg,
+            target_size,
+            &ReadOptions::default(),
+        )
+    }
+
+    /// Open and parse a SAFE directory with reprojection, target size and read options (AOI)
+    pub fn open_with_read_options<P: AsRef<Path>>(
+        safe_dir: P,
+        polarization: Option<&str>,
+        target_crs: Option<TargetCrsArg>,
+        resample_alg: Option<ResampleAlg>,
+        target_size: Option<usize>,
+        read_opts: &ReadOptions,
     ) -> Result<Self, SafeError> {
         let base = safe_dir.as_ref().to_path_buf();
         let annotation = base.join("annotation");
@@ -192,6 +291,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("VV measurement file"));
@@ -208,6 +308,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("VH measurement file"));
@@ -224,6 +325,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("HH measurement file"));
@@ -240,6 +342,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("HV measurement file"));
@@ -255,6 +358,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("VV measurement file"));
@@ -268,6 +372,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("VH measurement file"));
@@ -284,6 +389,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("VV measurement file"));
@@ -297,6 +403,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("VH measurement file"));
@@ -313,6 +420,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("HH measurement file"));
@@ -326,6 +434,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     return Err(SafeError::MissingField("HV measurement file"));
@@ -347,6 +456,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
                 if let Some(path) = vh_path {
@@ -357,6 +467,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
                 if let Some(path) = hh_path {
@@ -367,6 +478,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
                 if let Some(path) = hv_path {
@@ -377,6 +489,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
             }
@@ -595,6 +708,25 @@ impl SafeReader {
         target_crs: Option<TargetCrsArg>,
         resample_alg: Option<ResampleAlg>,
         target_size: Option<usize>,
+    ) -> Result<Option<Self>, SafeError> {
+        Self::open_with_warnings_with_read_options(
+            safe_dir,
+            polarization,
+            target_crs,
+            resample_alg,
+            target_size,
+            &ReadOptions::default(),
+        )
+    }
+
+    /// Batch-friendly variant of [`SafeReader::open_with_read_options`]: unsupported products yield `Ok(None)`
+    pub fn open_with_warnings_with_read_options<P: AsRef<Path>>(
+        safe_dir: P,
+        polarization: Option<&str>,
+        target_crs: Option<TargetCrsArg>,
+        resample_alg: Option<ResampleAlg>,
+        target_size: Option<usize>,
+        read_opts: &ReadOptions,
     ) -> Result<Option<Self>, SafeError> {
         let base = safe_dir.as_ref().to_path_buf();
         let annotation = base.join("annotation");
@@ -646,6 +778,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     warn!("VV measurement file not found, skipping product");
@@ -662,6 +795,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     warn!("VH measurement file not found, skipping product");
@@ -678,6 +812,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     warn!("HH measurement file not found, skipping product");
@@ -694,6 +829,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     warn!("HV measurement file not found, skipping product");
@@ -709,6 +845,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
                 if let Some(path) = vh_path {
@@ -719,6 +856,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
                 if let Some(path) = hh_path {
@@ -729,6 +867,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
                 if let Some(path) = hv_path {
@@ -739,6 +878,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 }
                 if vv_data.is_none() && vh_data.is_none() && hh_data.is_none() && hv_data.is_none()
@@ -756,6 +896,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     warn!("HH measurement file not found, skipping product");
@@ -769,6 +910,7 @@ impl SafeReader {
                         effective_target_crs.as_deref(),
                         resample_alg,
                         target_size,
+                        read_opts,
                     )?);
                 } else {
                     warn!("HV measurement file not found, skipping product");
@@ -917,7 +1059,12 @@ impl SafeReader {
         target_crs: Option<&str>,
         resample_alg: Option<ResampleAlg>,
         target_size: Option<usize>,
+        read_opts: &ReadOptions,
     ) -> Result<Array2<f32>, SafeError> {
+        if let Some(aoi) = &read_opts.aoi {
+            metadata.aoi = Some(aoi.label());
+        }
+        metadata.target_pixel_spacing = read_opts.pixel_spacing;
         if let Some(dst) = target_crs {
             info!("Warping to target CRS: {}", dst);
             let tmp_in = file_path;
@@ -957,7 +1104,8 @@ impl SafeReader {
                 None
             };
             // Guard: if dataset already has a projection equal to target, skip warping
-            if !ds_proj.is_empty() {
+            // (AOI subsetting still goes through gdalwarp so -te/-cutline apply)
+            if !ds_proj.is_empty() && read_opts.aoi.is_none() {
                 if let Some(epsg) = parse_epsg(&ds_proj).or_else(|| {
                     if ds_proj.starts_with("EPSG:") {
                         Some(ds_proj.clone())
@@ -1001,8 +1149,61 @@ impl SafeReader {
                 "GDAL_CACHEMAX".into(),
                 "512".into(),
             ];
-            // If a target image long side is provided, compute output cols/rows preserving aspect
-            if let Some(ts) = target_size {
+            // Keep the cutline file alive until gdalwarp has built the VRT
+            let mut _cutline_file: Option<tempfile::NamedTempFile> = None;
+            if let Some(aoi) = &read_opts.aoi {
+                // Crop output extent to the AOI bounds, expressed in lon/lat
+                let [min_lon, min_lat, max_lon, max_lat] = aoi.bounds();
+                args.push("-te".into());
+                args.push(min_lon.to_string());
+                args.push(min_lat.to_string());
+                args.push(max_lon.to_string());
+                args.push(max_lat.to_string());
+                args.push("-te_srs".into());
+                args.push("EPSG:4326".into());
+                if read_opts.mask_outside_aoi {
+                    let cutline = tempfile::Builder::new()
+                        .prefix(&format!("{}_aoi_", stem))
+                        .suffix(".geojson")
+                        .tempfile()
+                        .map_err(|e| SafeError::Parse(format!("tempfile error: {}", e)))?;
+                    fs::write(cutline.path(), aoi.to_geojson().to_string())?;
+                    args.push("-cutline".into());
+                    args.push(cutline.path().to_str().unwrap().to_string());
+                    args.push("-dstnodata".into());
+                    args.push("0".into());
+                    _cutline_file = Some(cutline);
+                }
+            }
+            // Fixed ground resolution wins over long-side sizing (-tr and -ts are exclusive)
+            if let Some(spacing) = read_opts.pixel_spacing {
+                let geographic = gdal::spatial_ref::SpatialRef::from_definition(dst)
+                    .map(|srs| srs.is_geographic())
+                    .unwrap_or(false);
+                // Geographic CRS: express meters as degrees of latitude
+                let res = if geographic { spacing / METERS_PER_DEGREE } else { spacing };
+                info!("Warping at fixed resolution: {} m ({} target units)", spacing, res);
+                args.push("-tr".into());
+                args.push(res.to_string());
+                args.push(res.to_string());
+                if read_opts.target_aligned_pixels {
+                    args.push("-tap".into());
+                }
+            } else if let (Some(ts), Some(aoi)) = (target_size, read_opts.aoi.as_ref()) {
+                // Output extent is the AOI: pin the long side and let GDAL derive the other (0)
+                let [min_lon, min_lat, max_lon, max_lat] = aoi.bounds();
+                let mid_lat = ((min_lat + max_lat) / 2.0).to_radians();
+                let width = (max_lon - min_lon) * mid_lat.cos();
+                let height = max_lat - min_lat;
+                args.push("-ts".into());
+                if width >= height {
+                    args.push(ts.to_string());
+                    args.push("0".into());
+                } else {
+                    args.push("0".into());
+                    args.push(ts.to_string());
+                }
+            } else if let Some(ts) = target_size {
                 let (size_x, size_y) = src_ds.raster_size();
                 let (orig_cols, orig_rows) = (size_x as usize, size_y as usize);
                 let long_side = orig_cols.max(orig_rows);
@@ -1070,6 +1271,50 @@ impl SafeReader {
             let _ = std::fs::remove_file(&tmp_out);
             return Ok(data);
         }
+        if read_opts.target_aligned_pixels {
+            warn!("Target-aligned pixels require --target-crs; ignoring in radar geometry");
+        }
+        // No warp with an AOI: read only the window covering it
+        if let Some(aoi) = &read_opts.aoi {
+            return Self::load_aoi_window(
+                file_path,
+                metadata,
+                aoi,
+                read_opts,
+                resample_alg,
+                target_size,
+            );
+        }
+        // No warp with a fixed ground resolution: derive the read shape from annotation spacing
+        if let Some(spacing) = read_opts.pixel_spacing {
+            let gdal_reader = GdalSarReader::open(file_path)
+                .map_err(|e| SafeError::Parse(format!("GDAL error: {}", e)))?;
+            let (orig_cols, orig_rows) = (gdal_reader.metadata.size_x, gdal_reader.metadata.size_y);
+            let (out_cols, out_rows) =
+                Self::shape_for_pixel_spacing(metadata, orig_cols, orig_rows, spacing)?;
+            info!(
+                "Reading at {} m pixel spacing: {}x{} -> {}x{}",
+                spacing, orig_cols, orig_rows, out_cols, out_rows
+            );
+            let reduction = (orig_cols as f64 / out_cols as f64).max(orig_rows as f64 / out_rows as f64);
+            let chosen_alg = resample_alg.unwrap_or(if reduction >= 4.0 {
+                ResampleAlg::Average
+            } else {
+                ResampleAlg::Lanczos
+            });
+            let arr_f32 = gdal_reader
+                .read_band_resampled(1, out_cols, out_rows, Some(chosen_alg))
+                .map_err(|e| SafeError::Parse(format!("GDAL error: {}", e)))?;
+            let (sx, sy) = (orig_cols as f64 / out_cols as f64, orig_rows as f64 / out_rows as f64);
+            metadata.geotransform =
+                Some(scale_geotransform(gdal_reader.metadata.geotransform, sx, sy));
+            scale_pixel_spacing(metadata, sx, sy);
+            metadata.projection = Some(gdal_reader.metadata.projection.clone());
+            metadata.crs = Some(gdal_reader.metadata.projection.clone());
+            metadata.lines = out_rows;
+            metadata.samples = out_cols;
+            return Ok(arr_f32);
+        }
         // Fallback: no warp. If target_size is provided, downsample on read.
         if let Some(ts) = target_size {
             info!("Reading at target size (long side): {}", ts);
@@ -1103,6 +1348,11 @@ impl SafeReader {
             let arr_f32 = gdal_reader
                 .read_band_resampled(1, out_cols, out_rows, Some(chosen_alg))
                 .map_err(|e| SafeError::Parse(format!("GDAL error: {}", e)))?;
+            scale_pixel_spacing(
+                metadata,
+                orig_cols as f64 / out_cols as f64,
+                orig_rows as f64 / out_rows as f64,
+            );
             metadata.lines = out_rows;
             metadata.samples = out_cols;
             return Ok(arr_f32);
@@ -1111,6 +1361,131 @@ impl SafeReader {
         Self::load_polarization_data(file_path, metadata)
     }
 
+    /// Read the source window covering `aoi` (located through the GCP geolocation grid),
+    /// optionally downsampled to `target_size` and masked outside the AOI geometry
+    fn load_aoi_window(
+        file_path: &Path,
+        metadata: &mut SafeMetadata,
+        aoi: &Aoi,
+        read_opts: &ReadOptions,
+        resample_alg: Option<ResampleAlg>,
+        target_size: Option<usize>,
+    ) -> Result<Array2<f32>, SafeError> {
+        let gdal_reader = GdalSarReader::open(file_path)
+            .map_err(|e| SafeError::Parse(format!("GDAL error: {}", e)))?;
+        let grid = GcpGrid::from_dataset(&gdal_reader.dataset).ok_or_else(|| {
+            SafeError::Parse("AOI subsetting requires a regular GCP geolocation grid".to_string())
+        })?;
+        let (size_x, size_y) = (gdal_reader.metadata.size_x, gdal_reader.metadata.size_y);
+        let (x_off, y_off, win_w, win_h) = grid
+            .pixel_window(aoi, size_x, size_y)
+            .ok_or_else(|| SafeError::Parse(format!("AOI {} does not intersect product", aoi.label())))?;
+        info!(
+            "AOI window: offset=({}, {}), size={}x{} of {}x{}",
+            x_off, y_off, win_w, win_h, size_x, size_y
+        );
+
+        let (out_cols, out_rows) = match (read_opts.pixel_spacing, target_size) {
+            (Some(spacing), _) => Self::shape_for_pixel_spacing(metadata, win_w, win_h, spacing)?,
+            (None, Some(ts)) => {
+                let scale = (ts as f64 / win_w.max(win_h) as f64).min(1.0);
+                (
+                    ((win_w as f64) * scale).round().max(1.0) as usize,
+                    ((win_h as f64) * scale).round().max(1.0) as usize,
+                )
+            }
+            (None, None) => (win_w, win_h),
+        };
+        let alg = if (out_cols, out_rows) == (win_w, win_h) {
+            ResampleAlg::NearestNeighbour
+        } else {
+            resample_alg.unwrap_or(ResampleAlg::Average)
+        };
+        let mut arr_f32 = gdal_reader
+            .read_window(1, (x_off, y_off), (win_w, win_h), out_cols, out_rows, Some(alg))
+            .map_err(|e| SafeError::Parse(format!("GDAL error: {}", e)))?;
+
+        let sx = win_w as f64 / out_cols as f64;
+        let sy = win_h as f64 / out_rows as f64;
+        if read_opts.mask_outside_aoi {
+            if let Some(data) = arr_f32.as_slice_mut() {
+                data.par_chunks_mut(out_cols).enumerate().for_each(|(r, row)| {
+                    let line = y_off as f64 + (r as f64 + 0.5) * sy;
+                    for (c, v) in row.iter_mut().enumerate() {
+                        let pixel = x_off as f64 + (c as f64 + 0.5) * sx;
+                        let (lon, lat) = grid.lonlat(pixel, line);
+                        if !aoi.contains(lon, lat) {
+                            *v = 0.0;
+                        }
+                    }
+                });
+            }
+        }
+
+        // Shift/scale a real geotransform to the window; pixel-space identity stays as-is
+        let gt = gdal_reader.metadata.geotransform;
+        let shifted = if gt == IDENTITY_GEOTRANSFORM {
+            gt
+        } else {
+            [
+                gt[0] + x_off as f64 * gt[1] + y_off as f64 * gt[2],
+                gt[1],
+                gt[2],
+                gt[3] + x_off as f64 * gt[4] + y_off as f64 * gt[5],
+                gt[4],
+                gt[5],
+            ]
+        };
+        if gt == IDENTITY_GEOTRANSFORM {
+            // Radar geometry has no geotransform to shift: move the GCPs into the window's
+            // pixel/line space instead, so the written subset stays georeferenced
+            metadata.gcps = gdal_reader
+                .dataset
+                .gcps()
+                .iter()
+                .map(|g| Gcp {
+                    id: g.id(),
+                    info: g.info(),
+                    pixel: (g.pixel() - x_off as f64) / sx,
+                    line: (g.line() - y_off as f64) / sy,
+                    x: g.x(),
+                    y: g.y(),
+                    z: g.z(),
+                })
+                .collect();
+        }
+        metadata.geotransform = Some(scale_geotransform(shifted, sx, sy));
+        scale_pixel_spacing(metadata, sx, sy);
+        metadata.projection = Some(gdal_reader.metadata.projection.clone());
+        metadata.crs = Some(gdal_reader.metadata.projection.clone());
+        metadata.aoi_window = Some([x_off, y_off, win_w, win_h]);
+        metadata.lines = out_rows;
+        metadata.samples = out_cols;
+        Ok(arr_f32)
+    }
+
+    /// Output shape for a `cols` x `rows` radar-geometry raster resampled to `spacing` meters,
+    /// using the annotation range/azimuth pixel spacing
+    fn shape_for_pixel_spacing(
+        metadata: &SafeMetadata,
+        cols: usize,
+        rows: usize,
+        spacing: f64,
+    ) -> Result<(usize, usize), SafeError> {
+        if !spacing.is_finite() || spacing <= 0.0 {
+            return Err(SafeError::Parse(format!("Invalid pixel spacing: {}", spacing)));
+        }
+        let range = metadata
+            .pixel_spacing_range
+            .ok_or(SafeError::MissingField("rangePixelSpacing"))?;
+        let azimuth = metadata
+            .pixel_spacing_azimuth
+            .ok_or(SafeError::MissingField("azimuthPixelSpacing"))?;
+        let out_cols = ((cols as f64) * range / spacing).round().max(1.0) as usize;
+        let out_rows = ((rows as f64) * azimuth / spacing).round().max(1.0) as usize;
+        Ok((out_cols, out_rows))
+    }
+
     fn parse_comprehensive_metadata(base_path: &Path) -> Result<SafeMetadata, SafeError> {
         // Initialize metadata with conversion provenance
         let mut meta = SafeMetadata {
@@ -1119,6 +1494,7 @@ impl SafeReader {
             acquisition_start: String::new(),
             acquisition_stop: String::new(),
             orbit_number: 0,
+            relative_orbit: None,
             polarizations: Vec::new(),
             lines: 0,
             samples: 0,
@@ -1135,15 +1511,26 @@ impl SafeReader {
             crs: None,
             pixel_spacing_range: None,
             pixel_spacing_azimuth: None,
+            aoi: None,
+            aoi_window: None,
+            gcps: Vec::new(),
+            target_pixel_spacing: None,
+            expression: None,
             instrument_mode: None,
             pass_direction: None,
             data_take_id: None,
+            slices: Vec::new(),
             product_id: None,
             processing_level: None,
             multilook_factor: None,
             calibration_type: None,
             noise_estimate: None,
             processing_center: None,
+            autoscale: None,
+            autoscale_windows: Vec::new(),
+            autoscale_max_value: None,
+            autoscale_params: None,
+            colormap: None,
             software_version: None,
             pixel_data_type: None,
             bits_per_sample: None,
@@ -1170,6 +1557,15 @@ impl SafeReader {
             meta = Self::parse_annotation_files(&annotation_path, meta)?;
         }
 
+        if meta.product_id.is_none() {
+            meta.product_id = base_path
+                .file_name()
+                .map(|n| n.to_string_lossy().trim_end_matches(".SAFE").to_string());
+        }
+        if meta.relative_orbit.is_none() {
+            meta.relative_orbit = relative_orbit_from_absolute(&meta.platform, meta.orbit_number);
+        }
+
         Ok(meta)
     }
 
@@ -1246,6 +1642,9 @@ impl SafeReader {
                         "orbitNumber" if in_orbit_reference => {
                             meta.orbit_number = txt.parse().unwrap_or(0)
                         }
+                        "relativeOrbitNumber" if in_orbit_reference => {
+                            meta.relative_orbit = txt.parse().ok()
+                        }
                         "pass" if in_orbit_properties => {
                             meta.pass_direction = Some(txt.to_string())
                         }
@@ -1441,11 +1840,171 @@ impl SafeReader {
         Ok(meta)
     }
 
+    /// Files listed in the `dataObjectSection` of `manifest.safe`, with their recorded sizes
+    /// and MD5 checksums
+    pub fn read_data_objects<P: AsRef<Path>>(safe_dir: P) -> Result<Vec<DataObject>, SafeError> {
+        let mut reader = Reader::from_file(safe_dir.as_ref().join("manifest.safe"))?;
+        reader.trim_text(true);
+        let mut buf = Vec::new();
+        let mut objects = Vec::new();
+        let mut current: Option<DataObject> = None;
+        let mut in_checksum = false;
+
+        loop {
+            let event = reader.read_event_into(&mut buf)?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                    b"dataObject" => {
+                        let attr = |name: &str| -> Result<String, SafeError> {
+                            Ok(match e.try_get_attribute(name)? {
+                                Some(a) => a.unescape_value()?.to_string(),
+                                None => String::new(),
+                            })
+                        };
+                        current = Some(DataObject {
+                            id: attr("ID")?,
+                            rep_id: attr("repID")?,
+                            href: String::new(),
+                            size: None,
+                            md5: None,
+                        });
+                    }
+                    b"byteStream" => {
+                        if let (Some(obj), Some(size)) =
+                            (current.as_mut(), e.try_get_attribute("size")?)
+                        {
+                            obj.size = size.unescape_value()?.trim().parse().ok();
+                        }
+                    }
+                    b"fileLocation" => {
+                        if let (Some(obj), Some(href)) =
+                            (current.as_mut(), e.try_get_attribute("href")?)
+                        {
+                            let href = href.unescape_value()?;
+                            obj.href = href.trim_start_matches("./").to_string();
+                        }
+                    }
+                    b"checksum" => {
+                        let is_md5 = match e.try_get_attribute("checksumName")? {
+                            Some(name) => name.unescape_value()?.eq_ignore_ascii_case("MD5"),
+                            None => true,
+                        };
+                        in_checksum = is_md5 && matches!(event, Event::Start(_));
+                    }
+                    _ => {}
+                },
+                Event::Text(e) if in_checksum => {
+                    if let Some(obj) = current.as_mut() {
+                        obj.md5 = Some(e.unescape()?.trim().to_lowercase());
+                    }
+                }
+                Event::End(e) => match e.local_name().as_ref() {
+                    b"checksum" => in_checksum = false,
+                    b"dataObject" => {
+                        if let Some(obj) = current.take() {
+                            objects.push(obj);
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(objects)
+    }
+
+    /// Parse manifest and annotation metadata of a SAFE directory without reading rasters
+    pub fn read_metadata<P: AsRef<Path>>(safe_dir: P) -> Result<SafeMetadata, SafeError> {
+        Self::parse_comprehensive_metadata(safe_dir.as_ref())
+    }
+
     /// Access parsed metadata
     pub fn metadata(&self) -> &SafeMetadata {
         &self.metadata
     }
 
+    /// Geolocation of the loaded output pixels: the geotransform when the data was
+    /// reprojected, otherwise the measurement's GCP grid mapped through the read window
+    /// and resampling. None when neither is available.
+    pub fn geolocation(&self) -> Option<PixelGeolocation> {
+        let meta = &self.metadata;
+        if let (Some(gt), Some(proj)) = (meta.geotransform, meta.projection.as_deref()) {
+            if gt != [0.0, 1.0, 0.0, 0.0, 0.0, 1.0] && !proj.trim().is_empty() {
+                return PixelGeolocation::from_geotransform(gt, proj);
+            }
+        }
+
+        let measurement = self.base_path.join("measurement");
+        let (vv, vh, hh, hv) =
+            Self::identify_polarization_files(&measurement, &meta.polarizations).ok()?;
+        let path = vv.or(vh).or(hh).or(hv)?;
+        let ds = Dataset::open(&path).ok()?;
+        let grid = GcpGrid::from_dataset(&ds)?;
+        let (size_x, size_y) = ds.raster_size();
+        let [x_off, y_off, win_w, win_h] = meta.aoi_window.unwrap_or([0, 0, size_x, size_y]);
+        Some(PixelGeolocation::Grid {
+            grid,
+            offset: (x_off as f64, y_off as f64),
+            scale: (
+                win_w as f64 / meta.samples.max(1) as f64,
+                win_h as f64 / meta.lines.max(1) as f64,
+            ),
+        })
+    }
+
+    /// Footprint of the loaded data: the outline of the valid (finite, non-zero) pixels of
+    /// the first loaded band, geolocated through [`Self::geolocation`]. It follows the actual
+    /// data edge of the output grid, so border noise removal and AOI masking are reflected.
+    pub fn footprint(&self) -> Option<Footprint> {
+        let data = [&self.vv_data, &self.vh_data, &self.hh_data, &self.hv_data]
+            .into_iter()
+            .find_map(|d| d.as_ref())?;
+        let geolocation = self.geolocation()?;
+        let (rows, cols) = data.dim();
+        let step = (rows / FOOTPRINT_ROW_SAMPLES).max(1);
+        let outline = valid_outline(cols, rows, step, |r, c| {
+            let v = data[[r, c]];
+            v.is_finite() && v > 0.0
+        })?;
+        let ring: Vec<[f64; 2]> = outline
+            .iter()
+            .filter_map(|p| geolocation.lonlat(p[0], p[1]).map(|(lon, lat)| [lon, lat]))
+            .collect();
+        Footprint::from_ring(&ring)
+    }
+
+    /// Measurement rasters of a product as (polarization, path), in VV, VH, HH, HV order,
+    /// without reading them
+    pub fn read_measurements<P: AsRef<Path>>(
+        safe_dir: P,
+    ) -> Result<Vec<(String, PathBuf)>, SafeError> {
+        let safe_dir = safe_dir.as_ref();
+        let meta = Self::read_metadata(safe_dir)?;
+        let (vv, vh, hh, hv) =
+            Self::identify_polarization_files(&safe_dir.join("measurement"), &meta.polarizations)?;
+        Ok([("VV", vv), ("VH", vh), ("HH", hh), ("HV", hv)]
+            .into_iter()
+            .filter_map(|(pol, path)| Some((pol.to_string(), path?)))
+            .collect())
+    }
+
+    /// Footprint of a product from the geolocation grid (GCPs) of its measurement, without
+    /// reading any raster data; meant for catalog indexing and AOI checks before processing
+    pub fn read_footprint<P: AsRef<Path>>(safe_dir: P) -> Result<Footprint, SafeError> {
+        let safe_dir = safe_dir.as_ref();
+        let meta = Self::read_metadata(safe_dir)?;
+        let (vv, vh, hh, hv) =
+            Self::identify_polarization_files(&safe_dir.join("measurement"), &meta.polarizations)?;
+        let path = vv.or(vh).or(hh).or(hv).ok_or(SafeError::MissingField("measurement"))?;
+        let ds = Dataset::open(&path)
+            .map_err(|e| SafeError::Parse(format!("Cannot open {:?}: {}", path, e)))?;
+        let grid = GcpGrid::from_dataset(&ds).ok_or(SafeError::MissingField("geolocation grid"))?;
+        Footprint::from_ring(&grid.perimeter())
+            .ok_or_else(|| SafeError::Parse(format!("Degenerate geolocation grid in {:?}", path)))
+    }
+
     /// Retrieve the image data as a borrowed array (returns VV data if available, otherwise VH)
     pub fn data(&self) -> Result<&Array2<f32>, SafeError> {
         if let Some(ref arr) = self.vv_data {
@@ -1535,6 +2094,38 @@ impl SafeReader {
         Ok(crate::core::processing::ops::log_ratio_arrays(vv, vh))
     }
 
+    /// Get Radar Vegetation Index of VV and VH data (4·vh / (vv + vh))
+    pub fn rvi_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating RVI of VV and VH data");
+        let vv = self.vv_data()?;
+        let vh = self.vh_data()?;
+        Ok(crate::core::processing::ops::rvi_arrays(vv, vh))
+    }
+
+    /// Get cross-pol ratio of VV and VH data in dB (10 * log10(vh / vv))
+    pub fn cross_pol_db_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating cross-pol ratio (dB) of VV and VH data");
+        let vv = self.vv_data()?;
+        let vh = self.vh_data()?;
+        Ok(crate::core::processing::ops::cross_pol_db_arrays(vv, vh))
+    }
+
+    /// Get depolarization ratio of VV and VH data (vh / vv)
+    pub fn depol_ratio_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating depolarization ratio of VV and VH data");
+        let vv = self.vv_data()?;
+        let vh = self.vh_data()?;
+        Ok(crate::core::processing::ops::depol_ratio_arrays(vv, vh))
+    }
+
+    /// Get Dual-pol SAR Vegetation Index of VV and VH data
+    pub fn dpsvi_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating DPSVI of VV and VH data");
+        let vv = self.vv_data()?;
+        let vh = self.vh_data()?;
+        Ok(crate::core::processing::ops::dpsvi_arrays(vv, vh))
+    }
+
     // HH/HV pair operations
     /// Get sum of HH and HV data (hh + hv)
     pub fn sum_hh_hv_data(&self) -> Result<Array2<f32>, SafeError> {
@@ -1578,6 +2169,49 @@ impl SafeReader {
         Ok(crate::core::processing::ops::log_ratio_arrays(hh, hv))
     }
 
+    /// Get Radar Vegetation Index of HH and HV data (4·hv / (hh + hv))
+    pub fn rvi_hh_hv_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating RVI of HH and HV data");
+        let hh = self.hh_data()?;
+        let hv = self.hv_data()?;
+        Ok(crate::core::processing::ops::rvi_arrays(hh, hv))
+    }
+
+    /// Get cross-pol ratio of HH and HV data in dB (10 * log10(hv / hh))
+    pub fn cross_pol_db_hh_hv_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating cross-pol ratio (dB) of HH and HV data");
+        let hh = self.hh_data()?;
+        let hv = self.hv_data()?;
+        Ok(crate::core::processing::ops::cross_pol_db_arrays(hh, hv))
+    }
+
+    /// Get depolarization ratio of HH and HV data (hv / hh)
+    pub fn depol_ratio_hh_hv_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating depolarization ratio of HH and HV data");
+        let hh = self.hh_data()?;
+        let hv = self.hv_data()?;
+        Ok(crate::core::processing::ops::depol_ratio_arrays(hh, hv))
+    }
+
+    /// Get Dual-pol SAR Vegetation Index of HH and HV data
+    pub fn dpsvi_hh_hv_data(&self) -> Result<Array2<f32>, SafeError> {
+        info!("Calculating DPSVI of HH and HV data");
+        let hh = self.hh_data()?;
+        let hv = self.hv_data()?;
+        Ok(crate::core::processing::ops::dpsvi_arrays(hh, hv))
+    }
+
+    /// Evaluate a band-math expression over the loaded polarizations
+    pub fn expression_data(&self, expr: &BandExpr) -> Result<Array2<f32>, ExprError> {
+        info!("Evaluating expression {}", expr);
+        expr.evaluate(|band| match band {
+            Band::Vv => self.vv_data().ok(),
+            Band::Vh => self.vh_data().ok(),
+            Band::Hh => self.hh_data().ok(),
+            Band::Hv => self.hv_data().ok(),
+        })
+    }
+
     /// Get a string representation of available polarizations for error reporting
     pub fn get_available_polarizations(&self) -> String {
         let mut available = Vec::new();
@@ -1603,6 +2237,29 @@ impl SafeReader {
     }
 }
 
+/// GDAL's default geotransform for rasters without georeferencing (pixel space)
+const IDENTITY_GEOTRANSFORM: [f64; 6] = [0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+
+/// Keep the annotation pixel spacing describing output pixels after an unwarped resample
+/// by (`sx`, `sy`) source pixels per output pixel
+fn scale_pixel_spacing(metadata: &mut SafeMetadata, sx: f64, sy: f64) {
+    if let Some(range) = metadata.pixel_spacing_range.as_mut() {
+        *range *= sx;
+    }
+    if let Some(azimuth) = metadata.pixel_spacing_azimuth.as_mut() {
+        *azimuth *= sy;
+    }
+}
+
+/// Scale a geotransform's pixel size by (`sx`, `sy`) source pixels per output pixel;
+/// the pixel-space identity transform is left untouched
+fn scale_geotransform(gt: [f64; 6], sx: f64, sy: f64) -> [f64; 6] {
+    if gt == IDENTITY_GEOTRANSFORM {
+        return gt;
+    }
+    [gt[0], gt[1] * sx, gt[2] * sy, gt[3], gt[4] * sx, gt[5] * sy]
+}
+
 /// Resolve an automatic target CRS (EPSG:XXXXX) based on the SAFE product's geolocation.
 ///
 /// Strategy:
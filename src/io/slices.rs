@@ -0,0 +1,216 @@
+//! Slice assembly: long Sentinel-1 acquisitions are distributed as consecutive GRD slices
+//! that share a datatake. This module finds such runs and mosaics their warped rasters
+//! into one `SafeReader`, so a whole datatake is autoscaled and written as one product.
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use ndarray::{Array2, s};
+use tracing::{info, warn};
+
+use crate::io::sentinel1::{SafeError, SafeMetadata, SafeReader};
+
+/// Largest gap (seconds) between the stop of one slice and the start of the next that still
+/// counts as consecutive; slices of one datatake normally overlap by a few lines.
+pub const MAX_SLICE_GAP_SECS: f64 = 5.0;
+
+/// Consecutive slices of one datatake, ordered by acquisition start
+#[derive(Debug, Clone)]
+pub struct SliceGroup {
+    pub data_take_id: Option<String>,
+    pub products: Vec<PathBuf>,
+}
+
+fn parse_time(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s.trim_end_matches('Z'), "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+/// Group products into runs of consecutive slices. Products match when platform, datatake
+/// ID, product type and polarizations agree and the next slice starts at most
+/// `MAX_SLICE_GAP_SECS` after the previous one stops. Products without a datatake ID, or
+/// without parseable times, form single-product groups. Groups are returned in input order
+/// of their first product.
+pub fn group_slices(products: &[(PathBuf, SafeMetadata)]) -> Vec<SliceGroup> {
+    type Key = (String, String, String, String);
+    let mut by_take: BTreeMap<Key, Vec<usize>> = BTreeMap::new();
+    let mut singles: Vec<usize> = Vec::new();
+    for (i, (_, meta)) in products.iter().enumerate() {
+        match (&meta.data_take_id, parse_time(&meta.acquisition_start)) {
+            (Some(id), Some(_)) => by_take
+                .entry((
+                    meta.platform.to_ascii_uppercase(),
+                    id.clone(),
+                    meta.product_type.to_ascii_uppercase(),
+                    meta.polarizations.join(","),
+                ))
+                .or_default()
+                .push(i),
+            _ => singles.push(i),
+        }
+    }
+
+    let mut runs: Vec<Vec<usize>> = singles.into_iter().map(|i| vec![i]).collect();
+    for (_, mut members) in by_take {
+        members.sort_by_key(|&i| parse_time(&products[i].1.acquisition_start));
+        let mut run: Vec<usize> = Vec::new();
+        let mut last_stop: Option<NaiveDateTime> = None;
+        for i in members {
+            let meta = &products[i].1;
+            let start = parse_time(&meta.acquisition_start);
+            let consecutive = match (last_stop, start) {
+                (Some(stop), Some(start)) => {
+                    (start - stop).num_milliseconds() as f64 / 1000.0 <= MAX_SLICE_GAP_SECS
+                }
+                _ => false,
+            };
+            if !consecutive && !run.is_empty() {
+                runs.push(std::mem::take(&mut run));
+            }
+            run.push(i);
+            last_stop = parse_time(&meta.acquisition_stop).or(start);
+        }
+        if !run.is_empty() {
+            runs.push(run);
+        }
+    }
+    runs.sort_by_key(|run| run.iter().copied().min());
+
+    runs.into_iter()
+        .map(|run| SliceGroup {
+            data_take_id: products[run[0]].1.data_take_id.clone(),
+            products: run.into_iter().map(|i| products[i].0.clone()).collect(),
+        })
+        .collect()
+}
+
+/// Mosaic slices that were warped onto one target-aligned grid (same CRS and pixel
+/// spacing) into a single reader. Slices are expected in acquisition order; where they
+/// overlap, the earlier slice's valid pixels are kept and gaps are filled from later ones.
+/// Metadata is taken from the first slice with the acquisition window, raster size and
+/// geotransform widened to the whole run and `slices` listing the product IDs.
+pub fn assemble_slices(slices: Vec<SafeReader>) -> Result<SafeReader, SafeError> {
+    let first = slices
+        .first()
+        .ok_or(SafeError::MissingField("slices to assemble"))?;
+    let gts: Vec<[f64; 6]> = slices
+        .iter()
+        .map(|r| {
+            r.metadata
+                .geotransform
+                .ok_or(SafeError::MissingField("geotransform (slices must be reprojected)"))
+        })
+        .collect::<Result<_, _>>()?;
+    let (px, py) = (gts[0][1], gts[0][5]);
+    let tol = 1e-6 * px.abs().max(py.abs());
+    for (r, gt) in slices.iter().zip(&gts) {
+        if (gt[1] - px).abs() > tol || (gt[5] - py).abs() > tol || gt[2] != 0.0 || gt[4] != 0.0 {
+            return Err(SafeError::Parse(format!(
+                "Slice {:?} is not on the common north-up pixel grid",
+                r.base_path
+            )));
+        }
+        if r.metadata.projection != first.metadata.projection {
+            return Err(SafeError::Parse(format!(
+                "Slice {:?} uses a different projection",
+                r.base_path
+            )));
+        }
+    }
+
+    let dims: Vec<(usize, usize)> = slices
+        .iter()
+        .map(|r| r.data().map(|a| a.dim()).or_else(|_| first_band(r).map(|a| a.dim())))
+        .collect::<Result<_, _>>()?;
+    let x0 = gts.iter().map(|gt| gt[0]).fold(f64::INFINITY, f64::min);
+    let y0 = gts.iter().map(|gt| gt[3]).fold(f64::NEG_INFINITY, f64::max);
+    let x1 = gts
+        .iter()
+        .zip(&dims)
+        .map(|(gt, &(_, cols))| gt[0] + cols as f64 * px)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let y1 = gts
+        .iter()
+        .zip(&dims)
+        .map(|(gt, &(rows, _))| gt[3] + rows as f64 * py)
+        .fold(f64::INFINITY, f64::min);
+    let cols = ((x1 - x0) / px).round() as usize;
+    let rows = ((y1 - y0) / py).round() as usize;
+    let offsets: Vec<(usize, usize)> = gts
+        .iter()
+        .map(|gt| {
+            (
+                ((gt[0] - x0) / px).round() as usize,
+                ((gt[3] - y0) / py).round() as usize,
+            )
+        })
+        .collect();
+
+    let mosaic = |band: fn(&SafeReader) -> Option<&Array2<f32>>| -> Option<Array2<f32>> {
+        if slices.iter().any(|r| band(r).is_none()) {
+            return None;
+        }
+        let mut out = Array2::<f32>::zeros((rows, cols));
+        for (r, &(x, y)) in slices.iter().zip(&offsets) {
+            let data = band(r)?;
+            // Offsets and the mosaic size are rounded separately, so a slice can overhang
+            // the last row or column by a pixel; clip it instead of slicing out of bounds
+            let (y, x) = (y.min(rows), x.min(cols));
+            let h = data.nrows().min(rows - y);
+            let w = data.ncols().min(cols - x);
+            let mut dst = out.slice_mut(s![y..y + h, x..x + w]);
+            let src = data.slice(s![..h, ..w]);
+            ndarray::Zip::from(&mut dst).and(&src).par_for_each(|o, &v| {
+                if *o == 0.0 && v.is_finite() && v > 0.0 {
+                    *o = v;
+                }
+            });
+        }
+        Some(out)
+    };
+    let vv_data = mosaic(|r| r.vv_data.as_ref());
+    let vh_data = mosaic(|r| r.vh_data.as_ref());
+    let hh_data = mosaic(|r| r.hh_data.as_ref());
+    let hv_data = mosaic(|r| r.hv_data.as_ref());
+    if vv_data.is_none() && vh_data.is_none() && hh_data.is_none() && hv_data.is_none() {
+        warn!("Slices share no loaded polarization");
+        return Err(SafeError::MissingField("polarization common to all slices"));
+    }
+
+    let mut metadata = first.metadata.clone();
+    let last = &slices[slices.len() - 1].metadata;
+    metadata.acquisition_stop = last.acquisition_stop.clone();
+    metadata.lines = rows;
+    metadata.samples = cols;
+    metadata.geotransform = Some([x0, px, 0.0, y0, 0.0, py]);
+    metadata.aoi_window = None;
+    metadata.gcps.clear();
+    metadata.slices = slices
+        .iter()
+        .map(|r| r.metadata.product_id.clone().unwrap_or_default())
+        .collect();
+    info!(
+        "Assembled {} slices of datatake {} into {}x{}",
+        slices.len(),
+        metadata.data_take_id.as_deref().unwrap_or("?"),
+        cols,
+        rows
+    );
+
+    let first = slices.into_iter().next().expect("non-empty slice list");
+    Ok(SafeReader {
+        base_path: first.base_path,
+        metadata,
+        product_type: first.product_type,
+        vv_data,
+        vh_data,
+        hh_data,
+        hv_data,
+    })
+}
+
+fn first_band(r: &SafeReader) -> Result<&Array2<f32>, SafeError> {
+    r.hh_data
+        .as_ref()
+        .or(r.hv_data.as_ref())
+        .ok_or(SafeError::MissingField("no polarization data available"))
+}
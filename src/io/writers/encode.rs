@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::types::JpegSubsampling;
+
+/// Encoder settings for JPEG outputs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JpegOptions {
+    /// Quality (1..100)
+    pub quality: u8,
+    /// Write a progressive instead of a baseline JPEG
+    pub progressive: bool,
+    /// Chroma subsampling of RGB outputs
+    pub subsampling: JpegSubsampling,
+    /// Write a tiled, JPEG-compressed GeoTIFF (`<output>.tif`) when the image exceeds the
+    /// 65535-pixel JPEG limit, instead of failing
+    pub tiff_fallback: bool,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            quality: 100,
+            progressive: false,
+            subsampling: JpegSubsampling::S444,
+            tiff_fallback: false,
+        }
+    }
+}
+
+/// Encoder settings for WebP outputs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebpOptions {
+    /// Lossy quality (1..100); ignored when `lossless` is set
+    pub quality: u8,
+    /// Encode losslessly instead of at `quality`
+    pub lossless: bool,
+}
+
+impl Default for WebpOptions {
+    fn default() -> Self {
+        Self {
+            quality: 85,
+            lossless: false,
+        }
+    }
+}
+
+/// Per-format encoder settings for image outputs. Formats without tunable settings (TIFF,
+/// PNG) ignore this.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncodeOptions {
+    pub jpeg: JpegOptions,
+    pub webp: WebpOptions,
+}
+
+impl EncodeOptions {
+    /// Reject out-of-range quality settings
+    pub fn validate(&self) -> Result<()> {
+        for (arg, quality) in [
+            ("jpeg_quality", self.jpeg.quality),
+            ("webp_quality", self.webp.quality),
+        ] {
+            if !(1..=100).contains(&quality) {
+                return Err(Error::InvalidArgument {
+                    arg,
+                    value: format!("{} (must be within 1..100)", quality),
+                });
+            }
+        }
+        Ok(())
+    }
+}
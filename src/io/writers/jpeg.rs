@@ -1,30 +1,85 @@
-use jpeg_encoder::{ColorType, Encoder};
+use jpeg_encoder::{ColorType, Encoder, SamplingFactor};
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::Path;
 
-pub fn write_gray_jpeg(
+use crate::error::Error;
+use crate::io::writers::encode::JpegOptions;
+use crate::types::JpegSubsampling;
+
+/// Largest width or height a JPEG can store
+pub const JPEG_MAX_DIMENSION: usize = u16::MAX as usize;
+
+/// Reject image sizes the JPEG header cannot represent
+pub fn check_jpeg_dimensions(cols: usize, rows: usize) -> Result<(), Error> {
+    if cols > JPEG_MAX_DIMENSION || rows > JPEG_MAX_DIMENSION {
+        return Err(Error::Processing(format!(
+            "{}x{} exceeds the JPEG limit of {} pixels per side; reduce --size, or use \
+             --jpeg-tiff-fallback or another format",
+            cols, rows, JPEG_MAX_DIMENSION
+        )));
+    }
+    Ok(())
+}
+
+fn encode_jpeg(
     output: &Path,
     cols: usize,
     rows: usize,
     data: &[u8],
+    color: ColorType,
+    options: &JpegOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    check_jpeg_dimensions(cols, rows)?;
     let file = File::create(output)?;
     let mut writer = BufWriter::new(file);
-    let encoder = Encoder::new(&mut writer, 100);
-    encoder.encode(data, cols as u16, rows as u16, ColorType::Luma)?;
+    let mut encoder = Encoder::new(&mut writer, options.quality);
+    encoder.set_progressive(options.progressive);
+    encoder.set_sampling_factor(match options.subsampling {
+        JpegSubsampling::S444 => SamplingFactor::F_1_1,
+        JpegSubsampling::S422 => SamplingFactor::F_2_1,
+        JpegSubsampling::S420 => SamplingFactor::F_2_2,
+    });
+    encoder.encode(data, cols as u16, rows as u16, color)?;
     Ok(())
 }
 
+pub fn write_gray_jpeg(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    data: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_gray_jpeg_with_options(output, cols, rows, data, &JpegOptions::default())
+}
+
+/// [`write_gray_jpeg`] with quality and progressive mode from `options`
+pub fn write_gray_jpeg_with_options(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    data: &[u8],
+    options: &JpegOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    encode_jpeg(output, cols, rows, data, ColorType::Luma, options)
+}
+
 pub fn write_rgb_jpeg(
     output: &Path,
     cols: usize,
     rows: usize,
     rgb_data: &[u8],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let file = File::create(output)?;
-    let mut writer = BufWriter::new(file);
-    let encoder = Encoder::new(&mut writer, 100);
-    encoder.encode(rgb_data, cols as u16, rows as u16, ColorType::Rgb)?;
-    Ok(())
+    write_rgb_jpeg_with_options(output, cols, rows, rgb_data, &JpegOptions::default())
+}
+
+/// [`write_rgb_jpeg`] with quality, progressive mode and chroma subsampling from `options`
+pub fn write_rgb_jpeg_with_options(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    rgb_data: &[u8],
+    options: &JpegOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    encode_jpeg(output, cols, rows, rgb_data, ColorType::Rgb, options)
 }
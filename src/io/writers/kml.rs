@@ -0,0 +1,218 @@
+//! KML `GroundOverlay` documents and KMZ packages for Google Earth.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::io::geolocation::PixelGeolocation;
+
+/// Where the overlay image is draped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlayPlacement {
+    /// North-up image on a lon/lat grid
+    LatLonBox {
+        north: f64,
+        south: f64,
+        east: f64,
+        west: f64,
+    },
+    /// `gx:LatLonQuad`: (lon, lat) of the lower-left, lower-right, upper-right and upper-left
+    /// image corners, for swaths and projected grids that are not north-up in lon/lat
+    LatLonQuad([(f64, f64); 4]),
+}
+
+impl OverlayPlacement {
+    /// Placement of a `cols` x `rows` image located by `geolocation`. Only an unrotated
+    /// geographic grid gets a `LatLonBox`; radar geometry uses the corner GCPs.
+    pub fn from_geolocation(
+        geolocation: &PixelGeolocation,
+        cols: usize,
+        rows: usize,
+    ) -> Option<Self> {
+        let (w, h) = (cols as f64, rows as f64);
+        if let PixelGeolocation::Affine {
+            geotransform: gt,
+            to_wgs84: None,
+        } = geolocation
+        {
+            if gt[2] == 0.0 && gt[4] == 0.0 {
+                let (x0, x1) = (gt[0], gt[0] + w * gt[1]);
+                let (y0, y1) = (gt[3], gt[3] + h * gt[5]);
+                return Some(Self::LatLonBox {
+                    north: y0.max(y1),
+                    south: y0.min(y1),
+                    east: x0.max(x1),
+                    west: x0.min(x1),
+                });
+            }
+        }
+        Some(Self::LatLonQuad([
+            geolocation.lonlat(0.0, h)?,
+            geolocation.lonlat(w, h)?,
+            geolocation.lonlat(w, 0.0)?,
+            geolocation.lonlat(0.0, 0.0)?,
+        ]))
+    }
+
+    /// Mean of the corners, for the placemark
+    pub fn center(&self) -> (f64, f64) {
+        match *self {
+            Self::LatLonBox {
+                north,
+                south,
+                east,
+                west,
+            } => ((east + west) / 2.0, (north + south) / 2.0),
+            Self::LatLonQuad(corners) => {
+                let (lon, lat) = corners
+                    .iter()
+                    .fold((0.0, 0.0), |(a, b), (lon, lat)| (a + lon, b + lat));
+                (lon / 4.0, lat / 4.0)
+            }
+        }
+    }
+}
+
+/// One image draped on the globe plus a placemark carrying its description
+#[derive(Debug, Clone)]
+pub struct GroundOverlay {
+    pub name: String,
+    /// HTML shown in the Google Earth balloon
+    pub description: String,
+    /// Image path inside the KMZ
+    pub image_href: String,
+    pub placement: OverlayPlacement,
+    /// Acquisition start and stop (ISO 8601)
+    pub time_span: Option<(String, String)>,
+}
+
+impl GroundOverlay {
+    pub fn to_kml(&self) -> String {
+        let name = xml_escape(&self.name);
+        let description = format!("<![CDATA[{}]]>", self.description.replace("]]>", "]]&gt;"));
+        let time_span = self.time_span.as_ref().map(|(begin, end)| {
+            format!(
+                "<TimeSpan><begin>{}</begin><end>{}</end></TimeSpan>",
+                xml_escape(&utc_time(begin)),
+                xml_escape(&utc_time(end))
+            )
+        });
+        let (lon, lat) = self.placement.center();
+
+        let mut lines = vec![
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string(),
+            "<kml xmlns=\"http://www.opengis.net/kml/2.2\" \
+             xmlns:gx=\"http://www.google.com/kml/ext/2.2\">"
+                .to_string(),
+            "<Document>".to_string(),
+            format!("  <name>{}</name>", name),
+            "  <open>1</open>".to_string(),
+            "  <Placemark>".to_string(),
+            format!("    <name>{}</name>", name),
+            format!("    <description>{}</description>", description),
+        ];
+        lines.extend(time_span.iter().map(|t| format!("    {}", t)));
+        lines.push(format!("    <Point><coordinates>{},{},0</coordinates></Point>", lon, lat));
+        lines.push("  </Placemark>".to_string());
+        lines.push("  <GroundOverlay>".to_string());
+        lines.push(format!("    <name>{}</name>", name));
+        lines.push(format!("    <description>{}</description>", description));
+        lines.extend(time_span.iter().map(|t| format!("    {}", t)));
+        lines.push(format!("    <Icon><href>{}</href></Icon>", xml_escape(&self.image_href)));
+        match self.placement {
+            OverlayPlacement::LatLonBox {
+                north,
+                south,
+                east,
+                west,
+            } => {
+                lines.push("    <LatLonBox>".to_string());
+                lines.push(format!("      <north>{}</north>", north));
+                lines.push(format!("      <south>{}</south>", south));
+                lines.push(format!("      <east>{}</east>", east));
+                lines.push(format!("      <west>{}</west>", west));
+                lines.push("    </LatLonBox>".to_string());
+            }
+            OverlayPlacement::LatLonQuad(corners) => {
+                let coords: Vec<String> =
+                    corners.iter().map(|(lon, lat)| format!("{},{}", lon, lat)).collect();
+                lines.push(format!(
+                    "    <gx:LatLonQuad><coordinates>{}</coordinates></gx:LatLonQuad>",
+                    coords.join(" ")
+                ));
+            }
+        }
+        lines.push("  </GroundOverlay>".to_string());
+        lines.push("</Document>".to_string());
+        lines.push("</kml>".to_string());
+        lines.join("\n") + "\n"
+    }
+}
+
+/// HTML table of the product fields worth showing in a balloon, taken from
+/// `extract_metadata_fields` output
+pub fn kml_description(fields: &HashMap<String, String>) -> String {
+    const ROWS: [(&str, &str); 9] = [
+        ("PRODUCT_ID", "Product"),
+        ("PLATFORM", "Platform"),
+        ("INSTRUMENT_MODE", "Mode"),
+        ("ORBIT_NUMBER", "Absolute orbit"),
+        ("RELATIVE_ORBIT", "Relative orbit"),
+        ("PASS_DIRECTION", "Pass"),
+        ("POLARIZATIONS", "Polarization"),
+        ("ACQUISITION_START", "Start (UTC)"),
+        ("ACQUISITION_STOP", "Stop (UTC)"),
+    ];
+    let mut html = String::from("<table>");
+    for (key, label) in ROWS {
+        if let Some(value) = fields.get(key).filter(|v| !v.is_empty()) {
+            html.push_str(&format!(
+                "<tr><th align=\"left\">{}</th><td>{}</td></tr>",
+                label,
+                xml_escape(value)
+            ));
+        }
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Write `overlay` as `doc.kml` plus the `image` file into the KMZ archive at `path`.
+/// `overlay.image_href` must be the name the image gets inside the archive.
+pub fn write_kmz(
+    path: &Path,
+    overlay: &GroundOverlay,
+    image: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+    // Google Earth reads the first .kml entry of the archive
+    zip.start_file(
+        "doc.kml",
+        SimpleFileOptions::default().compression_method(CompressionMethod::Deflated),
+    )?;
+    zip.write_all(overlay.to_kml().as_bytes())?;
+    // JPEG and PNG are compressed already
+    zip.start_file(
+        overlay.image_href.as_str(),
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    std::io::copy(&mut File::open(image)?, &mut zip)?;
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// SAFE times carry no zone but are UTC; KML reads zone-less times as local
+fn utc_time(t: &str) -> String {
+    let has_zone = t.ends_with('Z') || t.get(19..).is_some_and(|s| s.contains(['+', '-']));
+    if has_zone { t.to_string() } else { format!("{}Z", t) }
+}
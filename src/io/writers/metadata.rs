@@ -1,11 +1,15 @@
 use gdal::Dataset;
 use gdal::Metadata;
+use gdal::spatial_ref::SpatialRef;
+use gdal::Gcp;
 use serde_json;
 use std::collections::HashMap;
 use std::path::Path;
 use tracing::info;
 
+use crate::core::processing::autoscale::{StretchTransfer, StretchWindow};
 use crate::io::sentinel1::SafeMetadata;
+use crate::types::AutoscaleStrategy;
 
 /// Metadata format for different output types
 #[derive(Debug, Clone, Copy)]
@@ -35,6 +39,9 @@ pub fn extract_metadata_fields(
         meta.acquisition_stop.clone(),
     );
     metadata.insert("ORBIT_NUMBER".to_string(), meta.orbit_number.to_string());
+    if let Some(relative_orbit) = meta.relative_orbit {
+        metadata.insert("RELATIVE_ORBIT".to_string(), relative_orbit.to_string());
+    }
 
     // Handle polarization field based on operation
     let polarization_field = if let Some(op) = operation {
@@ -104,6 +111,62 @@ pub fn extract_metadata_fields(
                     meta.polarizations.join(",")
                 }
             }
+            "rvi" => {
+                if meta.polarizations.contains(&"VV".to_string())
+                    && meta.polarizations.contains(&"VH".to_string())
+                {
+                    "RVI(VV, VH)".to_string()
+                } else if meta.polarizations.contains(&"HH".to_string())
+                    && meta.polarizations.contains(&"HV".to_string())
+                {
+                    "RVI(HH, HV)".to_string()
+                } else {
+                    meta.polarizations.join(",")
+                }
+            }
+            "cross_pol_db" => {
+                if meta.polarizations.contains(&"VV".to_string())
+                    && meta.polarizations.contains(&"VH".to_string())
+                {
+                    "CROSS_POL_DB(VV, VH)".to_string()
+                } else if meta.polarizations.contains(&"HH".to_string())
+                    && meta.polarizations.contains(&"HV".to_string())
+                {
+                    "CROSS_POL_DB(HH, HV)".to_string()
+                } else {
+                    meta.polarizations.join(",")
+                }
+            }
+            "depol_ratio" => {
+                if meta.polarizations.contains(&"VV".to_string())
+                    && meta.polarizations.contains(&"VH".to_string())
+                {
+                    "DEPOL_RATIO(VV, VH)".to_string()
+                } else if meta.polarizations.contains(&"HH".to_string())
+                    && meta.polarizations.contains(&"HV".to_string())
+                {
+                    "DEPOL_RATIO(HH, HV)".to_string()
+                } else {
+                    meta.polarizations.join(",")
+                }
+            }
+            "dpsvi" => {
+                if meta.polarizations.contains(&"VV".to_string())
+                    && meta.polarizations.contains(&"VH".to_string())
+                {
+                    "DPSVI(VV, VH)".to_string()
+                } else if meta.polarizations.contains(&"HH".to_string())
+                    && meta.polarizations.contains(&"HV".to_string())
+                {
+                    "DPSVI(HH, HV)".to_string()
+                } else {
+                    meta.polarizations.join(",")
+                }
+            }
+            "expression" | "expression_db" | "expression_native" => match &meta.expression {
+                Some(expr) => format!("EXPR({})", expr),
+                None => meta.polarizations.join(","),
+            },
             "multiband_vv_vh" => "MULTIBAND(VV, VH)".to_string(),
             "multiband_hh_hv" => "MULTIBAND(HH, HV)".to_string(),
             _ => meta.polarizations.join(","),
@@ -152,6 +215,21 @@ pub fn extract_metadata_fields(
         );
     }
 
+    if let Some(aoi) = &meta.aoi {
+        metadata.insert("AOI".to_string(), aoi.clone());
+    }
+    if let Some([x, y, w, h]) = meta.aoi_window {
+        metadata.insert("AOI_WINDOW".to_string(), format!("{},{},{},{}", x, y, w, h));
+    }
+    if let Some(spacing) = meta.target_pixel_spacing {
+        metadata.insert("TARGET_PIXEL_SPACING".to_string(), spacing.to_string());
+    }
+    if let Some(expr) = &meta.expression {
+        metadata.insert("EXPRESSION".to_string(), expr.clone());
+        let scale = operation.and_then(|op| op.strip_prefix("expression_")).unwrap_or("linear");
+        metadata.insert("EXPRESSION_SCALE".to_string(), scale.to_string());
+    }
+
     // Acquisition details
     if let Some(mode) = &meta.instrument_mode {
         metadata.insert("INSTRUMENT_MODE".to_string(), mode.clone());
@@ -165,6 +243,10 @@ pub fn extract_metadata_fields(
     if let Some(product_id) = &meta.product_id {
         metadata.insert("PRODUCT_ID".to_string(), product_id.clone());
     }
+    if !meta.slices.is_empty() {
+        metadata.insert("SLICE_COUNT".to_string(), meta.slices.len().to_string());
+        metadata.insert("SLICES".to_string(), meta.slices.join(","));
+    }
 
     // Processing parameters
     if let Some(level) = &meta.processing_level {
@@ -185,6 +267,70 @@ pub fn extract_metadata_fields(
     if let Some(version) = &meta.software_version {
         metadata.insert("SOFTWARE_VERSION".to_string(), version.clone());
     }
+    if let Some(strategy) = meta.autoscale {
+        metadata.insert("AUTOSCALE_STRATEGY".to_string(), strategy.to_string());
+    }
+    if let Some(params) = &meta.autoscale_params {
+        metadata.insert("AUTOSCALE_DB_FLOOR".to_string(), params.db_floor.to_string());
+        if meta.autoscale == Some(AutoscaleStrategy::Clahe) {
+            metadata.insert(
+                "AUTOSCALE_CLAHE".to_string(),
+                format!(
+                    "tiles={}x{},clip_limit={},bins={}",
+                    params.clahe_tiles_x,
+                    params.clahe_tiles_y,
+                    params.clahe_clip_limit,
+                    params.clahe_bins
+                ),
+            );
+        }
+    }
+    if let Some(colormap) = &meta.colormap {
+        metadata.insert("COLORMAP".to_string(), colormap.clone());
+    }
+    if !meta.autoscale_windows.is_empty() {
+        // One value per output band, comma-joined when there are several
+        let join = |f: fn(&StretchWindow) -> f64| {
+            meta.autoscale_windows
+                .iter()
+                .map(|w| f(w).to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        metadata.insert("AUTOSCALE_MIN_DB".to_string(), join(|w| w.min_db));
+        metadata.insert("AUTOSCALE_MAX_DB".to_string(), join(|w| w.max_db));
+        metadata.insert("AUTOSCALE_GAMMA".to_string(), join(|w| w.gamma));
+    }
+    if let (Some(strategy), Some(max_value)) = (meta.autoscale, meta.autoscale_max_value) {
+        let transfers: Vec<StretchTransfer> = meta
+            .autoscale_windows
+            .iter()
+            .map(|w| StretchTransfer::for_strategy(strategy, w))
+            .collect();
+        if !transfers.is_empty() {
+            metadata.insert(
+                "AUTOSCALE_TRANSFER".to_string(),
+                transfers
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            metadata.insert("AUTOSCALE_MAX_VALUE".to_string(), max_value.to_string());
+        }
+        if !transfers.is_empty() && transfers.iter().all(|t| *t == StretchTransfer::Linear) {
+            let (scales, offsets): (Vec<String>, Vec<String>) = meta
+                .autoscale_windows
+                .iter()
+                .map(|w| {
+                    let (scale, offset) = w.scale_offset(max_value);
+                    (scale.to_string(), offset.to_string())
+                })
+                .unzip();
+            metadata.insert("AUTOSCALE_SCALE".to_string(), scales.join(","));
+            metadata.insert("AUTOSCALE_OFFSET".to_string(), offsets.join(","));
+        }
+    }
 
     // Image characteristics
     if let Some(data_type) = &meta.pixel_data_type {
@@ -291,6 +437,71 @@ pub fn add_special_json_fields(
             );
         }
     }
+
+    if let Some(transfer) = autoscale_transfer_json(meta) {
+        json_metadata.insert("autoscale_transfer".to_string(), transfer);
+    }
+}
+
+/// Per-band description of how output integers map back to dB (or index units), with
+/// `scale`/`offset` for linear bands and the inverse formula otherwise
+fn autoscale_transfer_json(meta: &SafeMetadata) -> Option<serde_json::Value> {
+    let strategy = meta.autoscale?;
+    let max_value = meta.autoscale_max_value?;
+    if meta.autoscale_windows.is_empty() {
+        return None;
+    }
+    let bands: Vec<serde_json::Value> = meta
+        .autoscale_windows
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let transfer = StretchTransfer::for_strategy(strategy, w);
+            let (scale, offset) = w.scale_offset(max_value);
+            let description = match transfer {
+                StretchTransfer::Linear => format!("value = {} + {} * v", offset, scale),
+                StretchTransfer::Power => format!(
+                    "value = {} + {} * (v / {})^(1 / {})",
+                    w.min_db,
+                    w.max_db - w.min_db,
+                    max_value,
+                    w.gamma
+                ),
+                StretchTransfer::Clahe => {
+                    let p = meta.autoscale_params.unwrap_or_default();
+                    format!(
+                        "linear clip to [{}, {}] then CLAHE ({}x{} tiles, clip limit {}, {} bins); \
+                         per-pixel values are not recoverable, the linear inverse is approximate",
+                        w.min_db,
+                        w.max_db,
+                        p.clahe_tiles_x,
+                        p.clahe_tiles_y,
+                        p.clahe_clip_limit,
+                        p.clahe_bins
+                    )
+                }
+            };
+            let mut band = serde_json::json!({
+                "band": i + 1,
+                "transfer": transfer,
+                "min_db": w.min_db,
+                "max_db": w.max_db,
+                "gamma": w.gamma,
+                "description": description,
+            });
+            if transfer == StretchTransfer::Linear {
+                band["scale"] = serde_json::json!(scale);
+                band["offset"] = serde_json::json!(offset);
+            }
+            band
+        })
+        .collect();
+    Some(serde_json::json!({
+        "strategy": strategy.to_string(),
+        "max_value": max_value,
+        "nodata": 0,
+        "bands": bands,
+    }))
 }
 
 /// Embed comprehensive metadata into a GeoTIFF dataset
@@ -308,7 +519,23 @@ pub fn embed_tiff_metadata(
 
     // Determine which geotransform to use and whether it's valid
     let mut set_gt = false;
-    if let Some(gt) = geotransform_override {
+    if !meta.gcps.is_empty() {
+        // Radar-geometry AOI subset placed by GCPs: an override is then only the resize and
+        // padding map from written pixels back to the loaded raster, applied to the GCPs
+        let identity = [0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let [x0, kx, _, y0, _, ky] = geotransform_override.unwrap_or(identity);
+        let gcps = meta
+            .gcps
+            .iter()
+            .map(|g| Gcp {
+                pixel: (g.pixel - x0) / kx,
+                line: (g.line - y0) / ky,
+                ..g.clone()
+            })
+            .collect();
+        let srs = SpatialRef::from_definition(meta.projection.as_deref().unwrap_or("EPSG:4326"))?;
+        ds.set_gcps(gcps, &srs)?;
+    } else if let Some(gt) = geotransform_override {
         if !is_identity(gt) {
             ds.set_geo_transform(&gt)?;
             set_gt = true;
@@ -337,6 +564,22 @@ pub fn embed_tiff_metadata(
         ds.set_metadata_item(&key, &value, "")?;
     }
 
+    // Linear stretches become GDAL band scale/offset, so readers unscale to dB directly
+    if let (Some(strategy), Some(max_value)) = (meta.autoscale, meta.autoscale_max_value) {
+        for (i, window) in meta.autoscale_windows.iter().enumerate() {
+            if i >= ds.raster_count() {
+                break;
+            }
+            if StretchTransfer::for_strategy(strategy, window) != StretchTransfer::Linear {
+                continue;
+            }
+            let (scale, offset) = window.scale_offset(max_value);
+            let mut band = ds.rasterband(i + 1)?;
+            band.set_scale(scale)?;
+            band.set_offset(offset)?;
+        }
+    }
+
     Ok(())
 }
 
@@ -419,6 +662,75 @@ pub fn create_jpeg_metadata_sidecar_with_overrides_and_extras(
     Ok(())
 }
 
+/// Create a JSON sidecar (`<output>.json`) next to any output, with structured extra fields
+/// (e.g. analysis statistics) merged in under their own keys
+pub fn create_metadata_sidecar_with_json_extras(
+    output_path: &Path,
+    meta: &SafeMetadata,
+    operation: Option<&str>,
+    extras: &[(&str, serde_json::Value)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = extract_metadata_fields(meta, operation);
+    let mut json_metadata = convert_metadata_to_json(&metadata);
+    add_special_json_fields(&mut json_metadata, meta, None, None);
+    for (k, v) in extras {
+        json_metadata.insert(k.to_lowercase(), v.clone());
+    }
+    let sidecar_path = output_path.with_extension("json");
+    let json_string = serde_json::to_string_pretty(&json_metadata)?;
+    std::fs::write(&sidecar_path, json_string)?;
+    info!("Created metadata sidecar: {:?}", sidecar_path);
+    Ok(())
+}
+
+/// Load the clip window a previous output was autoscaled with, for reuse as a fixed
+/// stretch. Accepts a JSON sidecar or plain JSON file (`autoscale_min_db`/`autoscale_max_db`/
+/// `autoscale_gamma`, or the short `min_db`/`max_db`/`gamma` keys) or any GDAL-readable
+/// raster carrying the `AUTOSCALE_*` metadata items. Multi-band values use the first band.
+pub fn read_autoscale_reference(path: &Path) -> Result<StretchWindow, Box<dyn std::error::Error>> {
+    let is_json = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let lookup: Box<dyn Fn(&str) -> Option<String>> = if is_json {
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Box::new(move |key: &str| {
+            [format!("autoscale_{}", key), key.to_string()]
+                .iter()
+                .find_map(|k| match json.get(k)? {
+                    serde_json::Value::Number(n) => Some(n.to_string()),
+                    serde_json::Value::String(s) => Some(s.clone()),
+                    _ => None,
+                })
+        })
+    } else {
+        let ds = Dataset::open(path)?;
+        Box::new(move |key: &str| {
+            ds.metadata_item(&format!("AUTOSCALE_{}", key.to_uppercase()), "")
+        })
+    };
+    let value = |key: &str| -> Result<Option<f64>, Box<dyn std::error::Error>> {
+        match lookup(key) {
+            Some(raw) => {
+                let first = raw.split(',').next().unwrap_or_default().trim();
+                first.parse::<f64>().map(Some).map_err(|_| {
+                    format!("Invalid autoscale {} '{}' in {:?}", key, raw, path).into()
+                })
+            }
+            None => Ok(None),
+        }
+    };
+    match (value("min_db")?, value("max_db")?) {
+        (Some(min_db), Some(max_db)) if max_db > min_db => Ok(StretchWindow {
+            min_db,
+            max_db,
+            gamma: value("gamma")?.unwrap_or(1.0),
+        }),
+        (Some(_), Some(_)) => Err(format!("Empty autoscale window in {:?}", path).into()),
+        _ => Err(format!("No autoscale clip limits found in {:?}", path).into()),
+    }
+}
+
 /// Generic metadata handler that can work with any format
 pub fn handle_metadata(
     meta: &SafeMetadata,
@@ -1,4 +1,10 @@
+pub mod encode;
 pub mod jpeg;
+pub mod kml;
 pub mod metadata;
+pub mod png;
+pub mod tilearchive;
 pub mod tiff;
+pub mod vector;
+pub mod webp;
 pub mod worldfile;
@@ -0,0 +1,81 @@
+use gdal::DriverManager;
+use gdal::raster::{Buffer, GdalType, RasterCreationOptions};
+use std::path::Path;
+
+/// Write planar `bands` (`cols * rows` samples each) to an in-memory dataset and copy it to
+/// `output` with the GDAL driver `driver`
+pub(crate) fn write_bands_via_gdal<T: GdalType + Copy>(
+    driver: &str,
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    bands: &[&[T]],
+    options: &RasterCreationOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mem = DriverManager::get_driver_by_name("MEM")?;
+    let ds = mem.create_with_band_type::<T, _>("", cols, rows, bands.len())?;
+    for (i, data) in bands.iter().enumerate() {
+        let mut buf = Buffer::new((cols, rows), data.to_vec());
+        let mut band = ds.rasterband(i + 1)?;
+        band.write((0, 0), (cols, rows), &mut buf)?;
+    }
+    let driver = DriverManager::get_driver_by_name(driver)?;
+    ds.create_copy(&driver, output, options)?;
+    Ok(())
+}
+
+/// Split interleaved RGB samples into one plane per channel
+pub(crate) fn rgb_planes(rgb: &[u8]) -> [Vec<u8>; 3] {
+    let mut planes = [
+        Vec::with_capacity(rgb.len() / 3),
+        Vec::with_capacity(rgb.len() / 3),
+        Vec::with_capacity(rgb.len() / 3),
+    ];
+    for px in rgb.chunks_exact(3) {
+        planes[0].push(px[0]);
+        planes[1].push(px[1]);
+        planes[2].push(px[2]);
+    }
+    planes
+}
+
+/// 8-bit grayscale PNG, with a second (alpha) channel when `alpha` is given
+pub fn write_gray_png(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    data: &[u8],
+    alpha: Option<&[u8]>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bands = vec![data];
+    bands.extend(alpha);
+    write_bands_via_gdal("PNG", output, cols, rows, &bands, &RasterCreationOptions::new())
+}
+
+/// 16-bit grayscale PNG; an 8-bit `alpha` (0/255) is widened to the full 16-bit range
+pub fn write_gray_png_u16(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    data: &[u16],
+    alpha: Option<&[u8]>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let alpha16: Option<Vec<u16>> = alpha.map(|a| a.iter().map(|&v| v as u16 * 257).collect());
+    let mut bands = vec![data];
+    bands.extend(alpha16.as_deref());
+    write_bands_via_gdal("PNG", output, cols, rows, &bands, &RasterCreationOptions::new())
+}
+
+/// RGB PNG from interleaved samples, RGBA when `alpha` is given
+pub fn write_rgb_png(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    rgb_data: &[u8],
+    alpha: Option<&[u8]>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let [r, g, b] = rgb_planes(rgb_data);
+    let mut bands = vec![r.as_slice(), g.as_slice(), b.as_slice()];
+    bands.extend(alpha);
+    write_bands_via_gdal("PNG", output, cols, rows, &bands, &RasterCreationOptions::new())
+}
@@ -1,6 +1,9 @@
 use gdal::Dataset;
 use gdal::DriverManager;
-use gdal::raster::{Buffer, ColorInterpretation};
+use gdal::raster::{
+    Buffer, ColorEntry, ColorInterpretation, ColorTable, PaletteInterpretation,
+    RasterCreationOptions,
+};
 use std::path::Path;
 
 pub fn write_tiff_u8(
@@ -17,6 +20,27 @@ pub fn write_tiff_u8(
     Ok(ds)
 }
 
+/// Single-band u8 GeoTIFF with a GDAL color table; value 0 is transparent and the nodata value
+pub fn write_tiff_paletted(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    data: &[u8],
+    palette: &[[u8; 3]; 256],
+) -> Result<Dataset, Box<dyn std::error::Error>> {
+    let ds = write_tiff_u8(output, cols, rows, data)?;
+    let mut table = ColorTable::new(PaletteInterpretation::Rgba);
+    for (i, [r, g, b]) in palette.iter().enumerate() {
+        let alpha = if i == 0 { 0 } else { 255 };
+        table.set_color_entry(i as u16, &ColorEntry::rgba(*r as i16, *g as i16, *b as i16, alpha));
+    }
+    let mut band = ds.rasterband(1)?;
+    band.set_color_interpretation(ColorInterpretation::PaletteIndex)?;
+    band.set_color_table(&table);
+    band.set_no_data_value(Some(0.0))?;
+    Ok(ds)
+}
+
 pub fn write_tiff_u16(
     output: &Path,
     cols: usize,
@@ -76,3 +100,55 @@ pub fn write_tiff_multiband_u16(
 
     Ok(ds)
 }
+
+/// Float32 GeoTIFF with one band per slice (e.g. analysis rasters with NaN nodata)
+pub fn write_tiff_bands_f32(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    bands: &[&[f32]],
+) -> Result<Dataset, Box<dyn std::error::Error>> {
+    let driver = DriverManager::get_driver_by_name("GTiff")?;
+    let ds = driver.create_with_band_type::<f32, _>(output, cols, rows, bands.len())?;
+    for (i, data) in bands.iter().enumerate() {
+        let mut band = ds.rasterband(i + 1)?;
+        band.set_color_interpretation(ColorInterpretation::GrayIndex)?;
+        band.set_no_data_value(Some(f64::NAN))?;
+        let mut buf = Buffer::new((cols, rows), data.to_vec());
+        band.write((0, 0), (cols, rows), &mut buf)?;
+    }
+    Ok(ds)
+}
+
+/// Tiled, JPEG-compressed u8 GeoTIFF from planar `bands` (1 = gray, 3 = RGB stored as YCbCr).
+/// Keeps quicklook-sized files for images too large for a plain JPEG.
+pub fn write_tiff_jpeg_tiled(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    bands: &[&[u8]],
+    quality: u8,
+) -> Result<Dataset, Box<dyn std::error::Error>> {
+    let mut options = RasterCreationOptions::new();
+    options.set_name_value("TILED", "YES")?;
+    options.set_name_value("COMPRESS", "JPEG")?;
+    options.set_name_value("JPEG_QUALITY", &quality.to_string())?;
+    options.set_name_value("BIGTIFF", "IF_SAFER")?;
+    if bands.len() == 3 {
+        options.set_name_value("PHOTOMETRIC", "YCBCR")?;
+    }
+    let driver = DriverManager::get_driver_by_name("GTiff")?;
+    let ds = driver.create_with_band_type_with_options::<u8, _>(
+        output,
+        cols,
+        rows,
+        bands.len(),
+        &options,
+    )?;
+    for (i, data) in bands.iter().enumerate() {
+        let mut band = ds.rasterband(i + 1)?;
+        let mut buf = Buffer::new((cols, rows), data.to_vec());
+        band.write((0, 0), (cols, rows), &mut buf)?;
+    }
+    Ok(ds)
+}
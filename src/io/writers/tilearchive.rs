@@ -0,0 +1,810 @@
+//! Single-file tile archives: MBTiles (SQLite) and PMTiles v3, plus the tileset description
+//! they share with `tilejson.json`. The readers serve archives written here back out.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+
+use crate::types::TileFormat;
+
+/// Description of a tile pyramid as written to TileJSON, MBTiles metadata and PMTiles headers
+#[derive(Debug, Clone)]
+pub struct TilesetMetadata {
+    pub name: String,
+    pub description: String,
+    pub attribution: String,
+    pub format: TileFormat,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    /// `[west, south, east, north]` in degrees
+    pub bounds: [f64; 4],
+    /// Longitude, latitude and zoom a viewer opens at
+    pub center: (f64, f64, u8),
+}
+
+impl TilesetMetadata {
+    /// TileJSON 3.0 document with `tile_url` as the only tile URL template
+    pub fn to_tilejson(&self, tile_url: &str) -> serde_json::Value {
+        serde_json::json!({
+            "tilejson": "3.0.0",
+            "name": self.name,
+            "description": self.description,
+            "attribution": self.attribution,
+            "scheme": "xyz",
+            "tiles": [tile_url],
+            "minzoom": self.min_zoom,
+            "maxzoom": self.max_zoom,
+            "bounds": self.bounds,
+            "center": [self.center.0, self.center.1, self.center.2],
+        })
+    }
+
+    /// Tileset described by a TileJSON document; the format comes from the extension of the
+    /// first tile URL. None when that URL has no image extension.
+    pub fn from_tilejson(json: &serde_json::Value) -> Option<Self> {
+        let url = json["tiles"].get(0)?.as_str()?;
+        let format = TileFormat::from_extension(url.rsplit('.').next()?)?;
+        let text = |key: &str| json[key].as_str().unwrap_or_default().to_string();
+        let zoom = |key: &str| json[key].as_u64().and_then(|z| u8::try_from(z).ok());
+        let numbers = |key: &str| -> Vec<f64> {
+            let values = json[key].as_array();
+            values.map_or(Vec::new(), |a| a.iter().filter_map(|v| v.as_f64()).collect())
+        };
+        let (min_zoom, max_zoom) = (zoom("minzoom").unwrap_or(0), zoom("maxzoom").unwrap_or(0));
+        let bounds = numbers("bounds").try_into().unwrap_or(WORLD_BOUNDS);
+        let center = match numbers("center")[..] {
+            [lon, lat, z] => (lon, lat, z as u8),
+            _ => default_center(bounds, min_zoom),
+        };
+        Some(Self {
+            name: text("name"),
+            description: text("description"),
+            attribution: text("attribution"),
+            format,
+            min_zoom,
+            max_zoom,
+            bounds,
+            center,
+        })
+    }
+}
+
+/// Web Mercator extent in degrees, for tilesets that do not record their bounds
+const WORLD_BOUNDS: [f64; 4] = [-180.0, -85.051129, 180.0, 85.051129];
+
+/// Middle of `bounds` at `zoom`
+fn default_center(bounds: [f64; 4], zoom: u8) -> (f64, f64, u8) {
+    ((bounds[0] + bounds[2]) / 2.0, (bounds[1] + bounds[3]) / 2.0, zoom)
+}
+
+/// MBTiles 1.3 archive. Tiles go into one transaction that `finish` commits.
+pub struct MbtilesWriter {
+    conn: Connection,
+}
+
+impl MbtilesWriter {
+    /// Create `path`, replacing an existing file
+    pub fn create(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA application_id = 0x4d504258;
+             CREATE TABLE metadata (name TEXT, value TEXT);
+             CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER,
+                                 tile_data BLOB);
+             CREATE UNIQUE INDEX tile_index ON tiles (zoom_level, tile_column, tile_row);
+             BEGIN;",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Add XYZ tile `z/x/y`; MBTiles stores rows bottom-up (TMS)
+    pub fn add_tile(
+        &mut self,
+        z: u8,
+        x: u32,
+        y: u32,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let tms_row = (1i64 << z) - 1 - y as i64;
+        self.conn
+            .prepare_cached(
+                "INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![z, x, tms_row, data])?;
+        Ok(())
+    }
+
+    /// Write the metadata table and commit
+    pub fn finish(self, meta: &TilesetMetadata) -> Result<(), Box<dyn std::error::Error>> {
+        let [west, south, east, north] = meta.bounds;
+        let rows = [
+            ("name", meta.name.clone()),
+            ("description", meta.description.clone()),
+            ("attribution", meta.attribution.clone()),
+            ("type", "overlay".to_string()),
+            ("version", "1.3".to_string()),
+            ("format", meta.format.extension().to_string()),
+            ("minzoom", meta.min_zoom.to_string()),
+            ("maxzoom", meta.max_zoom.to_string()),
+            ("bounds", format!("{},{},{},{}", west, south, east, north)),
+            ("center", format!("{},{},{}", meta.center.0, meta.center.1, meta.center.2)),
+        ];
+        for (name, value) in rows {
+            self.conn.execute(
+                "INSERT INTO metadata (name, value) VALUES (?1, ?2)",
+                params![name, value],
+            )?;
+        }
+        self.conn.execute_batch("COMMIT;")?;
+        Ok(())
+    }
+}
+
+/// Read-only access to an MBTiles archive
+pub struct MbtilesReader {
+    conn: Connection,
+}
+
+impl MbtilesReader {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self { conn })
+    }
+
+    /// XYZ tile `z/x/y` (rows are flipped to TMS for the lookup); None when it is missing
+    pub fn tile(
+        &self,
+        z: u8,
+        x: u32,
+        y: u32,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        if !tile_in_range(z, x, y) {
+            return Ok(None);
+        }
+        let tms_row = (1i64 << z) - 1 - y as i64;
+        let data = self
+            .conn
+            .prepare_cached(
+                "SELECT tile_data FROM tiles
+                 WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+            )?
+            .query_row(params![z, x, tms_row], |row| row.get(0))
+            .optional()?;
+        Ok(data)
+    }
+
+    /// Tileset description from the metadata table. Zoom levels missing there are taken from
+    /// the tiles, bounds default to the Web Mercator extent.
+    pub fn metadata(&self) -> Result<TilesetMetadata, Box<dyn std::error::Error>> {
+        let rows: HashMap<String, String> = self
+            .conn
+            .prepare("SELECT name, value FROM metadata")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        let text = |key: &str| rows.get(key).cloned().unwrap_or_default();
+        let numbers = |key: &str| -> Vec<f64> {
+            rows.get(key).map_or(Vec::new(), |v| {
+                v.split(',').filter_map(|n| n.trim().parse().ok()).collect()
+            })
+        };
+        let format = rows
+            .get("format")
+            .and_then(|f| TileFormat::from_extension(f))
+            .ok_or("MBTiles metadata has no png, jpg or webp tile format")?;
+        let (tile_min, tile_max): (Option<u8>, Option<u8>) = self.conn.query_row(
+            "SELECT MIN(zoom_level), MAX(zoom_level) FROM tiles",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let zoom = |key: &str| rows.get(key).and_then(|z| z.trim().parse::<u8>().ok());
+        let min_zoom = zoom("minzoom").or(tile_min).unwrap_or(0);
+        let max_zoom = zoom("maxzoom").or(tile_max).unwrap_or(min_zoom);
+        let bounds = numbers("bounds").try_into().unwrap_or(WORLD_BOUNDS);
+        let center = match numbers("center")[..] {
+            [lon, lat, z] => (lon, lat, z as u8),
+            _ => default_center(bounds, min_zoom),
+        };
+        Ok(TilesetMetadata {
+            name: text("name"),
+            description: text("description"),
+            attribution: text("attribution"),
+            format,
+            min_zoom,
+            max_zoom,
+            bounds,
+            center,
+        })
+    }
+}
+
+/// PMTiles header size in bytes
+const PMTILES_HEADER_LEN: usize = 127;
+/// Header and root directory must fit in the first 16 KiB so clients can fetch both at once
+const PMTILES_ROOT_BUDGET: usize = 16_384 - PMTILES_HEADER_LEN;
+/// Entries per leaf directory before the root has to be split further
+const PMTILES_LEAF_ENTRIES: usize = 4096;
+
+/// One directory entry; `run_length` 0 points at a leaf directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PmtilesEntry {
+    tile_id: u64,
+    offset: u64,
+    length: u32,
+    run_length: u32,
+}
+
+/// PMTiles v3 archive. Tile data is spooled to a temporary file; tiles must be added in
+/// ascending tile ID order (zoom by zoom, Hilbert order within a zoom, see
+/// [`pmtiles_tile_id`]), which makes the archive clustered.
+pub struct PmtilesWriter {
+    path: PathBuf,
+    data: BufWriter<File>,
+    entries: Vec<PmtilesEntry>,
+    data_len: u64,
+}
+
+impl PmtilesWriter {
+    pub fn create(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            data: BufWriter::new(tempfile::tempfile()?),
+            entries: Vec::new(),
+            data_len: 0,
+        })
+    }
+
+    pub fn add_tile(
+        &mut self,
+        z: u8,
+        x: u32,
+        y: u32,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let tile_id = pmtiles_tile_id(z, x, y);
+        if self.entries.last().is_some_and(|e| e.tile_id >= tile_id) {
+            return Err(format!("PMTiles tile {}/{}/{} added out of order", z, x, y).into());
+        }
+        self.data.write_all(data)?;
+        self.entries.push(PmtilesEntry {
+            tile_id,
+            offset: self.data_len,
+            length: data.len() as u32,
+            run_length: 1,
+        });
+        self.data_len += data.len() as u64;
+        Ok(())
+    }
+
+    /// Write header, directories, JSON metadata and tile data to the archive
+    pub fn finish(self, meta: &TilesetMetadata) -> Result<(), Box<dyn std::error::Error>> {
+        let (root, leaves) = pmtiles_directories(&self.entries);
+        let metadata = serde_json::to_vec(&serde_json::json!({
+            "name": meta.name,
+            "description": meta.description,
+            "attribution": meta.attribution,
+            "type": "overlay",
+            "format": meta.format.extension(),
+        }))?;
+
+        let root_offset = PMTILES_HEADER_LEN as u64;
+        let metadata_offset = root_offset + root.len() as u64;
+        let leaves_offset = metadata_offset + metadata.len() as u64;
+        let data_offset = leaves_offset + leaves.len() as u64;
+        let e7 = |deg: f64| ((deg * 1e7).round() as i32).to_le_bytes();
+
+        let mut header = Vec::with_capacity(PMTILES_HEADER_LEN);
+        header.extend_from_slice(b"PMTiles");
+        header.push(3);
+        for v in [
+            root_offset,
+            root.len() as u64,
+            metadata_offset,
+            metadata.len() as u64,
+            leaves_offset,
+            leaves.len() as u64,
+            data_offset,
+            self.data_len,
+            self.entries.len() as u64, // addressed tiles
+            self.entries.len() as u64, // tile entries
+            self.entries.len() as u64, // tile contents
+        ] {
+            header.extend_from_slice(&v.to_le_bytes());
+        }
+        header.push(1); // clustered
+        header.push(1); // internal compression: none
+        header.push(1); // tile compression: none, the images are compressed already
+        header.push(match meta.format {
+            TileFormat::Png => 2,
+            TileFormat::Jpeg => 3,
+            TileFormat::Webp => 4,
+        });
+        header.push(meta.min_zoom);
+        header.push(meta.max_zoom);
+        let [west, south, east, north] = meta.bounds;
+        for deg in [west, south, east, north] {
+            header.extend_from_slice(&e7(deg));
+        }
+        header.push(meta.center.2);
+        header.extend_from_slice(&e7(meta.center.0));
+        header.extend_from_slice(&e7(meta.center.1));
+        debug_assert_eq!(header.len(), PMTILES_HEADER_LEN);
+
+        let mut spool = self.data.into_inner().map_err(|e| e.into_error())?;
+        spool.seek(SeekFrom::Start(0))?;
+        let mut out = BufWriter::new(File::create(&self.path)?);
+        out.write_all(&header)?;
+        out.write_all(&root)?;
+        out.write_all(&metadata)?;
+        out.write_all(&leaves)?;
+        std::io::copy(&mut spool, &mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Read-only access to a PMTiles v3 archive with uncompressed directories, as written by
+/// [`PmtilesWriter`]. Tiles are looked up through the root and leaf directories.
+pub struct PmtilesReader {
+    file: File,
+    root: Vec<PmtilesEntry>,
+    leaves_offset: u64,
+    data_offset: u64,
+    metadata: TilesetMetadata,
+}
+
+impl PmtilesReader {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(path)?;
+        let mut header = [0u8; PMTILES_HEADER_LEN];
+        file.read_exact(&mut header)?;
+        if &header[..7] != b"PMTiles" || header[7] != 3 {
+            return Err(format!("{:?} is not a PMTiles v3 archive", path).into());
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(header[i..i + 8].try_into().unwrap());
+        let deg_at =
+            |i: usize| i32::from_le_bytes(header[i..i + 4].try_into().unwrap()) as f64 / 1e7;
+        // Compression 1 is "none"; 0 ("unknown") is accepted for tiles
+        if header[97] != 1 || header[98] > 1 {
+            return Err(format!(
+                "{:?}: compressed PMTiles directories or tiles are not supported",
+                path
+            )
+            .into());
+        }
+        let format = match header[99] {
+            2 => TileFormat::Png,
+            3 => TileFormat::Jpeg,
+            4 => TileFormat::Webp,
+            t => return Err(format!("{:?}: unsupported PMTiles tile type {}", path, t).into()),
+        };
+
+        let root = read_directory(&mut file, u64_at(8), u64_at(16))?;
+        let json: serde_json::Value =
+            serde_json::from_slice(&read_range(&mut file, u64_at(24), u64_at(32))?)
+                .unwrap_or_default();
+        let text = |key: &str| json[key].as_str().unwrap_or_default().to_string();
+        let metadata = TilesetMetadata {
+            name: text("name"),
+            description: text("description"),
+            attribution: text("attribution"),
+            format,
+            min_zoom: header[100],
+            max_zoom: header[101],
+            bounds: [deg_at(102), deg_at(106), deg_at(110), deg_at(114)],
+            center: (deg_at(119), deg_at(123), header[118]),
+        };
+        Ok(Self {
+            file,
+            root,
+            leaves_offset: u64_at(40),
+            data_offset: u64_at(56),
+            metadata,
+        })
+    }
+
+    /// Tileset description from the header and JSON metadata
+    pub fn metadata(&self) -> &TilesetMetadata {
+        &self.metadata
+    }
+
+    /// XYZ tile `z/x/y`; None when the archive has no such tile
+    pub fn tile(
+        &mut self,
+        z: u8,
+        x: u32,
+        y: u32,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        if !tile_in_range(z, x, y) {
+            return Ok(None);
+        }
+        let tile_id = pmtiles_tile_id(z, x, y);
+        let mut leaf: Option<Vec<PmtilesEntry>> = None;
+        // The spec allows the root plus at most three levels of leaf directories
+        for _ in 0..4 {
+            let entries = leaf.as_deref().unwrap_or(&self.root);
+            let Some(entry) = find_entry(entries, tile_id) else {
+                return Ok(None);
+            };
+            if entry.run_length > 0 {
+                let offset = self.data_offset + entry.offset;
+                return Ok(Some(read_range(&mut self.file, offset, entry.length as u64)?));
+            }
+            let offset = self.leaves_offset + entry.offset;
+            leaf = Some(read_directory(&mut self.file, offset, entry.length as u64)?);
+        }
+        Ok(None)
+    }
+}
+
+/// Whether `x`/`y` lie on the grid of zoom `z`
+fn tile_in_range(z: u8, x: u32, y: u32) -> bool {
+    z < 32 && u64::from(x) < 1u64 << z && u64::from(y) < 1u64 << z
+}
+
+/// Entry covering `tile_id`: the last one starting at or before it, if its run (or leaf
+/// directory) reaches that far
+fn find_entry(entries: &[PmtilesEntry], tile_id: u64) -> Option<PmtilesEntry> {
+    let i = entries.partition_point(|e| e.tile_id <= tile_id);
+    let entry = *entries.get(i.checked_sub(1)?)?;
+    (entry.run_length == 0 || tile_id < entry.tile_id + entry.run_length as u64).then_some(entry)
+}
+
+fn read_range(file: &mut File, offset: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    file.seek(SeekFrom::Start(offset))?;
+    file.take(len).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+fn read_directory(
+    file: &mut File,
+    offset: u64,
+    len: u64,
+) -> Result<Vec<PmtilesEntry>, Box<dyn std::error::Error>> {
+    let buf = read_range(file, offset, len)?;
+    deserialize_directory(&buf).ok_or_else(|| "Corrupt PMTiles directory".into())
+}
+
+/// PMTiles tile ID: tiles of all shallower zooms first, then the Hilbert curve index of
+/// `x`/`y` within zoom `z`
+pub fn pmtiles_tile_id(z: u8, x: u32, y: u32) -> u64 {
+    let base = ((1u64 << (2 * z as u32)) - 1) / 3;
+    let n = 1u64 << z;
+    let (mut x, mut y) = (x as u64, y as u64);
+    let mut d = 0u64;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    base + d
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8 & 0x7f) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *buf.get(*pos)?;
+        *pos += 1;
+        v |= u64::from(b & 0x7f) << shift;
+        if b < 0x80 {
+            return Some(v);
+        }
+    }
+    None
+}
+
+/// Inverse of [`serialize_directory`]; None for truncated or inconsistent input
+fn deserialize_directory(buf: &[u8]) -> Option<Vec<PmtilesEntry>> {
+    let mut pos = 0;
+    let n = read_varint(buf, &mut pos)? as usize;
+    // Every entry takes at least four bytes
+    if n > buf.len() / 4 {
+        return None;
+    }
+    let mut entries = Vec::with_capacity(n);
+    let mut tile_id = 0u64;
+    for _ in 0..n {
+        tile_id = tile_id.checked_add(read_varint(buf, &mut pos)?)?;
+        entries.push(PmtilesEntry {
+            tile_id,
+            offset: 0,
+            length: 0,
+            run_length: 0,
+        });
+    }
+    for e in entries.iter_mut() {
+        e.run_length = u32::try_from(read_varint(buf, &mut pos)?).ok()?;
+    }
+    for e in entries.iter_mut() {
+        e.length = u32::try_from(read_varint(buf, &mut pos)?).ok()?;
+    }
+    for i in 0..n {
+        entries[i].offset = match read_varint(buf, &mut pos)? {
+            0 if i > 0 => entries[i - 1].offset + entries[i - 1].length as u64,
+            0 => return None,
+            v => v - 1,
+        };
+    }
+    Some(entries)
+}
+
+/// Column-wise directory encoding of the PMTiles v3 spec
+fn serialize_directory(entries: &[PmtilesEntry]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_varint(&mut buf, entries.len() as u64);
+    let mut last_id = 0;
+    for e in entries {
+        write_varint(&mut buf, e.tile_id - last_id);
+        last_id = e.tile_id;
+    }
+    for e in entries {
+        write_varint(&mut buf, e.run_length as u64);
+    }
+    for e in entries {
+        write_varint(&mut buf, e.length as u64);
+    }
+    for (i, e) in entries.iter().enumerate() {
+        // 0 means "right after the previous entry"
+        let contiguous =
+            i > 0 && e.offset == entries[i - 1].offset + entries[i - 1].length as u64;
+        write_varint(&mut buf, if contiguous { 0 } else { e.offset + 1 });
+    }
+    buf
+}
+
+/// Root directory and concatenated leaf directories. Entries go into the root while it fits
+/// its budget; otherwise they are split into leaves, doubled in size until the root fits.
+fn pmtiles_directories(entries: &[PmtilesEntry]) -> (Vec<u8>, Vec<u8>) {
+    let root = serialize_directory(entries);
+    if root.len() <= PMTILES_ROOT_BUDGET {
+        return (root, Vec::new());
+    }
+    let mut leaf_entries = PMTILES_LEAF_ENTRIES;
+    loop {
+        let mut leaves = Vec::new();
+        let mut root_entries = Vec::new();
+        for chunk in entries.chunks(leaf_entries) {
+            let leaf = serialize_directory(chunk);
+            root_entries.push(PmtilesEntry {
+                tile_id: chunk[0].tile_id,
+                offset: leaves.len() as u64,
+                length: leaf.len() as u32,
+                run_length: 0,
+            });
+            leaves.extend_from_slice(&leaf);
+        }
+        let root = serialize_directory(&root_entries);
+        if root.len() <= PMTILES_ROOT_BUDGET {
+            return (root, leaves);
+        }
+        leaf_entries *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tile_id: u64, offset: u64, length: u32) -> PmtilesEntry {
+        PmtilesEntry {
+            tile_id,
+            offset,
+            length,
+            run_length: 1,
+        }
+    }
+
+    #[test]
+    fn tile_ids_follow_the_spec() {
+        assert_eq!(pmtiles_tile_id(0, 0, 0), 0);
+        let z1: Vec<u64> = [(0, 0), (0, 1), (1, 1), (1, 0)]
+            .iter()
+            .map(|&(x, y)| pmtiles_tile_id(1, x, y))
+            .collect();
+        assert_eq!(z1, [1, 2, 3, 4]);
+        assert_eq!(pmtiles_tile_id(2, 0, 0), 5);
+        assert_eq!(pmtiles_tile_id(2, 3, 0), 20);
+        // Worked example of the PMTiles v3 specification
+        assert_eq!(pmtiles_tile_id(12, 3423, 1763), 19_078_479);
+    }
+
+    #[test]
+    fn tile_ids_walk_each_zoom_tile_by_tile() {
+        for z in 1..=5u8 {
+            let n = 1u32 << z;
+            let base = pmtiles_tile_id(z, 0, 0);
+            let mut tiles = vec![(0, 0); (n * n) as usize];
+            for y in 0..n {
+                for x in 0..n {
+                    tiles[(pmtiles_tile_id(z, x, y) - base) as usize] = (x, y);
+                }
+            }
+            // Every ID of the zoom is used once and consecutive IDs are neighbouring tiles
+            assert_eq!(base, ((1u64 << (2 * z)) - 1) / 3);
+            for pair in tiles.windows(2) {
+                let (dx, dy) = (pair[0].0.abs_diff(pair[1].0), pair[0].1.abs_diff(pair[1].1));
+                assert_eq!(dx + dy, 1, "z{} {:?}", z, pair);
+            }
+        }
+    }
+
+    #[test]
+    fn varints_are_little_endian_base_128() {
+        let mut buf = Vec::new();
+        for v in [0, 127, 128, 300, u64::from(u32::MAX)] {
+            write_varint(&mut buf, v);
+        }
+        assert_eq!(
+            buf,
+            [0x00, 0x7f, 0x80, 0x01, 0xac, 0x02, 0xff, 0xff, 0xff, 0xff, 0x0f]
+        );
+    }
+
+    #[test]
+    fn directory_is_column_wise_with_delta_ids_and_offsets() {
+        let entries = [entry(0, 0, 10), entry(1, 10, 5), entry(5, 40, 300)];
+        assert_eq!(
+            serialize_directory(&entries),
+            [
+                3, // entries
+                0, 1, 4, // tile ID deltas
+                1, 1, 1, // run lengths
+                10, 5, 0xac, 0x02, // lengths
+                1, 0, 41, // offset + 1, or 0 when contiguous with the previous entry
+            ]
+        );
+    }
+
+    #[test]
+    fn large_directories_are_split_into_leaves() {
+        let entries: Vec<PmtilesEntry> = (0..20_000u64)
+            .map(|i| entry(i * 3, i * 1_000_003, 1_000_000))
+            .collect();
+        assert!(serialize_directory(&entries).len() > PMTILES_ROOT_BUDGET);
+
+        let (root, leaves) = pmtiles_directories(&entries);
+        assert!(root.len() <= PMTILES_ROOT_BUDGET);
+        let leaf_count = entries.len().div_ceil(PMTILES_LEAF_ENTRIES);
+        assert_eq!(root[0] as usize, leaf_count);
+        let expected: Vec<u8> = entries
+            .chunks(PMTILES_LEAF_ENTRIES)
+            .flat_map(serialize_directory)
+            .collect();
+        assert_eq!(leaves, expected);
+
+        let small = [entry(0, 0, 1)];
+        assert_eq!(pmtiles_directories(&small), (serialize_directory(&small), Vec::new()));
+    }
+
+    #[test]
+    fn directories_round_trip() {
+        let entries: Vec<PmtilesEntry> = (0..500u64)
+            .map(|i| PmtilesEntry {
+                run_length: 1 + (i % 3) as u32,
+                ..entry(i * 7 + 2, i * 40 + (i % 5) * 1000, 37 + i as u32)
+            })
+            .collect();
+        let buf = serialize_directory(&entries);
+        assert_eq!(deserialize_directory(&buf), Some(entries));
+        assert_eq!(deserialize_directory(&buf[..buf.len() - 1]), None);
+        assert_eq!(deserialize_directory(&[]), None);
+    }
+
+    #[test]
+    fn pmtiles_archives_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiles.pmtiles");
+        let meta = TilesetMetadata {
+            name: "S1A".to_string(),
+            description: "VV".to_string(),
+            attribution: "ESA".to_string(),
+            format: TileFormat::Webp,
+            min_zoom: 0,
+            max_zoom: 7,
+            bounds: [10.5, 45.25, 12.75, 46.5],
+            center: (11.625, 45.875, 3),
+        };
+        // Enough tiles that the directory is split into leaves
+        let mut tiles: Vec<(u8, u32, u32)> = (0..=7u8)
+            .flat_map(|z| (0..1u32 << z).flat_map(move |x| (0..1u32 << z).map(move |y| (z, x, y))))
+            .filter(|&(z, x, y)| (x ^ y ^ z as u32) & 3 != 0)
+            .collect();
+        tiles.sort_by_key(|&(z, x, y)| pmtiles_tile_id(z, x, y));
+        let data = |z: u8, x: u32, y: u32| format!("{}/{}/{}", z, x, y).into_bytes();
+        let mut writer = PmtilesWriter::create(&path).unwrap();
+        for &(z, x, y) in &tiles {
+            writer.add_tile(z, x, y, &data(z, x, y)).unwrap();
+        }
+        writer.finish(&meta).unwrap();
+
+        let archive = std::fs::read(&path).unwrap();
+        let leaves_len = u64::from_le_bytes(archive[48..56].try_into().unwrap());
+        assert!(leaves_len > 0, "expected leaf directories");
+
+        let mut reader = PmtilesReader::open(&path).unwrap();
+        let read = reader.metadata().clone();
+        assert_eq!((read.name.as_str(), read.attribution.as_str()), ("S1A", "ESA"));
+        assert_eq!((read.format, read.min_zoom, read.max_zoom), (TileFormat::Webp, 0, 7));
+        assert_eq!((read.bounds, read.center), (meta.bounds, meta.center));
+        for &(z, x, y) in tiles.iter().step_by(97).chain(tiles.last()) {
+            assert_eq!(reader.tile(z, x, y).unwrap(), Some(data(z, x, y)));
+        }
+        assert_eq!(reader.tile(1, 1, 0).unwrap(), None);
+        assert_eq!(reader.tile(8, 0, 0).unwrap(), None);
+        assert_eq!(reader.tile(2, 4, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn mbtiles_archives_read_back_with_xyz_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiles.mbtiles");
+        let meta = TilesetMetadata {
+            name: "S1A".to_string(),
+            description: String::new(),
+            attribution: "ESA".to_string(),
+            format: TileFormat::Png,
+            min_zoom: 1,
+            max_zoom: 2,
+            bounds: [10.5, 45.25, 12.75, 46.5],
+            center: (11.625, 45.875, 1),
+        };
+        let mut writer = MbtilesWriter::create(&path).unwrap();
+        writer.add_tile(1, 1, 0, b"north-east").unwrap();
+        writer.add_tile(2, 2, 3, b"deep").unwrap();
+        writer.finish(&meta).unwrap();
+
+        let reader = MbtilesReader::open(&path).unwrap();
+        assert_eq!(reader.tile(1, 1, 0).unwrap().as_deref(), Some(&b"north-east"[..]));
+        assert_eq!(reader.tile(2, 2, 3).unwrap().as_deref(), Some(&b"deep"[..]));
+        assert_eq!(reader.tile(1, 1, 1).unwrap(), None);
+        let read = reader.metadata().unwrap();
+        assert_eq!((read.format, read.min_zoom, read.max_zoom), (TileFormat::Png, 1, 2));
+        assert_eq!((read.bounds, read.center), (meta.bounds, meta.center));
+    }
+
+    #[test]
+    fn tilejson_describes_the_tileset() {
+        let meta = TilesetMetadata {
+            name: "S1A".to_string(),
+            description: "VV".to_string(),
+            attribution: "ESA".to_string(),
+            format: TileFormat::Jpeg,
+            min_zoom: 2,
+            max_zoom: 9,
+            bounds: [10.5, 45.25, 12.75, 46.5],
+            center: (11.625, 45.875, 4),
+        };
+        let json = meta.to_tilejson("{z}/{x}/{y}.jpg");
+        let read = TilesetMetadata::from_tilejson(&json).unwrap();
+        assert_eq!((read.name, read.format), ("S1A".to_string(), TileFormat::Jpeg));
+        assert_eq!((read.min_zoom, read.max_zoom), (2, 9));
+        assert_eq!((read.bounds, read.center), (meta.bounds, meta.center));
+        assert!(TilesetMetadata::from_tilejson(&serde_json::json!({"tiles": ["a/b"]})).is_none());
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde_json::{Value, json};
+use tracing::info;
+
+use crate::core::processing::ships::ShipTarget;
+use crate::io::footprint::Footprint;
+use crate::io::sentinel1::SafeMetadata;
+
+/// Write ship targets as a GeoJSON FeatureCollection of WGS84 points.
+/// Targets that could not be geolocated get a null geometry; pixel coordinates are
+/// always kept in the properties.
+pub fn write_ships_geojson(
+    path: &Path,
+    targets: &[ShipTarget],
+    meta: Option<&SafeMetadata>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let features: Vec<Value> = targets
+        .iter()
+        .map(|t| {
+            let geometry = match (t.lon, t.lat) {
+                (Some(lon), Some(lat)) => json!({ "type": "Point", "coordinates": [lon, lat] }),
+                _ => Value::Null,
+            };
+            json!({
+                "type": "Feature",
+                "id": t.id,
+                "geometry": geometry,
+                "properties": t,
+            })
+        })
+        .collect();
+
+    let mut collection = json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    if let Some(meta) = meta {
+        collection["properties"] = json!({
+            "product_id": meta.product_id,
+            "platform": meta.platform,
+            "acquisition_start": meta.acquisition_start,
+            "polarizations": meta.polarizations.join(","),
+        });
+    }
+
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &collection)?;
+    info!("Wrote {} ship targets to {:?}", targets.len(), path);
+    Ok(())
+}
+
+/// Write ship targets as CSV (one row per target; unknown values are left empty)
+pub fn write_ships_csv(path: &Path, targets: &[ShipTarget]) -> Result<(), Box<dyn std::error::Error>> {
+    let opt = |v: Option<f64>, prec: usize| v.map(|x| format!("{:.*}", prec, x)).unwrap_or_default();
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "id,lon,lat,col,row,pixels,length_m,width_m,peak_db")?;
+    for t in targets {
+        writeln!(
+            file,
+            "{},{},{},{:.2},{:.2},{},{},{},{:.2}",
+            t.id,
+            opt(t.lon, 7),
+            opt(t.lat, 7),
+            t.col,
+            t.row,
+            t.pixels,
+            opt(t.length_m, 1),
+            opt(t.width_m, 1),
+            t.peak_db,
+        )?;
+    }
+    file.flush()?;
+    info!("Wrote {} ship targets to {:?}", targets.len(), path);
+    Ok(())
+}
+
+/// Write a product footprint as a GeoJSON Feature with a `bbox` and the product's identity
+/// in its properties
+pub fn write_footprint_geojson(
+    path: &Path,
+    footprint: &Footprint,
+    meta: Option<&SafeMetadata>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut properties = json!({ "crosses_antimeridian": footprint.crosses_antimeridian() });
+    if let Some(meta) = meta {
+        properties["product_id"] = json!(meta.product_id);
+        properties["platform"] = json!(meta.platform);
+        properties["acquisition_start"] = json!(meta.acquisition_start);
+        properties["acquisition_stop"] = json!(meta.acquisition_stop);
+        properties["relative_orbit"] = json!(meta.relative_orbit);
+        properties["pass_direction"] = json!(meta.pass_direction);
+        properties["polarizations"] = json!(meta.polarizations.join(","));
+    }
+    let feature = json!({
+        "type": "Feature",
+        "bbox": footprint.bounds(),
+        "geometry": footprint.to_geojson(),
+        "properties": properties,
+    });
+
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &feature)?;
+    info!("Wrote footprint to {:?}", path);
+    Ok(())
+}
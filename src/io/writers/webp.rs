@@ -0,0 +1,47 @@
+use gdal::raster::RasterCreationOptions;
+use std::path::Path;
+
+use crate::io::writers::encode::WebpOptions;
+use crate::io::writers::png::{rgb_planes, write_bands_via_gdal};
+
+fn creation_options(
+    options: &WebpOptions,
+) -> Result<RasterCreationOptions, Box<dyn std::error::Error>> {
+    let mut opts = RasterCreationOptions::new();
+    if options.lossless {
+        opts.set_name_value("LOSSLESS", "YES")?;
+    } else {
+        opts.set_name_value("QUALITY", &options.quality.to_string())?;
+    }
+    Ok(opts)
+}
+
+/// Grayscale WebP. The format has no gray mode, so the channel is repeated as RGB (RGBA
+/// when `alpha` is given).
+pub fn write_gray_webp(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    data: &[u8],
+    alpha: Option<&[u8]>,
+    options: &WebpOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bands = vec![data, data, data];
+    bands.extend(alpha);
+    write_bands_via_gdal("WEBP", output, cols, rows, &bands, &creation_options(options)?)
+}
+
+/// RGB WebP from interleaved samples, RGBA when `alpha` is given
+pub fn write_rgb_webp(
+    output: &Path,
+    cols: usize,
+    rows: usize,
+    rgb_data: &[u8],
+    alpha: Option<&[u8]>,
+    options: &WebpOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let [r, g, b] = rgb_planes(rgb_data);
+    let mut bands = vec![r.as_slice(), g.as_slice(), b.as_slice()];
+    bands.extend(alpha);
+    write_bands_via_gdal("WEBP", output, cols, rows, &bands, &creation_options(options)?)
+}
@@ -17,6 +17,7 @@ pub fn write_world_file(
     let world_ext = match ext.as_str() {
         "jpg" | "jpeg" => "jgw",
         "png" => "pgw",
+        "webp" => "wpw",
         "tif" | "tiff" => "tfw",
         other if !other.is_empty() => {
             // Fallback: use first letter + "w"
@@ -46,6 +46,7 @@ fn main() -> sarpro::Result<()> {
         synrgb_mode: SyntheticRgbMode::Default,
         size: Some(2048),
         pad: true,
+        ..Default::default()
     };
 
     process_safe_to_path(
@@ -144,6 +145,7 @@ fn main() -> sarpro::Result<()> {
         synrgb_mode: SyntheticRgbMode::Default,
         size: Some(1024),
         pad: true,
+        ..Default::default()
     };
 
     let report = process_directory_to_path(
@@ -179,6 +181,7 @@ fn main() {
         synrgb_mode: SyntheticRgbMode::Default,
         size: None,
         pad: false,
+        ..Default::default()
     };
 
     match process_safe_to_path(Path::new("/bad/path.SAFE"), Path::new("/out.tiff"), &params) {
@@ -217,24 +220,51 @@ pub mod gui;
 // Curated public API surface
 // Types
 pub use core::params::ProcessingParams;
+pub use core::processing::autoscale::{AutoscaleParams, StretchTransfer, StretchWindow};
+pub use core::processing::change::ChangeOptions;
+pub use core::processing::chips::{ChipOptions, ChipWindow};
+pub use core::processing::colormap::{ColorRamp, ColormapOptions};
+pub use core::processing::expr::BandExpr;
+pub use core::processing::ships::{ShipOptions, ShipTarget};
+pub use core::processing::water::{WaterOptions, WaterResult};
 pub use error::{Error, Result};
 pub use types::{
-    AutoscaleStrategy, BitDepth, BitDepthArg, InputFormat, OutputFormat, Polarization, SyntheticRgbMode,
-    PolarizationOperation, ProcessingOperation,
+    AutoscaleStrategy, BitDepth, BitDepthArg, ChangeMethod, ChipValues, Colormap, ExprScale, InputFormat, JpegSubsampling, OutputFormat, Polarization,
+    SyntheticRgbMode,
+    PolarizationOperation, ProcessingOperation, ProductMode, StackFormat, TemporalStat,
+    TileArchive, TileFormat, WaterThreshold,
 };
 
 // Readers
 pub use io::gdal::{GdalError, GdalMetadata, GdalSarReader};
-pub use io::sentinel1::{ProductType, SafeError, SafeMetadata, SafeReader};
+pub use io::aoi::Aoi;
+pub use io::discovery::{
+    DiscoveryOptions, ProductFilter, ProductName, discover_products, read_product_list,
+};
+pub use io::footprint::Footprint;
+pub use io::geolocation::PixelGeolocation;
+pub use io::sentinel1::{
+    DataObject, ProductType, ReadOptions, SafeError, SafeMetadata, SafeReader,
+};
+pub use io::slices::{SliceGroup, assemble_slices};
 
 // Selected writer helpers (keep low-level metadata helpers public)
+pub use io::writers::encode::{EncodeOptions, JpegOptions, WebpOptions};
+pub use io::writers::kml::{GroundOverlay, OverlayPlacement};
+pub use io::writers::tilearchive::{
+    MbtilesReader, MbtilesWriter, PmtilesReader, PmtilesWriter, TilesetMetadata,
+};
 pub use io::writers::metadata::{
-    create_jpeg_metadata_sidecar, embed_tiff_metadata, extract_metadata_fields,
+    create_jpeg_metadata_sidecar, embed_tiff_metadata, extract_metadata_fields, read_autoscale_reference,
 };
 
 // High-level API re-exports
 pub use api::{
-    BatchReport, ProcessedImage, iterate_safe_products, load_operation, load_polarization,
-    process_directory_to_path, process_safe_to_buffer, process_safe_to_buffer_with_mode,
-    process_safe_to_path, process_safe_with_options, save_image, save_multiband_image,
+    BatchReport, ChangeReport, ProcessedImage, StackOptions, StackReport, autoscale_from_reference, build_stacks,
+    build_stacks_from_dir, group_safe_slices, iterate_safe_products, load_expression,
+    load_operation, load_polarization, process_change_to_path, process_directory_to_path, process_safe_to_buffer,
+    process_safe_to_buffer_with_mode, process_safe_to_path, process_safe_with_options, process_slices_to_path, save_image,
+    save_multiband_image, process_safe_to_tiles, TileOptions, TileReport, process_safe_to_chips,
+    ChipInfo, ChipReport, ProductInfo, SafeFile, inspect_safe, ValidationIssue, ValidationReport,
+    validate_safe, process_products_to_path, TileServer,
 };
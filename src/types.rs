@@ -11,6 +11,27 @@ pub enum PolarizationOperation {
     Ratio,
     NDiff,
     LogRatio,
+    /// Dual-pol Radar Vegetation Index: 4·cross / (co + cross)
+    Rvi,
+    /// Cross-pol ratio in dB: 10·log10(cross / co)
+    CrossPolDb,
+    /// Depolarization ratio (linear): cross / co
+    DepolRatio,
+    /// Dual-pol SAR Vegetation Index (Periasamy, 2018)
+    Dpsvi,
+}
+
+impl PolarizationOperation {
+    /// True for indices whose values are already on their final scale (bounded ratios,
+    /// dB differences); these bypass the dB conversion applied to intensity-like outputs
+    pub fn skips_db_conversion(&self) -> bool {
+        matches!(
+            self,
+            PolarizationOperation::Rvi
+                | PolarizationOperation::CrossPolDb
+                | PolarizationOperation::DepolRatio
+        )
+    }
 }
 
 impl std::fmt::Display for PolarizationOperation {
@@ -21,6 +42,10 @@ impl std::fmt::Display for PolarizationOperation {
             PolarizationOperation::Ratio => "Ratio",
             PolarizationOperation::NDiff => "NDiff",
             PolarizationOperation::LogRatio => "LogRatio",
+            PolarizationOperation::Rvi => "RVI",
+            PolarizationOperation::CrossPolDb => "CrossPolDb",
+            PolarizationOperation::DepolRatio => "DepolRatio",
+            PolarizationOperation::Dpsvi => "DPSVI",
         };
         write!(f, "{}", s)
     }
@@ -42,6 +67,8 @@ pub enum ProcessingOperation {
     MultibandVvVh,
     MultibandHhHv,
     PolarOp(PolarizationOperation),
+    /// User band-math expression; `scale` says what its values are
+    Expression { scale: ExprScale },
 }
 
 impl std::fmt::Display for ProcessingOperation {
@@ -51,6 +78,45 @@ impl std::fmt::Display for ProcessingOperation {
             ProcessingOperation::MultibandVvVh => write!(f, "MultibandVvVh"),
             ProcessingOperation::MultibandHhHv => write!(f, "MultibandHhHv"),
             ProcessingOperation::PolarOp(op) => write!(f, "PolarOp({})", op),
+            ProcessingOperation::Expression { scale } => write!(f, "Expression({})", scale),
+        }
+    }
+}
+
+impl ProcessingOperation {
+    /// See [`PolarizationOperation::skips_db_conversion`]; expressions skip it unless their
+    /// result is linear intensity
+    pub fn skips_db_conversion(&self) -> bool {
+        match self {
+            ProcessingOperation::PolarOp(op) => op.skips_db_conversion(),
+            ProcessingOperation::Expression { scale } => *scale != ExprScale::Linear,
+            _ => false,
+        }
+    }
+}
+
+/// Units of a band-math expression result, which decide how it is autoscaled
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ExprScale {
+    /// Linear intensity (e.g. "VV+VH"): converted to dB, then stretched like a band
+    #[default]
+    Linear,
+    /// Already dB (e.g. "10*log10(VH/VV)"): stretched as-is
+    Db,
+    /// Index in its own units, possibly negative (e.g. "(VV-VH)/(VV+VH)"): stretched as-is
+    /// like RVI or the depolarization ratio
+    Native,
+}
+
+impl std::fmt::Display for ExprScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExprScale::Linear => write!(f, "linear"),
+            ExprScale::Db => write!(f, "db"),
+            ExprScale::Native => write!(f, "native"),
         }
     }
 }
@@ -69,6 +135,10 @@ impl clap::ValueEnum for Polarization {
             Polarization::OP(PolarizationOperation::Ratio),
             Polarization::OP(PolarizationOperation::NDiff),
             Polarization::OP(PolarizationOperation::LogRatio),
+            Polarization::OP(PolarizationOperation::Rvi),
+            Polarization::OP(PolarizationOperation::CrossPolDb),
+            Polarization::OP(PolarizationOperation::DepolRatio),
+            Polarization::OP(PolarizationOperation::Dpsvi),
         ]
     }
 
@@ -94,6 +164,18 @@ impl clap::ValueEnum for Polarization {
             Polarization::OP(PolarizationOperation::LogRatio) => {
                 clap::builder::PossibleValue::new("log-ratio")
             }
+            Polarization::OP(PolarizationOperation::Rvi) => {
+                clap::builder::PossibleValue::new("rvi")
+            }
+            Polarization::OP(PolarizationOperation::CrossPolDb) => {
+                clap::builder::PossibleValue::new("cross-pol-db")
+            }
+            Polarization::OP(PolarizationOperation::DepolRatio) => {
+                clap::builder::PossibleValue::new("depol-ratio")
+            }
+            Polarization::OP(PolarizationOperation::Dpsvi) => {
+                clap::builder::PossibleValue::new("dpsvi")
+            }
         })
     }
 }
@@ -111,7 +193,7 @@ impl std::fmt::Display for Polarization {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum AutoscaleStrategy {
     Standard,
     Robust,
@@ -120,6 +202,36 @@ pub enum AutoscaleStrategy {
     Clahe,
     Tamed,
     Default,
+    /// Same clip window and gamma for every scene (time series, batches); no histogram fitting
+    Fixed { min_db: f64, max_db: f64, gamma: f64 },
+}
+
+// Manual implementation for ValueEnum: `Fixed` carries values and is set via --fixed-range
+impl clap::ValueEnum for AutoscaleStrategy {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            AutoscaleStrategy::Standard,
+            AutoscaleStrategy::Robust,
+            AutoscaleStrategy::Adaptive,
+            AutoscaleStrategy::Equalized,
+            AutoscaleStrategy::Clahe,
+            AutoscaleStrategy::Tamed,
+            AutoscaleStrategy::Default,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        match self {
+            AutoscaleStrategy::Standard => Some(clap::builder::PossibleValue::new("standard")),
+            AutoscaleStrategy::Robust => Some(clap::builder::PossibleValue::new("robust")),
+            AutoscaleStrategy::Adaptive => Some(clap::builder::PossibleValue::new("adaptive")),
+            AutoscaleStrategy::Equalized => Some(clap::builder::PossibleValue::new("equalized")),
+            AutoscaleStrategy::Clahe => Some(clap::builder::PossibleValue::new("clahe")),
+            AutoscaleStrategy::Tamed => Some(clap::builder::PossibleValue::new("tamed")),
+            AutoscaleStrategy::Default => Some(clap::builder::PossibleValue::new("default")),
+            AutoscaleStrategy::Fixed { .. } => None,
+        }
+    }
 }
 
 impl std::fmt::Display for AutoscaleStrategy {
@@ -132,6 +244,7 @@ impl std::fmt::Display for AutoscaleStrategy {
             AutoscaleStrategy::Clahe => write!(f, "Clahe"),
             AutoscaleStrategy::Tamed => write!(f, "Tamed"),
             AutoscaleStrategy::Default => write!(f, "Default"),
+            AutoscaleStrategy::Fixed { .. } => write!(f, "Fixed"),
         }
     }
 }
@@ -162,6 +275,9 @@ pub enum BitDepthArg {
 pub enum OutputFormat {
     TIFF,
     JPEG, // Lossy, preview only
+    PNG,  // Lossless, 8/16-bit with alpha for nodata
+    #[value(name = "webp")]
+    WebP, // Lossy or lossless, 8-bit with alpha for nodata
 }
 
 #[derive(
@@ -191,3 +307,266 @@ impl std::fmt::Display for SyntheticRgbMode {
         }
     }
 }
+
+/// What to derive from the input: an autoscaled image or an analysis product
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Default, Serialize, Deserialize,
+)]
+pub enum ProductMode {
+    /// Autoscaled backscatter / polarization image
+    #[default]
+    Image,
+    /// Binary water mask GeoTIFF with area statistics
+    Water,
+    /// Image plus CA-CFAR ship detections (GeoJSON + CSV)
+    Ships,
+}
+
+impl std::fmt::Display for ProductMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProductMode::Image => write!(f, "Image"),
+            ProductMode::Water => write!(f, "Water"),
+            ProductMode::Ships => write!(f, "Ships"),
+        }
+    }
+}
+
+/// Automatic threshold used for water mapping
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+pub enum WaterThreshold {
+    /// Otsu: maximum between-class variance
+    Otsu,
+    /// Kittler-Illingworth: minimum error for two Gaussian classes
+    Ki,
+}
+
+impl std::fmt::Display for WaterThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaterThreshold::Otsu => write!(f, "Otsu"),
+            WaterThreshold::Ki => write!(f, "KittlerIllingworth"),
+        }
+    }
+}
+
+/// Built-in color ramp for single-band renderings
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+pub enum Colormap {
+    /// Perceptually uniform, dark purple to yellow
+    Viridis,
+    /// Perceptually uniform, black to pale yellow
+    Magma,
+    /// Diverging red-white-blue, for signed measures such as change or log ratios
+    #[value(name = "rdbu")]
+    RdBu,
+    /// Blue-green-brown-white elevation style ramp
+    Terrain,
+}
+
+impl std::fmt::Display for Colormap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Colormap::Viridis => write!(f, "viridis"),
+            Colormap::Magma => write!(f, "magma"),
+            Colormap::RdBu => write!(f, "rdbu"),
+            Colormap::Terrain => write!(f, "terrain"),
+        }
+    }
+}
+
+/// JPEG chroma subsampling of RGB outputs (grayscale JPEGs have no chroma)
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Default, Serialize, Deserialize,
+)]
+pub enum JpegSubsampling {
+    /// Full chroma resolution
+    #[default]
+    #[value(name = "444")]
+    S444,
+    /// Chroma halved horizontally
+    #[value(name = "422")]
+    S422,
+    /// Chroma halved in both directions; smallest files
+    #[value(name = "420")]
+    S420,
+}
+
+impl std::fmt::Display for JpegSubsampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JpegSubsampling::S444 => write!(f, "4:4:4"),
+            JpegSubsampling::S422 => write!(f, "4:2:2"),
+            JpegSubsampling::S420 => write!(f, "4:2:0"),
+        }
+    }
+}
+
+/// Per-pixel change measure between two acquisitions
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+pub enum ChangeMethod {
+    /// 10·log10(after / before), in dB
+    LogRatio,
+    /// after − before on linear intensity
+    Difference,
+    /// (after − before) / (after + before) on linear intensity
+    NormalizedDiff,
+}
+
+impl std::fmt::Display for ChangeMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeMethod::LogRatio => write!(f, "LogRatio"),
+            ChangeMethod::Difference => write!(f, "Difference"),
+            ChangeMethod::NormalizedDiff => write!(f, "NormalizedDiff"),
+        }
+    }
+}
+
+/// Per-pixel statistic over the dates of a time-series stack
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+pub enum TemporalStat {
+    Median,
+    Mean,
+    Std,
+    Min,
+    Max,
+    /// Coefficient of variation (std / mean)
+    Cv,
+}
+
+impl std::fmt::Display for TemporalStat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemporalStat::Median => write!(f, "median"),
+            TemporalStat::Mean => write!(f, "mean"),
+            TemporalStat::Std => write!(f, "std"),
+            TemporalStat::Min => write!(f, "min"),
+            TemporalStat::Max => write!(f, "max"),
+            TemporalStat::Cv => write!(f, "cv"),
+        }
+    }
+}
+
+/// Container for a time-series stack
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+pub enum StackFormat {
+    /// One multi-band GeoTIFF, one band per date
+    Gtiff,
+    /// One Cloud Optimized GeoTIFF per date plus a VRT referencing them as bands
+    Vrt,
+}
+
+impl std::fmt::Display for StackFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StackFormat::Gtiff => write!(f, "GTiff"),
+            StackFormat::Vrt => write!(f, "VRT"),
+        }
+    }
+}
+
+/// Image format of XYZ map tiles
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Default, Serialize, Deserialize,
+)]
+pub enum TileFormat {
+    /// RGBA/gray+alpha PNG; nodata is transparent
+    #[default]
+    Png,
+    /// RGBA WebP; nodata is transparent
+    Webp,
+    /// JPEG without transparency; nodata is black
+    Jpeg,
+}
+
+impl TileFormat {
+    /// File extension of the tiles
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TileFormat::Png => "png",
+            TileFormat::Webp => "webp",
+            TileFormat::Jpeg => "jpg",
+        }
+    }
+
+    /// Format of a `png`, `jpg`/`jpeg` or `webp` extension (case-insensitive)
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(TileFormat::Png),
+            "webp" => Some(TileFormat::Webp),
+            "jpg" | "jpeg" => Some(TileFormat::Jpeg),
+            _ => None,
+        }
+    }
+
+    /// MIME type of the tiles
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            TileFormat::Png => "image/png",
+            TileFormat::Webp => "image/webp",
+            TileFormat::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+impl std::fmt::Display for TileFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileFormat::Png => write!(f, "png"),
+            TileFormat::Webp => write!(f, "webp"),
+            TileFormat::Jpeg => write!(f, "jpeg"),
+        }
+    }
+}
+
+/// Single-file container for a tile pyramid
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Serialize, Deserialize)]
+pub enum TileArchive {
+    /// MBTiles 1.3 (SQLite)
+    Mbtiles,
+    /// PMTiles v3, a single file readable over HTTP range requests
+    Pmtiles,
+}
+
+impl TileArchive {
+    /// Archive type implied by the `.mbtiles` / `.pmtiles` extension of `path`
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "mbtiles" => Some(TileArchive::Mbtiles),
+            "pmtiles" => Some(TileArchive::Pmtiles),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for TileArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileArchive::Mbtiles => write!(f, "MBTiles"),
+            TileArchive::Pmtiles => write!(f, "PMTiles"),
+        }
+    }
+}
+
+/// Sample values written to ML chips
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug, Default, Serialize, Deserialize,
+)]
+pub enum ChipValues {
+    /// Float32 values as processed (linear intensity, operation or expression values), NaN nodata
+    #[default]
+    Float,
+    /// Autoscaled 8/16-bit values as in image outputs, 0 nodata
+    Autoscaled,
+}
+
+impl std::fmt::Display for ChipValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChipValues::Float => write!(f, "float"),
+            ChipValues::Autoscaled => write!(f, "autoscaled"),
+        }
+    }
+}